@@ -0,0 +1,82 @@
+/// Benchmarks for the update hot path: parsing a `getUpdates` response, and routing each
+/// update to its handlers. Run with `cargo bench`, and `cargo bench --features simd-json` to
+/// compare JSON backends.
+use std::hint::black_box;
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use mobot::{api, json, ApiResponse, Matcher, Route, Update};
+
+/// Updates representative of a busy group: plain text, replies, media, and callback queries.
+const FIXTURES: &[&str] = &[
+    include_str!("../tests/fixtures/types/update_message.json"),
+    include_str!("../tests/fixtures/types/update_reply.json"),
+    include_str!("../tests/fixtures/types/update_photo.json"),
+    include_str!("../tests/fixtures/types/update_media.json"),
+    include_str!("../tests/fixtures/types/update_callback_query.json"),
+];
+
+const BATCH_SIZE: usize = 100;
+
+/// Build a `getUpdates` response body with `BATCH_SIZE` updates.
+fn get_updates_body() -> Vec<u8> {
+    let updates: Vec<&str> = FIXTURES.iter().cycle().take(BATCH_SIZE).copied().collect();
+    format!(r#"{{"ok":true,"result":[{}]}}"#, updates.join(",")).into_bytes()
+}
+
+fn routes() -> Vec<Route> {
+    vec![
+        Route::Message(Matcher::BotCommand("start".into())),
+        Route::Message(Matcher::Exact("ping".into())),
+        Route::Message(Matcher::Prefix("!".into())),
+        Route::Message(Matcher::Regex(r"(?i)\bspam\b".into())),
+        Route::Message(Matcher::Photo),
+        Route::CallbackQuery(Matcher::Exact("yes".into())),
+        Route::Message(Matcher::Any),
+    ]
+}
+
+fn deserialize(c: &mut Criterion) {
+    let body = get_updates_body();
+
+    let mut group = c.benchmark_group("deserialize");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.bench_function("get_updates", |b| {
+        b.iter_batched(
+            || Bytes::from(body.clone()),
+            |body| json::from_bytes::<ApiResponse<Vec<api::Update>>>(body).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn dispatch(c: &mut Criterion) {
+    let updates = ApiResponse::<Vec<api::Update>>::from_slice(&get_updates_body())
+        .unwrap()
+        .into_result()
+        .unwrap();
+    let routes = routes();
+
+    let mut group = c.benchmark_group("dispatch");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.bench_function("match_routes", |b| {
+        b.iter(|| {
+            black_box(&updates)
+                .iter()
+                .map(|update| routes.iter().filter(|r| r.match_update(update)).count())
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("into_event", |b| {
+        b.iter_batched(
+            || updates.clone(),
+            |updates| updates.into_iter().map(Update::from).collect::<Vec<_>>(),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, deserialize, dispatch);
+criterion_main!(benches);
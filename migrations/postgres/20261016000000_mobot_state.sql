@@ -0,0 +1,12 @@
+-- Key-value state for mobot's StateStorage. Values are JSON, keyed by a namespace and a chat
+-- or user ID.
+CREATE TABLE IF NOT EXISTS mobot_state (
+    namespace TEXT NOT NULL,
+    id BIGINT NOT NULL,
+    value JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (namespace, id)
+);
+
+-- User data exports and deletions look up every namespace by ID.
+CREATE INDEX IF NOT EXISTS mobot_state_id ON mobot_state (id);
@@ -0,0 +1,220 @@
+/// `mobot-cli` makes Telegram Bot API calls from the command line, for scripts and for poking
+/// at a bot while developing it. Build it with `cargo install mobot --features cli`.
+///
+///     $ export TELEGRAM_TOKEN=...
+///     $ mobot-cli send 123456789 "The backup finished."
+///     $ mobot-cli set-webhook https://example.com/telegram --secret hunter2
+///     $ mobot-cli download <file_id> --output photo.jpg
+///
+/// Results are printed as JSON.
+use std::{env, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use argh::FromArgs;
+use mobot::{api::*, Client};
+use serde::Serialize;
+
+#[derive(FromArgs)]
+/// Make Telegram Bot API calls.
+struct Args {
+    /// the bot's API token (default: $TELEGRAM_TOKEN)
+    #[argh(option)]
+    token: Option<String>,
+
+    /// the Bot API server URL (default: https://api.telegram.org)
+    #[argh(option)]
+    server: Option<String>,
+
+    #[argh(subcommand)]
+    command: Command,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Me(Me),
+    Send(Send),
+    SendFile(SendFile),
+    Chat(Chat),
+    SetWebhook(SetWebhook),
+    DeleteWebhook(DeleteWebhook),
+    WebhookInfo(WebhookInfo),
+    Download(Download),
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "me")]
+/// Show the bot's user.
+struct Me {}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "send")]
+/// Send a text message.
+struct Send {
+    /// the chat to send to
+    #[argh(positional)]
+    chat_id: i64,
+
+    /// the message
+    #[argh(positional)]
+    text: String,
+
+    /// parse the text as "html" or "markdown" (MarkdownV2)
+    #[argh(option)]
+    parse_mode: Option<String>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "send-file")]
+/// Upload a file and send it as a document.
+struct SendFile {
+    /// the chat to send to
+    #[argh(positional)]
+    chat_id: i64,
+
+    /// the file to upload
+    #[argh(positional)]
+    path: PathBuf,
+
+    /// a caption for the file
+    #[argh(option)]
+    caption: Option<String>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "chat")]
+/// Show information about a chat.
+struct Chat {
+    /// the chat's ID, or @username for public chats
+    #[argh(positional)]
+    chat_id: String,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "set-webhook")]
+/// Deliver updates to a webhook.
+struct SetWebhook {
+    /// the webhook's HTTPS URL
+    #[argh(positional)]
+    url: String,
+
+    /// the secret token Telegram sends with each update
+    #[argh(option)]
+    secret: Option<String>,
+
+    /// drop updates that haven't been delivered yet
+    #[argh(switch)]
+    drop_pending_updates: bool,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "delete-webhook")]
+/// Stop delivering updates to the webhook.
+struct DeleteWebhook {
+    /// drop updates that haven't been delivered yet
+    #[argh(switch)]
+    drop_pending_updates: bool,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "webhook-info")]
+/// Show the webhook's status.
+struct WebhookInfo {}
+
+#[derive(FromArgs)]
+#[argh(subcommand, name = "download")]
+/// Download a file by its file ID.
+struct Download {
+    /// the file's ID
+    #[argh(positional)]
+    file_id: String,
+
+    /// where to save the file (default: its name on the server)
+    #[argh(option, short = 'o')]
+    output: Option<PathBuf>,
+}
+
+fn parse_mode(mode: &str) -> Result<ParseMode> {
+    match mode.to_ascii_lowercase().as_str() {
+        "html" => Ok(ParseMode::HTML),
+        "markdown" | "markdownv2" => Ok(ParseMode::MarkdownV2),
+        _ => Err(anyhow!(
+            "Unknown parse mode {:?}, use html or markdown",
+            mode
+        )),
+    }
+}
+
+fn print(result: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(result)?);
+    Ok(())
+}
+
+async fn run(api: &API, command: Command) -> Result<()> {
+    match command {
+        Command::Me(_) => print(&api.get_me().await?),
+        Command::Send(args) => {
+            let mut req = SendMessageRequest::new(args.chat_id, args.text);
+            if let Some(mode) = args.parse_mode {
+                req = req.with_parse_mode(parse_mode(&mode)?);
+            }
+            print(&api.send_message(&req).await?)
+        }
+        Command::SendFile(args) => {
+            let mut req = SendDocumentRequest::new(args.chat_id, "");
+            if let Some(caption) = args.caption {
+                req = req.with_caption(caption);
+            }
+            let file = InputFile::from_path(&args.path).await?;
+            print(&api.send_document_file(&req, &file).await?)
+        }
+        Command::Chat(args) => print(&api.get_chat(&GetChatRequest::new(args.chat_id)).await?),
+        Command::SetWebhook(args) => {
+            let mut req = SetWebhookRequest::new(args.url)
+                .with_drop_pending_updates(args.drop_pending_updates);
+            if let Some(secret) = args.secret {
+                req = req.with_secret_token(secret);
+            }
+            print(&api.set_webhook(&req).await?)
+        }
+        Command::DeleteWebhook(args) => {
+            let req = DeleteWebhookRequest {
+                drop_pending_updates: Some(args.drop_pending_updates),
+            };
+            print(&api.delete_webhook(&req).await?)
+        }
+        Command::WebhookInfo(_) => print(&api.get_webhook_info().await?),
+        Command::Download(args) => {
+            let file = api.get_file(&GetFileRequest::new(args.file_id)).await?;
+            let file_path = file
+                .file_path
+                .ok_or_else(|| anyhow!("Telegram didn't return a path for {}", file.file_id))?;
+            let output = match args.output {
+                Some(output) => output,
+                None => PathBuf::from(file_path.rsplit('/').next().unwrap_or(&file.file_id)),
+            };
+            let data = api.download_file(&DownloadRequest::new(file_path)).await?;
+            tokio::fs::write(&output, &data).await?;
+            eprintln!("Saved {} bytes to {}", data.len(), output.display());
+            Ok(())
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args: Args = argh::from_env();
+    let Some(token) = args.token.or_else(|| env::var("TELEGRAM_TOKEN").ok()) else {
+        eprintln!("Pass --token, or set TELEGRAM_TOKEN");
+        std::process::exit(2);
+    };
+
+    let mut client = Client::new(token);
+    if let Some(server) = args.server {
+        client = client.with_server_url(server);
+    }
+    if let Err(err) = run(&API::new(client), args.command).await {
+        eprintln!("Error: {:#}", err);
+        std::process::exit(1);
+    }
+}
@@ -1,6 +1,5 @@
 /// This is a simple ping bot that responds to every message with a sticker and
 /// a text message.
-
 #[macro_use]
 extern crate log;
 
@@ -1,5 +1,4 @@
 /// This is a simple bot that replies to every message with the current uptime.
-
 #[macro_use]
 extern crate log;
 
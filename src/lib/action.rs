@@ -1,4 +1,10 @@
+use std::ops::ControlFlow;
+
 /// `Action` represents an action to take after handling a chat event.
+///
+/// [`Action::Next`] and [`Action::Done`] control whether the handlers after this one see the
+/// event. Handlers that prefer [`ControlFlow`] can return `ControlFlow::Continue(()).into()`
+/// and `ControlFlow::Break(()).into()` instead.
 #[derive(Debug, Clone)]
 pub enum Action {
     /// Continue to the next handler.
@@ -16,5 +22,15 @@ pub enum Action {
     ReplyMarkdown(String),
 
     /// Reply to the message with the given sticker and stop running handlers.
+    #[cfg(feature = "stickers")]
     ReplySticker(String),
 }
+
+impl From<ControlFlow<()>> for Action {
+    fn from(flow: ControlFlow<()>) -> Self {
+        match flow {
+            ControlFlow::Continue(()) => Action::Next,
+            ControlFlow::Break(()) => Action::Done,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+/// Media group (album) aggregation. Telegram delivers each item of an album as a separate
+/// update sharing a `media_group_id`. [`AlbumBuffer`] holds the parts back until no more have
+/// arrived for a short window, and then hands them over as a single album.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{api, clock::Clock};
+
+/// Called with a completed album: the parts in order, as received.
+pub(crate) type AlbumHandler = Arc<dyn Fn(Vec<api::Update>) + Send + Sync>;
+
+/// Album parts received so far, keyed by chat ID and media group ID.
+type Pending = Arc<Mutex<HashMap<(i64, String), Vec<api::Update>>>>;
+
+pub(crate) struct AlbumBuffer {
+    /// How long to wait after the most recent part before the album is considered complete.
+    window: Duration,
+
+    pending: Pending,
+
+    on_album: AlbumHandler,
+
+    /// Times the window.
+    clock: Arc<dyn Clock>,
+}
+
+impl AlbumBuffer {
+    pub(crate) fn new(window: Duration, on_album: AlbumHandler, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            window,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            on_album,
+            clock,
+        }
+    }
+
+    /// Buffer `update` if it's part of an album, or return it if it isn't.
+    pub(crate) fn push(&self, update: api::Update) -> Option<api::Update> {
+        let Some(key) = update
+            .message
+            .as_ref()
+            .or(update.channel_post.as_ref())
+            .and_then(|m| Some((m.chat.id, m.media_group_id.clone()?)))
+        else {
+            return Some(update);
+        };
+
+        let mut pending = self.pending.lock().unwrap();
+        let parts = pending.entry(key.clone()).or_default();
+        parts.push(update);
+
+        // The first part starts a timer that waits for the album to stop growing.
+        if parts.len() == 1 {
+            let pending = Arc::clone(&self.pending);
+            let on_album = Arc::clone(&self.on_album);
+            let (window, clock) = (self.window, Arc::clone(&self.clock));
+            let mut sleep = clock.sleep(window);
+
+            tokio::spawn(async move {
+                let mut seen = 1;
+                loop {
+                    sleep.await;
+                    sleep = clock.sleep(window);
+
+                    let mut pending = pending.lock().unwrap();
+                    let count = pending.get(&key).map_or(0, Vec::len);
+                    if count == seen {
+                        let mut parts = pending.remove(&key).unwrap_or_default();
+                        drop(pending);
+
+                        parts.sort_by_key(|u| u.update_id);
+                        on_album(parts);
+                        return;
+                    }
+                    seen = count;
+                }
+            });
+        }
+
+        None
+    }
+}
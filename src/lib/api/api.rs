@@ -1,20 +1,77 @@
+use std::{sync::Arc, time::Duration};
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use tokio::sync::OnceCell;
+
+use crate::{
+    audit::AuditSink,
+    clock::{Clock, SystemClock},
+    Client,
+};
 
-use crate::Client;
+use super::User;
 
 /// This is the main Telegram API client. Requires an instance of `Client` initialized
 /// with a valid API token.
+///
+/// `API` is cheap to clone, and clones share the same connection pool, settings, and cached
+/// state, so a clone can be moved into a background task spawned from a handler.
+#[derive(Clone)]
 pub struct API {
     /// The underlying HTTP client.
-    pub client: Client,
+    pub client: Arc<Client>,
+
+    /// If set, moderation actions (bans, restrictions, promotions, permission changes) are
+    /// recorded here.
+    pub(crate) audit: Option<Arc<dyn AuditSink>>,
+
+    /// The time source used for timestamps and `until_date` computations.
+    pub(crate) clock: Arc<dyn Clock>,
+
+    /// The bot's own user, cached on first use. See [`API::me`].
+    pub(crate) me: Arc<OnceCell<User>>,
 }
 
 impl API {
     /// Returns a new Telegram API client.
     pub fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client: Arc::new(client),
+            audit: None,
+            clock: Arc::new(SystemClock),
+            me: Arc::new(OnceCell::new()),
+        }
+    }
+
+    /// Record all moderation actions made through this API to `sink`. See [`crate::audit`].
+    pub fn with_audit_sink(mut self, sink: impl AuditSink + 'static) -> Self {
+        self.audit = Some(Arc::new(sink));
+        self
+    }
+
+    /// Use `clock` as the time source, instead of the system clock. Useful in tests, with a
+    /// [`crate::clock::FakeClock`].
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Return the clock used by this API.
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Return the current time in Unix time, according to the API's clock.
+    pub fn now(&self) -> i64 {
+        self.clock.timestamp()
+    }
+
+    /// Return the Unix time `duration` from now, suitable for `until_date` fields in ban and
+    /// restrict requests.
+    pub fn until_date(&self, duration: Duration) -> i64 {
+        self.now() + duration.as_secs() as i64
     }
 }
 
@@ -32,6 +89,104 @@ pub enum ApiError {
 
     #[error("No result")]
     NoResult,
+
+    /// The call for this method ran past its deadline. See [`crate::CallOptions`].
+    #[error("{0} timed out")]
+    Timeout(String),
+
+    /// The call for this method was cancelled. See [`crate::CallOptions`].
+    #[error("{0} was cancelled")]
+    Cancelled(String),
+}
+
+impl ApiError {
+    /// Returns true if Telegram rejected the request because the bot lacks the rights for it
+    /// (e.g., it isn't an administrator, or can't restrict members). Retrying won't help.
+    pub fn is_permission_error(&self) -> bool {
+        let ApiError::AppError(description) = self else {
+            return false;
+        };
+
+        let description = description.to_lowercase();
+        description.starts_with("forbidden")
+            || description.contains("not enough rights")
+            || description.contains("chat_admin_required")
+            || description.contains("need administrator rights")
+            || description.contains("not an administrator")
+    }
+
+    /// Returns true if Telegram rejected the request with `409 Conflict`, e.g. because another
+    /// instance of the bot is polling `getUpdates`, or a webhook is set.
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, ApiError::AppError(description)
+            if description.to_lowercase().starts_with("conflict"))
+    }
+
+    /// Returns true if Telegram rejected the request with `429 Too Many Requests`.
+    pub fn is_rate_limit(&self) -> bool {
+        matches!(self, ApiError::AppError(description)
+            if description.to_lowercase().starts_with("too many requests"))
+    }
+
+    /// Returns how long Telegram asked the bot to wait before retrying, for rate limit errors.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let ApiError::AppError(description) = self else {
+            return None;
+        };
+
+        let description = description.to_lowercase();
+        let (_, secs) = description.split_once("retry after ")?;
+        let secs = secs.split(|c: char| !c.is_ascii_digit()).next()?;
+        secs.parse().ok().map(Duration::from_secs)
+    }
+}
+
+/// `FailedRequest` is attached as context to errors from API calls made by the convenience
+/// helpers on [`crate::Event`] and by the router's replies, so the error shows exactly what was
+/// sent. Its message includes the underlying error's, and the underlying error (e.g., an
+/// [`ApiError`]) can still be downcast as usual.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # async fn handler(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+/// if let Err(err) = e.send_message("Hello").await {
+///     if let Some(failed) = err.downcast_ref::<api::FailedRequest>() {
+///         eprintln!("{} failed: {}", failed.method, failed.request);
+///     }
+/// }
+/// # Ok(Action::Done)
+/// # }
+/// ```
+#[derive(Error, Debug, Clone)]
+#[error("{error} (request: {method} {request})")]
+pub struct FailedRequest {
+    /// The API method, e.g. `sendMessage`.
+    pub method: String,
+
+    /// The request, as it was serialized.
+    pub request: serde_json::Value,
+
+    /// The underlying error's message.
+    pub error: String,
+}
+
+impl FailedRequest {
+    pub fn new(method: impl Into<String>, request: &impl Serialize, error: impl ToString) -> Self {
+        Self {
+            method: method.into(),
+            request: serde_json::to_value(request).unwrap_or_default(),
+            error: error.to_string(),
+        }
+    }
+}
+
+/// Attach the request for `method` to the error in `result`, if there is one. See
+/// [`FailedRequest`].
+pub fn with_request<T>(result: Result<T>, method: &str, request: &impl Serialize) -> Result<T> {
+    result.map_err(|err| {
+        let failed = FailedRequest::new(method, request, &err);
+        err.context(failed)
+    })
 }
 
 /// This is a wrapper around the Telegram API response. If `ok` is `true`, then
@@ -55,6 +210,13 @@ impl<'de, T: Deserialize<'de>> ApiResponse<T> {
         let response: ApiResponse<T> = serde_json::from_str(data)?;
         Ok(response)
     }
+
+    /// Parses a response directly from the raw response body, without first validating and
+    /// copying it into a `String`.
+    pub fn from_slice(data: &'de [u8]) -> Result<Self> {
+        let response: ApiResponse<T> = serde_json::from_slice(data)?;
+        Ok(response)
+    }
 }
 
 impl<T> ApiResponse<T> {
@@ -101,4 +263,17 @@ impl<T> ApiResponse<T> {
 
         Ok(self.result.as_ref().unwrap())
     }
+
+    /// Like [`ApiResponse::result`], but consumes the response and returns the result by value.
+    pub fn into_result(self) -> Result<T> {
+        if !self.ok {
+            return Err(ApiError::AppError(
+                self.description
+                    .unwrap_or_else(|| "No error description".to_string()),
+            )
+            .into());
+        }
+
+        self.result.ok_or_else(|| ApiError::NoResult.into())
+    }
 }
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use super::Location;
+#[cfg(feature = "stickers")]
+use super::Sticker;
+
+/// Contains information about the start page settings of a Telegram Business account.
+/// <https://core.telegram.org/bots/api#businessintro>
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BusinessIntro {
+    /// Optional. Title text of the business intro
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Optional. Message text of the business intro
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Optional. Sticker of the business intro
+    #[cfg(feature = "stickers")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sticker: Option<Sticker>,
+}
+
+/// Contains information about the location of a Telegram Business account.
+/// <https://core.telegram.org/bots/api#businesslocation>
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BusinessLocation {
+    /// Address of the business
+    pub address: String,
+
+    /// Optional. Location of the business
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+}
+
+/// Describes an interval of time during which a business is open.
+/// <https://core.telegram.org/bots/api#businessopeninghoursinterval>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BusinessOpeningHoursInterval {
+    /// The minute's sequence number in a week, starting on Monday, marking the start of the time
+    /// interval during which the business is open; 0 - 7 * 24 * 60
+    pub opening_minute: i64,
+
+    /// The minute's sequence number in a week, starting on Monday, marking the end of the time
+    /// interval during which the business is open; 0 - 8 * 24 * 60
+    pub closing_minute: i64,
+}
+
+/// Describes the opening hours of a business.
+/// <https://core.telegram.org/bots/api#businessopeninghours>
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct BusinessOpeningHours {
+    /// Unique name of the time zone for which the opening hours are defined
+    pub time_zone_name: String,
+
+    /// List of time intervals describing business opening hours
+    pub opening_hours: Vec<BusinessOpeningHoursInterval>,
+}
+
+impl BusinessOpeningHours {
+    /// Returns true if the business is open at `minute_of_week`, counted from Monday 00:00 in
+    /// the business's time zone. Intervals may run past the end of the week (up to
+    /// 8 * 24 * 60), wrapping around into Monday.
+    pub fn is_open(&self, minute_of_week: i64) -> bool {
+        const WEEK: i64 = 7 * 24 * 60;
+        let minute = minute_of_week.rem_euclid(WEEK);
+
+        self.opening_hours.iter().any(|interval| {
+            (interval.opening_minute..interval.closing_minute).contains(&minute)
+                || (interval.opening_minute..interval.closing_minute).contains(&(minute + WEEK))
+        })
+    }
+}
@@ -1,7 +1,87 @@
+use super::user::User;
+use super::{ApiError, API};
+#[cfg(feature = "business")]
+use super::{BusinessIntro, BusinessLocation, BusinessOpeningHours};
+use crate::RateLimiter;
 use mobot_derive::BotRequest;
 use serde::{Deserialize, Serialize};
-use super::user::User;
-use super::API;
+use std::{future::Future, time::Duration};
+
+/// The type of a chat.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatType {
+    #[default]
+    Private,
+    Group,
+    Supergroup,
+    Channel,
+
+    /// A chat type this version of mobot doesn't know about, with its name in the Bot API.
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl ChatType {
+    /// Returns the type's name in the Bot API, e.g. "supergroup".
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Private => "private",
+            Self::Group => "group",
+            Self::Supergroup => "supergroup",
+            Self::Channel => "channel",
+            Self::Unknown(name) => name,
+        }
+    }
+
+    pub fn is_private(&self) -> bool {
+        *self == Self::Private
+    }
+
+    /// Returns true for groups and supergroups.
+    pub fn is_group(&self) -> bool {
+        matches!(self, Self::Group | Self::Supergroup)
+    }
+
+    pub fn is_channel(&self) -> bool {
+        *self == Self::Channel
+    }
+}
+
+impl std::fmt::Display for ChatType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for ChatType {
+    type Err = std::convert::Infallible;
+
+    /// Parse a Bot API chat type. Unrecognized types parse as [`ChatType::Unknown`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "private" => Self::Private,
+            "group" => Self::Group,
+            "supergroup" => Self::Supergroup,
+            "channel" => Self::Channel,
+            _ => Self::Unknown(s.to_string()),
+        })
+    }
+}
+
+/// Chat types compare equal to their Bot API names, so code written when `chat_type` was a
+/// string (e.g., `chat.chat_type == "private"`) keeps working.
+impl PartialEq<str> for ChatType {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ChatType {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Chat {
@@ -10,7 +90,7 @@ pub struct Chat {
 
     /// Type of chat, can be either “private”, “group”, “supergroup” or “channel”
     #[serde(rename = "type")]
-    pub chat_type: String,
+    pub chat_type: ChatType,
 
     /// Title, for supergroups, channels and group chats
     pub title: Option<String>,
@@ -32,11 +112,26 @@ pub struct Chat {
     pub is_forum: Option<bool>,
 }
 
+impl Chat {
+    pub fn is_private(&self) -> bool {
+        self.chat_type.is_private()
+    }
+
+    /// Returns true for groups and supergroups.
+    pub fn is_group(&self) -> bool {
+        self.chat_type.is_group()
+    }
+
+    pub fn is_channel(&self) -> bool {
+        self.chat_type.is_channel()
+    }
+}
+
 impl<T: Into<String>> From<T> for Chat {
     fn from(s: T) -> Self {
         let from = s.into();
         Self {
-            chat_type: "private".to_string(),
+            chat_type: ChatType::Private,
             username: Some(from.clone()),
             first_name: Some(from),
             ..Default::default()
@@ -90,7 +185,11 @@ impl SendChatActionRequest {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The request rate used by [`API::ban_many`] and [`API::restrict_many`] when the client has
+/// no [`RateLimiter`] of its own.
+pub const BULK_RATE_PER_SECOND: u32 = 10;
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct ChatPermissions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub can_send_messages: Option<bool>,
@@ -135,6 +234,39 @@ pub struct ChatPermissions {
     pub can_manage_topics: Option<bool>
 }
 
+impl ChatPermissions {
+    /// Every permission set to `allowed`.
+    fn with_all(allowed: bool) -> Self {
+        Self {
+            can_send_messages: Some(allowed),
+            can_send_audios: Some(allowed),
+            can_send_documents: Some(allowed),
+            can_send_photos: Some(allowed),
+            can_send_videos: Some(allowed),
+            can_send_video_notes: Some(allowed),
+            can_send_voice_notes: Some(allowed),
+            can_send_polls: Some(allowed),
+            can_send_other_messages: Some(allowed),
+            can_add_web_page_previews: Some(allowed),
+            can_change_info: Some(allowed),
+            can_invite_users: Some(allowed),
+            can_pin_messages: Some(allowed),
+            can_manage_topics: Some(allowed),
+        }
+    }
+
+    /// All permissions granted. Restricting a member with these lifts their restrictions
+    /// (the chat's default permissions still apply).
+    pub fn all() -> Self {
+        Self::with_all(true)
+    }
+
+    /// All permissions denied.
+    pub fn none() -> Self {
+        Self::with_all(false)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, BotRequest)]
 pub struct SetChatPermissionRequest {
     /// Unique identifier for the target chat or username of the target supergroup (in the format @supergroupusername)
@@ -198,9 +330,92 @@ impl GetChatAdministratorsRequest {
     }
 }
 
+/// A chat member's status.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatMemberStatus {
+    /// The chat's owner
+    Creator,
+    Administrator,
+    #[default]
+    Member,
+    Restricted,
+    Left,
+
+    /// Banned
+    Kicked,
+
+    /// A status this version of mobot doesn't know about.
+    #[serde(other)]
+    Unknown,
+}
+
+impl ChatMemberStatus {
+    /// Returns the status's name in the Bot API, e.g. "administrator".
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Creator => "creator",
+            Self::Administrator => "administrator",
+            Self::Member => "member",
+            Self::Restricted => "restricted",
+            Self::Left => "left",
+            Self::Kicked => "kicked",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl std::fmt::Display for ChatMemberStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// The Bot API's names for statuses, which `ChatMember::status` and
+// `ChatMemberAdministrator::status` still hold until they're removed.
+
+impl From<&str> for ChatMemberStatus {
+    fn from(s: &str) -> Self {
+        match s {
+            "creator" => Self::Creator,
+            "administrator" => Self::Administrator,
+            "member" => Self::Member,
+            "restricted" => Self::Restricted,
+            "left" => Self::Left,
+            "kicked" => Self::Kicked,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl From<String> for ChatMemberStatus {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<ChatMemberStatus> for String {
+    fn from(status: ChatMemberStatus) -> Self {
+        status.as_str().to_string()
+    }
+}
+
+impl PartialEq<str> for ChatMemberStatus {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ChatMemberStatus {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct ChatMemberAdministrator {
-    /// The member's status in the chat, always “administrator”
+    /// The member's status in the chat, always "administrator" (or "creator" for the owner).
+    #[deprecated(note = "use `status()`, which returns a `ChatMemberStatus`")]
     pub status: String,
     /// Information about the user
     pub user: User,
@@ -256,6 +471,21 @@ impl GetChatRequest {
     }
 }
 
+/// Describes the birthdate of a user.
+/// <https://core.telegram.org/bots/api#birthdate>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Birthdate {
+    /// Day of the user's birth; 1-31
+    pub day: u32,
+
+    /// Month of the user's birth; 1-12
+    pub month: u32,
+
+    /// Optional. Year of the user's birth
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+}
+
 /// This object contains full information about a chat.
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct ChatFullInfo {
@@ -264,7 +494,7 @@ pub struct ChatFullInfo {
     pub id: i64,
     /// Type of chat, can be either “private”, “group”, “supergroup” or “channel”
     #[serde(rename = "type")]
-    pub type_: String,
+    pub type_: ChatType,
     /// Title for supergroups, channels and group chats
     pub title: Option<String>,
     /// Username, for private chats, supergroups and channels if available
@@ -276,13 +506,54 @@ pub struct ChatFullInfo {
     /// True, if the supergroup is a forum (has topics enabled)
     pub is_forum: Option<bool>,
     /// Identifier of the accent color for the chat name and backgrounds of the chat photo, reply header, and link preview. See accent colors for more details.
+    #[serde(alias = "accent_color_id")]
     pub accent_color: Option<i64>,
+    /// Custom emoji identifier of the emoji chosen by the chat for the reply header and link preview background
+    pub background_custom_emoji_id: Option<String>,
+    /// Identifier of the accent color for the chat's profile background. See profile accent colors for more details.
+    pub profile_accent_color_id: Option<i64>,
+    /// Custom emoji identifier of the emoji chosen by the chat for its profile background
+    pub profile_background_custom_emoji_id: Option<String>,
     /// The maximum number of reactons that can be set on a message in the chat.
     pub max_reaction_count: Option<i64>,
     /// If non-empty, the list of all active chat usernames; for private chats, supergroups, and channels
     pub active_usernames: Option<Vec<String>>,
     /// Description, for groups, supergroups and channel chats
     pub description: Option<String>,
+    /// Default chat member permissions, for groups and supergroups
+    pub permissions: Option<ChatPermissions>,
+    /// Custom emoji identifier of the emoji status of the chat or the other party in a private chat
+    pub emoji_status_custom_emoji_id: Option<String>,
+    /// Expiration date of the emoji status of the chat or the other party in a private chat, in Unix time, if any
+    pub emoji_status_expiration_date: Option<i64>,
+    /// For private chats, the date of birth of the user
+    pub birthdate: Option<Birthdate>,
+    /// For private chats with business accounts, the intro of the business
+    #[cfg(feature = "business")]
+    pub business_intro: Option<BusinessIntro>,
+    /// For private chats with business accounts, the location of the business
+    #[cfg(feature = "business")]
+    pub business_location: Option<BusinessLocation>,
+    /// For private chats with business accounts, the opening hours of the business
+    #[cfg(feature = "business")]
+    pub business_opening_hours: Option<BusinessOpeningHours>,
+    /// For private chats, the personal channel of the user
+    pub personal_chat: Option<Chat>,
+}
+
+impl ChatFullInfo {
+    pub fn is_private(&self) -> bool {
+        self.type_.is_private()
+    }
+
+    /// Returns true for groups and supergroups.
+    pub fn is_group(&self) -> bool {
+        self.type_.is_group()
+    }
+
+    pub fn is_channel(&self) -> bool {
+        self.type_.is_channel()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, BotRequest)]
@@ -334,6 +605,28 @@ impl AnswerChatJoinRequest {
     }
 }
 
+/// Represents a join request sent to a chat.
+/// <https://core.telegram.org/bots/api#chatjoinrequest>
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatJoinRequest {
+    /// Chat to which the request was sent
+    pub chat: Chat,
+
+    /// User that sent the join request
+    pub from: User,
+
+    /// Identifier of a private chat with the user who sent the join request. The bot can use
+    /// this identifier for 5 minutes to send messages until the join request is processed.
+    pub user_chat_id: i64,
+
+    /// Date the request was sent in Unix time
+    pub date: i64,
+
+    /// Optional. Bio of the user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bio: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, BotRequest)]
 pub struct UnbanChatMemberRequest {
     /// Unique identifier for the target chat or username of the target supergroup (in the format @supergroupusername)
@@ -357,7 +650,76 @@ impl UnbanChatMemberRequest {
     }
 }
 
-#[derive(Debug, Clone, Serialize, BotRequest)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize, BotRequest)]
+pub struct PromoteChatMemberRequest {
+    /// Unique identifier for the target chat or username of the target channel (in the format @channelusername)
+    pub chat_id: String,
+
+    /// Unique identifier of the target user
+    pub user_id: i64,
+
+    /// Pass True if the administrator's presence in the chat is hidden
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_anonymous: Option<bool>,
+
+    /// Pass True if the administrator can access the chat event log, get boost list, see hidden supergroup and channel members,
+    /// report spam messages and ignore slow mode. Implied by any other administrator privilege.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_manage_chat: Option<bool>,
+
+    /// Pass True if the administrator can delete messages of other users
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_delete_messages: Option<bool>,
+
+    /// Pass True if the administrator can manage video chats
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_manage_video_chats: Option<bool>,
+
+    /// Pass True if the administrator can restrict, ban or unban chat members, or access supergroup statistics
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_restrict_members: Option<bool>,
+
+    /// Pass True if the administrator can add new administrators with a subset of their own privileges or demote administrators
+    /// that they have promoted, directly or indirectly (promoted by administrators that were appointed by them)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_promote_members: Option<bool>,
+
+    /// Pass True if the administrator can change chat title, photo and other settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_change_info: Option<bool>,
+
+    /// Pass True if the administrator can invite new users to the chat
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_invite_users: Option<bool>,
+
+    /// Pass True if the administrator can post messages in the channel, or access channel statistics; for channels only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_post_messages: Option<bool>,
+
+    /// Pass True if the administrator can edit messages of other users and can pin messages; for channels only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_edit_messages: Option<bool>,
+
+    /// Pass True if the administrator can pin messages; for supergroups only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_pin_messages: Option<bool>,
+
+    /// Pass True if the user is allowed to create, rename, close, and reopen forum topics; for supergroups only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub can_manage_topics: Option<bool>,
+}
+
+impl PromoteChatMemberRequest {
+    pub fn new(chat_id: String, user_id: i64) -> Self {
+        Self {
+            chat_id,
+            user_id,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, BotRequest)]
 pub struct GetChatMemberRequest {
     /// Unique identifier for the target chat or username of the target supergroup or channel in the format @username
     pub chat_id: String,
@@ -377,7 +739,8 @@ impl GetChatMemberRequest {
 
 #[derive(Debug, Clone, Serialize, Deserialize, BotRequest)]
 pub struct ChatMember {
-    /// The member's status in the chat. Can be: "creator", "administrator", "member", "restricted", "left" or "kicked"
+    /// The member's status in the chat
+    #[deprecated(note = "use `status()`, which returns a `ChatMemberStatus`")]
     pub status: String,
 
     /// Information about the user
@@ -418,6 +781,43 @@ pub struct ChatMember {
     pub is_member: Option<bool>,
 }
 
+impl ChatMemberAdministrator {
+    /// The member's status in the chat, always [`ChatMemberStatus::Administrator`] (or
+    /// [`ChatMemberStatus::Creator`] for the owner).
+    #[allow(deprecated)]
+    pub fn status(&self) -> ChatMemberStatus {
+        self.status.as_str().into()
+    }
+
+    #[allow(deprecated)]
+    pub fn with_status(mut self, status: ChatMemberStatus) -> Self {
+        self.status = status.into();
+        self
+    }
+}
+
+impl ChatMember {
+    /// The member's status in the chat.
+    #[allow(deprecated)]
+    pub fn status(&self) -> ChatMemberStatus {
+        self.status.as_str().into()
+    }
+
+    #[allow(deprecated)]
+    pub fn with_status(mut self, status: ChatMemberStatus) -> Self {
+        self.status = status.into();
+        self
+    }
+
+    /// Returns true if the member is the chat's owner or an administrator.
+    pub fn is_admin(&self) -> bool {
+        matches!(
+            self.status(),
+            ChatMemberStatus::Creator | ChatMemberStatus::Administrator
+        )
+    }
+}
+
 /// API methods for sending, editing, set message permission, and deleting messages.
 impl API {
     /// Send a message.
@@ -429,7 +829,10 @@ impl API {
     /// The bot must be an administrator in the group or a supergroup for this to work and must have the can_restrict_members administrator rights.
     /// Returns True on success
     pub async fn set_chat_permissions(&self, req: &SetChatPermissionRequest) -> anyhow::Result<bool> {
-        self.client.post("setChatPermissions", req).await
+        let old = self.audit_permissions_snapshot(&req.chat_id).await;
+        let result = self.client.post("setChatPermissions", req).await?;
+        self.audit_record("setChatPermissions", &req.chat_id, None, old, req).await;
+        Ok(result)
     }
 
     /// Use this method to restrict a user in a supergroup.
@@ -437,7 +840,10 @@ impl API {
     /// Pass True for all permissions to lift restrictions from a user.
     /// Returns True on success.
     pub async fn restrict_chat_member(&self, req: &RestrictChatMemberRequest) -> anyhow::Result<bool> {
-        self.client.post("restrictChatMember", req).await
+        let old = self.audit_member_snapshot(&req.chat_id, req.user_id).await;
+        let result = self.client.post("restrictChatMember", req).await?;
+        self.audit_record("restrictChatMember", &req.chat_id, Some(req.user_id), old, req).await;
+        Ok(result)
     }
 
     /// Use this method to get a list of administrators in a chat, which aren't bots. Returns an Array of ChatMember objects.
@@ -456,7 +862,10 @@ impl API {
     /// So if the user is a member of the chat they will also be removed from the chat.
     /// If you don't want this, use the parameter only_if_banned. Returns True on success.
     pub async fn unban_chat_member(&self, req: &UnbanChatMemberRequest) -> anyhow::Result<bool> {
-        self.client.post("unbanChatMember", req).await
+        let old = self.audit_member_snapshot(&req.chat_id, req.user_id).await;
+        let result = self.client.post("unbanChatMember", req).await?;
+        self.audit_record("unbanChatMember", &req.chat_id, Some(req.user_id), old, req).await;
+        Ok(result)
     }
 
     /// Use this method to ban a user in a group, a supergroup or a channel.
@@ -464,7 +873,180 @@ impl API {
     /// The bot must be an administrator in the chat for this to work and must have the appropriate administrator rights.
     /// Returns True on success.
     pub async fn ban_chat_member(&self, req: &BanChatMemberRequest) -> anyhow::Result<bool> {
-        self.client.post("banChatMember", req).await
+        let old = self.audit_member_snapshot(&req.chat_id, req.user_id).await;
+        let result = self.client.post("banChatMember", req).await?;
+        self.audit_record("banChatMember", &req.chat_id, Some(req.user_id), old, req).await;
+        Ok(result)
+    }
+
+    /// Remove `user_id` from the chat without banning them: the user is banned and then
+    /// immediately unbanned, so they can rejoin with an invite link. The bot must be an
+    /// administrator with the right to restrict members. Both calls are audited separately.
+    ///
+    /// ```no_run
+    /// # use mobot::*;
+    /// # async fn kick(e: Event) -> anyhow::Result<()> {
+    /// let user_id = e.update.from_user()?.id;
+    /// e.api.kick_chat_member(e.update.chat_id()?, user_id).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn kick_chat_member(
+        &self,
+        chat_id: impl ToString,
+        user_id: i64,
+    ) -> anyhow::Result<bool> {
+        let chat_id = chat_id.to_string();
+        self.ban_chat_member(&BanChatMemberRequest::new(
+            chat_id.clone(),
+            user_id,
+            None,
+            None,
+        ))
+        .await?;
+
+        self.unban_chat_member(&UnbanChatMemberRequest::new(chat_id, user_id, Some(true)))
+            .await
+            .map_err(|err| {
+                err.context(format!(
+                    "User {} was banned, but couldn't be unbanned after the kick",
+                    user_id
+                ))
+            })
+    }
+
+    /// Mute `user_id` in a supergroup for `duration`, by denying them every permission. Pass
+    /// `None` to mute them until they're unmuted. Telegram treats durations shorter than 30
+    /// seconds or longer than 366 days as forever.
+    pub async fn mute_chat_member(
+        &self,
+        chat_id: impl ToString,
+        user_id: i64,
+        duration: Option<Duration>,
+    ) -> anyhow::Result<bool> {
+        self.restrict_chat_member(&RestrictChatMemberRequest::new(
+            chat_id.to_string(),
+            user_id,
+            ChatPermissions::none(),
+            Some(true),
+            duration.map(|d| self.until_date(d)),
+        ))
+        .await
+    }
+
+    /// Lift the restrictions on `user_id`, e.g. after [`API::mute_chat_member`].
+    pub async fn unmute_chat_member(
+        &self,
+        chat_id: impl ToString,
+        user_id: i64,
+    ) -> anyhow::Result<bool> {
+        self.restrict_chat_member(&RestrictChatMemberRequest::new(
+            chat_id.to_string(),
+            user_id,
+            ChatPermissions::all(),
+            Some(true),
+            None,
+        ))
+        .await
+    }
+
+    /// Ban every user in `user_ids` from the chat, e.g. to clean up after a raid. Returns each
+    /// user's result, in order. See [`API::restrict_many`] for how requests are paced and
+    /// errors are handled.
+    pub async fn ban_many(
+        &self,
+        chat_id: impl ToString,
+        user_ids: impl IntoIterator<Item = i64>,
+    ) -> Vec<(i64, anyhow::Result<bool>)> {
+        let chat_id = chat_id.to_string();
+        self.for_each_member(user_ids, |user_id| {
+            let req = BanChatMemberRequest::new(chat_id.clone(), user_id, None, None);
+            async move { self.ban_chat_member(&req).await }
+        })
+        .await
+    }
+
+    /// Restrict every user in `user_ids` to `permissions`, until `until_date` (or forever).
+    /// Returns each user's result, in order.
+    ///
+    /// Requests are sent one at a time, paced by the client's [`crate::RateLimiter`] (or
+    /// [`BULK_RATE_PER_SECOND`] if it has none). If Telegram answers with a permission error,
+    /// the remaining users are skipped, since every other request would fail the same way;
+    /// other errors only fail the user they're for.
+    pub async fn restrict_many(
+        &self,
+        chat_id: impl ToString,
+        user_ids: impl IntoIterator<Item = i64>,
+        permissions: ChatPermissions,
+        until_date: Option<i64>,
+    ) -> Vec<(i64, anyhow::Result<bool>)> {
+        let chat_id = chat_id.to_string();
+        self.for_each_member(user_ids, |user_id| {
+            let req = RestrictChatMemberRequest::new(
+                chat_id.clone(),
+                user_id,
+                permissions.clone(),
+                Some(true),
+                until_date,
+            );
+            async move { self.restrict_chat_member(&req).await }
+        })
+        .await
+    }
+
+    /// Run `f` for each user in turn, under the rate limiter, stopping at the first permission
+    /// error.
+    async fn for_each_member<F, Fut>(
+        &self,
+        user_ids: impl IntoIterator<Item = i64>,
+        f: F,
+    ) -> Vec<(i64, anyhow::Result<bool>)>
+    where
+        F: Fn(i64) -> Fut,
+        Fut: Future<Output = anyhow::Result<bool>>,
+    {
+        // The client's limiter, if any, already paces every request.
+        let limiter = match self.client.rate_limiter() {
+            Some(_) => None,
+            None => Some(RateLimiter::per_second(BULK_RATE_PER_SECOND)),
+        };
+
+        let mut results = vec![];
+        let mut user_ids = user_ids.into_iter();
+        for user_id in user_ids.by_ref() {
+            if let Some(limiter) = &limiter {
+                limiter.acquire().await;
+            }
+
+            let result = f(user_id).await;
+            let denied = result.as_ref().is_err_and(|err| {
+                err.downcast_ref::<ApiError>()
+                    .is_some_and(ApiError::is_permission_error)
+            });
+            results.push((user_id, result));
+
+            if denied {
+                break;
+            }
+        }
+
+        for user_id in user_ids {
+            results.push((
+                user_id,
+                Err(anyhow::anyhow!("Skipped after a permission error")),
+            ));
+        }
+        results
+    }
+
+    /// Use this method to promote or demote a user in a supergroup or a channel.
+    /// The bot must be an administrator in the chat for this to work and must have the appropriate administrator rights.
+    /// Pass False for all boolean parameters to demote a user. Returns True on success.
+    pub async fn promote_chat_member(&self, req: &PromoteChatMemberRequest) -> anyhow::Result<bool> {
+        let old = self.audit_member_snapshot(&req.chat_id, req.user_id).await;
+        let result = self.client.post("promoteChatMember", req).await?;
+        self.audit_record("promoteChatMember", &req.chat_id, Some(req.user_id), old, req).await;
+        Ok(result)
     }
 
     /// Use this method to approve a chat join request.
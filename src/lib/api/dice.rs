@@ -0,0 +1,264 @@
+use std::{fmt, str::FromStr};
+
+use mobot_derive::BotRequest;
+use serde::{Deserialize, Serialize};
+
+use super::{message::Message, ReplyParameters, API};
+
+/// An animated emoji with a random value, sent with [`API::send_dice`] or by users.
+/// <https://core.telegram.org/bots/api#dice>
+///
+/// The value is what the animation lands on, but Telegram doesn't document what it means for
+/// emoji other than 🎲. [`Dice::outcome`] decodes it:
+///
+/// ```
+/// # use mobot::api::{Dice, DiceOutcome, SlotSymbol};
+/// let dice = Dice::new("🎰", 64);
+/// assert_eq!(dice.outcome(), Some(DiceOutcome::Slots([SlotSymbol::Seven; 3])));
+/// assert!(dice.outcome().unwrap().is_win());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Dice {
+    /// Emoji on which the dice throw animation is based
+    pub emoji: String,
+
+    /// Value of the dice, 1-6 for 🎲, 🎯 and 🎳, 1-5 for 🏀 and ⚽, 1-64 for 🎰
+    pub value: i64,
+}
+
+impl Dice {
+    pub fn new(emoji: impl Into<String>, value: i64) -> Self {
+        Self {
+            emoji: emoji.into(),
+            value,
+        }
+    }
+
+    /// Returns the dice's emoji, if it's one Telegram animates.
+    pub fn kind(&self) -> Option<DiceEmoji> {
+        self.emoji.parse().ok()
+    }
+
+    /// Returns what the value means for the dice's emoji. Returns `None` for unknown emoji, or
+    /// values out of range.
+    pub fn outcome(&self) -> Option<DiceOutcome> {
+        self.kind()?.outcome(self.value)
+    }
+}
+
+/// The emoji Telegram animates as dice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiceEmoji {
+    /// 🎲, a six-sided die.
+    Dice,
+
+    /// 🎯, a dart thrown at a board.
+    Darts,
+
+    /// 🎳, a bowling ball rolled at the pins.
+    Bowling,
+
+    /// 🏀, a basketball thrown at the hoop.
+    Basketball,
+
+    /// ⚽, a football kicked at the goal.
+    Football,
+
+    /// 🎰, a slot machine with three reels.
+    SlotMachine,
+}
+
+impl DiceEmoji {
+    pub const ALL: [DiceEmoji; 6] = [
+        DiceEmoji::Dice,
+        DiceEmoji::Darts,
+        DiceEmoji::Bowling,
+        DiceEmoji::Basketball,
+        DiceEmoji::Football,
+        DiceEmoji::SlotMachine,
+    ];
+
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            DiceEmoji::Dice => "🎲",
+            DiceEmoji::Darts => "🎯",
+            DiceEmoji::Bowling => "🎳",
+            DiceEmoji::Basketball => "🏀",
+            DiceEmoji::Football => "⚽",
+            DiceEmoji::SlotMachine => "🎰",
+        }
+    }
+
+    /// The largest value the emoji can land on. Values start at 1.
+    pub fn max_value(&self) -> i64 {
+        match self {
+            DiceEmoji::Dice | DiceEmoji::Darts | DiceEmoji::Bowling => 6,
+            DiceEmoji::Basketball | DiceEmoji::Football => 5,
+            DiceEmoji::SlotMachine => 64,
+        }
+    }
+
+    /// Returns what `value` means for this emoji, or `None` if it's out of range.
+    pub fn outcome(&self, value: i64) -> Option<DiceOutcome> {
+        if !(1..=self.max_value()).contains(&value) {
+            return None;
+        }
+
+        let outcome = match (self, value) {
+            (DiceEmoji::Dice, value) => DiceOutcome::Roll(value as u8),
+            (DiceEmoji::Darts, 1) => DiceOutcome::Miss,
+            (DiceEmoji::Darts, 6) => DiceOutcome::Bullseye,
+            (DiceEmoji::Darts, value) => DiceOutcome::Ring(6 - value as u8),
+            (DiceEmoji::Bowling, 1) => DiceOutcome::Miss,
+            (DiceEmoji::Bowling, 2) => DiceOutcome::Pins(1),
+            (DiceEmoji::Bowling, 6) => DiceOutcome::Strike,
+            (DiceEmoji::Bowling, value) => DiceOutcome::Pins(value as u8),
+            (DiceEmoji::Basketball, 4..=5) => DiceOutcome::Score,
+            (DiceEmoji::Football, 3..=5) => DiceOutcome::Score,
+            (DiceEmoji::Basketball | DiceEmoji::Football, _) => DiceOutcome::Miss,
+            (DiceEmoji::SlotMachine, value) => {
+                let reel = |n: i64| SlotSymbol::ALL[((value - 1) >> (2 * n) & 3) as usize];
+                DiceOutcome::Slots([reel(0), reel(1), reel(2)])
+            }
+        };
+        Some(outcome)
+    }
+}
+
+impl fmt::Display for DiceEmoji {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.emoji())
+    }
+}
+
+impl FromStr for DiceEmoji {
+    type Err = anyhow::Error;
+
+    /// Parse the emoji, with or without a trailing variation selector.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let emoji = s.trim().trim_end_matches('\u{fe0f}');
+        DiceEmoji::ALL
+            .into_iter()
+            .find(|dice| dice.emoji() == emoji)
+            .ok_or_else(|| anyhow::anyhow!("Not a dice emoji: {}", s))
+    }
+}
+
+/// A symbol on a slot machine reel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlotSymbol {
+    Bar,
+    Grapes,
+    Lemon,
+    Seven,
+}
+
+impl SlotSymbol {
+    /// The symbols in the order the value encodes them.
+    pub const ALL: [SlotSymbol; 4] = [
+        SlotSymbol::Bar,
+        SlotSymbol::Grapes,
+        SlotSymbol::Lemon,
+        SlotSymbol::Seven,
+    ];
+
+    /// An emoji that looks like the symbol, for replies.
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            SlotSymbol::Bar => "🍫",
+            SlotSymbol::Grapes => "🍇",
+            SlotSymbol::Lemon => "🍋",
+            SlotSymbol::Seven => "7️⃣",
+        }
+    }
+}
+
+/// What a dice value means for its emoji. See [`DiceEmoji::outcome`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiceOutcome {
+    /// 🎲: the number rolled, 1-6.
+    Roll(u8),
+
+    /// 🎯 missed the board, 🎳 missed all the pins, or 🏀 or ⚽ missed.
+    Miss,
+
+    /// 🎯 hit a ring, counted from the outermost (1) to the one around the bullseye (4).
+    Ring(u8),
+
+    /// 🎯 hit the bullseye.
+    Bullseye,
+
+    /// 🎳 knocked down some of the pins, but not all of them.
+    Pins(u8),
+
+    /// 🎳 knocked down all the pins.
+    Strike,
+
+    /// 🏀 or ⚽ scored.
+    Score,
+
+    /// 🎰 stopped on these symbols, from the left reel to the right.
+    Slots([SlotSymbol; 3]),
+}
+
+impl DiceOutcome {
+    /// Returns true for the best outcome of games that can be won: a bullseye, a strike, a
+    /// score, or three of a kind on the slot machine. 🎲 has no winning roll.
+    pub fn is_win(&self) -> bool {
+        match self {
+            DiceOutcome::Bullseye | DiceOutcome::Strike | DiceOutcome::Score => true,
+            DiceOutcome::Slots([left, center, right]) => left == center && center == right,
+            _ => false,
+        }
+    }
+
+    /// Returns true for three sevens on the slot machine.
+    pub fn is_jackpot(&self) -> bool {
+        *self == DiceOutcome::Slots([SlotSymbol::Seven; 3])
+    }
+}
+
+#[derive(Debug, Serialize, Clone, BotRequest)]
+pub struct SendDiceRequest {
+    /// Unique identifier for the target chat
+    pub chat_id: i64,
+
+    /// Emoji on which the dice throw animation is based
+    pub emoji: String,
+
+    /// Sends the message silently. Users will receive a notification with no sound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_notification: Option<bool>,
+
+    /// If the message is a reply, ID of the original message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_parameters: Option<ReplyParameters>,
+}
+
+impl SendDiceRequest {
+    pub fn new(chat_id: i64, emoji: DiceEmoji) -> Self {
+        Self {
+            chat_id,
+            emoji: emoji.emoji().to_string(),
+            disable_notification: None,
+            reply_parameters: None,
+        }
+    }
+
+    pub fn with_disable_notification(mut self, disable_notification: bool) -> Self {
+        self.disable_notification = Some(disable_notification);
+        self
+    }
+
+    pub fn with_reply_parameters(mut self, reply_parameters: ReplyParameters) -> Self {
+        self.reply_parameters = Some(reply_parameters);
+        self
+    }
+}
+
+impl API {
+    /// Send an animated dice. The result is in the returned message's `dice`.
+    pub async fn send_dice(&self, req: &SendDiceRequest) -> anyhow::Result<Message> {
+        self.client.post("sendDice", req).await
+    }
+}
@@ -1,8 +1,11 @@
+use std::path::Path;
+
+use anyhow::anyhow;
 use bytes;
 use mobot_derive::BotRequest;
 use serde::{Deserialize, Serialize};
 
-use super::API;
+use super::{InputFile, Message, SendDocumentRequest, SendPhotoRequest, SendVideoRequest, API};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct File {
@@ -48,4 +51,43 @@ impl API {
     pub async fn download_file(&self, req: &DownloadRequest) -> anyhow::Result<bytes::Bytes> {
         self.client.download_file(&req.file_path).await
     }
+
+    /// Download the file `file_id` and send it to `chat_id` as a new upload. Use this when
+    /// forwarding or copying isn't possible, e.g. for messages with protected content, or to
+    /// pass a file to another bot, since file IDs only work for the bot that received them.
+    /// Photos and videos are sent as such, and anything else as a document. With a local Bot
+    /// API server, the file is passed by path, and not downloaded.
+    pub async fn reupload(&self, file_id: &str, chat_id: i64) -> anyhow::Result<Message> {
+        let file = self.get_file(&GetFileRequest::new(file_id.into())).await?;
+        let file_path = file
+            .file_path
+            .ok_or_else(|| anyhow!("No file path for {}", file_id))?;
+
+        let input = if self.client.is_local_mode() && Path::new(&file_path).is_absolute() {
+            InputFile::path(&file_path)
+        } else {
+            let name = file_path.rsplit('/').next().unwrap_or(&file_path);
+            let data = self
+                .download_file(&DownloadRequest::new(file_path.clone()))
+                .await?;
+            InputFile::new(name, data)
+        };
+
+        // Files are stored by type, e.g. `photos/file_0.jpg`.
+        let kind = file_path.rsplit('/').nth(1).unwrap_or_default();
+        match kind {
+            "photos" => {
+                self.send_photo_file(&SendPhotoRequest::new(chat_id, ""), &input)
+                    .await
+            }
+            "videos" => {
+                self.send_video_file(&SendVideoRequest::new(chat_id, ""), &input)
+                    .await
+            }
+            _ => {
+                self.send_document_file(&SendDocumentRequest::new(chat_id, ""), &input)
+                    .await
+            }
+        }
+    }
 }
@@ -0,0 +1,309 @@
+use mobot_derive::BotRequest;
+use serde::{Deserialize, Serialize};
+
+use super::{message::Message, InputFile, ParseMode, ReplyMarkup, ReplyParameters, API};
+
+/// Use this method to send photos. On success, the sent Message is returned.
+/// <https://core.telegram.org/bots/api#sendphoto>
+#[derive(Debug, Clone, Default, Deserialize, Serialize, BotRequest)]
+pub struct SendPhotoRequest {
+    /// Unique identifier for the target chat
+    pub chat_id: i64,
+
+    /// Photo to send. Pass a file_id to send a photo that exists on the Telegram servers, or an
+    /// HTTP URL for Telegram to get a photo from the Internet.
+    pub photo: String,
+
+    /// Photo caption, 0-1024 characters after entities parsing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+
+    /// Mode for parsing entities in the photo caption
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_mode: Option<ParseMode>,
+
+    /// Pass True, if the caption must be shown above the message media
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_caption_above_media: Option<bool>,
+
+    /// Pass True if the photo needs to be covered with a spoiler animation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_spoiler: Option<bool>,
+
+    /// Sends the message silently. Users will receive a notification with no sound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_notification: Option<bool>,
+
+    /// Unique identifier of the message effect to be added to the message; for private chats only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_effect_id: Option<String>,
+
+    /// Description of the message to reply to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_parameters: Option<ReplyParameters>,
+
+    /// Additional interface options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<ReplyMarkup>,
+}
+
+impl SendPhotoRequest {
+    pub fn new(chat_id: i64, photo: impl Into<String>) -> Self {
+        Self {
+            chat_id,
+            photo: photo.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    pub fn with_parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = Some(parse_mode);
+        self
+    }
+
+    pub fn with_show_caption_above_media(mut self, show_caption_above_media: bool) -> Self {
+        self.show_caption_above_media = Some(show_caption_above_media);
+        self
+    }
+
+    /// Cover the photo with a spoiler animation, e.g. for NSFW content.
+    pub fn with_spoiler(mut self, has_spoiler: bool) -> Self {
+        self.has_spoiler = Some(has_spoiler);
+        self
+    }
+
+    /// Add the message effect `message_effect_id` (e.g. confetti) to the message. Effects are
+    /// only shown in private chats.
+    pub fn with_message_effect_id(mut self, message_effect_id: impl Into<String>) -> Self {
+        self.message_effect_id = Some(message_effect_id.into());
+        self
+    }
+
+    pub fn with_reply_parameters(mut self, reply_parameters: ReplyParameters) -> Self {
+        self.reply_parameters = Some(reply_parameters);
+        self
+    }
+
+    pub fn with_reply_markup(mut self, reply_markup: ReplyMarkup) -> Self {
+        self.reply_markup = Some(reply_markup);
+        self
+    }
+}
+
+/// Use this method to send video files. On success, the sent Message is returned.
+/// <https://core.telegram.org/bots/api#sendvideo>
+#[derive(Debug, Clone, Default, Deserialize, Serialize, BotRequest)]
+pub struct SendVideoRequest {
+    /// Unique identifier for the target chat
+    pub chat_id: i64,
+
+    /// Video to send. Pass a file_id to send a video that exists on the Telegram servers, or an
+    /// HTTP URL for Telegram to get a video from the Internet.
+    pub video: String,
+
+    /// Video caption, 0-1024 characters after entities parsing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+
+    /// Mode for parsing entities in the video caption
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_mode: Option<ParseMode>,
+
+    /// Pass True, if the caption must be shown above the message media
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_caption_above_media: Option<bool>,
+
+    /// Pass True if the video needs to be covered with a spoiler animation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_spoiler: Option<bool>,
+
+    /// Pass True if the uploaded video is suitable for streaming
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_streaming: Option<bool>,
+
+    /// Sends the message silently. Users will receive a notification with no sound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_notification: Option<bool>,
+
+    /// Unique identifier of the message effect to be added to the message; for private chats only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_effect_id: Option<String>,
+
+    /// Description of the message to reply to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_parameters: Option<ReplyParameters>,
+
+    /// Additional interface options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<ReplyMarkup>,
+}
+
+impl SendVideoRequest {
+    pub fn new(chat_id: i64, video: impl Into<String>) -> Self {
+        Self {
+            chat_id,
+            video: video.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    pub fn with_parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = Some(parse_mode);
+        self
+    }
+
+    pub fn with_show_caption_above_media(mut self, show_caption_above_media: bool) -> Self {
+        self.show_caption_above_media = Some(show_caption_above_media);
+        self
+    }
+
+    /// Cover the video with a spoiler animation, e.g. for NSFW content.
+    pub fn with_spoiler(mut self, has_spoiler: bool) -> Self {
+        self.has_spoiler = Some(has_spoiler);
+        self
+    }
+
+    pub fn with_supports_streaming(mut self, supports_streaming: bool) -> Self {
+        self.supports_streaming = Some(supports_streaming);
+        self
+    }
+
+    /// Add the message effect `message_effect_id` (e.g. confetti) to the message. Effects are
+    /// only shown in private chats.
+    pub fn with_message_effect_id(mut self, message_effect_id: impl Into<String>) -> Self {
+        self.message_effect_id = Some(message_effect_id.into());
+        self
+    }
+
+    pub fn with_reply_parameters(mut self, reply_parameters: ReplyParameters) -> Self {
+        self.reply_parameters = Some(reply_parameters);
+        self
+    }
+
+    pub fn with_reply_markup(mut self, reply_markup: ReplyMarkup) -> Self {
+        self.reply_markup = Some(reply_markup);
+        self
+    }
+}
+
+/// Use this method to send general files. On success, the sent Message is returned.
+/// <https://core.telegram.org/bots/api#senddocument>
+#[derive(Debug, Clone, Default, Deserialize, Serialize, BotRequest)]
+pub struct SendDocumentRequest {
+    /// Unique identifier for the target chat
+    pub chat_id: i64,
+
+    /// File to send. Pass a file_id to send a file that exists on the Telegram servers, or an
+    /// HTTP URL for Telegram to get a file from the Internet.
+    pub document: String,
+
+    /// Document caption, 0-1024 characters after entities parsing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+
+    /// Mode for parsing entities in the document caption
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parse_mode: Option<ParseMode>,
+
+    /// Sends the message silently. Users will receive a notification with no sound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_notification: Option<bool>,
+
+    /// Description of the message to reply to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_parameters: Option<ReplyParameters>,
+
+    /// Additional interface options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_markup: Option<ReplyMarkup>,
+}
+
+impl SendDocumentRequest {
+    pub fn new(chat_id: i64, document: impl Into<String>) -> Self {
+        Self {
+            chat_id,
+            document: document.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    pub fn with_parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = Some(parse_mode);
+        self
+    }
+
+    pub fn with_reply_parameters(mut self, reply_parameters: ReplyParameters) -> Self {
+        self.reply_parameters = Some(reply_parameters);
+        self
+    }
+
+    pub fn with_reply_markup(mut self, reply_markup: ReplyMarkup) -> Self {
+        self.reply_markup = Some(reply_markup);
+        self
+    }
+}
+
+impl API {
+    /// Send a photo by file ID or URL.
+    pub async fn send_photo(&self, req: &SendPhotoRequest) -> anyhow::Result<Message> {
+        self.client.post("sendPhoto", req).await
+    }
+
+    /// Send a video by file ID or URL.
+    pub async fn send_video(&self, req: &SendVideoRequest) -> anyhow::Result<Message> {
+        self.client.post("sendVideo", req).await
+    }
+
+    /// Send a file by file ID or URL.
+    pub async fn send_document(&self, req: &SendDocumentRequest) -> anyhow::Result<Message> {
+        self.client.post("sendDocument", req).await
+    }
+
+    /// Upload a photo and send it. `req.photo` is ignored.
+    pub async fn send_photo_file(
+        &self,
+        req: &SendPhotoRequest,
+        photo: &InputFile,
+    ) -> anyhow::Result<Message> {
+        self.client
+            .post_file("sendPhoto", req, "photo", photo)
+            .await
+    }
+
+    /// Upload a video and send it. `req.video` is ignored.
+    pub async fn send_video_file(
+        &self,
+        req: &SendVideoRequest,
+        video: &InputFile,
+    ) -> anyhow::Result<Message> {
+        self.client
+            .post_file("sendVideo", req, "video", video)
+            .await
+    }
+
+    /// Upload a file and send it. `req.document` is ignored.
+    pub async fn send_document_file(
+        &self,
+        req: &SendDocumentRequest,
+        document: &InputFile,
+    ) -> anyhow::Result<Message> {
+        self.client
+            .post_file("sendDocument", req, "document", document)
+            .await
+    }
+}
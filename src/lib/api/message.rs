@@ -1,8 +1,12 @@
-use chrono::Utc;
 use mobot_derive::BotRequest;
 use serde::{Deserialize, Serialize};
 
-use super::{chat::Chat, sticker::Sticker, user::User, Document, PhotoSize, ReplyMarkup, API};
+#[cfg(feature = "stickers")]
+use super::sticker::Sticker;
+use super::{
+    chat::Chat, dice::Dice, user::User, ChatBackground, Document, PhotoSize, ReplyMarkup, API,
+};
+use crate::clock::{Clock, SystemClock};
 
 /// This object represents a point on the map.
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -90,6 +94,51 @@ pub struct Audio {
     pub thumbnail: Option<PhotoSize>,
 }
 
+/// This object represents one special entity in a text message. For example, hashtags,
+/// usernames, URLs, etc. <https://core.telegram.org/bots/api#messageentity>
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct MessageEntity {
+    /// Type of the entity. Currently, can be "mention" (@username), "hashtag" (#hashtag or #hashtag@chatusername),
+    /// "cashtag", "bot_command" (/start@jobs_bot), "url", "email", "phone_number", "bold", "italic",
+    /// "underline", "strikethrough", "spoiler", "blockquote", "expandable_blockquote", "code", "pre",
+    /// "text_link" (for clickable text URLs), "text_mention" (for users without usernames), "custom_emoji"
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// Offset in UTF-16 code units to the start of the entity
+    pub offset: i64,
+
+    /// Length of the entity in UTF-16 code units
+    pub length: i64,
+
+    /// Optional. For "text_link" only, URL that will be opened after user taps on the text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Optional. For "text_mention" only, the mentioned user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+
+    /// Optional. For "pre" only, the programming language of the entity text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Optional. For "custom_emoji" only, unique identifier of the custom emoji
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_emoji_id: Option<String>,
+}
+
+impl MessageEntity {
+    /// Returns the part of `text` covered by this entity. `text` must be the text (or caption)
+    /// of the message the entity belongs to. Returns `None` if the entity is out of range.
+    pub fn text(&self, text: &str) -> Option<String> {
+        let units: Vec<u16> = text.encode_utf16().collect();
+        let start = usize::try_from(self.offset).ok()?;
+        let end = start.checked_add(usize::try_from(self.length).ok()?)?;
+        String::from_utf16(units.get(start..end)?).ok()
+    }
+}
+
 /// This object represents a service message about a new forum topic created in the chat.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ForumTopicCreated {
@@ -122,10 +171,18 @@ pub struct Message {
     /// Date the message was sent in Unix time
     pub date: i64,
 
+    /// Optional. Date the message was last edited in Unix time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_date: Option<i64>,
+
     /// Message text
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
 
+    /// For text messages, special entities like usernames, URLs, bot commands, etc. that appear in the text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entities: Option<Vec<MessageEntity>>,
+
     /// Message is a photo, available sizes of the photo
     #[serde(skip_serializing_if = "Option::is_none")]
     pub photo: Option<Vec<PhotoSize>>,
@@ -152,6 +209,27 @@ pub struct Message {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub caption: Option<String>,
 
+    /// Optional. For messages with a caption, special entities like usernames, URLs, bot commands,
+    /// etc. that appear in the caption
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption_entities: Option<Vec<MessageEntity>>,
+
+    /// Optional. True, if the caption must be shown above the message media
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_caption_above_media: Option<bool>,
+
+    /// Optional. Unique identifier of the message effect added to the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effect_id: Option<String>,
+
+    /// Optional. True, if the message media is covered by a spoiler animation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_media_spoiler: Option<bool>,
+
+    /// Optional. The unique identifier of a media message group this message belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_group_id: Option<String>,
+
     /// Optional. Message is a shared location, information about the location
     #[serde(skip_serializing_if = "Option::is_none")]
     pub location: Option<Location>,
@@ -182,18 +260,32 @@ pub struct Message {
 
     /// For replies, the original message. Note that the Message object in this field will not contain further `reply_to_message` fields even if it itself is a reply.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub reply_to_message: Option<serde_json::value::Value>,
+    pub reply_to_message: Option<Box<Message>>,
 
     /// Sticker for messages with a sticker
+    #[cfg(feature = "stickers")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sticker: Option<Sticker>,
 
+    /// Optional. Message is a dice with random value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dice: Option<Dice>,
+
     /// Optional. Service message: forum topic created
     #[serde(skip_serializing_if = "Option::is_none")]
     pub forum_topic_created: Option<ForumTopicCreated>,
 
+    /// Optional. New members that were added to the group or supergroup (the bot itself may be
+    /// one of them)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_chat_members: Option<Vec<User>>,
+
+    /// Optional. Service message: chat background set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_background_set: Option<ChatBackground>,
+
     /// Inline keyboard attached to the message.
-    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<ReplyMarkup>,
 }
 
@@ -205,11 +297,91 @@ impl Message {
         message
     }
 
+    /// Returns the message text, or the caption for media messages.
+    pub fn text_or_caption(&self) -> Option<&str> {
+        self.text.as_deref().or(self.caption.as_deref())
+    }
+
+    /// Returns the entities of [`Message::text_or_caption`]: the text's entities, or the
+    /// caption's for media messages.
+    pub fn text_or_caption_entities(&self) -> Option<&Vec<MessageEntity>> {
+        if self.text.is_some() {
+            self.entities.as_ref()
+        } else {
+            self.caption_entities.as_ref()
+        }
+    }
+
+    /// Returns true if the message (or its caption) mentions the bot with the username
+    /// `bot_username` (with or without the leading "@"), either as an @mention or as a bot command
+    /// addressed to it (e.g., "/start@mobot").
+    pub fn mentions_bot(&self, bot_username: &str) -> bool {
+        let username = bot_username.trim_start_matches('@');
+        let (Some(text), Some(entities)) =
+            (self.text_or_caption(), self.text_or_caption_entities())
+        else {
+            return false;
+        };
+
+        entities.iter().any(|entity| match entity.type_.as_str() {
+            "mention" => entity
+                .text(text)
+                .is_some_and(|m| m.trim_start_matches('@').eq_ignore_ascii_case(username)),
+            "bot_command" => entity
+                .text(text)
+                .and_then(|c| c.split_once('@').map(|(_, u)| u.eq_ignore_ascii_case(username)))
+                .unwrap_or(false),
+            "text_mention" => entity
+                .user
+                .as_ref()
+                .and_then(|u| u.username.as_deref())
+                .is_some_and(|u| u.eq_ignore_ascii_case(username)),
+            _ => false,
+        })
+    }
+
+    /// Returns true if the message is a reply to a message sent by the user `bot_id`.
+    pub fn is_reply_to_bot(&self, bot_id: i64) -> bool {
+        self.reply_to_message
+            .as_ref()
+            .and_then(|m| m.from.as_ref())
+            .is_some_and(|from| from.id == bot_id)
+    }
+
+    /// Returns the links in the message (or its caption): "url" entities, and the targets of
+    /// "text_link" entities, in order.
+    pub fn urls(&self) -> Vec<String> {
+        let (Some(text), Some(entities)) =
+            (self.text_or_caption(), self.text_or_caption_entities())
+        else {
+            return vec![];
+        };
+
+        entities
+            .iter()
+            .filter_map(|entity| match entity.type_.as_str() {
+                "url" => entity.text(text),
+                "text_link" => entity.url.clone(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the Telegram invite links in the message (see [`crate::links::is_invite_link`]).
+    pub fn invite_links(&self) -> Vec<String> {
+        self.urls()
+            .into_iter()
+            .filter(|url| crate::links::is_invite_link(url))
+            .collect()
+    }
+
+    /// A message from `from`, dated now by the system clock. Messages from
+    /// [`crate::fake::FakeAPI`] are dated by its clock instead.
     pub fn fake(from: impl AsRef<str>) -> Self {
         Message {
             message_id: rand::random(),
             from: Some(from.as_ref().into()),
-            date: Utc::now().timestamp(),
+            date: SystemClock.timestamp(),
             chat: from.as_ref().into(),
             ..Default::default()
         }
@@ -261,7 +433,7 @@ pub struct SendMessageRequest {
     /// Unique identifier for the target chat or username of the target
     pub chat_id: i64,
 
-    /// Optional	Unique identifier for the target message thread (topic) of the forum; for forum supergroups only
+    /// Optional. Unique identifier for the target message thread (topic) of the forum; for forum supergroups only
     pub message_thread_id: Option<i64>,
 
     /// Text of the message to be sent
@@ -278,6 +450,10 @@ pub struct SendMessageRequest {
     /// Reply markup for the message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_markup: Option<ReplyMarkup>,
+
+    /// Unique identifier of the message effect to be added to the message; for private chats only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_effect_id: Option<String>,
 }
 
 impl SendMessageRequest {
@@ -298,6 +474,13 @@ impl SendMessageRequest {
         self
     }
 
+    /// Add the message effect `message_effect_id` (e.g. confetti) to the message. Effects are
+    /// only shown in private chats.
+    pub fn with_message_effect_id(mut self, message_effect_id: impl Into<String>) -> Self {
+        self.message_effect_id = Some(message_effect_id.into());
+        self
+    }
+
     pub fn with_parse_mode(mut self, parse_mode: ParseMode) -> Self {
         self.parse_mode = Some(parse_mode);
         self
@@ -455,7 +638,7 @@ impl DeleteMessageRequest {
     }
 }
 
-#[derive(Debug, Serialize, Clone, BotRequest)]
+#[derive(Debug, Serialize, Deserialize, Clone, BotRequest)]
 pub struct ReactionType {
     /// Type of the reaction. For type "emoji", the emoji field must be set. For type "custom_emoji", the custom_emoji_id field must be set. Otherwise "paid" for paid emojis
     #[serde(rename = "type")]
@@ -477,6 +660,60 @@ impl ReactionType {
             custom_emoji_id,
         }
     }
+
+    /// A reaction with the emoji `emoji`.
+    pub fn emoji(emoji: impl Into<String>) -> Self {
+        Self::new("emoji".into(), Some(emoji.into()), None)
+    }
+}
+
+/// This object represents a change of a reaction on a message performed by a user. Bots only
+/// receive these if they're administrators in the chat, and explicitly ask for
+/// `message_reaction` updates (see [`crate::Router::with_allowed_updates`]).
+/// <https://core.telegram.org/bots/api#messagereactionupdated>
+#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+pub struct MessageReactionUpdated {
+    /// The chat containing the message the user reacted to
+    pub chat: Chat,
+
+    /// Unique identifier of the message inside the chat
+    pub message_id: i64,
+
+    /// Optional. The user that changed the reaction, if the user isn't anonymous
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+
+    /// Optional. The chat on behalf of which the reaction was changed, if the user is anonymous
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor_chat: Option<Chat>,
+
+    /// Date of the change in Unix time
+    pub date: i64,
+
+    /// Previous list of reaction types that were set by the user
+    #[serde(default)]
+    pub old_reaction: Vec<ReactionType>,
+
+    /// New list of reaction types that have been set by the user
+    #[serde(default)]
+    pub new_reaction: Vec<ReactionType>,
+}
+
+impl MessageReactionUpdated {
+    /// Returns the emoji reactions in `new_reaction` that weren't in `old_reaction`.
+    pub fn added_emoji(&self) -> Vec<&str> {
+        let old = self
+            .old_reaction
+            .iter()
+            .filter_map(|r| r.emoji.as_deref())
+            .collect::<Vec<_>>();
+
+        self.new_reaction
+            .iter()
+            .filter_map(|r| r.emoji.as_deref())
+            .filter(|emoji| !old.contains(emoji))
+            .collect()
+    }
 }
 
 #[derive(Default, Debug, Serialize, Clone, BotRequest)]
@@ -498,6 +735,56 @@ impl MessageReactionRequest {
     }
 }
 
+/// Use this method to copy messages of any kind. The copy has no link to the original message.
+/// <https://core.telegram.org/bots/api#copymessage>
+#[derive(Default, Debug, Serialize, Deserialize, Clone, BotRequest)]
+pub struct CopyMessageRequest {
+    /// Unique identifier for the target chat
+    pub chat_id: i64,
+
+    /// Unique identifier for the chat where the original message was sent
+    pub from_chat_id: i64,
+
+    /// Message identifier in the chat specified in from_chat_id
+    pub message_id: i64,
+
+    /// New caption for media. If not specified, the original caption is kept
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub caption: Option<String>,
+
+    /// Sends the message silently. Users will receive a notification with no sound.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disable_notification: Option<bool>,
+}
+
+impl CopyMessageRequest {
+    pub fn new(chat_id: i64, from_chat_id: i64, message_id: i64) -> Self {
+        Self {
+            chat_id,
+            from_chat_id,
+            message_id,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    pub fn with_disable_notification(mut self, disable_notification: bool) -> Self {
+        self.disable_notification = Some(disable_notification);
+        self
+    }
+}
+
+/// This object represents a unique message identifier.
+#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+pub struct MessageId {
+    /// Unique message identifier
+    pub message_id: i64,
+}
+
 /// API methods for sending, editing, and deleting messages.
 impl API {
     /// Send a message to a chat or channel.
@@ -582,4 +869,10 @@ impl API {
     ) -> anyhow::Result<bool> {
         self.client.post("setMessageReaction", req).await
     }
+
+    /// Copy a message to another chat, without a link to the original. Returns the ID of the
+    /// copy.
+    pub async fn copy_message(&self, req: &CopyMessageRequest) -> anyhow::Result<MessageId> {
+        self.client.post("copyMessage", req).await
+    }
 }
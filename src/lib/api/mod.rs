@@ -1,28 +1,50 @@
 #[allow(clippy::module_inception)]
 pub mod api;
+pub mod background;
 pub mod botcommand;
+#[cfg(feature = "business")]
+pub mod business;
 pub mod chat;
+pub mod dice;
 pub mod document;
 pub mod file;
 pub mod format;
+#[cfg(feature = "inline")]
+pub mod inline;
+pub mod media;
 pub mod message;
 pub mod photo_size;
 pub mod query;
 pub mod reply_markup;
+#[cfg(feature = "stickers")]
 pub mod sticker;
 pub mod update;
+pub mod upload;
 pub mod user;
+pub mod version;
+pub mod webhook;
 
 pub use api::*;
+pub use background::*;
 pub use botcommand::*;
+#[cfg(feature = "business")]
+pub use business::*;
 pub use chat::*;
+pub use dice::*;
 pub use document::*;
 pub use file::*;
 pub use format::*;
+#[cfg(feature = "inline")]
+pub use inline::*;
+pub use media::*;
 pub use message::*;
 pub use photo_size::*;
 pub use query::*;
 pub use reply_markup::*;
+#[cfg(feature = "stickers")]
 pub use sticker::*;
 pub use update::*;
+pub use upload::{InputFile, UploadProgress};
 pub use user::*;
+pub use version::BotApiVersion;
+pub use webhook::*;
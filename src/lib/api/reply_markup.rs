@@ -13,7 +13,7 @@ impl<T: Into<String>> From<T> for KeyboardButton {
     }
 }
 
-#[derive(Default, Debug, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct InlineKeyboardButton {
     /// Label text on the button
     pub text: String,
@@ -51,12 +51,15 @@ pub enum ReplyMarkup {
         inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
 
         /// Requests clients to resize the keyboard vertically for optimal fit
+        #[serde(default)]
         resize_keyboard: bool,
 
         /// Requests clients to hide the keyboard as soon as it's been used
+        #[serde(default)]
         one_time_keyboard: bool,
 
         /// Use this parameter if you want to show the keyboard to specific users only
+        #[serde(default)]
         selective: bool,
 
         /// The placeholder to be shown in the input field when the keyboard is active; 1-64 characters
@@ -64,6 +67,7 @@ pub enum ReplyMarkup {
         input_field_placeholder: Option<String>,
 
         /// Requests clients to always show the keyboard in the chat (users may not otherwise see the keyboard)
+        #[serde(default)]
         is_persistent: bool,
     },
     ReplyKeyboardMarkup {
@@ -71,12 +75,15 @@ pub enum ReplyMarkup {
         keyboard: Vec<Vec<KeyboardButton>>,
 
         /// Requests clients to resize the keyboard vertically for optimal fit
+        #[serde(default)]
         resize_keyboard: bool,
 
         /// Requests clients to hide the keyboard as soon as it's been used
+        #[serde(default)]
         one_time_keyboard: bool,
 
         /// Use this parameter if you want to show the keyboard to specific users only
+        #[serde(default)]
         selective: bool,
 
         /// The placeholder to be shown in the input field when the keyboard is active; 1-64 characters
@@ -84,6 +91,7 @@ pub enum ReplyMarkup {
         input_field_placeholder: Option<String>,
 
         /// Requests clients to always show the keyboard in the chat (users may not otherwise see the keyboard)
+        #[serde(default)]
         is_persistent: bool,
     },
     ReplyKeyboardRemove {
@@ -93,6 +101,7 @@ pub enum ReplyMarkup {
         remove_keyboard: bool,
 
         /// Use this parameter if you want to remove the keyboard for specific users only
+        #[serde(default)]
         selective: bool,
     },
     ForceReply {
@@ -104,6 +113,7 @@ pub enum ReplyMarkup {
         input_field_placeholder: Option<String>,
 
         /// Use this parameter if you want to force reply from specific users only
+        #[serde(default)]
         selective: bool,
     },
 }
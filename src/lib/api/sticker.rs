@@ -1,7 +1,7 @@
 use mobot_derive::BotRequest;
 use serde::{Deserialize, Serialize};
 
-use super::{message::Message, ReplyParameters, API};
+use super::{file::File, message::Message, InputFile, ReplyParameters, API};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Sticker {
@@ -39,6 +39,10 @@ pub struct SendStickerRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disable_notification: Option<bool>,
 
+    /// Unique identifier of the message effect to be added to the message; for private chats only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_effect_id: Option<String>,
+
     /// If the message is a reply, ID of the original message
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reply_parameters: Option<ReplyParameters>,
@@ -50,18 +54,171 @@ impl SendStickerRequest {
             chat_id,
             sticker,
             disable_notification: None,
+            message_effect_id: None,
             reply_parameters: None,
         }
     }
 
+    /// Add the message effect `message_effect_id` (e.g. confetti) to the message. Effects are
+    /// only shown in private chats.
+    pub fn with_message_effect_id(mut self, message_effect_id: impl Into<String>) -> Self {
+        self.message_effect_id = Some(message_effect_id.into());
+        self
+    }
+
     pub fn with_reply_parameters(mut self, reply_parameters: ReplyParameters) -> Self {
         self.reply_parameters = Some(reply_parameters);
         self
     }
 }
 
+/// The format of a sticker's file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StickerFormat {
+    /// A .WEBP or .PNG image, with one side exactly 512 pixels, and the other at most 512.
+    #[default]
+    Static,
+
+    /// A .TGS animation.
+    Animated,
+
+    /// A .WEBM video.
+    Video,
+}
+
+/// A sticker to add to a sticker set.
+/// <https://core.telegram.org/bots/api#inputsticker>
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct InputSticker {
+    /// The file_id of the sticker's file, e.g. from [`API::upload_sticker_file`]
+    pub sticker: String,
+
+    /// Format of the sticker
+    pub format: StickerFormat,
+
+    /// List of 1-20 emoji associated with the sticker
+    pub emoji_list: Vec<String>,
+
+    /// List of 0-20 search keywords for the sticker
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keywords: Option<Vec<String>>,
+}
+
+impl InputSticker {
+    pub fn new(sticker: impl Into<String>, format: StickerFormat, emoji_list: Vec<String>) -> Self {
+        Self {
+            sticker: sticker.into(),
+            format,
+            emoji_list,
+            keywords: None,
+        }
+    }
+}
+
+/// Use this method to upload a file with a sticker for later use in the createNewStickerSet and
+/// addStickerToSet methods. Returns the uploaded File on success.
+/// <https://core.telegram.org/bots/api#uploadstickerfile>
+#[derive(Debug, Clone, Serialize, BotRequest)]
+pub struct UploadStickerFileRequest {
+    /// User identifier of sticker file owner
+    pub user_id: i64,
+
+    /// Format of the sticker
+    pub sticker_format: StickerFormat,
+}
+
+impl UploadStickerFileRequest {
+    pub fn new(user_id: i64, sticker_format: StickerFormat) -> Self {
+        Self {
+            user_id,
+            sticker_format,
+        }
+    }
+}
+
+/// Use this method to create a new sticker set owned by a user. Returns True on success.
+/// <https://core.telegram.org/bots/api#createnewstickerset>
+#[derive(Debug, Clone, Serialize, BotRequest)]
+pub struct CreateNewStickerSetRequest {
+    /// User identifier of created sticker set owner
+    pub user_id: i64,
+
+    /// Short name of sticker set, to be used in t.me/addstickers/ URLs. Must end with
+    /// `_by_<bot_username>`.
+    pub name: String,
+
+    /// Sticker set title, 1-64 characters
+    pub title: String,
+
+    /// A list of 1-50 initial stickers to be added to the sticker set
+    pub stickers: Vec<InputSticker>,
+}
+
+impl CreateNewStickerSetRequest {
+    pub fn new(
+        user_id: i64,
+        name: impl Into<String>,
+        title: impl Into<String>,
+        stickers: Vec<InputSticker>,
+    ) -> Self {
+        Self {
+            user_id,
+            name: name.into(),
+            title: title.into(),
+            stickers,
+        }
+    }
+}
+
+/// Use this method to add a new sticker to a set created by the bot. Returns True on success.
+/// <https://core.telegram.org/bots/api#addstickertoset>
+#[derive(Debug, Clone, Serialize, BotRequest)]
+pub struct AddStickerToSetRequest {
+    /// User identifier of sticker set owner
+    pub user_id: i64,
+
+    /// Sticker set name
+    pub name: String,
+
+    /// The sticker to add to the set
+    pub sticker: InputSticker,
+}
+
+impl AddStickerToSetRequest {
+    pub fn new(user_id: i64, name: impl Into<String>, sticker: InputSticker) -> Self {
+        Self {
+            user_id,
+            name: name.into(),
+            sticker,
+        }
+    }
+}
+
 impl API {
     pub async fn send_sticker(&self, req: &SendStickerRequest) -> anyhow::Result<Message> {
         self.client.post("sendSticker", req).await
     }
+
+    /// Upload `sticker` for use in a sticker set.
+    pub async fn upload_sticker_file(
+        &self,
+        req: &UploadStickerFileRequest,
+        sticker: &InputFile,
+    ) -> anyhow::Result<File> {
+        self.client
+            .post_file("uploadStickerFile", req, "sticker", sticker)
+            .await
+    }
+
+    pub async fn create_new_sticker_set(
+        &self,
+        req: &CreateNewStickerSetRequest,
+    ) -> anyhow::Result<bool> {
+        self.client.post("createNewStickerSet", req).await
+    }
+
+    pub async fn add_sticker_to_set(&self, req: &AddStickerToSetRequest) -> anyhow::Result<bool> {
+        self.client.post("addStickerToSet", req).await
+    }
 }
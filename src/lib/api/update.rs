@@ -1,7 +1,9 @@
 use mobot_derive::BotRequest;
 use serde::{Deserialize, Serialize};
 
-use super::{message::Message, query::InlineQuery, CallbackQuery, API};
+#[cfg(feature = "inline")]
+use super::InlineQuery;
+use super::{message::Message, CallbackQuery, ChatJoinRequest, MessageReactionUpdated, User, API};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Update {
@@ -32,12 +34,60 @@ pub struct Update {
     pub edited_channel_post: Option<Message>,
 
     /// New incoming inline query
+    #[cfg(feature = "inline")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub inline_query: Option<InlineQuery>,
 
     /// Callbakc query
     #[serde(skip_serializing_if = "Option::is_none")]
     pub callback_query: Option<CallbackQuery>,
+
+    /// A reaction to a message was changed by a user. Only sent if `message_reaction` is
+    /// listed in `allowed_updates`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_reaction: Option<MessageReactionUpdated>,
+
+    /// A request to join the chat has been sent. The bot must have the can_invite_users
+    /// administrator right in the chat to receive these updates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat_join_request: Option<ChatJoinRequest>,
+}
+
+impl Update {
+    /// The date the update's message (or reaction) was sent, in Unix time, or for edited
+    /// messages and channel posts, when they were edited. Returns `None` for updates that don't
+    /// carry a date (callback and inline queries).
+    pub fn date(&self) -> Option<i64> {
+        self.edited_message
+            .as_ref()
+            .or(self.edited_channel_post.as_ref())
+            .and_then(|m| m.edit_date)
+            .or(self.any_message().map(|m| m.date))
+            .or(self.message_reaction.as_ref().map(|r| r.date))
+            .or(self.chat_join_request.as_ref().map(|r| r.date))
+    }
+
+    /// The message carried by the update: a new or edited message or channel post.
+    pub fn any_message(&self) -> Option<&Message> {
+        self.message
+            .as_ref()
+            .or(self.edited_message.as_ref())
+            .or(self.channel_post.as_ref())
+            .or(self.edited_channel_post.as_ref())
+    }
+
+    /// The user that sent the update, if known. Channel posts typically have no sender.
+    pub fn sender(&self) -> Option<&User> {
+        let sender = self
+            .any_message()
+            .and_then(|m| m.from.as_ref())
+            .or(self.callback_query.as_ref().map(|q| &q.from))
+            .or(self.message_reaction.as_ref().and_then(|r| r.user.as_ref()))
+            .or(self.chat_join_request.as_ref().map(|r| &r.from));
+        #[cfg(feature = "inline")]
+        let sender = sender.or(self.inline_query.as_ref().map(|q| &q.from));
+        sender
+    }
 }
 
 /// Use this method to receive incoming updates using long or short
@@ -91,6 +141,14 @@ impl GetUpdatesRequest {
         self.offset = Some(offset);
         self
     }
+
+    /// Receive only the listed update types (e.g., "message", "message_reaction"). An empty
+    /// list means all types except `chat_member`, `message_reaction` and
+    /// `message_reaction_count`.
+    pub fn with_allowed_updates(mut self, allowed_updates: Vec<String>) -> Self {
+        self.allowed_updates = Some(allowed_updates);
+        self
+    }
 }
 
 impl API {
@@ -0,0 +1,254 @@
+use std::{
+    collections::VecDeque,
+    convert::Infallible,
+    fmt,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use anyhow::Result;
+use bytes::Bytes;
+use http_body::{Frame, SizeHint};
+use serde_json::Value;
+
+/// File data is sent in chunks of this size, with a progress report after each one.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Called with the number of bytes of the file sent so far, and the file's size.
+pub type UploadProgress = Arc<dyn Fn(u64, u64) + Send + Sync>;
+
+/// `InputFile` is a file uploaded with a request, e.g. with [`super::API::send_photo_file`].
+/// Uploads are sent as `multipart/form-data`.
+///
+/// To show progress while uploading a large file, pass a progress callback. It's called from
+/// the HTTP client as the file is sent, so it should return quickly; to update a message with
+/// the progress, send it to a task that does:
+///
+/// ```no_run
+/// # use mobot::*;
+/// # async fn upload(e: Event, data: Vec<u8>) -> anyhow::Result<()> {
+/// let (tx, mut rx) = tokio::sync::watch::channel(0);
+/// let file = api::InputFile::new("video.mp4", data)
+///     .with_mime_type("video/mp4")
+///     .with_progress(move |sent, total| {
+///         tx.send_replace(sent * 100 / total.max(1));
+///     });
+///
+/// let status = e.send_message("Uploading...").await?;
+/// let status_event = e.clone();
+/// tokio::spawn(async move {
+///     while rx.changed().await.is_ok() {
+///         let percent = *rx.borrow_and_update();
+///         let text = format!("Uploading... {}%", percent);
+///         let _ = status_event.edit_message(status.message_id, text).await;
+///     }
+/// });
+///
+/// let req = api::SendVideoRequest::new(e.update.chat_id()?, "");
+/// e.api.send_video_file(&req, &file).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct InputFile {
+    /// The file name Telegram sees.
+    pub name: String,
+
+    /// The file's contents. Empty for files created with [`InputFile::path`] until they're
+    /// sent.
+    pub data: Bytes,
+
+    /// Where the file is on disk, if it's from a file. With a local Bot API server, files
+    /// with a path are passed to the server by path instead of uploaded (see
+    /// [`crate::Client::with_local_mode`]).
+    pub path: Option<PathBuf>,
+
+    /// The file's MIME type. Defaults to `application/octet-stream`.
+    pub mime_type: Option<String>,
+
+    progress: Option<UploadProgress>,
+}
+
+impl InputFile {
+    pub fn new(name: impl Into<String>, data: impl Into<Bytes>) -> Self {
+        Self {
+            name: name.into(),
+            data: data.into(),
+            path: None,
+            mime_type: None,
+            progress: None,
+        }
+    }
+
+    /// Read the file at `path`, named after its file name.
+    #[cfg(any(feature = "runtime", feature = "notify"))]
+    pub async fn from_path(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let data = tokio::fs::read(path.as_ref()).await?;
+        Ok(Self::path(path.as_ref()).with_data(data))
+    }
+
+    /// The file at `path`, named after its file name. It's only read when it's sent, and not
+    /// at all if it's passed to a local Bot API server by path, so large files don't have to
+    /// be held in memory.
+    pub fn path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file".to_string());
+        Self {
+            path: Some(path),
+            ..Self::new(name, Bytes::new())
+        }
+    }
+
+    pub fn with_data(mut self, data: impl Into<Bytes>) -> Self {
+        self.data = data.into();
+        self
+    }
+
+    pub fn with_mime_type(mut self, mime_type: impl Into<String>) -> Self {
+        self.mime_type = Some(mime_type.into());
+        self
+    }
+
+    /// Call `progress` with the bytes sent so far and the total, as the file is uploaded.
+    pub fn with_progress(mut self, progress: impl Fn(u64, u64) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+}
+
+impl fmt::Debug for InputFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InputFile")
+            .field("name", &self.name)
+            .field("size", &self.data.len())
+            .field("path", &self.path)
+            .field("mime_type", &self.mime_type)
+            .finish()
+    }
+}
+
+enum Part {
+    Form(Bytes),
+    File(Bytes),
+}
+
+/// A `multipart/form-data` request body, with the request's fields as form fields, and a file.
+/// Reports progress as the file's chunks are sent.
+pub(crate) struct Multipart {
+    boundary: String,
+    parts: VecDeque<Part>,
+    remaining: u64,
+    sent: u64,
+    total: u64,
+    progress: Option<UploadProgress>,
+}
+
+impl Multipart {
+    /// The body for `req` (a JSON object), with `file` uploaded as the field `field`. The
+    /// request's own `field`, if any, is replaced by the file.
+    pub(crate) fn new(req: &Value, field: &str, file: &InputFile) -> Self {
+        let boundary = format!("mobot-{:016x}", rand::random::<u64>());
+        let mut head = String::new();
+
+        if let Value::Object(fields) = req {
+            for (name, value) in fields.iter().filter(|(name, _)| *name != field) {
+                let value = match value {
+                    Value::Null => continue,
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                head.push_str(&format!(
+                    "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                    boundary, name, value
+                ));
+            }
+        }
+
+        head.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+            boundary,
+            field,
+            file.name.replace(['"', '\r', '\n'], "_"),
+            file.mime_type.as_deref().unwrap_or("application/octet-stream")
+        ));
+
+        let mut parts = VecDeque::from([Part::Form(head.into())]);
+        let mut offset = 0;
+        while offset < file.data.len() {
+            let end = (offset + CHUNK_SIZE).min(file.data.len());
+            parts.push_back(Part::File(file.data.slice(offset..end)));
+            offset = end;
+        }
+        parts.push_back(Part::Form(format!("\r\n--{}--\r\n", boundary).into()));
+
+        let remaining = parts
+            .iter()
+            .map(|part| match part {
+                Part::Form(bytes) | Part::File(bytes) => bytes.len() as u64,
+            })
+            .sum();
+
+        Self {
+            boundary,
+            parts,
+            remaining,
+            sent: 0,
+            total: file.data.len() as u64,
+            progress: file.progress.clone(),
+        }
+    }
+
+    pub(crate) fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Returns the next chunk of the body, reporting progress if it's part of the file.
+    fn next_chunk(&mut self) -> Option<Bytes> {
+        let chunk = match self.parts.pop_front()? {
+            Part::Form(bytes) => bytes,
+            Part::File(bytes) => {
+                self.sent += bytes.len() as u64;
+                if let Some(progress) = &self.progress {
+                    progress(self.sent, self.total);
+                }
+                bytes
+            }
+        };
+        self.remaining -= chunk.len() as u64;
+        Some(chunk)
+    }
+
+    /// Returns the whole body. Progress is reported as if it was sent.
+    pub(crate) fn into_bytes(mut self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(self.remaining as usize);
+        while let Some(chunk) = self.next_chunk() {
+            body.extend_from_slice(&chunk);
+        }
+        body
+    }
+}
+
+impl http_body::Body for Multipart {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        Poll::Ready(self.next_chunk().map(|chunk| Ok(Frame::data(chunk))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.parts.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining)
+    }
+}
@@ -5,13 +5,17 @@ use serde::{Deserialize, Serialize};
 
 use std::hash::{Hash, Hasher};
 
-use super::API;
+use super::{GetChatRequest, API};
 
 #[derive(Default, Debug, Clone, Deserialize, Serialize)]
 pub struct User {
     /// Unique identifier for this user or bot
     pub id: i64,
 
+    /// True, if this user is a bot
+    #[serde(default)]
+    pub is_bot: bool,
+
     /// User‘s or bot’s first name
     pub first_name: String,
 
@@ -36,6 +40,7 @@ impl<T: Into<String>> From<T> for User {
         let from = s.into();
         Self {
             id: hash(&from.clone()) as i64,
+            is_bot: false,
             first_name: from.clone(),
             last_name: None,
             username: Some(from),
@@ -47,9 +52,87 @@ impl<T: Into<String>> From<T> for User {
 #[derive(Debug, Clone, Serialize, BotRequest)]
 pub struct GetMeRequest {}
 
+/// A user's custom emoji status, as shown next to their name.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EmojiStatus {
+    /// Custom emoji identifier of the emoji status
+    pub custom_emoji_id: String,
+
+    /// Expiration date of the emoji status in Unix time, if any
+    pub expiration_date: Option<i64>,
+}
+
+/// Changes the emoji status for a given user that previously allowed the bot to manage their
+/// emoji status via the Mini App method requestEmojiStatusAccess.
+/// <https://core.telegram.org/bots/api#setuseremojistatus>
+#[derive(Debug, Clone, Default, Deserialize, Serialize, BotRequest)]
+pub struct SetUserEmojiStatusRequest {
+    /// Unique identifier of the target user
+    pub user_id: i64,
+
+    /// Custom emoji identifier of the emoji status to set. Pass an empty string to remove the
+    /// status.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji_status_custom_emoji_id: Option<String>,
+
+    /// Expiration date of the emoji status, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji_status_expiration_date: Option<i64>,
+}
+
+impl SetUserEmojiStatusRequest {
+    pub fn new(user_id: i64, custom_emoji_id: impl Into<String>) -> Self {
+        Self {
+            user_id,
+            emoji_status_custom_emoji_id: Some(custom_emoji_id.into()),
+            emoji_status_expiration_date: None,
+        }
+    }
+
+    /// Remove the user's emoji status.
+    pub fn clear(user_id: i64) -> Self {
+        Self::new(user_id, "")
+    }
+
+    pub fn with_expiration_date(mut self, expiration_date: i64) -> Self {
+        self.emoji_status_expiration_date = Some(expiration_date);
+        self
+    }
+}
+
 impl API {
     pub async fn get_me(&self) -> anyhow::Result<User> {
         let req = GetMeRequest {};
         self.client.post("getMe", &req).await
     }
+
+    /// Returns the bot's own user. This calls `getMe` the first time, and returns the cached
+    /// user afterwards.
+    pub async fn me(&self) -> anyhow::Result<User> {
+        self.me.get_or_try_init(|| self.get_me()).await.cloned()
+    }
+
+    /// Set (or clear) a user's emoji status. Returns True on success.
+    pub async fn set_user_emoji_status(
+        &self,
+        req: &SetUserEmojiStatusRequest,
+    ) -> anyhow::Result<bool> {
+        self.client.post("setUserEmojiStatus", req).await
+    }
+
+    /// Returns the emoji status of `user_id`, if they have one. There's no API method for this;
+    /// the status is read from the user's private chat with `getChat`.
+    pub async fn get_user_emoji_status(&self, user_id: i64) -> anyhow::Result<Option<EmojiStatus>> {
+        let chat = self
+            .get_chat(&GetChatRequest::new(user_id.to_string()))
+            .await?;
+
+        Ok(chat
+            .emoji_status_custom_emoji_id
+            .filter(|id| !id.is_empty())
+            .map(|custom_emoji_id| EmojiStatus {
+                custom_emoji_id,
+                expiration_date: chat.emoji_status_expiration_date,
+            }))
+    }
 }
@@ -0,0 +1,185 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use super::{GetMeRequest, API};
+
+/// A Telegram Bot API version, e.g. 7.10. Telegram's servers always run the latest version,
+/// but self-hosted [Bot API servers](https://github.com/tdlib/telegram-bot-api) can be pinned
+/// to older ones. Set the server's version with [`crate::Client::with_api_version`] (or detect
+/// it with [`API::check_api_version`]), and the client refuses methods the server doesn't
+/// have, and drops request fields it doesn't know, with a warning.
+///
+/// Only the methods and request fields this crate sends are checked: those added after Bot
+/// API 6.9, up to 8.0 (see [`method_version`] and [`field_version`]). Other methods, e.g.
+/// ones called with [`crate::Client::post`] directly, are never refused.
+///
+/// ```
+/// # use mobot::api::BotApiVersion;
+/// let version: BotApiVersion = "7.10".parse().unwrap();
+/// assert!(version > BotApiVersion::new(7, 9));
+/// assert_eq!(version.to_string(), "7.10");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BotApiVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl BotApiVersion {
+    /// The newest version whose additions this crate checks. It's not necessarily the newest
+    /// Bot API version: later versions added nothing the crate sends.
+    pub const LATEST: BotApiVersion = BotApiVersion::new(8, 0);
+
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl fmt::Display for BotApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for BotApiVersion {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (major, minor) = s.trim().split_once('.').unwrap_or((s.trim(), "0"));
+        let parse = |n: &str| {
+            n.parse()
+                .map_err(|_| anyhow!("Invalid Bot API version: {}", s))
+        };
+        Ok(Self::new(parse(major)?, parse(minor)?))
+    }
+}
+
+/// Methods this crate calls that were added after Bot API 6.9, with the version that added
+/// them.
+const METHOD_VERSIONS: &[(&str, BotApiVersion)] = &[
+    ("setMessageReaction", BotApiVersion::new(7, 0)),
+    ("deleteMessages", BotApiVersion::new(7, 0)),
+    ("forwardMessages", BotApiVersion::new(7, 0)),
+    ("copyMessages", BotApiVersion::new(7, 0)),
+    ("getBusinessConnection", BotApiVersion::new(7, 2)),
+    ("sendPaidMedia", BotApiVersion::new(7, 6)),
+    ("setUserEmojiStatus", BotApiVersion::new(8, 0)),
+    ("savePreparedInlineMessage", BotApiVersion::new(8, 0)),
+];
+
+/// Fields of this crate's requests that were added after Bot API 6.9, with the version that
+/// added them.
+const FIELD_VERSIONS: &[(&str, BotApiVersion)] = &[
+    ("reply_parameters", BotApiVersion::new(7, 0)),
+    ("link_preview_options", BotApiVersion::new(7, 0)),
+    ("business_connection_id", BotApiVersion::new(7, 2)),
+    ("message_effect_id", BotApiVersion::new(7, 4)),
+    ("show_caption_above_media", BotApiVersion::new(7, 6)),
+    ("allow_paid_broadcast", BotApiVersion::new(7, 10)),
+];
+
+/// Fields of the bot's `User` that `getMe` returns from the version that added them, and the
+/// version before it. Used to detect the server's version.
+const GET_ME_MARKERS: &[(&str, BotApiVersion, BotApiVersion)] = &[
+    (
+        "can_connect_to_business",
+        BotApiVersion::new(7, 2),
+        BotApiVersion::new(7, 1),
+    ),
+    (
+        "has_main_web_app",
+        BotApiVersion::new(8, 0),
+        BotApiVersion::new(7, 11),
+    ),
+];
+
+/// Returns the version that added `method`, if it's one this crate calls, and newer than Bot
+/// API 6.9.
+pub fn method_version(method: &str) -> Option<BotApiVersion> {
+    METHOD_VERSIONS
+        .iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, version)| *version)
+}
+
+/// Returns the version that added the request field `field`, if it's one this crate sends, and
+/// newer than Bot API 6.9.
+pub fn field_version(field: &str) -> Option<BotApiVersion> {
+    FIELD_VERSIONS
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, version)| *version)
+}
+
+/// Check the request `req` for `method` against the server's `version`. Returns an error if the
+/// server doesn't have `method`, and the request without the fields the server doesn't know,
+/// if there are any.
+pub fn gate(version: BotApiVersion, method: &str, req: &Value) -> Result<Option<Value>> {
+    if let Some(required) = method_version(method).filter(|required| *required > version) {
+        warn!(
+            "{} requires Bot API {}, but the server runs {}",
+            method, required, version
+        );
+        return Err(anyhow!(
+            "{} requires Bot API {}, but the server runs {}",
+            method,
+            required,
+            version
+        ));
+    }
+
+    let Value::Object(fields) = req else {
+        return Ok(None);
+    };
+
+    let mut gated = fields.clone();
+    gated.retain(|field, value| {
+        match field_version(field).filter(|required| *required > version && !value.is_null()) {
+            Some(required) => {
+                warn!(
+                    "Dropping {} from {}: requires Bot API {}, but the server runs {}",
+                    field, method, required, version
+                );
+                false
+            }
+            None => true,
+        }
+    });
+
+    Ok((gated.len() != fields.len()).then_some(Value::Object(gated)))
+}
+
+impl API {
+    /// Guess the server's Bot API version from the fields `getMe` returns. Since not every
+    /// version adds fields to `getMe`, the result is the newest version consistent with them,
+    /// and servers newer than [`BotApiVersion::LATEST`] are reported as `LATEST`.
+    pub async fn detect_api_version(&self) -> Result<BotApiVersion> {
+        let me: Value = self.client.post("getMe", &GetMeRequest {}).await?;
+        let version = GET_ME_MARKERS
+            .iter()
+            .find(|(field, _, _)| me.get(field).is_none())
+            .map_or(BotApiVersion::LATEST, |(_, _, previous)| *previous);
+        Ok(version)
+    }
+
+    /// Detect the server's Bot API version (see [`API::detect_api_version`]), and warn if it's
+    /// older than the version the client is configured for. The client is switched to the
+    /// detected version, so features the server doesn't have are gated.
+    pub async fn check_api_version(&self) -> Result<BotApiVersion> {
+        let detected = self.detect_api_version().await?;
+        match self.client.api_version() {
+            Some(configured) if detected < configured => warn!(
+                "Bot API server looks older ({}) than configured ({})",
+                detected, configured
+            ),
+            Some(configured) if detected > configured => {
+                return Ok(configured);
+            }
+            _ => {}
+        }
+        self.client.set_api_version(Some(detected));
+        Ok(detected)
+    }
+}
@@ -0,0 +1,148 @@
+use mobot_derive::BotRequest;
+use serde::{Deserialize, Serialize};
+
+use super::API;
+
+/// Use this method to remove webhook integration if you decide to switch back to getUpdates.
+/// <https://core.telegram.org/bots/api#deletewebhook>
+#[derive(Debug, Clone, Default, Deserialize, Serialize, BotRequest)]
+pub struct DeleteWebhookRequest {
+    /// Pass True to drop all pending updates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop_pending_updates: Option<bool>,
+}
+
+impl DeleteWebhookRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_drop_pending_updates(mut self, drop_pending_updates: bool) -> Self {
+        self.drop_pending_updates = Some(drop_pending_updates);
+        self
+    }
+}
+
+/// Use this method to specify a URL and receive incoming updates via an outgoing webhook.
+/// <https://core.telegram.org/bots/api#setwebhook>
+#[derive(Debug, Clone, Default, Deserialize, Serialize, BotRequest)]
+pub struct SetWebhookRequest {
+    /// HTTPS URL to send updates to. Use an empty string to remove webhook integration
+    pub url: String,
+
+    /// The maximum allowed number of simultaneous HTTPS connections to the webhook for update
+    /// delivery, 1-100. Defaults to 40.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<i64>,
+
+    /// A list of the update types you want your bot to receive.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_updates: Option<Vec<String>>,
+
+    /// Pass True to drop all pending updates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop_pending_updates: Option<bool>,
+
+    /// A secret token to be sent in a header “X-Telegram-Bot-Api-Secret-Token” in every webhook
+    /// request, 1-256 characters. Only characters A-Z, a-z, 0-9, _ and - are allowed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_token: Option<String>,
+}
+
+impl SetWebhookRequest {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_max_connections(mut self, max_connections: i64) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    pub fn with_allowed_updates(mut self, allowed_updates: Vec<String>) -> Self {
+        self.allowed_updates = Some(allowed_updates);
+        self
+    }
+
+    pub fn with_drop_pending_updates(mut self, drop_pending_updates: bool) -> Self {
+        self.drop_pending_updates = Some(drop_pending_updates);
+        self
+    }
+
+    pub fn with_secret_token(mut self, secret_token: impl Into<String>) -> Self {
+        self.secret_token = Some(secret_token.into());
+        self
+    }
+}
+
+/// Use this method to get current webhook status.
+/// <https://core.telegram.org/bots/api#getwebhookinfo>
+#[derive(Debug, Clone, Default, Deserialize, Serialize, BotRequest)]
+pub struct GetWebhookInfoRequest {}
+
+/// Describes the current status of a webhook.
+/// <https://core.telegram.org/bots/api#webhookinfo>
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WebhookInfo {
+    /// Webhook URL, may be empty if webhook is not set up
+    pub url: String,
+
+    /// True, if a custom certificate was provided for webhook certificate checks
+    #[serde(default)]
+    pub has_custom_certificate: bool,
+
+    /// Number of updates awaiting delivery
+    #[serde(default)]
+    pub pending_update_count: i64,
+
+    /// Currently used webhook IP address
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<String>,
+
+    /// Unix time for the most recent error that happened when trying to deliver an update via
+    /// webhook
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error_date: Option<i64>,
+
+    /// Error message in human-readable format for the most recent error that happened when
+    /// trying to deliver an update via webhook
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error_message: Option<String>,
+
+    /// Unix time of the most recent error that happened when trying to synchronize available
+    /// updates with Telegram datacenters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_synchronization_error_date: Option<i64>,
+
+    /// The maximum allowed number of simultaneous HTTPS connections to the webhook for update
+    /// delivery
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<i64>,
+
+    /// A list of update types the bot is subscribed to. Defaults to all update types except
+    /// chat_member
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_updates: Option<Vec<String>>,
+}
+
+impl API {
+    /// Set the bot's webhook. Returns True on success.
+    pub async fn set_webhook(&self, req: &SetWebhookRequest) -> anyhow::Result<bool> {
+        self.client.post("setWebhook", req).await
+    }
+
+    /// Get the current status of the bot's webhook.
+    pub async fn get_webhook_info(&self) -> anyhow::Result<WebhookInfo> {
+        self.client
+            .post("getWebhookInfo", &GetWebhookInfoRequest {})
+            .await
+    }
+
+    /// Remove the bot's webhook, optionally dropping all pending updates. Returns True on success.
+    pub async fn delete_webhook(&self, req: &DeleteWebhookRequest) -> anyhow::Result<bool> {
+        self.client.post("deleteWebhook", req).await
+    }
+}
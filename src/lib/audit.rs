@@ -0,0 +1,165 @@
+/// Audit trail for moderation actions. When an [`AuditSink`] is attached to the [`API`] (see
+/// [`API::with_audit_sink`]), every permission change, ban, unban, restriction, and promotion made
+/// through the API is recorded as a structured [`AuditEntry`], including the state of the chat or
+/// member before the change.
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use crate::api::{GetChatMemberRequest, GetChatRequest, Request, API};
+
+/// `AuditEntry` describes a single change made by the bot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the change was made, in Unix time.
+    pub timestamp: i64,
+
+    /// The user ID of the bot that made the change, if known.
+    pub actor: Option<i64>,
+
+    /// The Telegram API method that was called (e.g., "banChatMember").
+    pub method: String,
+
+    /// The chat that was changed.
+    pub chat_id: String,
+
+    /// The member that was changed, for member-level actions.
+    pub user_id: Option<i64>,
+
+    /// The state before the change: the `ChatMember` for member-level actions, or the chat's
+    /// `ChatPermissions` for chat-level actions. `None` if it could not be retrieved.
+    pub old: Option<Value>,
+
+    /// The request that was sent to make the change.
+    pub new: Value,
+
+    /// True if the client was in dry-run mode, and the change was not actually made.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// `AuditSink`s receive audit entries. Implement this to persist entries to a database, a log
+/// channel, etc.
+#[async_trait]
+pub trait AuditSink: Send + Sync {
+    async fn record(&self, entry: AuditEntry) -> Result<()>;
+}
+
+/// `LogAuditSink` writes audit entries to the log at `info` level.
+#[derive(Debug, Clone, Default)]
+pub struct LogAuditSink;
+
+#[async_trait]
+impl AuditSink for LogAuditSink {
+    async fn record(&self, entry: AuditEntry) -> Result<()> {
+        info!(
+            "audit{}: {} chat={} user={:?} old={} new={}",
+            if entry.dry_run { " (dry run)" } else { "" },
+            entry.method,
+            entry.chat_id,
+            entry.user_id,
+            entry.old.unwrap_or_default(),
+            entry.new
+        );
+        Ok(())
+    }
+}
+
+/// `MemoryAuditSink` keeps audit entries in memory. Clones share the same entries. Useful
+/// for tests.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryAuditSink {
+    entries: Arc<Mutex<Vec<AuditEntry>>>,
+}
+
+impl MemoryAuditSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return all entries recorded so far.
+    pub async fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().await.clone()
+    }
+}
+
+#[async_trait]
+impl AuditSink for MemoryAuditSink {
+    async fn record(&self, entry: AuditEntry) -> Result<()> {
+        self.entries.lock().await.push(entry);
+        Ok(())
+    }
+}
+
+/// Audit helpers used by the moderation methods in `api::chat`. These are no-ops unless an
+/// audit sink is attached.
+impl API {
+    /// Snapshot a chat member before a member-level change.
+    pub(crate) async fn audit_member_snapshot(&self, chat_id: &str, user_id: i64) -> Option<Value> {
+        self.audit.as_ref()?;
+
+        match self
+            .get_chat_member(&GetChatMemberRequest::new(chat_id.to_string(), user_id))
+            .await
+        {
+            Ok(member) => serde_json::to_value(member).ok(),
+            Err(err) => {
+                warn!(
+                    "audit: can't get chat member {} in {}: {}",
+                    user_id, chat_id, err
+                );
+                None
+            }
+        }
+    }
+
+    /// Snapshot a chat's default permissions before a chat-level change.
+    pub(crate) async fn audit_permissions_snapshot(&self, chat_id: &str) -> Option<Value> {
+        self.audit.as_ref()?;
+
+        match self
+            .get_chat(&GetChatRequest::new(chat_id.to_string()))
+            .await
+        {
+            Ok(chat) => serde_json::to_value(chat.permissions).ok(),
+            Err(err) => {
+                warn!("audit: can't get chat {}: {}", chat_id, err);
+                None
+            }
+        }
+    }
+
+    /// Record a change with the attached audit sink. Failures are logged, but never
+    /// propagated, since the change itself has already been made.
+    pub(crate) async fn audit_record(
+        &self,
+        method: &str,
+        chat_id: &str,
+        user_id: Option<i64>,
+        old: Option<Value>,
+        req: &impl Request,
+    ) {
+        let Some(sink) = self.audit.as_ref() else {
+            return;
+        };
+
+        let entry = AuditEntry {
+            timestamp: self.now(),
+            actor: self.me().await.ok().map(|me| me.id),
+            method: method.to_string(),
+            chat_id: chat_id.to_string(),
+            user_id,
+            old,
+            new: serde_json::to_value(req).unwrap_or_default(),
+            dry_run: self.client.is_dry_run(method),
+        };
+
+        if let Err(err) = sink.record(entry).await {
+            error!("audit: failed to record {}: {}", method, err);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+/// A blocking wrapper around [`crate::API`], for scripts and command line tools that just need
+/// to send a notification, and don't otherwise use async. Calls run to completion on a runtime
+/// owned by the wrapper.
+///
+/// ```no_run
+/// use mobot::{api::SendMessageRequest, blocking, Client};
+///
+/// let api = blocking::API::new(Client::new(std::env::var("TELEGRAM_TOKEN").unwrap())).unwrap();
+/// api.send_message(&SendMessageRequest::new(123456789, "The backup finished."))
+///     .unwrap();
+/// ```
+///
+/// Only the most common methods have blocking versions. Everything else is available through
+/// [`API::call`]:
+///
+/// ```no_run
+/// # use mobot::{api::DeleteMessageRequest, blocking, Client};
+/// # let api = blocking::API::new(Client::new("token".to_string())).unwrap();
+/// api.call(|api| async move {
+///     api.delete_message(&DeleteMessageRequest::new(123456789, 42)).await
+/// })
+/// .unwrap();
+/// ```
+///
+/// The blocking methods panic if they're called from async code, where they'd block the
+/// executor; use [`crate::API`] there.
+use std::{future::Future, sync::Arc};
+
+use anyhow::Result;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    api::{
+        GetUpdatesRequest, InputFile, Message, SendDocumentRequest, SendMessageRequest,
+        SendPhotoRequest, Update, User,
+    },
+    Client,
+};
+
+/// `API` makes Telegram API calls, blocking until they're done. Clones share the same runtime
+/// and connection pool.
+#[derive(Clone)]
+pub struct API {
+    api: crate::API,
+    runtime: Arc<Runtime>,
+}
+
+impl API {
+    /// Returns a new blocking API client. Fails if the runtime can't be started.
+    pub fn new(client: Client) -> Result<Self> {
+        Self::from_async(crate::API::new(client))
+    }
+
+    /// Wrap an existing [`crate::API`], to keep its settings (clock, audit sink, etc).
+    pub fn from_async(api: crate::API) -> Result<Self> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(Self {
+            api,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Returns the async API this wraps.
+    pub fn inner(&self) -> &crate::API {
+        &self.api
+    }
+
+    /// Run `f` with the async API, and block until its future completes. Use it for methods
+    /// without a blocking version.
+    pub fn call<F, T>(&self, f: impl FnOnce(crate::API) -> F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        self.runtime.block_on(f(self.api.clone()))
+    }
+
+    /// See [`crate::API::get_me`].
+    pub fn get_me(&self) -> Result<User> {
+        self.call(|api| async move { api.get_me().await })
+    }
+
+    /// See [`crate::API::send_message`].
+    pub fn send_message(&self, req: &SendMessageRequest) -> Result<Message> {
+        self.call(|api| async move { api.send_message(req).await })
+    }
+
+    /// See [`crate::API::send_photo_file`].
+    pub fn send_photo_file(&self, req: &SendPhotoRequest, photo: &InputFile) -> Result<Message> {
+        self.call(|api| async move { api.send_photo_file(req, photo).await })
+    }
+
+    /// See [`crate::API::send_document_file`].
+    pub fn send_document_file(
+        &self,
+        req: &SendDocumentRequest,
+        document: &InputFile,
+    ) -> Result<Message> {
+        self.call(|api| async move { api.send_document_file(req, document).await })
+    }
+
+    /// See [`crate::API::get_updates`].
+    pub fn get_updates(&self, req: &GetUpdatesRequest) -> Result<Vec<Update>> {
+        self.call(|api| async move { api.get_updates(req).await })
+    }
+}
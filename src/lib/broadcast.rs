@@ -0,0 +1,288 @@
+/// Delivery and engagement tracking for broadcasts. [`BroadcastTracker::send`] sends a message
+/// to each recipient with a deep-link button that's unique to them (see
+/// [`crate::links::start_link`]). Tapping it opens the bot and sends `/start <payload>`, and
+/// [`BroadcastTracker::record_click`] decodes the payload to record who read the broadcast.
+///
+/// Since the button identifies the recipient it was sent to, clicks by someone else (e.g.,
+/// after the message was forwarded) are counted separately.
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    api::{self, InlineKeyboardButton, ReplyMarkup, SendMessageRequest, API, BULK_RATE_PER_SECOND},
+    links,
+    storage::{MemoryStorage, NamespacedStorage, StateStorage},
+    RateLimiter, Text,
+};
+
+/// The storage namespace used for broadcasts, keyed by broadcast ID.
+const NAMESPACE: &str = "broadcasts";
+
+/// The prefix of tracked `/start` payloads.
+const PAYLOAD_PREFIX: &str = "ack_";
+
+/// `Click` is a tap on a broadcast's button.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Click {
+    pub broadcast_id: i64,
+
+    /// The recipient the button was sent to.
+    pub recipient_id: i64,
+
+    /// The user who tapped it. Differs from the recipient for forwarded broadcasts.
+    pub user_id: i64,
+
+    /// When the user tapped it, in Unix time.
+    pub date: i64,
+}
+
+/// `BroadcastStats` summarizes a broadcast's delivery and engagement.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BroadcastStats {
+    /// Recipients the message was sent to.
+    pub delivered: usize,
+
+    /// Recipients the message couldn't be sent to, e.g. because they blocked the bot.
+    pub failed: usize,
+
+    /// Recipients who tapped their own button.
+    pub read: usize,
+
+    /// Other users who tapped a recipient's button.
+    pub forwarded_reads: usize,
+}
+
+/// A broadcast's recipients and clicks, as stored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Record {
+    delivered: Vec<i64>,
+    failed: Vec<i64>,
+    clicks: Vec<Click>,
+}
+
+/// `BroadcastTracker` sends broadcasts with tracked buttons, and records their clicks. Clones
+/// share the same storage.
+///
+/// ```no_run
+/// # use mobot::{broadcast::BroadcastTracker, *};
+/// # async fn example(api: API, mut router: Router<()>) -> anyhow::Result<()> {
+/// let tracker = BroadcastTracker::default();
+/// let stats = tracker
+///     .send(&api, 1, [1001, 1002], "Maintenance tonight at 22:00.", "Got it 👍")
+///     .await?;
+/// println!("Sent to {} users", stats.delivered);
+///
+/// // Record clicks as users open the bot from their buttons.
+/// router.add_route(Route::Message(Matcher::BotCommand("start".into())), {
+///     let tracker = tracker.clone();
+///     move |e: Event, _: State<()>| {
+///         let tracker = tracker.clone();
+///         async move {
+///             match tracker.record_click(e.update.get_message()?).await? {
+///                 Some(_) => Ok(Action::ReplyText("Thanks for reading!".into())),
+///                 None => Ok(Action::Next),
+///             }
+///         }
+///     }
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BroadcastTracker {
+    storage: Arc<dyn StateStorage>,
+
+    /// Serializes read-modify-write cycles on a broadcast's record.
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl Default for BroadcastTracker {
+    fn default() -> Self {
+        Self::new(MemoryStorage::new())
+    }
+}
+
+impl BroadcastTracker {
+    /// Create a new `BroadcastTracker` persisted in `storage`.
+    pub fn new(storage: impl StateStorage + 'static) -> Self {
+        Self::from_arc(Arc::new(storage))
+    }
+
+    /// Create a new `BroadcastTracker` persisted in a shared `storage`.
+    pub fn from_arc(storage: Arc<dyn StateStorage>) -> Self {
+        Self {
+            storage,
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Returns a separate tracker, kept in the same storage under `prefix`, e.g. for one
+    /// tenant of a multi-tenant router.
+    pub fn with_namespace(&self, prefix: impl Into<String>) -> Self {
+        Self::from_arc(Arc::new(NamespacedStorage::new(
+            Arc::clone(&self.storage),
+            prefix,
+        )))
+    }
+
+    /// Returns the `/start` payload identifying `recipient_id`'s copy of the broadcast.
+    pub fn payload(broadcast_id: i64, recipient_id: i64) -> String {
+        format!("{}{}_{}", PAYLOAD_PREFIX, broadcast_id, recipient_id)
+    }
+
+    /// Returns the broadcast and recipient IDs in a tracked `/start` payload.
+    pub fn parse_payload(payload: &str) -> Option<(i64, i64)> {
+        let (broadcast_id, recipient_id) = payload.strip_prefix(PAYLOAD_PREFIX)?.split_once('_')?;
+        Some((broadcast_id.parse().ok()?, recipient_id.parse().ok()?))
+    }
+
+    /// Returns a button labelled `label` that opens the bot `bot_username` with
+    /// `recipient_id`'s payload.
+    pub fn button(
+        bot_username: &str,
+        label: impl Into<String>,
+        broadcast_id: i64,
+        recipient_id: i64,
+    ) -> InlineKeyboardButton {
+        let payload = Self::payload(broadcast_id, recipient_id);
+        InlineKeyboardButton {
+            text: label.into(),
+            // Payloads are at most 45 characters of digits, '-' and '_', so they're valid.
+            url: links::start_link(bot_username, &payload),
+            callback_data: None,
+        }
+    }
+
+    /// Send `text` to each recipient, with a tracked button labelled `label`. Messages are
+    /// sent one at a time, paced by the client's [`RateLimiter`] (or
+    /// [`BULK_RATE_PER_SECOND`] if it has none). Returns the broadcast's stats, including
+    /// earlier sends with the same `broadcast_id`.
+    pub async fn send(
+        &self,
+        api: &API,
+        broadcast_id: i64,
+        recipients: impl IntoIterator<Item = i64>,
+        text: impl Into<Text>,
+        label: &str,
+    ) -> Result<BroadcastStats> {
+        let me = api.me().await?;
+        let bot_username = me
+            .username
+            .ok_or_else(|| anyhow!("The bot has no username to link to"))?;
+        let text = text.into();
+
+        // The client's limiter, if any, already paces every request.
+        let limiter = match api.client.rate_limiter() {
+            Some(_) => None,
+            None => Some(RateLimiter::per_second(BULK_RATE_PER_SECOND)),
+        };
+
+        let (mut delivered, mut failed) = (vec![], vec![]);
+        for recipient_id in recipients {
+            if let Some(limiter) = &limiter {
+                limiter.acquire().await;
+            }
+
+            let button = Self::button(&bot_username, label, broadcast_id, recipient_id);
+            let req = SendMessageRequest::new(recipient_id, text.clone())
+                .with_parse_mode(text.clone().into())
+                .with_reply_markup(ReplyMarkup::inline_keyboard_markup(vec![vec![button]]));
+            let result = api.send_message(&req).await;
+            match api::with_request(result, "sendMessage", &req) {
+                Ok(_) => delivered.push(recipient_id),
+                Err(err) => {
+                    warn!(
+                        "Can't send broadcast {} to {}: {}",
+                        broadcast_id, recipient_id, err
+                    );
+                    failed.push(recipient_id);
+                }
+            }
+        }
+
+        let _guard = self.write_lock.lock().await;
+        let mut record = self.load(broadcast_id).await?;
+        record.delivered.extend(delivered);
+        record.failed.extend(failed);
+        self.store(broadcast_id, &record).await?;
+        Ok(Self::summarize(&record))
+    }
+
+    /// Record a click, if `message` is a `/start` command from a tracked button. Returns the
+    /// click, or `None` for other messages. Repeated clicks by the same user are only recorded
+    /// once.
+    pub async fn record_click(&self, message: &api::Message) -> Result<Option<Click>> {
+        let Some((broadcast_id, recipient_id)) = message
+            .text
+            .as_deref()
+            .and_then(links::start_payload)
+            .and_then(Self::parse_payload)
+        else {
+            return Ok(None);
+        };
+        let user_id = message
+            .from
+            .as_ref()
+            .ok_or_else(|| anyhow!("No sender for broadcast click"))?
+            .id;
+
+        let click = Click {
+            broadcast_id,
+            recipient_id,
+            user_id,
+            date: message.date,
+        };
+
+        let _guard = self.write_lock.lock().await;
+        let mut record = self.load(broadcast_id).await?;
+        if !record
+            .clicks
+            .iter()
+            .any(|c| c.recipient_id == recipient_id && c.user_id == user_id)
+        {
+            record.clicks.push(click.clone());
+            self.store(broadcast_id, &record).await?;
+        }
+        Ok(Some(click))
+    }
+
+    /// Return the broadcast's clicks, oldest first.
+    pub async fn clicks(&self, broadcast_id: i64) -> Result<Vec<Click>> {
+        Ok(self.load(broadcast_id).await?.clicks)
+    }
+
+    /// Return the broadcast's delivery and engagement stats.
+    pub async fn stats(&self, broadcast_id: i64) -> Result<BroadcastStats> {
+        Ok(Self::summarize(&self.load(broadcast_id).await?))
+    }
+
+    fn summarize(record: &Record) -> BroadcastStats {
+        let read = record
+            .clicks
+            .iter()
+            .filter(|c| c.user_id == c.recipient_id)
+            .count();
+        BroadcastStats {
+            delivered: record.delivered.len(),
+            failed: record.failed.len(),
+            read,
+            forwarded_reads: record.clicks.len() - read,
+        }
+    }
+
+    async fn load(&self, broadcast_id: i64) -> Result<Record> {
+        match self.storage.get(NAMESPACE, broadcast_id).await? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(Record::default()),
+        }
+    }
+
+    async fn store(&self, broadcast_id: i64, record: &Record) -> Result<()> {
+        self.storage
+            .set(NAMESPACE, broadcast_id, serde_json::to_value(record)?)
+            .await
+    }
+}
@@ -0,0 +1,94 @@
+/// Per-chat mutual exclusion. With [`crate::Router::with_chat_serialization`], the router holds
+/// a chat's lock while running handlers for an update in that chat, so updates for the same chat
+/// are handled one at a time (updates for different chats still run concurrently). User code can
+/// take the same lock to keep handlers for a chat from running while it updates external
+/// resources for that chat.
+///
+/// Don't lock the current chat from inside its own handler when serialization is on: the router
+/// already holds the lock, and the call will wait forever. Background tasks (see [`crate::Event::spawn`]) run outside
+/// the handler, and can take the lock.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// Unused locks aren't pruned before the map grows past this many chats.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// `ChatLock` is a set of async mutexes, one per chat ID. Clones share the same locks.
+///
+/// Locks that nobody holds or waits on are pruned as the map grows, so it stays within twice
+/// the number of chats with locks in use (or 1024, if that's more). Pruning scans the map, but
+/// only runs after it has doubled, so it costs O(1) per lock taken on average.
+///
+/// ```
+/// # use mobot::*;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let lock = ChatLock::new();
+/// let guard = lock.lock(42).await;
+/// assert!(lock.try_lock(42).is_none());
+/// assert!(lock.try_lock(43).is_some());
+///
+/// drop(guard);
+/// assert!(lock.try_lock(42).is_some());
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ChatLock {
+    locks: Arc<Mutex<Locks>>,
+}
+
+#[derive(Debug, Default)]
+struct Locks {
+    mutexes: HashMap<i64, Arc<AsyncMutex<()>>>,
+
+    /// Twice the size of the map after it was last pruned. It's pruned again when it reaches
+    /// this size, or `PRUNE_THRESHOLD`.
+    prune_at: usize,
+}
+
+/// `ChatGuard` holds a chat's lock. The lock is released when the guard is dropped.
+#[derive(Debug)]
+pub struct ChatGuard {
+    pub chat_id: i64,
+    _guard: OwnedMutexGuard<()>,
+}
+
+impl ChatLock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait for the lock for `chat_id`.
+    pub async fn lock(&self, chat_id: i64) -> ChatGuard {
+        ChatGuard {
+            chat_id,
+            _guard: self.mutex(chat_id).lock_owned().await,
+        }
+    }
+
+    /// Take the lock for `chat_id` if it's free, or return `None` if it's held.
+    pub fn try_lock(&self, chat_id: i64) -> Option<ChatGuard> {
+        Some(ChatGuard {
+            chat_id,
+            _guard: self.mutex(chat_id).try_lock_owned().ok()?,
+        })
+    }
+
+    fn mutex(&self, chat_id: i64) -> Arc<AsyncMutex<()>> {
+        let mut locks = self.locks.lock().unwrap();
+
+        if locks.mutexes.len() >= locks.prune_at.max(PRUNE_THRESHOLD) {
+            // Only the map holds a reference to locks that nobody holds or waits on.
+            locks
+                .mutexes
+                .retain(|_, mutex| Arc::strong_count(mutex) > 1);
+            locks.prune_at = 2 * locks.mutexes.len();
+        }
+
+        Arc::clone(locks.mutexes.entry(chat_id).or_default())
+    }
+}
@@ -0,0 +1,265 @@
+/// Automatic cleanup of the bot's ephemeral messages. Error notices, menus, and "please wait"
+/// messages clutter groups once they've served their purpose. [`Cleanup`] deletes them after a
+/// TTL, with a background task (see [`Tasks`]) per message.
+///
+/// Pending deletions are tracked per chat in a [`StateStorage`]. With persistent storage, the
+/// router reschedules them when it starts (see [`crate::Router::with_cleanup`]), so messages
+/// still get deleted if the bot restarts before their TTL is up.
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    api::{self, DeleteMessageRequest, API},
+    storage::{MemoryStorage, NamespacedStorage, StateStorage},
+    Tasks,
+};
+
+/// The storage namespace used for pending deletions, keyed by chat ID.
+const NAMESPACE: &str = "cleanup";
+
+/// The storage namespace for the IDs of chats with pending deletions, kept under ID 0.
+const CHATS_NAMESPACE: &str = "cleanup_chats";
+
+/// A message waiting to be deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Pending {
+    message_id: i64,
+
+    /// When to delete the message, in Unix time.
+    delete_at: i64,
+}
+
+/// `Cleanup` deletes messages after a TTL. Clones share the same storage.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use std::time::Duration;
+/// async fn handle(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     // Deleted after the router's cleanup TTL.
+///     e.send_ephemeral("Only admins can do that.").await?;
+///
+///     // Deleted after 10 seconds.
+///     let message = e.send_message("Working on it...").await?;
+///     let cleanup = e.cleanup.as_ref().ok_or(anyhow::anyhow!("cleanup disabled"))?;
+///     cleanup
+///         .schedule_in(&e.api, &e.tasks, &message, Duration::from_secs(10))
+///         .await?;
+///     Ok(Action::Done)
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Cleanup {
+    storage: Arc<dyn StateStorage>,
+    ttl: Duration,
+
+    /// Serializes read-modify-write cycles on pending deletions.
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl Default for Cleanup {
+    fn default() -> Self {
+        Self::new(MemoryStorage::new())
+    }
+}
+
+impl Cleanup {
+    /// Create a new `Cleanup` persisted in `storage`, deleting messages after a minute.
+    pub fn new(storage: impl StateStorage + 'static) -> Self {
+        Self::from_arc(Arc::new(storage))
+    }
+
+    /// Create a new `Cleanup` persisted in a shared `storage`.
+    pub fn from_arc(storage: Arc<dyn StateStorage>) -> Self {
+        Self {
+            storage,
+            ttl: Duration::from_secs(60),
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Returns a separate cleanup, kept in the same storage under `prefix`, e.g. for one tenant
+    /// of a multi-tenant router.
+    pub fn with_namespace(&self, prefix: impl Into<String>) -> Self {
+        Self::from_arc(Arc::new(NamespacedStorage::new(
+            Arc::clone(&self.storage),
+            prefix,
+        )))
+        .with_ttl(self.ttl)
+    }
+
+    /// Set how long messages are kept before they're deleted.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns how long messages are kept before they're deleted.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Delete `message` once the TTL is up.
+    pub async fn schedule(
+        &self,
+        api: &Arc<API>,
+        tasks: &Tasks,
+        message: &api::Message,
+    ) -> Result<()> {
+        self.schedule_in(api, tasks, message, self.ttl).await
+    }
+
+    /// Delete `message` after `ttl`.
+    pub async fn schedule_in(
+        &self,
+        api: &Arc<API>,
+        tasks: &Tasks,
+        message: &api::Message,
+        ttl: Duration,
+    ) -> Result<()> {
+        let chat_id = message.chat.id;
+        let pending = Pending {
+            message_id: message.message_id,
+            delete_at: api.now() + ttl.as_secs_f64().ceil() as i64,
+        };
+
+        {
+            let _guard = self.write_lock.lock().await;
+            let mut entries = self.load(chat_id).await?;
+            entries.retain(|p| p.message_id != pending.message_id);
+            entries.push(pending.clone());
+            self.store(chat_id, entries).await?;
+        }
+
+        self.spawn(api, tasks, chat_id, pending, ttl);
+        Ok(())
+    }
+
+    /// Keep the message after all, e.g., if a menu is still in use.
+    pub async fn keep(&self, chat_id: i64, message_id: i64) -> Result<()> {
+        self.take(chat_id, message_id, None).await?;
+        Ok(())
+    }
+
+    /// Return the IDs of the chat's messages waiting to be deleted.
+    pub async fn pending(&self, chat_id: i64) -> Result<Vec<i64>> {
+        Ok(self
+            .load(chat_id)
+            .await?
+            .into_iter()
+            .map(|p| p.message_id)
+            .collect())
+    }
+
+    /// Schedule the deletions left pending by an earlier run, e.g. after a restart. Messages
+    /// whose TTL is up are deleted right away. Returns the number of deletions scheduled.
+    pub async fn resume(&self, api: &Arc<API>, tasks: &Tasks) -> Result<usize> {
+        let now = api.now();
+        let mut count = 0;
+        for chat_id in self.chats().await? {
+            for pending in self.load(chat_id).await? {
+                let delay = Duration::from_secs((pending.delete_at - now).max(0) as u64);
+                self.spawn(api, tasks, chat_id, pending, delay);
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Delete the message after `delay`, unless it's kept or rescheduled in the meantime.
+    /// Deletions still waiting when the router shuts down stay pending, for
+    /// [`Cleanup::resume`].
+    fn spawn(
+        &self,
+        api: &Arc<API>,
+        tasks: &Tasks,
+        chat_id: i64,
+        pending: Pending,
+        delay: Duration,
+    ) {
+        let cleanup = self.clone();
+        let api = Arc::clone(api);
+        let shutdown = tasks.clone();
+        let sleep = api.clock().sleep(delay);
+        tasks.spawn(async move {
+            tokio::select! {
+                _ = sleep => {}
+                _ = shutdown.cancelled() => return Ok(()),
+            }
+
+            let message_id = pending.message_id;
+            if !cleanup
+                .take(chat_id, message_id, Some(pending.delete_at))
+                .await?
+            {
+                return Ok(());
+            }
+            let req = DeleteMessageRequest::new(chat_id, message_id);
+            if let Err(err) = api.delete_message(&req).await {
+                // The message may have been deleted by someone else.
+                warn!(
+                    "Can't clean up message {} in chat {}: {}",
+                    message_id, chat_id, err
+                );
+            }
+            Ok(())
+        });
+    }
+
+    /// Remove the message from the pending deletions, if it's due at `delete_at` (or at any
+    /// time, if `None`). Returns false if it wasn't pending.
+    async fn take(&self, chat_id: i64, message_id: i64, delete_at: Option<i64>) -> Result<bool> {
+        let _guard = self.write_lock.lock().await;
+        let mut entries = self.load(chat_id).await?;
+        let len = entries.len();
+        entries.retain(|p| {
+            p.message_id != message_id || delete_at.is_some_and(|at| at != p.delete_at)
+        });
+        if entries.len() == len {
+            return Ok(false);
+        }
+        self.store(chat_id, entries).await?;
+        Ok(true)
+    }
+
+    async fn chats(&self) -> Result<Vec<i64>> {
+        match self.storage.get(CHATS_NAMESPACE, 0).await? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn load(&self, chat_id: i64) -> Result<Vec<Pending>> {
+        match self.storage.get(NAMESPACE, chat_id).await? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Store the chat's pending deletions, and keep the chat index in sync. The caller holds
+    /// the write lock.
+    async fn store(&self, chat_id: i64, entries: Vec<Pending>) -> Result<()> {
+        let mut chats = self.chats().await?;
+        let indexed = chats.contains(&chat_id);
+        if entries.is_empty() {
+            self.storage.delete(NAMESPACE, chat_id).await?;
+            chats.retain(|&id| id != chat_id);
+        } else {
+            self.storage
+                .set(NAMESPACE, chat_id, serde_json::to_value(entries)?)
+                .await?;
+            if !indexed {
+                chats.push(chat_id);
+            }
+        }
+
+        if indexed != chats.contains(&chat_id) {
+            self.storage
+                .set(CHATS_NAMESPACE, 0, serde_json::to_value(chats)?)
+                .await?;
+        }
+        Ok(())
+    }
+}
@@ -1,11 +1,27 @@
-use std::fmt::{self, Formatter};
+use std::{
+    collections::HashMap,
+    fmt::{self, Formatter},
+    net::{IpAddr, SocketAddr},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use bytes;
 use derive_more::{From, Into, FromStr, Display};
+use futures::{future, Future};
 use serde::{de::DeserializeOwned, Serialize};
+use tokio_util::sync::CancellationToken;
+use web_time::Instant;
 
-use crate::api::ApiResponse;
+use crate::{
+    api::{self, upload::Multipart, ApiError, ApiResponse, BotApiVersion, InputFile},
+    RateLimiter,
+};
 
 /// This is a wrapper around the Telegram API token string. Get your token from
 /// [@BotFather](https://t.me/BotFather).
@@ -33,13 +49,403 @@ impl fmt::Debug for PostFn {
     }
 }
 
+/// Methods that are not sent to Telegram when the client is in dry-run mode. All of these
+/// return `true` on success.
+const DESTRUCTIVE_METHODS: &[&str] = &[
+    "banChatMember",
+    "unbanChatMember",
+    "restrictChatMember",
+    "promoteChatMember",
+    "setChatPermissions",
+    "deleteMessage",
+    "deleteMessages",
+    "declineChatJoinRequest",
+];
+
+/// `IpPreference` picks which IP versions the client connects over. Some hosting environments
+/// have broken IPv6 routes to Telegram, so connections over IPv6 hang until they time out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpPreference {
+    /// Use addresses in the order the system resolver returns them.
+    #[default]
+    Any,
+
+    /// Try IPv4 addresses first, then IPv6.
+    PreferIpv4,
+
+    /// Try IPv6 addresses first, then IPv4.
+    PreferIpv6,
+
+    /// Only connect over IPv4.
+    Ipv4Only,
+
+    /// Only connect over IPv6.
+    Ipv6Only,
+}
+
+impl IpPreference {
+    /// Filter and order resolved `addrs` by this preference.
+    ///
+    /// ```
+    /// # use mobot::*;
+    /// # use std::net::SocketAddr;
+    /// let v6: SocketAddr = "[2001:67c:4e8:f004::9]:443".parse().unwrap();
+    /// let v4: SocketAddr = "149.154.167.220:443".parse().unwrap();
+    /// assert_eq!(IpPreference::PreferIpv4.apply(vec![v6, v4]), vec![v4, v6]);
+    /// assert_eq!(IpPreference::Ipv6Only.apply(vec![v6, v4]), vec![v6]);
+    /// ```
+    pub fn apply(self, mut addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+        match self {
+            IpPreference::Any => {}
+            IpPreference::PreferIpv4 => addrs.sort_by_key(|addr| !addr.is_ipv4()),
+            IpPreference::PreferIpv6 => addrs.sort_by_key(|addr| !addr.is_ipv6()),
+            IpPreference::Ipv4Only => addrs.retain(|addr| addr.is_ipv4()),
+            IpPreference::Ipv6Only => addrs.retain(|addr| addr.is_ipv6()),
+        }
+        addrs
+    }
+}
+
+/// A DNS resolver that applies an [`IpPreference`] to the system resolver's results.
+#[cfg(not(target_arch = "wasm32"))]
+struct PreferenceResolver(IpPreference);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl reqwest::dns::Resolve for PreferenceResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let preference = self.0;
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((host.as_str(), 0)).await?;
+            let addrs = preference.apply(addrs.collect());
+            if addrs.is_empty() {
+                return Err(format!("No {:?} addresses for {}", preference, host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// `HttpConfig` configures connection reuse for the underlying HTTP client. All requests made
+/// by a [`Client`] share one connection pool, and connections to `api.telegram.org` are
+/// negotiated as HTTP/2 (via ALPN) where possible, so concurrent requests are multiplexed over a
+/// single connection instead of each paying for a new TCP and TLS handshake.
+///
+/// On `wasm32`, requests are sent with the browser's `fetch`, which manages connections itself,
+/// so none of these apply.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// How long idle connections are kept in the pool. `None` keeps them forever.
+    pub pool_idle_timeout: Option<Duration>,
+
+    /// Maximum number of idle connections kept per host.
+    pub pool_max_idle_per_host: usize,
+
+    /// TCP keepalive interval for pooled connections.
+    pub tcp_keepalive: Option<Duration>,
+
+    /// Interval for HTTP/2 PING frames, which keep idle connections from being dropped by
+    /// proxies and load balancers between long polls.
+    pub http2_keep_alive_interval: Option<Duration>,
+
+    /// Which IP versions to connect over.
+    pub ip_preference: IpPreference,
+
+    /// Static addresses for hosts, used instead of DNS. For example, map `api.telegram.org` to
+    /// a known-good address when the local resolver returns unreachable ones.
+    pub dns_overrides: HashMap<String, Vec<IpAddr>>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            pool_max_idle_per_host: 8,
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            http2_keep_alive_interval: Some(Duration::from_secs(30)),
+            ip_preference: IpPreference::Any,
+            dns_overrides: HashMap::new(),
+        }
+    }
+}
+
+impl HttpConfig {
+    /// Connect to `host` at `addr`, instead of the addresses DNS returns for it.
+    pub fn with_dns_override(mut self, host: impl Into<String>, addr: impl Into<IpAddr>) -> Self {
+        self.dns_overrides
+            .entry(host.into().to_ascii_lowercase())
+            .or_default()
+            .push(addr.into());
+        self
+    }
+
+    pub fn with_ip_preference(mut self, ip_preference: IpPreference) -> Self {
+        self.ip_preference = ip_preference;
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn build(&self) -> reqwest::Client {
+        let mut builder = reqwest::Client::builder();
+        if self.ip_preference != IpPreference::Any {
+            builder = builder.dns_resolver(PreferenceResolver(self.ip_preference));
+        }
+        for (host, addrs) in &self.dns_overrides {
+            // Port 0 is replaced by the URL's port.
+            let addrs: Vec<_> = addrs.iter().map(|ip| SocketAddr::new(*ip, 0)).collect();
+            builder = builder.resolve_to_addrs(host, &addrs);
+        }
+
+        builder
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .tcp_keepalive(self.tcp_keepalive)
+            .http2_keep_alive_interval(self.http2_keep_alive_interval)
+            .http2_keep_alive_while_idle(true)
+            .http2_adaptive_window(true)
+            .build()
+            .expect("Can't build HTTP client")
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn build(&self) -> reqwest::Client {
+        reqwest::Client::new()
+    }
+}
+
+/// `ClientStats` is a snapshot of the client's request counters. See [`Client::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientStats {
+    /// Total number of API requests made.
+    pub requests: u64,
+
+    /// Number of requests that failed, either at the HTTP level or with an API error.
+    pub failures: u64,
+
+    /// Number of requests currently in progress.
+    pub in_flight: u64,
+
+    /// Number of responses received over HTTP/1.x connections. Always zero on wasm32, where
+    /// `fetch` doesn't say which version it used.
+    pub http1_responses: u64,
+
+    /// Number of responses received over HTTP/2 connections. If this stays at zero, something
+    /// between the bot and Telegram (e.g., a proxy) is preventing HTTP/2.
+    pub http2_responses: u64,
+
+    /// Total time spent in completed requests.
+    pub total_latency: Duration,
+}
+
+impl ClientStats {
+    /// Average time per completed request.
+    pub fn avg_latency(&self) -> Duration {
+        let completed = self.requests - self.in_flight;
+        if completed == 0 {
+            return Duration::ZERO;
+        }
+        self.total_latency / completed as u32
+    }
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    requests: AtomicU64,
+    failures: AtomicU64,
+    in_flight: AtomicU64,
+    http1_responses: AtomicU64,
+    http2_responses: AtomicU64,
+    latency_us: AtomicU64,
+}
+
+/// Counts a request as in flight until it's finished, or dropped (e.g., because its call was
+/// cancelled). Dropped requests count as failures.
+struct InFlight<'a> {
+    stats: &'a Stats,
+    start: Instant,
+    finished: bool,
+}
+
+impl<'a> InFlight<'a> {
+    fn start(stats: &'a Stats) -> Self {
+        stats.requests.fetch_add(1, Ordering::Relaxed);
+        stats.in_flight.fetch_add(1, Ordering::Relaxed);
+        Self {
+            stats,
+            start: Instant::now(),
+            finished: false,
+        }
+    }
+
+    fn finish(mut self, ok: bool) {
+        self.finished = ok;
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.stats.in_flight.fetch_sub(1, Ordering::Relaxed);
+        self.stats
+            .latency_us
+            .fetch_add(self.start.elapsed().as_micros() as u64, Ordering::Relaxed);
+        if !self.finished {
+            self.stats.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+tokio::task_local! {
+    static CALL_OPTIONS: CallOptions;
+}
+
+/// `CallOptions` bound the API calls made in a [`CallOptions::scope`]: calls still running at
+/// the deadline, or when a cancellation token is cancelled, are abandoned (dropping the HTTP
+/// request) and fail with [`ApiError::Timeout`] or [`ApiError::Cancelled`]. Calls made after
+/// that fail right away.
+///
+/// The router runs handlers in a scope when [`crate::Router::with_handler_timeout`] is set.
+/// Tasks spawned by a handler don't inherit its scope, so pass them the event's cancellation
+/// token to stop their calls when the handler times out:
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use std::time::Duration;
+/// async fn handler(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     let options = CallOptions::new().with_cancellation_token(e.cancellation.clone());
+///     let api = e.api.clone();
+///     tokio::spawn(options.scope(async move {
+///         let req = api::SendMessageRequest::new(1, "Still working...");
+///         api.send_message(&req).await
+///     }));
+///
+///     // Give this call 5 seconds, even if the handler has more time.
+///     CallOptions::new()
+///         .with_timeout(Duration::from_secs(5))
+///         .scope(e.send_message("Done!"))
+///         .await?;
+///     Ok(Action::Done)
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    deadline: Option<Instant>,
+    cancellation_tokens: Vec<CancellationToken>,
+}
+
+impl CallOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Abandon calls that are still running `timeout` from now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Abandon calls that are still running at `deadline`.
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(self.deadline.map_or(deadline, |d| d.min(deadline)));
+        self
+    }
+
+    /// Abandon calls that are still running when `token` is cancelled.
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_tokens.push(token);
+        self
+    }
+
+    /// Returns the options of the scope the current task is in, if any.
+    pub fn current() -> Option<CallOptions> {
+        CALL_OPTIONS.try_with(Clone::clone).ok()
+    }
+
+    /// Returns the earliest deadline, if there is one.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns true if any of the cancellation tokens was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_tokens.iter().any(|t| t.is_cancelled())
+    }
+
+    /// Run `f` with these options applied to the API calls it makes. Scopes nest: calls in an
+    /// inner scope are bound by the earliest deadline, and by every cancellation token, of the
+    /// scopes they're in.
+    pub fn scope<F: Future>(self, f: F) -> impl Future<Output = F::Output> {
+        let options = match Self::current() {
+            Some(outer) => {
+                let mut options = match outer.deadline {
+                    Some(deadline) => self.with_deadline(deadline),
+                    None => self,
+                };
+                options
+                    .cancellation_tokens
+                    .extend(outer.cancellation_tokens);
+                options
+            }
+            None => self,
+        };
+        CALL_OPTIONS.scope(options, f)
+    }
+
+    /// Run `call` for `method` within these options, and `timeout`, if it's set.
+    async fn run<T>(
+        &self,
+        method: &str,
+        timeout: Option<Duration>,
+        call: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let deadline = match timeout {
+            Some(timeout) => self.clone().with_timeout(timeout).deadline,
+            None => self.deadline,
+        };
+        // There's no tokio timer on wasm32, so deadlines aren't enforced there; cancel the
+        // request with a token instead.
+        #[cfg(target_arch = "wasm32")]
+        let expired = async {
+            let _ = deadline;
+            future::pending::<()>().await
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let expired = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => future::pending().await,
+            }
+        };
+        let cancelled = async {
+            if self.cancellation_tokens.is_empty() {
+                future::pending::<()>().await;
+            }
+            let tokens = self.cancellation_tokens.iter();
+            future::select_all(tokens.map(|t| Box::pin(t.cancelled()))).await;
+        };
+
+        tokio::select! {
+            biased;
+            _ = cancelled => Err(ApiError::Cancelled(method.to_string()).into()),
+            _ = expired => Err(ApiError::Timeout(method.to_string()).into()),
+            result = call => result,
+        }
+    }
+}
+
 #[async_trait::async_trait]
 pub trait Post {
     async fn post(&self, method: String, req: String) -> Result<String>;
 }
 
+/// The largest file Telegram's servers accept as an upload.
+pub const MAX_UPLOAD_SIZE: u64 = 50 * 1024 * 1024;
+
+/// The largest file a local Bot API server accepts as an upload.
+pub const LOCAL_MAX_UPLOAD_SIZE: u64 = 2000 * 1024 * 1024;
+
 /// This is a thin shim around the Telegram HTTP client. Requires a valid API token.
 pub struct Client {
+    token: ApiToken,
+
     /// This base URL is used for all requests and is constructed from the
     /// provided API token.
     base_url: String,
@@ -47,14 +453,39 @@ pub struct Client {
     /// This is URL is used for requests for download files
     file_url: String,
 
-    /// The underlying HTTP client.
+    /// The underlying HTTP client. This is shared by all requests, so connections are pooled.
     client: reqwest::Client,
 
+    /// Connection settings used to build `client`.
+    http_config: HttpConfig,
+
+    /// Request counters.
+    stats: Arc<Stats>,
+
     /// A post handler that implements the Post trait. Useful for testing.
     post_handler: Option<Box<dyn Post + Send + Sync>>,
 
     /// A function that handles POST requests. This is useful for testing.
     post_handler_fn: Option<PostFn>,
+
+    /// If true, destructive methods are logged but not sent.
+    dry_run: bool,
+
+    /// If set, every request waits for its turn here before it's sent.
+    rate_limiter: Option<RateLimiter>,
+
+    /// If true, requests are logged as curl commands, and failed responses in full.
+    log_requests: bool,
+
+    /// The server's Bot API version, if known. Newer methods and fields are gated.
+    api_version: RwLock<Option<BotApiVersion>>,
+
+    /// If true, the server is a local Bot API server running in `--local` mode, on the same
+    /// machine: files are passed by path, and downloaded from disk.
+    local_mode: bool,
+
+    /// Timeouts for specific methods, by method name.
+    method_timeouts: HashMap<String, Duration>,
 }
 
 impl Client {
@@ -64,12 +495,155 @@ impl Client {
         Self {
             base_url: format!("https://api.telegram.org/bot{token}"),
             file_url: format!("https://api.telegram.org/file/bot{token}"),
-            client: reqwest::Client::new(),
+            token,
+            client: HttpConfig::default().build(),
+            http_config: HttpConfig::default(),
+            stats: Arc::new(Stats::default()),
             post_handler: None,
             post_handler_fn: None,
+            dry_run: false,
+            rate_limiter: None,
+            log_requests: false,
+            api_version: RwLock::new(None),
+            method_timeouts: HashMap::new(),
+            local_mode: false,
+        }
+    }
+
+    /// Send requests to the Bot API server at `url` (e.g., `http://localhost:8081` for a
+    /// [local Bot API server](https://github.com/tdlib/telegram-bot-api)), instead of
+    /// `https://api.telegram.org`.
+    pub fn with_server_url(mut self, url: impl Into<String>) -> Self {
+        let url = url.into();
+        let url = url.trim_end_matches('/');
+        self.base_url = format!("{}/bot{}", url, self.token);
+        self.file_url = format!("{}/file/bot{}", url, self.token);
+        self
+    }
+
+    /// Set if the server is a local Bot API server running in `--local` mode on the same
+    /// machine (see [`Client::with_server_url`]). In local mode, [`InputFile`]s with a path
+    /// are passed to the server by path instead of uploaded, uploads can be up to
+    /// [`LOCAL_MAX_UPLOAD_SIZE`], and [`Client::download_file`] reads the absolute paths
+    /// `getFile` returns from disk.
+    pub fn with_local_mode(mut self, local_mode: bool) -> Self {
+        self.local_mode = local_mode;
+        self
+    }
+
+    /// Returns true if the server is a local Bot API server in `--local` mode.
+    pub fn is_local_mode(&self) -> bool {
+        self.local_mode
+    }
+
+    /// Use `config` for the underlying HTTP client's connection pool.
+    pub fn with_http_config(mut self, config: HttpConfig) -> Self {
+        self.client = config.build();
+        self.http_config = config;
+        self
+    }
+
+    /// Returns the HTTP connection settings.
+    pub fn http_config(&self) -> &HttpConfig {
+        &self.http_config
+    }
+
+    /// Returns a snapshot of the client's request counters.
+    pub fn stats(&self) -> ClientStats {
+        ClientStats {
+            requests: self.stats.requests.load(Ordering::Relaxed),
+            failures: self.stats.failures.load(Ordering::Relaxed),
+            in_flight: self.stats.in_flight.load(Ordering::Relaxed),
+            http1_responses: self.stats.http1_responses.load(Ordering::Relaxed),
+            http2_responses: self.stats.http2_responses.load(Ordering::Relaxed),
+            total_latency: Duration::from_micros(self.stats.latency_us.load(Ordering::Relaxed)),
         }
     }
 
+    /// Enable or disable dry-run mode. In dry-run mode, destructive methods (bans, restrictions,
+    /// permission changes, message deletions, etc.) are logged but not sent to Telegram, and
+    /// return a synthesized success. This lets you test moderation logic against a live group
+    /// without side effects.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Returns true if `method` would be skipped because the client is in dry-run mode.
+    pub fn is_dry_run(&self, method: &str) -> bool {
+        self.dry_run && DESTRUCTIVE_METHODS.contains(&method)
+    }
+
+    /// Space out all requests made by this client with `limiter`.
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Returns the client's rate limiter, if any.
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+
+    /// Pin the Bot API version of the server, for self-hosted servers that run an older
+    /// version. Methods the server doesn't have fail without being sent, and request fields
+    /// it doesn't know are dropped, with a warning. See [`BotApiVersion`].
+    pub fn with_api_version(self, version: BotApiVersion) -> Self {
+        self.set_api_version(Some(version));
+        self
+    }
+
+    /// Returns the server's Bot API version, if it's known.
+    pub fn api_version(&self) -> Option<BotApiVersion> {
+        *self.api_version.read().unwrap()
+    }
+
+    /// Set the server's Bot API version, or `None` to stop gating requests.
+    pub fn set_api_version(&self, version: Option<BotApiVersion>) {
+        *self.api_version.write().unwrap() = version;
+    }
+
+    /// Log every request as a curl command (at `info` level) that reproduces it, with the API
+    /// token redacted, and the full response body of failed requests (at `warn` level). Useful
+    /// for tracking down requests Telegram rejects with `400 Bad Request`.
+    pub fn with_request_logging(mut self, log_requests: bool) -> Self {
+        self.log_requests = log_requests;
+        self
+    }
+
+    /// Abandon calls to `method` that take longer than `timeout`, failing them with
+    /// [`ApiError::Timeout`]. Calls in a [`CallOptions::scope`] also stop at its deadline, if
+    /// that's sooner. Long-polling `getUpdates` needs a timeout longer than its poll timeout.
+    pub fn with_method_timeout(mut self, method: impl Into<String>, timeout: Duration) -> Self {
+        self.method_timeouts.insert(method.into(), timeout);
+        self
+    }
+
+    /// Returns a curl command that sends `req` to `method`, with the API token redacted.
+    ///
+    /// ```
+    /// # use mobot::*;
+    /// let client = Client::new("123:secret".to_string());
+    /// let req = serde_json::json!({"chat_id": 1, "text": "it's"});
+    /// assert_eq!(
+    ///     client.curl_command("sendMessage", &req),
+    ///     r#"curl -X POST 'https://api.telegram.org/bot<TOKEN>/sendMessage' -H 'Content-Type: application/json' -d '{"chat_id":1,"text":"it'\''s"}'"#
+    /// );
+    /// ```
+    pub fn curl_command(&self, method: &str, req: &impl Serialize) -> String {
+        let base_url = match self.base_url.rsplit_once("/bot") {
+            Some((host, _)) => format!("{}/bot<TOKEN>", host),
+            None => self.base_url.clone(),
+        };
+        let body = serde_json::to_string(req).unwrap_or_default();
+        format!(
+            "curl -X POST '{}/{}' -H 'Content-Type: application/json' -d '{}'",
+            base_url,
+            method,
+            body.replace('\'', r"'\''")
+        )
+    }
+
     /// Sets a function that handles POST requests. This is useful for testing.
     pub fn with_post_handler_fn(mut self, post_fn: impl Into<PostFn>) -> Self {
         self.post_handler_fn = Some(post_fn.into());
@@ -87,38 +661,210 @@ impl Client {
         Req: crate::api::Request,
         Resp: Serialize + DeserializeOwned + Clone,
     {
-        let body;
+        self.post_upload(method, req, None).await
+    }
+
+    /// Like [`Client::post`], but uploads `file` as the field `field`, with the rest of `req`
+    /// sent as form fields. In local mode, files with a path are passed by path instead.
+    pub async fn post_file<Req, Resp>(
+        &self,
+        method: &str,
+        req: &Req,
+        field: &str,
+        file: &InputFile,
+    ) -> Result<Resp>
+    where
+        Req: crate::api::Request,
+        Resp: Serialize + DeserializeOwned + Clone,
+    {
+        if self.local_mode
+            && let Some(path) = &file.path
+        {
+            let path = std::path::absolute(path)?;
+            let mut req = serde_json::to_value(req)?;
+            req[field] = format!("file://{}", path.display()).into();
+            return self.post_upload(method, &req, None).await;
+        }
+
+        let limit = if self.local_mode {
+            LOCAL_MAX_UPLOAD_SIZE
+        } else {
+            MAX_UPLOAD_SIZE
+        };
+        let size = match (&file.path, file.data.is_empty()) {
+            (Some(path), true) => file_size(path).await?,
+            _ => file.data.len() as u64,
+        };
+        if size > limit {
+            bail!(
+                "Can't upload {}: {} bytes is over the {} byte limit{}",
+                file.name,
+                size,
+                limit,
+                if self.local_mode {
+                    ""
+                } else {
+                    " (a local Bot API server accepts larger files)"
+                }
+            );
+        }
+
+        let loaded;
+        let file = match (&file.path, file.data.is_empty()) {
+            (Some(path), true) => {
+                loaded = file.clone().with_data(read_file(path).await?);
+                &loaded
+            }
+            _ => file,
+        };
+        self.post_upload(method, req, Some((field, file))).await
+    }
+
+    async fn post_upload<Req, Resp>(
+        &self,
+        method: &str,
+        req: &Req,
+        upload: Option<(&str, &InputFile)>,
+    ) -> Result<Resp>
+    where
+        Req: Serialize + Sync,
+        Resp: Serialize + DeserializeOwned + Clone,
+    {
+        if self.is_dry_run(method) {
+            info!("Dry run: POST /{}: {}", method, serde_json::to_string(req)?);
+            return Ok(serde_json::from_value(serde_json::Value::Bool(true))?);
+        }
+
+        // Drop fields the server doesn't know, or fail if it doesn't have the method.
+        let gated = match self.api_version() {
+            Some(version) => api::version::gate(version, method, &serde_json::to_value(req)?)?,
+            None => None,
+        };
+
+        let options = CallOptions::current().unwrap_or_default();
+        let timeout = self.method_timeouts.get(method).copied();
+        let call = async {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+
+            let in_flight = InFlight::start(&self.stats);
+            let result = match &gated {
+                Some(gated) => self.send(method, gated, upload).await,
+                None => self.send(method, req, upload).await,
+            };
+            in_flight.finish(result.is_ok());
+            result
+        };
+        options.run(method, timeout, call).await
+    }
+
+    async fn send<Req, Resp>(
+        &self,
+        method: &str,
+        req: &Req,
+        upload: Option<(&str, &InputFile)>,
+    ) -> Result<Resp>
+    where
+        Req: Serialize + Sync,
+        Resp: Serialize + DeserializeOwned + Clone,
+    {
+        if self.log_requests {
+            info!("{}", self.curl_command(method, req));
+        }
+
+        let mut multipart = match upload {
+            Some((field, file)) => Some(Multipart::new(&serde_json::to_value(req)?, field, file)),
+            None => None,
+        };
+
+        // Post handlers only see the request's JSON, but uploads still report their progress.
+        let body: bytes::Bytes;
+        if (self.post_handler_fn.is_some() || self.post_handler.is_some())
+            && let Some(multipart) = multipart.take()
+        {
+            multipart.into_bytes();
+        }
         if let Some(ref post_handler) = self.post_handler_fn {
-            body = (post_handler.0)(method.to_string(), serde_json::to_string(req)?).unwrap();
+            body = (post_handler.0)(method.to_string(), serde_json::to_string(req)?)
+                .unwrap()
+                .into();
         } else if let Some(ref post_handler) = self.post_handler {
             body = post_handler
                 .post(method.to_string(), serde_json::to_string(req)?)
-                .await?;
+                .await?
+                .into();
         } else {
             debug!(
                 "POST /{}:\n{}",
                 method,
                 serde_json::to_string_pretty(req).unwrap()
             );
-            body = self
-                .client
-                .post(format!("{}/{}", self.base_url, method))
-                .json(&req)
-                .send()
-                .await?
-                .text()
-                .await?;
+            let request = self.client.post(format!("{}/{}", self.base_url, method));
+            let request = match multipart {
+                // fetch can't stream request bodies, so uploads are sent in one piece.
+                #[cfg(target_arch = "wasm32")]
+                Some(multipart) => request
+                    .header(reqwest::header::CONTENT_TYPE, multipart.content_type())
+                    .body(multipart.into_bytes()),
+                #[cfg(not(target_arch = "wasm32"))]
+                Some(multipart) => request
+                    .header(reqwest::header::CONTENT_TYPE, multipart.content_type())
+                    .body(reqwest::Body::wrap(multipart)),
+                None => request.json(&req),
+            };
+            let response = request.send().await?;
+
+            // fetch doesn't say which protocol version it used.
+            #[cfg(not(target_arch = "wasm32"))]
+            if response.version() == reqwest::Version::HTTP_2 {
+                self.stats.http2_responses.fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.stats.http1_responses.fetch_add(1, Ordering::Relaxed);
+            }
+            body = response.bytes().await?;
+        }
+
+        // Keep the raw body around to log it if the request fails. `Bytes` clones are cheap.
+        let raw_body = self.log_requests.then(|| body.clone());
+        let log_failure = |reason: &dyn fmt::Display| {
+            if let Some(raw_body) = &raw_body {
+                warn!(
+                    "Request failed ({}): {}\nResponse: {}",
+                    reason,
+                    self.curl_command(method, req),
+                    String::from_utf8_lossy(raw_body)
+                );
+            }
+        };
+
+        // Parse straight from the response bytes into the typed response, with no intermediate
+        // `String` or `serde_json::Value`.
+        let response: ApiResponse<Resp> = crate::json::from_bytes(body).inspect_err(|err| {
+            log_failure(err);
+        })?;
+        if !response.ok {
+            log_failure(&response.description.as_deref().unwrap_or("not ok"));
         }
-        let response = ApiResponse::<Resp>::from_str(&body)?;
         debug!(
             "Response /{}:\n{}",
             method,
             serde_json::to_string_pretty(&response).unwrap()
         );
-        Ok(response.result()?.clone())
+        response.into_result()
     }
 
+    /// Download the file at `file_path`, as returned by `getFile`. In local mode, the server
+    /// returns absolute paths (or `file://` URLs), which are read from disk.
     pub async fn download_file(&self, file_path: &String) -> Result<bytes::Bytes> {
+        if self.local_mode {
+            let path = file_path.strip_prefix("file://").unwrap_or(file_path);
+            if Path::new(path).is_absolute() {
+                debug!("Reading file {} from disk", path);
+                return Ok(read_file(path).await?.into());
+            }
+        }
+
         debug!("Downloading file /{}:\n", file_path);
         let body = self
             .client
@@ -131,3 +877,28 @@ impl Client {
         Ok(body)
     }
 }
+
+/// Files are only read from disk with the `runtime` or `notify` feature, neither of which is
+/// available on wasm32.
+#[cfg(any(feature = "runtime", feature = "notify"))]
+async fn read_file(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    Ok(tokio::fs::read(path).await?)
+}
+
+#[cfg(not(any(feature = "runtime", feature = "notify")))]
+async fn read_file(path: impl AsRef<Path>) -> Result<Vec<u8>> {
+    bail!(
+        "Can't read {}: reading files needs the `runtime` or `notify` feature",
+        path.as_ref().display()
+    )
+}
+
+#[cfg(any(feature = "runtime", feature = "notify"))]
+async fn file_size(path: impl AsRef<Path>) -> Result<u64> {
+    Ok(tokio::fs::metadata(path).await?.len())
+}
+
+#[cfg(not(any(feature = "runtime", feature = "notify")))]
+async fn file_size(path: impl AsRef<Path>) -> Result<u64> {
+    read_file(path).await.map(|data| data.len() as u64)
+}
@@ -0,0 +1,113 @@
+/// Time sources. The framework reads the current time, measures intervals and waits through a
+/// [`Clock`] (see [`API::with_clock`]), so tests can control time deterministically with a
+/// [`FakeClock`] instead of waiting on the system clock. This covers update ages, throttled
+/// edits and progress bars, reply pacing, album grouping, message cleanup, anti-raid windows,
+/// polling backoff, webhook health checks and token rotation, handler metrics, and the dates
+/// of fake messages (see [`crate::fake::FakeAPI::with_clock`]).
+///
+/// Timing that bounds real network I/O doesn't use the clock: HTTP request latency in
+/// [`crate::ClientStats`], [`crate::CallOptions`] deadlines, and [`crate::RateLimiter`] pacing
+/// always measure real time.
+///
+/// [`API::with_clock`]: crate::API::with_clock
+use std::{fmt, sync::Arc, time::Duration};
+
+use chrono::{DateTime, TimeZone, Utc};
+use futures::future::BoxFuture;
+use tokio::sync::watch;
+
+/// `Clock` returns the current time.
+pub trait Clock: Send + Sync + fmt::Debug {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// The current time in Unix time (seconds), as used by Telegram.
+    fn timestamp(&self) -> i64 {
+        self.now().timestamp()
+    }
+
+    /// Time elapsed since `earlier`, or zero if `earlier` is in the future.
+    fn elapsed(&self, earlier: DateTime<Utc>) -> Duration {
+        (self.now() - earlier).to_std().unwrap_or_default()
+    }
+
+    /// Wait for `duration` to pass on this clock.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// `SystemClock` is the default clock, and returns the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// `FakeClock` is a manually controlled clock for tests. Time only moves when you call
+/// [`FakeClock::advance`] or [`FakeClock::set`], and [`Clock::sleep`] returns once the clock
+/// has been moved past the end of the sleep. Clones share the same time.
+///
+/// ```
+/// # use mobot::clock::*;
+/// # use std::time::Duration;
+/// let clock = FakeClock::at(1_000_000);
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!(clock.timestamp(), 1_000_060);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FakeClock {
+    now: Arc<watch::Sender<DateTime<Utc>>>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new(Utc::now())
+    }
+}
+
+impl FakeClock {
+    /// Create a new `FakeClock` starting at `now`.
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(watch::Sender::new(now)),
+        }
+    }
+
+    /// Create a new `FakeClock` starting at the given Unix time.
+    pub fn at(timestamp: i64) -> Self {
+        Self::new(Utc.timestamp_opt(timestamp, 0).unwrap())
+    }
+
+    /// Move the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        let duration = chrono::Duration::from_std(duration).unwrap();
+        self.now.send_modify(|now| *now += duration);
+    }
+
+    /// Set the clock to `now`.
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.now.send_replace(now);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.borrow()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        let deadline = self.now() + chrono::Duration::from_std(duration).unwrap();
+        let mut now = self.now.subscribe();
+        Box::pin(async move {
+            // Once every clone of the clock is dropped, time can't move any more.
+            while *now.borrow_and_update() < deadline {
+                if now.changed().await.is_err() {
+                    return;
+                }
+            }
+        })
+    }
+}
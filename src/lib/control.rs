@@ -0,0 +1,256 @@
+/// An admin control chat. [`ControlChat`] designates a chat (e.g., a private group of the bot's
+/// operators) where the router reports handler errors, rate limit errors, and dead letters
+/// (updates no handler was installed for), and where operators can run commands against the
+/// running bot: `/stats` for the client's request counters (see [`crate::ClientStats`]) and
+/// per-handler metrics (see [`crate::HandlerMetrics`]), and `/toggle <feature>` for feature flags
+/// (see [`crate::Features`]).
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+
+use crate::{
+    api::{ApiError, SendMessageRequest, API},
+    handler::{BotHandlerFn, BotState},
+    Action, Event, State,
+};
+
+/// The number of handlers listed by `/stats`, slowest first.
+const TOP_HANDLERS: usize = 5;
+
+/// `ControlEvent` is something the router reports to the control chat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlEvent {
+    /// A handler failed.
+    Error { chat_id: i64, error: String },
+
+    /// A handler failed because Telegram rate limited the bot.
+    RateLimited {
+        chat_id: i64,
+        retry_after: Option<Duration>,
+    },
+
+    /// An update arrived for a route with no handlers.
+    DeadLetter { chat_id: i64, route: String },
+}
+
+impl ControlEvent {
+    /// Classify a handler error for `chat_id`.
+    pub fn from_error(chat_id: i64, err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<ApiError>() {
+            Some(api_err) if api_err.is_rate_limit() => ControlEvent::RateLimited {
+                chat_id,
+                retry_after: api_err.retry_after(),
+            },
+            _ => ControlEvent::Error {
+                chat_id,
+                error: err.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ControlEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControlEvent::Error { chat_id, error } => {
+                write!(f, "Error in chat {}: {}", chat_id, error)
+            }
+            ControlEvent::RateLimited {
+                chat_id,
+                retry_after: Some(retry_after),
+            } => write!(
+                f,
+                "Rate limited in chat {}, retry after {}s",
+                chat_id,
+                retry_after.as_secs()
+            ),
+            ControlEvent::RateLimited { chat_id, .. } => {
+                write!(f, "Rate limited in chat {}", chat_id)
+            }
+            ControlEvent::DeadLetter { chat_id, route } => {
+                write!(f, "No handlers for {} in chat {}", route, chat_id)
+            }
+        }
+    }
+}
+
+/// Counts of events reported to the control chat, whether or not they were sent.
+#[derive(Debug, Default)]
+struct Counters {
+    errors: AtomicU64,
+    rate_limits: AtomicU64,
+    dead_letters: AtomicU64,
+}
+
+/// `ControlChat` reports to, and takes commands from, the chat `chat_id`. Clones share the same
+/// counters. Attach it to the router with [`crate::Router::with_control_chat`], and add its
+/// command handler ([`ControlChat::handler`]) to take commands:
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use mobot::control::ControlChat;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN").unwrap());
+/// let control = ControlChat::new(-1001234567890);
+/// let mut router = Router::<()>::new(client).with_control_chat(control.clone());
+/// router.add_route_with_priority(Route::Message(Matcher::Any), 100, control.handler());
+/// router.start().await;
+/// # }
+/// ```
+///
+/// Anyone who can post in the control chat can run commands, so keep it private.
+#[derive(Debug, Clone)]
+pub struct ControlChat {
+    pub chat_id: i64,
+
+    /// If true, handler errors are sent to the chat.
+    pub report_errors: bool,
+
+    /// If true, rate limit errors are sent to the chat.
+    pub report_rate_limits: bool,
+
+    /// If true, dead letters are sent to the chat.
+    pub report_dead_letters: bool,
+
+    counters: Arc<Counters>,
+}
+
+impl ControlChat {
+    /// A control chat that's sent every report.
+    pub fn new(chat_id: i64) -> Self {
+        Self {
+            chat_id,
+            report_errors: true,
+            report_rate_limits: true,
+            report_dead_letters: true,
+            counters: Arc::new(Counters::default()),
+        }
+    }
+
+    pub fn with_report_errors(mut self, report: bool) -> Self {
+        self.report_errors = report;
+        self
+    }
+
+    pub fn with_report_rate_limits(mut self, report: bool) -> Self {
+        self.report_rate_limits = report;
+        self
+    }
+
+    pub fn with_report_dead_letters(mut self, report: bool) -> Self {
+        self.report_dead_letters = report;
+        self
+    }
+
+    /// Count `event`, and send it to the control chat if it's reported. Failures to send are
+    /// logged.
+    pub async fn report(&self, api: &API, event: ControlEvent) {
+        let (counter, enabled) = match event {
+            ControlEvent::Error { .. } => (&self.counters.errors, self.report_errors),
+            ControlEvent::RateLimited { .. } => {
+                (&self.counters.rate_limits, self.report_rate_limits)
+            }
+            ControlEvent::DeadLetter { .. } => {
+                (&self.counters.dead_letters, self.report_dead_letters)
+            }
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        if !enabled {
+            return;
+        }
+
+        let req = SendMessageRequest::new(self.chat_id, event.to_string());
+        if let Err(err) = api.send_message(&req).await {
+            error!("Can't report to control chat {}: {}", self.chat_id, err);
+        }
+    }
+
+    /// Returns the `/stats` report: the client's request counters, the events reported so
+    /// far, the handlers that took the most time, and the feature flags that were set.
+    pub fn stats(&self, e: &Event) -> String {
+        let stats = e.api.client.stats();
+        let mut lines = vec![
+            format!("Requests: {}", stats.requests),
+            format!("Failures: {}", stats.failures),
+            format!("In flight: {}", stats.in_flight),
+            format!("Average latency: {}ms", stats.avg_latency().as_millis()),
+            format!("Errors: {}", self.counters.errors.load(Ordering::Relaxed)),
+            format!(
+                "Rate limits: {}",
+                self.counters.rate_limits.load(Ordering::Relaxed)
+            ),
+            format!(
+                "Dead letters: {}",
+                self.counters.dead_letters.load(Ordering::Relaxed)
+            ),
+        ];
+
+        for (name, stats) in e.metrics.snapshot().into_iter().take(TOP_HANDLERS) {
+            lines.push(format!(
+                "Handler {}: {} runs, avg {}ms, max {}ms, {} slow",
+                name,
+                stats.invocations,
+                stats.avg_latency().as_millis(),
+                stats.max_latency.as_millis(),
+                stats.slow
+            ));
+        }
+
+        for (name, enabled) in e.features.flags() {
+            lines.push(format!(
+                "Feature {}: {}",
+                name,
+                if enabled { "on" } else { "off" }
+            ));
+        }
+        lines.join("\n")
+    }
+
+    /// A handler for the control chat's commands. Messages in other chats, and messages that
+    /// aren't commands, are passed on with [`Action::Next`].
+    pub fn handler<S: BotState>(&self) -> Box<dyn BotHandlerFn<S>> {
+        Box::new(self.clone())
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for ControlChat {
+    async fn run(&self, event: Event, _: State<S>) -> Result<Action, anyhow::Error> {
+        if event.update.chat_id().ok() != Some(self.chat_id) {
+            return Ok(Action::Next);
+        }
+
+        let Ok(text) = event.update.text() else {
+            return Ok(Action::Next);
+        };
+        let mut words = text.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let command = command.split('@').next().unwrap_or_default();
+
+        let reply = match (command, words.next()) {
+            ("/stats", _) => self.stats(&event),
+            ("/toggle", Some(name)) => {
+                let enabled = !event.features.is_enabled(name);
+                event.features.set(name, enabled);
+                format!(
+                    "Feature {} {}.",
+                    name,
+                    if enabled { "enabled" } else { "disabled" }
+                )
+            }
+            ("/toggle", None) => "Usage: /toggle <feature>".to_string(),
+            _ => return Ok(Action::Next),
+        };
+
+        Ok(Action::ReplyText(reply))
+    }
+}
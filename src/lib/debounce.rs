@@ -0,0 +1,124 @@
+/// Double-tap protection for inline keyboard buttons. Users often tap a button twice, or tap
+/// it again while the bot is slow to respond, and every tap arrives as a separate callback
+/// query. With [`crate::Router::with_callback_debounce`], the router drops repeated taps on the
+/// same message by the same user while the first tap is being handled, and for a short window
+/// after, so handlers don't run their side effects twice. Dropped taps are answered with a
+/// toast, so the user's client stops showing a spinner.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{api, clock::Clock};
+
+/// Identifies the message a tap was on, and who tapped it.
+type Key = (i64, String);
+
+/// When each (user, message) last finished handling a tap, or `None` while a tap is being
+/// handled.
+type Taps = Arc<Mutex<HashMap<Key, Option<DateTime<Utc>>>>>;
+
+/// `CallbackDebounce` tracks callback queries per (user, message). Clones share the same
+/// state.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use std::time::Duration;
+/// # let client = Client::new("token".to_string());
+/// let router: Router<()> = Router::new(client).with_callback_debounce(
+///     CallbackDebounce::new(Duration::from_secs(1)).with_text("Hang on, still working…"),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct CallbackDebounce {
+    window: Duration,
+    text: String,
+    taps: Taps,
+}
+
+impl CallbackDebounce {
+    /// Drop taps while an earlier tap on the same message is being handled, and for `window`
+    /// after it's done.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            text: "Already processing…".to_string(),
+            taps: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Set the toast shown for dropped taps.
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self
+    }
+
+    /// Returns the toast shown for dropped taps.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns a debounce with the same settings and no taps seen, e.g. for one tenant of a
+    /// multi-tenant router.
+    #[cfg(feature = "webhooks")]
+    pub(crate) fn cleared(&self) -> Self {
+        Self::new(self.window).with_text(self.text.clone())
+    }
+
+    /// Start handling `query`. Returns a guard to hold while it's handled, or `None` if the
+    /// query should be dropped.
+    pub fn start(
+        &self,
+        query: &api::CallbackQuery,
+        clock: &Arc<dyn Clock>,
+    ) -> Option<DebounceGuard> {
+        let target = match (&query.message, &query.inline_message_id) {
+            (Some(message), _) => format!("{}:{}", message.chat.id, message.message_id),
+            (None, Some(id)) => id.clone(),
+            (None, None) => return Some(DebounceGuard::noop()),
+        };
+        let key = (query.from.id, target);
+        let now = clock.now();
+        let window = chrono::Duration::from_std(self.window).unwrap_or(chrono::Duration::MAX);
+
+        let mut taps = self.taps.lock().unwrap();
+        taps.retain(|_, done| done.is_none_or(|done| now - done < window));
+        if taps.contains_key(&key) {
+            return None;
+        }
+        taps.insert(key.clone(), None);
+
+        Some(DebounceGuard {
+            taps: Some((Arc::clone(&self.taps), key)),
+            clock: Arc::clone(clock),
+        })
+    }
+}
+
+/// `DebounceGuard` marks a tap as being handled. When dropped, the tap's debounce window
+/// starts.
+pub struct DebounceGuard {
+    taps: Option<(Taps, Key)>,
+    clock: Arc<dyn Clock>,
+}
+
+impl DebounceGuard {
+    /// A guard for taps that can't be told apart, and aren't debounced.
+    fn noop() -> Self {
+        Self {
+            taps: None,
+            clock: Arc::new(crate::clock::SystemClock),
+        }
+    }
+}
+
+impl Drop for DebounceGuard {
+    fn drop(&mut self) {
+        if let Some((taps, key)) = self.taps.take() {
+            taps.lock().unwrap().insert(key, Some(self.clock.now()));
+        }
+    }
+}
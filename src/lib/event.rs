@@ -1,19 +1,195 @@
 use crate::{
     api::{self, API},
-    Text,
+    metrics::HandlerMetrics,
+    tasks::Tasks,
+    ChatLock, Cleanup, Extensions, Features, KeyboardState, MessageHistory, Settings, Text,
+    UserDataStores,
 };
-use std::sync::Arc;
+use std::{future::Future, sync::Arc};
+use tokio_util::sync::CancellationToken;
 
 /// `Event` represents an event sent to a chat handler.
 #[derive(Clone)]
 pub struct Event {
+    /// Handle to the Telegram API. Clone it to keep calling the API from background tasks
+    /// spawned by the handler.
     pub api: Arc<API>,
     pub update: crate::Update,
+
+    /// Per-chat settings, shared by all handlers registered with the router.
+    pub settings: Settings,
+
+    /// Background tasks, tied to the router's lifecycle. See [`Event::spawn`].
+    pub tasks: Tasks,
+
+    /// The router's per-chat locks. With [`crate::Router::with_chat_serialization`], the
+    /// current chat's lock is held while the handler runs.
+    pub chat_lock: ChatLock,
+
+    /// The stores holding user data, for handling data export and deletion requests.
+    pub user_data: UserDataStores,
+
+    /// Recent messages per chat, if enabled with [`crate::Router::with_message_history`].
+    pub history: Option<MessageHistory>,
+
+    /// The inline keyboards attached to sent messages, if enabled with
+    /// [`crate::Router::with_keyboard_state`].
+    pub keyboards: Option<KeyboardState>,
+
+    /// Deletes ephemeral messages after a TTL, if enabled with
+    /// [`crate::Router::with_cleanup`]. See [`Event::send_ephemeral`].
+    pub cleanup: Option<Cleanup>,
+
+    /// The router's feature flags.
+    pub features: Features,
+
+    /// The router's per-handler metrics.
+    pub metrics: HandlerMetrics,
+
+    /// Values attached to the update by earlier handlers. Shared by all handlers that run for
+    /// the update.
+    pub extensions: Extensions,
+
+    /// The tenant the update belongs to, for multi-tenant routers (see [`crate::tenants`]).
+    pub tenant: Option<String>,
+
+    /// Cancelled if the handler runs past the router's handler timeout (see
+    /// [`crate::Router::with_handler_timeout`]). Pass it to [`crate::CallOptions`] in tasks the
+    /// handler spawns, to cancel their API calls along with the handler's.
+    pub cancellation: CancellationToken,
 }
 
 impl Event {
     pub fn new(api: Arc<API>, update: crate::Update) -> Self {
-        Self { api, update }
+        Self {
+            api,
+            update,
+            settings: Settings::default(),
+            tasks: Tasks::default(),
+            chat_lock: ChatLock::default(),
+            user_data: UserDataStores::default(),
+            history: None,
+            keyboards: None,
+            cleanup: None,
+            features: Features::default(),
+            metrics: HandlerMetrics::default(),
+            extensions: Extensions::default(),
+            tenant: None,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// Attach a settings store to the event.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Attach a background task tracker to the event.
+    pub fn with_tasks(mut self, tasks: Tasks) -> Self {
+        self.tasks = tasks;
+        self
+    }
+
+    /// Attach the router's per-chat locks to the event.
+    pub fn with_chat_lock(mut self, chat_lock: ChatLock) -> Self {
+        self.chat_lock = chat_lock;
+        self
+    }
+
+    /// Attach the router's user data stores to the event.
+    pub fn with_user_data(mut self, user_data: UserDataStores) -> Self {
+        self.user_data = user_data;
+        self
+    }
+
+    /// Attach the router's message history to the event.
+    pub fn with_history(mut self, history: Option<MessageHistory>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Attach the router's keyboard state to the event.
+    pub fn with_keyboards(mut self, keyboards: Option<KeyboardState>) -> Self {
+        self.keyboards = keyboards;
+        self
+    }
+
+    /// Attach the router's message cleanup to the event.
+    pub fn with_cleanup(mut self, cleanup: Option<Cleanup>) -> Self {
+        self.cleanup = cleanup;
+        self
+    }
+
+    /// Attach the router's feature flags to the event.
+    pub fn with_features(mut self, features: Features) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Attach the router's per-handler metrics to the event.
+    pub fn with_metrics(mut self, metrics: HandlerMetrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Attach the update's extensions to the event.
+    pub fn with_extensions(mut self, extensions: Extensions) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// Attach the tenant the update belongs to, for multi-tenant routers.
+    pub fn with_tenant(mut self, tenant: Option<String>) -> Self {
+        self.tenant = tenant;
+        self
+    }
+
+    /// Run `task` in the background, with a clone of this event. The task outlives the
+    /// handler, but not the router: on shutdown, the router waits for background tasks to
+    /// finish before cancelling them. Clone the handler's `State` into the task if it needs it.
+    ///
+    /// ```no_run
+    /// # use mobot::*;
+    /// async fn handle(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+    ///     e.spawn(|e| async move {
+    ///         tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    ///         e.send_message("Five seconds later...").await?;
+    ///         Ok(())
+    ///     });
+    ///     Ok(Action::Done)
+    /// }
+    /// ```
+    pub fn spawn<F, Fut>(&self, task: F)
+    where
+        F: FnOnce(Event) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.tasks.spawn(task(self.clone()));
+    }
+
+    /// Returns true if the update's message mentions this bot (see [`api::Message::mentions_bot`]).
+    /// The bot's username is fetched with `getMe` once, and cached.
+    pub async fn mentions_bot(&self) -> anyhow::Result<bool> {
+        let Ok(message) = self.update.get_message_or_post() else {
+            return Ok(false);
+        };
+
+        let me = self.api.me().await?;
+        Ok(me
+            .username
+            .as_deref()
+            .is_some_and(|username| message.mentions_bot(username)))
+    }
+
+    /// Returns true if the update's message is a reply to a message from this bot.
+    pub async fn is_reply_to_bot(&self) -> anyhow::Result<bool> {
+        let Ok(message) = self.update.get_message_or_post() else {
+            return Ok(false);
+        };
+
+        let me = self.api.me().await?;
+        Ok(message.is_reply_to_bot(me.id))
     }
 
     /// Acknowledge a callback query.
@@ -21,11 +197,12 @@ impl Event {
         let query_id = self.update.query_id()?.to_string();
 
         let mut req = api::AnswerCallbackQueryRequest::new(query_id);
-        if text.is_some() {
-            req = req.with_text(text.unwrap());
+        if let Some(text) = text {
+            req = req.with_text(text);
         }
 
-        self.api.answer_callback_query(&req).await
+        let result = self.api.answer_callback_query(&req).await;
+        api::with_request(result, "answerCallbackQuery", &req)
     }
 
     /// Remove the inline keyboard from a message.
@@ -34,36 +211,43 @@ impl Event {
         let message_id = self.update.message_id()?;
 
         // Remove the inline keyboard.
-        self.api
-            .edit_message_reply_markup(&api::EditMessageReplyMarkupRequest {
-                base: api::EditMessageBase::new()
-                    .with_chat_id(chat_id)
-                    .with_message_id(message_id)
-                    .with_reply_markup(api::ReplyMarkup::inline_keyboard_markup(vec![vec![]])),
-            })
-            .await
+        let req = api::EditMessageReplyMarkupRequest {
+            base: api::EditMessageBase::new()
+                .with_chat_id(chat_id)
+                .with_message_id(message_id)
+                .with_reply_markup(api::ReplyMarkup::inline_keyboard_markup(vec![vec![]])),
+        };
+        let result = self.api.edit_message_reply_markup(&req).await;
+        api::with_request(result, "editMessageReplyMarkup", &req)
     }
 
     /// Send a chat action.
     pub async fn send_chat_action(&self, action: api::ChatAction) -> anyhow::Result<bool> {
-        self.api
-            .send_chat_action(&api::SendChatActionRequest::new(
-                self.update.chat_id()?,
-                action,
-            ))
-            .await
+        let req = api::SendChatActionRequest::new(self.update.chat_id()?, action);
+        let result = self.api.send_chat_action(&req).await;
+        api::with_request(result, "sendChatAction", &req)
     }
 
     /// Send a message to the chat.
     pub async fn send_message(&self, text: impl Into<Text>) -> anyhow::Result<api::Message> {
         let text = text.into();
 
-        self.api
-            .send_message(
-                &api::SendMessageRequest::new(self.update.chat_id()?, text.clone())
-                    .with_parse_mode(text.into()),
-            )
-            .await
+        let req = api::SendMessageRequest::new(self.update.chat_id()?, text.clone())
+            .with_parse_mode(text.into());
+        let result = self.api.send_message(&req).await;
+        api::with_request(result, "sendMessage", &req)
+    }
+
+    /// Send a message to the chat, and delete it once the cleanup TTL is up (see
+    /// [`crate::cleanup`]). Fails without sending anything if the router has no cleanup (see
+    /// [`crate::Router::with_cleanup`]).
+    pub async fn send_ephemeral(&self, text: impl Into<Text>) -> anyhow::Result<api::Message> {
+        let cleanup = self.cleanup.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("Can't send an ephemeral message without Router::with_cleanup")
+        })?;
+        let message = self.send_message(text).await?;
+        cleanup.schedule(&self.api, &self.tasks, &message).await?;
+        Ok(message)
     }
 
     /// Edit the message with the given text (uses the parsemode of the message)
@@ -79,14 +263,14 @@ impl Event {
     ) -> anyhow::Result<api::Message> {
         let chat_id = self.update.chat_id()?;
 
-        self.api
-            .edit_message_text(&api::EditMessageTextRequest {
-                base: api::EditMessageBase::new()
-                    .with_chat_id(chat_id)
-                    .with_message_id(message_id),
-                text: text.into(),
-            })
-            .await
+        let req = api::EditMessageTextRequest {
+            base: api::EditMessageBase::new()
+                .with_chat_id(chat_id)
+                .with_message_id(message_id),
+            text: text.into(),
+        };
+        let result = self.api.edit_message_text(&req).await;
+        api::with_request(result, "editMessageText", &req)
     }
 
     // Delete the last message
@@ -94,18 +278,18 @@ impl Event {
         let chat_id = self.update.chat_id()?;
         let message_id = self.update.message_id()?;
 
-        self.api
-            .delete_message(&api::DeleteMessageRequest::new(chat_id, message_id))
-            .await
+        let req = api::DeleteMessageRequest::new(chat_id, message_id);
+        let result = self.api.delete_message(&req).await;
+        api::with_request(result, "deleteMessage", &req)
     }
 
     // Delete a specific message
     pub async fn delete_message(&self, message_id: i64) -> anyhow::Result<bool> {
         let chat_id = self.update.chat_id()?;
 
-        self.api
-            .delete_message(&api::DeleteMessageRequest::new(chat_id, message_id))
-            .await
+        let req = api::DeleteMessageRequest::new(chat_id, message_id);
+        let result = self.api.delete_message(&req).await;
+        api::with_request(result, "deleteMessage", &req)
     }
 
     pub async fn send_menu(
@@ -116,25 +300,28 @@ impl Event {
         let text = text.into();
         let chat_id = self.update.chat_id()?;
 
-        self.api
-            .send_message(
-                &api::SendMessageRequest::new(chat_id, text.clone())
-                    .with_parse_mode(text.into())
-                    .with_reply_markup(api::ReplyMarkup::inline_keyboard_markup(vec![menu
-                        .iter()
-                        .map(|item| api::InlineKeyboardButton::from(item).with_callback_data(item))
-                        .collect()])),
-            )
-            .await
+        let req = api::SendMessageRequest::new(chat_id, text.clone())
+            .with_parse_mode(text.into())
+            .with_reply_markup(api::ReplyMarkup::inline_keyboard_markup(vec![menu
+                .iter()
+                .map(|item| api::InlineKeyboardButton::from(item).with_callback_data(item))
+                .collect()]));
+        let result = self.api.send_message(&req).await;
+        api::with_request(result, "sendMessage", &req)
     }
 
     /// Send a sticker to the chat.
+    #[cfg(feature = "stickers")]
     pub async fn send_sticker(&self, sticker: impl Into<String>) -> anyhow::Result<api::Message> {
-        self.api
-            .send_sticker(&api::SendStickerRequest::new(
-                self.update.chat_id()?,
-                sticker.into(),
-            ))
-            .await
+        let req = api::SendStickerRequest::new(self.update.chat_id()?, sticker.into());
+        let result = self.api.send_sticker(&req).await;
+        api::with_request(result, "sendSticker", &req)
+    }
+
+    /// Throw an animated dice in the chat. The result is in the returned message's `dice`.
+    pub async fn send_dice(&self, emoji: api::DiceEmoji) -> anyhow::Result<api::Message> {
+        let req = api::SendDiceRequest::new(self.update.chat_id()?, emoji);
+        let result = self.api.send_dice(&req).await;
+        api::with_request(result, "sendDice", &req)
     }
 }
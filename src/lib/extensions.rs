@@ -0,0 +1,72 @@
+/// Typed values attached to an update while it's handled. The router creates one
+/// [`Extensions`] map per update, and every handler that runs for the update (including
+/// filters, see [`crate::filters`]) sees the same map in [`crate::Event::extensions`]. Handlers
+/// that run early, and return [`crate::Action::Next`], can use it to pass on what they worked
+/// out (e.g., the sender's admin status, a parsed command, or their locale) to later handlers.
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+/// `Extensions` is a map holding at most one value of each type. Clones share the same values.
+///
+/// ```
+/// # use mobot::*;
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Locale(String);
+///
+/// let extensions = Extensions::new();
+/// extensions.insert(Locale("de".into()));
+/// assert_eq!(extensions.get::<Locale>(), Some(Locale("de".into())));
+/// assert_eq!(extensions.get::<u32>(), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Extensions {
+    values: Arc<RwLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>>,
+}
+
+impl Extensions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert `value`, returning the previous value of the same type, if any.
+    pub fn insert<T: Send + Sync + 'static>(&self, value: T) -> Option<T> {
+        self.values
+            .write()
+            .unwrap()
+            .insert(TypeId::of::<T>(), Box::new(value))
+            .and_then(|old| old.downcast().ok())
+            .map(|old| *old)
+    }
+
+    /// Returns a clone of the value of type `T`, if there is one.
+    pub fn get<T: Clone + Send + Sync + 'static>(&self) -> Option<T> {
+        self.with(|value: Option<&T>| value.cloned())
+    }
+
+    /// Call `f` with a reference to the value of type `T`, if there is one. Useful for values
+    /// that can't be cloned. Don't touch the map from inside `f`.
+    pub fn with<T: Send + Sync + 'static, R>(&self, f: impl FnOnce(Option<&T>) -> R) -> R {
+        let values = self.values.read().unwrap();
+        f(values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref()))
+    }
+
+    /// Returns true if there's a value of type `T`.
+    pub fn contains<T: Send + Sync + 'static>(&self) -> bool {
+        self.values.read().unwrap().contains_key(&TypeId::of::<T>())
+    }
+
+    /// Remove and return the value of type `T`, if there is one.
+    pub fn remove<T: Send + Sync + 'static>(&self) -> Option<T> {
+        self.values
+            .write()
+            .unwrap()
+            .remove(&TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+    }
+}
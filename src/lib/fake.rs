@@ -5,12 +5,17 @@ use anyhow::Result;
 use async_trait::async_trait;
 use rand::distr::Alphanumeric;
 use rand::RngExt;
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::sync::{mpsc, Mutex};
 
 use crate::{
     api::{self, ApiResponse},
     client::Post,
+    clock::{Clock, SystemClock},
     Update,
 };
 
@@ -35,6 +40,12 @@ pub struct FakeChat {
 
     /// Events from the bot are received here.
     chat_rx: Arc<Mutex<tokio::sync::mpsc::Receiver<Update>>>,
+
+    /// Dates the messages sent to the bot.
+    clock: Arc<dyn Clock>,
+
+    /// Numbers the messages sent to the bot, shared with the `FakeAPI`.
+    message_ids: MessageIds,
 }
 
 impl FakeChat {
@@ -46,9 +57,7 @@ impl FakeChat {
         let chat_tx = Arc::clone(&self.chat_tx);
 
         Ok(chat_tx
-            .send(Update::Message(
-                FakeMessage::text(chat_id, from, text).into(),
-            ))
+            .send(Update::Message(self.message(chat_id, from, text)))
             .await?)
     }
 
@@ -61,6 +70,8 @@ impl FakeChat {
 
         let mut message: api::Message = FakeMessage::text(chat_id, from, text).into();
         message.message_id = message_id;
+        message.date = self.clock.timestamp();
+        message.edit_date = Some(message.date);
 
         Ok(chat_tx.send(Update::EditedMessage(message)).await?)
     }
@@ -80,7 +91,7 @@ impl FakeChat {
                     .map(char::from)
                     .collect(),
                 from: from.clone().into(),
-                message: Some(FakeMessage::text(chat_id, from, "callback query").into()),
+                message: Some(self.message(chat_id, from, "callback query")),
                 inline_message_id: None,
                 data: Some(data),
             }))
@@ -98,6 +109,29 @@ impl FakeChat {
         let mut rx = self.chat_rx.lock().await;
         rx.recv().await
     }
+
+    /// A new message from the user, dated by the clock.
+    fn message(&self, chat_id: i64, from: String, text: impl Into<String>) -> api::Message {
+        let mut message: api::Message = FakeMessage::text(chat_id, from, text).into();
+        message.message_id = self.message_ids.next(chat_id);
+        message.date = self.clock.timestamp();
+        message
+    }
+}
+
+/// The last message ID used in each chat. As in Telegram, IDs increase per chat, and messages
+/// from the bot and from users share them.
+#[derive(Debug, Clone, Default)]
+pub struct MessageIds(Arc<std::sync::Mutex<HashMap<i64, i64>>>);
+
+impl MessageIds {
+    /// Returns the ID for the next message in `chat_id`.
+    pub fn next(&self, chat_id: i64) -> i64 {
+        let mut ids = self.0.lock().unwrap();
+        let id = ids.entry(chat_id).or_default();
+        *id += 1;
+        *id
+    }
 }
 
 /// `FakeAPI` is a fake Telegram API server. It implements the Telegram API, but instead of
@@ -119,6 +153,21 @@ pub struct FakeAPI {
 
     /// A map of chat IDs to a channel to send messages to.
     pub chat_map: Arc<Mutex<HashMap<i64, Arc<mpsc::Sender<Update>>>>>,
+
+    /// User IDs that `getChatMember` reports as administrators. Everyone else is a member.
+    pub admins: Arc<Mutex<HashSet<i64>>>,
+
+    /// Chat permissions set with `setChatPermissions`, by chat ID. They're returned by `getChat`.
+    pub chat_permissions: Arc<Mutex<HashMap<i64, api::ChatPermissions>>>,
+
+    /// Emoji statuses set with `setUserEmojiStatus`, by user ID. They're returned by `getChat`.
+    pub emoji_statuses: Arc<Mutex<HashMap<i64, api::SetUserEmojiStatusRequest>>>,
+
+    /// Dates the messages sent by the bot, and by the users of chats created afterwards.
+    pub clock: Arc<dyn Clock>,
+
+    /// Numbers the messages sent by the bot, and by the users of its chats.
+    pub message_ids: MessageIds,
 }
 
 impl Default for FakeAPI {
@@ -138,9 +187,20 @@ impl FakeAPI {
             chat_tx: Arc::new(tx),
             chat_rx: Arc::new(Mutex::new(rx)),
             chat_map: Arc::new(Mutex::new(HashMap::new())),
+            admins: Arc::new(Mutex::new(HashSet::new())),
+            chat_permissions: Arc::new(Mutex::new(HashMap::new())),
+            emoji_statuses: Arc::new(Mutex::new(HashMap::new())),
+            clock: Arc::new(SystemClock),
+            message_ids: MessageIds::default(),
         }
     }
 
+    /// Date messages with `clock`, e.g. the [`crate::clock::FakeClock`] the bot's API uses.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
     /// Create a new `FakeChat` object.
     pub async fn create_chat(&self, from: impl Into<String>) -> FakeChat {
         // Create a new Chat ID and channel for this chat session.
@@ -157,9 +217,26 @@ impl FakeAPI {
             from: from.into(),
             chat_tx: Arc::clone(&self.chat_tx),
             chat_rx: Arc::new(Mutex::new(rx)),
+            clock: Arc::clone(&self.clock),
+            message_ids: self.message_ids.clone(),
         }
     }
 
+    /// A message from the bot, dated by the clock. Its ID is set by the caller.
+    fn message(&self) -> api::Message {
+        let mut message = api::Message::fake(self.bot_name.as_str());
+        message.date = self.clock.timestamp();
+        message
+    }
+
+    /// A new message from the bot in `chat_id`.
+    fn new_message(&self, chat_id: i64) -> api::Message {
+        let mut message = self.message();
+        message.chat.id = chat_id;
+        message.message_id = self.message_ids.next(chat_id);
+        message
+    }
+
     /// Wait for an event from the bot and return it as a standard Telegram update. Typically,
     /// this is called by the router in a loop.
     async fn get_updates(&self, req: api::GetUpdatesRequest) -> ApiResponse<Vec<api::Update>> {
@@ -197,6 +274,20 @@ impl FakeAPI {
                             ..Default::default()
                         }])
                     }
+                    Update::MessageReaction(reaction) => {
+                        ApiResponse::Ok(vec![api::Update {
+                            update_id,
+                            message_reaction: Some(reaction.clone()),
+                            ..Default::default()
+                        }])
+                    }
+                    Update::ChatJoinRequest(request) => {
+                        ApiResponse::Ok(vec![api::Update {
+                            update_id,
+                            chat_join_request: Some(request.clone()),
+                            ..Default::default()
+                        }])
+                    }
                     _ => { unimplemented!() }
                 }
             }
@@ -206,10 +297,21 @@ impl FakeAPI {
         }
     }
 
+    /// There's no webhook to delete, but pending messages from chat sessions are dropped if
+    /// requested.
+    async fn delete_webhook(&self, req: api::DeleteWebhookRequest) -> ApiResponse<bool> {
+        if req.drop_pending_updates.unwrap_or(false) {
+            let mut rx = self.chat_rx.lock().await;
+            while rx.try_recv().is_ok() {}
+        }
+
+        ApiResponse::Ok(true)
+    }
+
     async fn send_message(&self, req: api::SendMessageRequest) -> ApiResponse<api::Message> {
-        let mut message = api::Message::fake(self.bot_name.as_str());
-        message.chat.id = req.chat_id;
+        let mut message = self.new_message(req.chat_id);
         message.text = Some(req.text);
+        message.effect_id = req.message_effect_id;
         message.reply_to_message = None;
 
         if let Some(chat) = self.chat_map.lock().await.get(&req.chat_id) {
@@ -221,13 +323,165 @@ impl FakeAPI {
         ApiResponse::Ok(message)
     }
 
+    /// Media is echoed back as a message with a single photo size (or video) using the requested
+    /// file ID.
+    async fn send_media(
+        &self,
+        chat_id: i64,
+        caption: Option<String>,
+        has_spoiler: Option<bool>,
+        set_media: impl FnOnce(&mut api::Message),
+    ) -> ApiResponse<api::Message> {
+        let mut message = self.new_message(chat_id);
+        message.caption = caption;
+        message.has_media_spoiler = has_spoiler;
+        set_media(&mut message);
+
+        if let Some(chat) = self.chat_map.lock().await.get(&chat_id) {
+            chat.send(Update::Message(message.clone())).await.unwrap();
+        } else {
+            warn!("Can't find Chat with id = {}", chat_id);
+        }
+
+        ApiResponse::Ok(message)
+    }
+
+    async fn send_photo(&self, req: api::SendPhotoRequest) -> ApiResponse<api::Message> {
+        self.send_media(req.chat_id, req.caption, req.has_spoiler, |m| {
+            m.photo = Some(vec![api::PhotoSize {
+                file_id: req.photo,
+                file_unique_id: None,
+                width: 0,
+                height: 0,
+                file_size: None,
+            }])
+        })
+        .await
+    }
+
+    async fn send_video(&self, req: api::SendVideoRequest) -> ApiResponse<api::Message> {
+        self.send_media(req.chat_id, req.caption, req.has_spoiler, |m| {
+            m.video = Some(api::Video {
+                file_id: req.video,
+                file_unique_id: String::new(),
+                width: 0,
+                height: 0,
+                duration: 0,
+                thumbnail: None,
+                cover: None,
+                start_timestamp: None,
+                qualities: None,
+                file_name: None,
+                mime_type: None,
+                file_size: None,
+            })
+        })
+        .await
+    }
+
+    /// The fake doesn't keep message contents, so the copy delivered to the target chat has a
+    /// placeholder text naming the original message.
+    async fn copy_message(&self, req: api::CopyMessageRequest) -> ApiResponse<api::MessageId> {
+        let mut message = self.new_message(req.chat_id);
+        message.text = Some(format!("copy of {}/{}", req.from_chat_id, req.message_id));
+
+        if let Some(chat) = self.chat_map.lock().await.get(&req.chat_id) {
+            chat.send(Update::Message(message.clone())).await.unwrap();
+        } else {
+            warn!("Can't find Chat with id = {}", req.chat_id);
+        }
+
+        ApiResponse::Ok(api::MessageId {
+            message_id: message.message_id,
+        })
+    }
+
+    async fn get_me(&self) -> ApiResponse<api::User> {
+        ApiResponse::Ok(api::User {
+            id: 0,
+            is_bot: true,
+            first_name: self.bot_name.clone(),
+            username: Some(self.bot_name.clone()),
+            ..Default::default()
+        })
+    }
+
+    async fn set_user_emoji_status(
+        &self,
+        req: api::SetUserEmojiStatusRequest,
+    ) -> ApiResponse<bool> {
+        self.emoji_statuses.lock().await.insert(req.user_id, req);
+        ApiResponse::Ok(true)
+    }
+
+    async fn set_chat_permissions(&self, req: api::SetChatPermissionRequest) -> ApiResponse<bool> {
+        if let Ok(chat_id) = req.chat_id.parse() {
+            self.chat_permissions
+                .lock()
+                .await
+                .insert(chat_id, req.permissions);
+        }
+        ApiResponse::Ok(true)
+    }
+
+    async fn get_chat(&self, req: api::GetChatRequest) -> ApiResponse<api::ChatFullInfo> {
+        let id = req.chat_id.parse().unwrap_or_default();
+        let emoji_status = self.emoji_statuses.lock().await.get(&id).cloned();
+        let permissions = self.chat_permissions.lock().await.get(&id).cloned();
+
+        ApiResponse::Ok(api::ChatFullInfo {
+            id,
+            type_: api::ChatType::Supergroup,
+            permissions: Some(permissions.unwrap_or(api::ChatPermissions {
+                can_send_messages: Some(true),
+                ..Default::default()
+            })),
+            emoji_status_custom_emoji_id: emoji_status
+                .as_ref()
+                .and_then(|s| s.emoji_status_custom_emoji_id.clone()),
+            emoji_status_expiration_date: emoji_status.and_then(|s| s.emoji_status_expiration_date),
+            ..Default::default()
+        })
+    }
+
+    #[allow(deprecated)]
+    async fn get_chat_member(
+        &self,
+        req: api::GetChatMemberRequest,
+    ) -> ApiResponse<api::ChatMember> {
+        let status = if self.admins.lock().await.contains(&req.user_id) {
+            api::ChatMemberStatus::Administrator
+        } else {
+            api::ChatMemberStatus::Member
+        };
+
+        ApiResponse::Ok(api::ChatMember {
+            status: status.into(),
+            user: api::User {
+                id: req.user_id,
+                ..Default::default()
+            },
+            custom_title: None,
+            until_date: None,
+            can_be_edited: None,
+            is_anonymous: None,
+            can_manage_chat: None,
+            can_delete_messages: None,
+            can_manage_video_chats: None,
+            can_restrict_members: None,
+            can_promote_members: None,
+            is_member: Some(true),
+        })
+    }
+
     async fn edit_message_text(
         &self,
         req: api::EditMessageTextRequest,
     ) -> ApiResponse<api::Message> {
-        let mut message = api::Message::fake(self.bot_name.as_str());
+        let mut message = self.message();
         message.chat.id = req.base.chat_id.unwrap();
         message.message_id = req.base.message_id.unwrap();
+        message.edit_date = Some(message.date);
         message.text = Some(req.text);
 
         if let Some(chat) = self.chat_map.lock().await.get(&message.chat.id) {
@@ -245,7 +499,7 @@ impl FakeAPI {
         &self,
         req: api::EditMessageReplyMarkupRequest,
     ) -> ApiResponse<api::Message> {
-        let mut message = api::Message::fake(self.bot_name.as_str());
+        let mut message = self.message();
         message.chat.id = req.base.chat_id.unwrap();
         message.message_id = req.base.message_id.unwrap();
         message.reply_markup = Some(req.base.reply_markup.unwrap().into());
@@ -272,6 +526,9 @@ impl Post for FakeAPI {
         let response = match method.as_str() {
             "getUpdates" => from_json(&self.get_updates(to_json(req.as_str())?).await),
             "sendMessage" => from_json(&self.send_message(to_json(req.as_str())?).await),
+            "sendPhoto" => from_json(&self.send_photo(to_json(req.as_str())?).await),
+            "sendVideo" => from_json(&self.send_video(to_json(req.as_str())?).await),
+            "copyMessage" => from_json(&self.copy_message(to_json(req.as_str())?).await),
             "editMessageText" => from_json(
                 &self
                     .edit_message_text(serde_json::from_str(req.as_str())?)
@@ -280,6 +537,22 @@ impl Post for FakeAPI {
             "editMessageReplyMarkup" => {
                 from_json(&self.edit_message_reply_markup(to_json(req.as_str())?).await)
             }
+            "deleteWebhook" => from_json(&self.delete_webhook(to_json(req.as_str())?).await),
+            "getMe" => from_json(&self.get_me().await),
+            "setUserEmojiStatus" => {
+                from_json(&self.set_user_emoji_status(to_json(req.as_str())?).await)
+            }
+            "getChat" => from_json(&self.get_chat(to_json(req.as_str())?).await),
+            "getChatMember" => from_json(&self.get_chat_member(to_json(req.as_str())?).await),
+            "setChatPermissions" => {
+                from_json(&self.set_chat_permissions(to_json(req.as_str())?).await)
+            }
+            "banChatMember"
+            | "unbanChatMember"
+            | "restrictChatMember"
+            | "promoteChatMember"
+            | "approveChatJoinRequest"
+            | "declineChatJoinRequest" => from_json(&ApiResponse::Ok(true)),
             _ => {
                 warn!("Unknown method: {}", method);
                 from_json(&ApiResponse::<()>::Err(format!(
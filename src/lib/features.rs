@@ -0,0 +1,112 @@
+/// Runtime feature flags. [`Features`] is a registry of named on/off switches that's shared by
+/// the router and every handler, so a handler or a whole subsystem (e.g., an AI responder) can
+/// be turned off with an admin command (see [`crate::handlers::feature_handler`]) or a config
+/// reload, without redeploying the bot.
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, RwLock},
+};
+
+/// `Features` maps flag names to whether they're enabled. Clones share the same flags.
+///
+/// Flags that were never set are enabled, so flags work as kill switches by default. Register
+/// a flag with [`Features::with_flag`] to give it a different default.
+///
+/// ```
+/// # use mobot::*;
+/// let features = Features::new().with_flag("beta", false);
+/// assert!(features.is_enabled("ai"));
+/// assert!(!features.is_enabled("beta"));
+///
+/// features.disable("ai");
+/// assert!(!features.is_enabled("ai"));
+/// ```
+///
+/// Gate a handler on a flag with [`crate::filters::feature`], or check it in the handler with
+/// [`crate::Event::features`].
+#[derive(Debug, Clone, Default)]
+pub struct Features {
+    flags: Arc<RwLock<Flags>>,
+}
+
+#[derive(Debug, Default)]
+struct Flags {
+    /// Registered with [`Features::with_flag`].
+    defaults: HashMap<String, bool>,
+
+    /// Set at runtime, or loaded from config. These take precedence over the defaults.
+    set: HashMap<String, bool>,
+}
+
+impl Flags {
+    fn get(&self, name: &str) -> bool {
+        self.set
+            .get(name)
+            .or(self.defaults.get(name))
+            .copied()
+            .unwrap_or(true)
+    }
+}
+
+impl Features {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the flag `name` with a default, which applies until it's set, and again after
+    /// a [`Features::load`] that leaves it out.
+    pub fn with_flag(self, name: impl Into<String>, enabled: bool) -> Self {
+        self.flags
+            .write()
+            .unwrap()
+            .defaults
+            .insert(name.into(), enabled);
+        self
+    }
+
+    /// Returns true if the flag `name` is enabled, or was never set or registered.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.flags.read().unwrap().get(name)
+    }
+
+    /// Turn the flag `name` on or off. Returns its previous value.
+    pub fn set(&self, name: impl Into<String>, enabled: bool) -> bool {
+        let name = name.into();
+        let mut flags = self.flags.write().unwrap();
+        let previous = flags.get(&name);
+        flags.set.insert(name, enabled);
+        previous
+    }
+
+    /// Turn the flag `name` on. Returns its previous value.
+    pub fn enable(&self, name: impl Into<String>) -> bool {
+        self.set(name, true)
+    }
+
+    /// Turn the flag `name` off. Returns its previous value.
+    pub fn disable(&self, name: impl Into<String>) -> bool {
+        self.set(name, false)
+    }
+
+    /// Replace all flags that were set with `flags`, e.g. when reloading the bot's config.
+    /// Flags that aren't in `flags` go back to their registered default, or are enabled if
+    /// they don't have one.
+    pub fn load<K: Into<String>>(&self, flags: impl IntoIterator<Item = (K, bool)>) {
+        let flags = flags
+            .into_iter()
+            .map(|(name, enabled)| (name.into(), enabled))
+            .collect();
+        self.flags.write().unwrap().set = flags;
+    }
+
+    /// Returns all flags that were set or registered, sorted by name.
+    pub fn flags(&self) -> BTreeMap<String, bool> {
+        let flags = self.flags.read().unwrap();
+        flags
+            .defaults
+            .iter()
+            .chain(&flags.set)
+            .map(|(name, enabled)| (name.clone(), *enabled))
+            .collect()
+    }
+}
@@ -0,0 +1,221 @@
+/// Handler filters. A filter wraps a handler, and only runs it for updates that pass; other
+/// updates are passed on to the next handler with [`Action::Next`]. Filters compose with any
+/// route, so a handler meant only for private chats doesn't need to check the chat type itself.
+///
+/// ```no_run
+/// # use mobot::*;
+/// async fn start(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     Ok(Action::ReplyText("Welcome!".into()))
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN").unwrap());
+/// Router::new(client)
+///     .add_route(Route::Message(Matcher::BotCommand("start".into())), filters::private(start))
+///     .start()
+///     .await;
+/// # }
+/// ```
+use std::{future::Future, sync::Arc};
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use tokio::sync::RwLock;
+
+use crate::{
+    api::{ChatType, GetChatMemberRequest},
+    handler::{BotHandler, BotHandlerFn, BotState},
+    Action, Event, State,
+};
+
+/// An async check on an event, see [`when`].
+pub type Predicate = Arc<dyn Fn(Event) -> BoxFuture<'static, anyhow::Result<bool>> + Send + Sync>;
+
+/// Box an async function as a [`Predicate`].
+pub fn predicate<F, Fut>(f: F) -> Predicate
+where
+    F: Fn(Event) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = anyhow::Result<bool>> + Send + 'static,
+{
+    Arc::new(move |e| Box::pin(f(e)))
+}
+
+/// `FilteredHandler` runs its handler only for events that pass its predicate. Errors from the
+/// predicate are returned as handler errors.
+pub struct FilteredHandler<S: BotState> {
+    predicate: Predicate,
+    handler: Box<dyn BotHandler<S>>,
+}
+
+impl<S: BotState> FilteredHandler<S> {
+    pub fn new(predicate: Predicate, handler: impl Into<Box<dyn BotHandler<S>>>) -> Self {
+        Self {
+            predicate,
+            handler: handler.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandler<S> for FilteredHandler<S> {
+    fn get_state(&self) -> &State<S> {
+        self.handler.get_state()
+    }
+
+    fn set_state(&mut self, state: Arc<RwLock<S>>) {
+        self.handler.set_state(state)
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for FilteredHandler<S> {
+    async fn run(&self, event: Event, state: State<S>) -> Result<Action, anyhow::Error> {
+        if (self.predicate)(event.clone()).await? {
+            self.handler.run(event, state).await
+        } else {
+            Ok(Action::Next)
+        }
+    }
+}
+
+/// Run `handler` only for events for which the async function `f` returns true.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # async fn ban(e: Event, _: State<()>) -> Result<Action, anyhow::Error> { unimplemented!() }
+/// # let mut router: Router<()> = Router::new(Client::new("token".to_string()));
+/// router.add_route(
+///     Route::Message(Matcher::BotCommand("ban".into())),
+///     filters::when(filters::is_admin, ban),
+/// );
+/// ```
+pub fn when<S, F, Fut>(f: F, handler: impl Into<Box<dyn BotHandler<S>>>) -> Box<dyn BotHandler<S>>
+where
+    S: BotState,
+    F: Fn(Event) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = anyhow::Result<bool>> + Send + 'static,
+{
+    Box::new(FilteredHandler::new(predicate(f), handler))
+}
+
+/// Run `handler` only while the feature flag `name` is enabled (see [`crate::Features`]).
+///
+/// ```no_run
+/// # use mobot::*;
+/// # async fn ai_reply(e: Event, _: State<()>) -> Result<Action, anyhow::Error> { unimplemented!() }
+/// # let mut router: Router<()> = Router::new(Client::new("token".to_string()));
+/// router.add_route(Route::Message(Matcher::Any), filters::feature("ai", ai_reply));
+///
+/// // Later, e.g. if the AI provider is down:
+/// router.features().disable("ai");
+/// ```
+pub fn feature<S: BotState>(
+    name: impl Into<String>,
+    handler: impl Into<Box<dyn BotHandler<S>>>,
+) -> Box<dyn BotHandler<S>> {
+    let name: Arc<str> = name.into().into();
+    when(
+        move |e: Event| {
+            let name = Arc::clone(&name);
+            async move { Ok(e.features.is_enabled(&name)) }
+        },
+        handler,
+    )
+}
+
+/// A predicate that passes if the sender is the owner or an administrator of the chat. It's
+/// checked with `getChatMember` on every update. Updates without a sender, or sent in private
+/// chats, don't pass.
+pub async fn is_admin(e: Event) -> anyhow::Result<bool> {
+    let (Ok(chat), Ok(user)) = (e.update.chat(), e.update.from_user()) else {
+        return Ok(false);
+    };
+
+    if chat.is_private() {
+        return Ok(false);
+    }
+
+    let member = e
+        .api
+        .get_chat_member(&GetChatMemberRequest::new(chat.id.to_string(), user.id))
+        .await?;
+    Ok(member.is_admin())
+}
+
+/// `ChatTypeFilter` runs its handler only for updates in the given types of chat. Updates
+/// without a chat (e.g., inline queries) don't pass.
+pub struct ChatTypeFilter<S: BotState> {
+    pub chat_types: Vec<ChatType>,
+    handler: Box<dyn BotHandler<S>>,
+}
+
+impl<S: BotState> ChatTypeFilter<S> {
+    pub fn new(
+        chat_types: impl IntoIterator<Item = ChatType>,
+        handler: impl Into<Box<dyn BotHandler<S>>>,
+    ) -> Self {
+        Self {
+            chat_types: chat_types.into_iter().collect(),
+            handler: handler.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandler<S> for ChatTypeFilter<S> {
+    fn get_state(&self) -> &State<S> {
+        self.handler.get_state()
+    }
+
+    fn set_state(&mut self, state: Arc<RwLock<S>>) {
+        self.handler.set_state(state)
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for ChatTypeFilter<S> {
+    async fn run(&self, event: Event, state: State<S>) -> Result<Action, anyhow::Error> {
+        match event.update.chat() {
+            Ok(chat) if self.chat_types.contains(&chat.chat_type) => {
+                self.handler.run(event, state).await
+            }
+            _ => Ok(Action::Next),
+        }
+    }
+}
+
+/// Run `handler` only for updates in chats of the given types.
+pub fn chat_types<S: BotState>(
+    chat_types: impl IntoIterator<Item = ChatType>,
+    handler: impl Into<Box<dyn BotHandler<S>>>,
+) -> Box<dyn BotHandler<S>> {
+    Box::new(ChatTypeFilter::new(chat_types, handler))
+}
+
+/// Run `handler` only for updates in private chats with the bot.
+pub fn private<S: BotState>(handler: impl Into<Box<dyn BotHandler<S>>>) -> Box<dyn BotHandler<S>> {
+    chat_types([ChatType::Private], handler)
+}
+
+/// Run `handler` only for updates in basic groups. Use [`groups`] to include supergroups.
+pub fn group<S: BotState>(handler: impl Into<Box<dyn BotHandler<S>>>) -> Box<dyn BotHandler<S>> {
+    chat_types([ChatType::Group], handler)
+}
+
+/// Run `handler` only for updates in supergroups.
+pub fn supergroup<S: BotState>(
+    handler: impl Into<Box<dyn BotHandler<S>>>,
+) -> Box<dyn BotHandler<S>> {
+    chat_types([ChatType::Supergroup], handler)
+}
+
+/// Run `handler` only for updates in groups and supergroups.
+pub fn groups<S: BotState>(handler: impl Into<Box<dyn BotHandler<S>>>) -> Box<dyn BotHandler<S>> {
+    chat_types([ChatType::Group, ChatType::Supergroup], handler)
+}
+
+/// Run `handler` only for updates in channels.
+pub fn channel<S: BotState>(handler: impl Into<Box<dyn BotHandler<S>>>) -> Box<dyn BotHandler<S>> {
+    chat_types([ChatType::Channel], handler)
+}
@@ -0,0 +1,171 @@
+/// Handler groups, for splitting a large bot across modules (or crates). A [`HandlerGroup`] is
+/// a set of routes that's built on its own, and mounted on a [`crate::Router`] (or another
+/// group) with [`crate::Router::mount`]. Groups can share a prefix and filters, like nested
+/// routers in a web framework.
+use regex::escape;
+
+use crate::{
+    filters::{self, FilteredHandler, Predicate},
+    handler::{handler_name, BotHandler, BotState},
+    Event, Matcher, Route,
+};
+
+/// A route of a [`HandlerGroup`], with its priority and handler name, if it has one (see
+/// [`crate::Router::add_named_route`]).
+pub type GroupRoute<S> = (Route, i32, Option<String>, Box<dyn BotHandler<S>>);
+
+/// `HandlerGroup` collects routes to mount on a [`crate::Router`].
+///
+/// ```no_run
+/// # use mobot::*;
+/// # async fn ban(e: Event, _: State<()>) -> Result<Action, anyhow::Error> { unimplemented!() }
+/// # async fn mute(e: Event, _: State<()>) -> Result<Action, anyhow::Error> { unimplemented!() }
+/// // In the admin module: "/admin_ban" and "/admin_mute", for administrators only.
+/// fn admin_routes() -> HandlerGroup<()> {
+///     let mut group = HandlerGroup::new();
+///     group
+///         .add_route(Route::Message(Matcher::BotCommand("ban".into())), ban)
+///         .add_route(Route::Message(Matcher::BotCommand("mute".into())), mute);
+///     group.with_prefix("/admin_").filtered(filters::is_admin)
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN").unwrap());
+/// Router::new(client).mount(admin_routes()).start().await;
+/// # }
+/// ```
+pub struct HandlerGroup<S: BotState> {
+    routes: Vec<GroupRoute<S>>,
+    prefix: Option<String>,
+    predicates: Vec<Predicate>,
+}
+
+impl<S: BotState> Default for HandlerGroup<S> {
+    fn default() -> Self {
+        Self {
+            routes: vec![],
+            prefix: None,
+            predicates: vec![],
+        }
+    }
+}
+
+impl<S: BotState> HandlerGroup<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a handler to the group. See [`crate::Router::add_route`].
+    pub fn add_route(&mut self, r: Route, h: impl Into<Box<dyn BotHandler<S>>>) -> &mut Self {
+        self.add_route_with_priority(r, 0, h)
+    }
+
+    /// Add a handler to the group with a priority. See
+    /// [`crate::Router::add_route_with_priority`].
+    pub fn add_route_with_priority<H: Into<Box<dyn BotHandler<S>>>>(
+        &mut self,
+        r: Route,
+        priority: i32,
+        h: H,
+    ) -> &mut Self {
+        let name = handler_name::<H>();
+        self.routes.push((r, priority, name, h.into()));
+        self
+    }
+
+    /// Add a handler to the group, with a name for its metrics. See
+    /// [`crate::Router::add_named_route`].
+    pub fn add_named_route(
+        &mut self,
+        name: impl Into<String>,
+        r: Route,
+        h: impl Into<Box<dyn BotHandler<S>>>,
+    ) -> &mut Self {
+        self.routes.push((r, 0, Some(name.into()), h.into()));
+        self
+    }
+
+    /// Mount the routes of `group` in this group.
+    pub fn mount(&mut self, group: HandlerGroup<S>) -> &mut Self {
+        self.routes.extend(group.into_routes());
+        self
+    }
+
+    /// Prefix the group's text matchers with `prefix`, so e.g. `Matcher::BotCommand("ban")`
+    /// matches "/admin_ban" with the prefix "/admin_" (the slash is optional for commands).
+    /// `Matcher::Any` routes match text that starts with the prefix, and `Matcher::Regex`
+    /// patterns must match right after it. Media matchers are unchanged.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        self.prefix = Some(match self.prefix {
+            Some(inner) => format!("{}{}", prefix, inner),
+            None => prefix,
+        });
+        self
+    }
+
+    /// Only run the group's handlers for events for which the async function `f` returns
+    /// true (see [`filters::when`]). Filters added later run first.
+    pub fn filtered<F, Fut>(mut self, f: F) -> Self
+    where
+        F: Fn(Event) -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = anyhow::Result<bool>> + Send + 'static,
+    {
+        self.predicates.push(filters::predicate(f));
+        self
+    }
+
+    /// Returns the number of routes in the group.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Returns true if the group has no routes.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Returns the group's routes, with their priorities and handler names, and the group's
+    /// prefix and filters applied.
+    pub fn into_routes(self) -> Vec<GroupRoute<S>> {
+        let prefix = self.prefix;
+        let predicates = self.predicates;
+
+        self.routes
+            .into_iter()
+            .map(|(route, priority, name, handler)| {
+                let route = match &prefix {
+                    Some(prefix) => route.with(&prefixed(Matcher::from(route.clone()), prefix)),
+                    None => route,
+                };
+                let handler = predicates.iter().fold(handler, |handler, predicate| {
+                    Box::new(FilteredHandler::new(predicate.clone(), handler))
+                });
+                (route, priority, name, handler)
+            })
+            .collect()
+    }
+}
+
+/// Returns `matcher`, matching text that starts with `prefix`.
+fn prefixed(matcher: Matcher, prefix: &str) -> Matcher {
+    match matcher {
+        Matcher::Any => Matcher::Prefix(prefix.to_string()),
+        Matcher::Exact(s) => Matcher::Exact(format!("{}{}", prefix, s)),
+        Matcher::Prefix(s) => Matcher::Prefix(format!("{}{}", prefix, s)),
+        Matcher::BotCommand(s) => {
+            Matcher::BotCommand(format!("{}{}", prefix.trim_start_matches('/'), s))
+        }
+        Matcher::Regex(s) => Matcher::Regex(format!(
+            "^{}(?:{})",
+            escape(prefix),
+            s.trim_start_matches('^')
+        )),
+        Matcher::Photo
+        | Matcher::Document
+        | Matcher::Video
+        | Matcher::NewChatMembers
+        | Matcher::Language(_) => matcher,
+    }
+}
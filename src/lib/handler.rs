@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use futures::{future::BoxFuture, Future};
 use tokio::sync::RwLock;
 
-use crate::{Action, Event};
+use crate::{versioned, Action, Event, Versioned};
 
 /// Bot states need to derive the `BotState` trait. You can use `#[derive(BotState)]` to do this.
 pub trait BotState: Default + Clone + Send + Sync + 'static {}
@@ -36,6 +36,18 @@ impl<T: BotState> State<T> {
     }
 }
 
+impl<T: BotState + Versioned> State<T> {
+    /// Serialize the current state, tagged with its version (see [`crate::versioned`]).
+    pub async fn snapshot(&self) -> anyhow::Result<serde_json::Value> {
+        versioned::encode(&*self.state.read().await)
+    }
+
+    /// Restore a state from a snapshot, migrating it if it was taken by an older version.
+    pub fn restore(snapshot: serde_json::Value) -> anyhow::Result<Self> {
+        Ok(Self::new(versioned::decode(snapshot)?))
+    }
+}
+
 /// BotHandlerFns are async functions that take an `Event` and a `State` and return an `Action`
 #[async_trait]
 pub trait BotHandlerFn<S: BotState>: Send + Sync {
@@ -146,3 +158,11 @@ where
         Box::new(Handler::new(Box::new(HandlerFn::new(func))))
     }
 }
+
+/// Returns the path of the handler function `H`, to keep its metrics under. Closures are all
+/// named after the function they're defined in, and boxed handlers after the box, so they
+/// don't get one.
+pub(crate) fn handler_name<H>() -> Option<String> {
+    let name = std::any::type_name::<H>();
+    (!name.contains("{{closure}}") && !name.contains("dyn ")).then(|| name.to_string())
+}
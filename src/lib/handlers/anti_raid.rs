@@ -0,0 +1,223 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{ChatPermissions, GetChatRequest, SetChatPermissionRequest},
+    handler::{BotHandlerFn, BotState},
+    Action, ChatSetting, Event, State, Update, Versioned,
+};
+
+/// The anti-raid state of a chat, stored with the chat's [`crate::Settings`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AntiRaidState {
+    /// When the raid window ends, in Unix time. `None` if anti-raid mode is off.
+    pub until: Option<i64>,
+
+    /// The chat's permissions before anti-raid mode was turned on, restored when it's lifted.
+    pub saved_permissions: Option<ChatPermissions>,
+}
+
+impl ChatSetting for AntiRaidState {
+    const KEY: &'static str = "anti_raid";
+}
+
+impl Versioned for AntiRaidState {}
+
+/// A component that locks a chat down during a raid. [`AntiRaid::trigger`] switches the chat
+/// to restrictive [`ChatPermissions`] for a while, and then restores the previous permissions.
+///
+/// Register it as a handler for [`crate::Route::ChatJoinRequest`] and for
+/// [`crate::Matcher::NewChatMembers`] messages. While the window is open, it approves no join
+/// requests: they're left pending for the chat's admins, and not passed on to later handlers.
+/// With [`AntiRaid::with_new_account_threshold`], it also bans new members whose accounts look
+/// recently created. Outside the window, updates are passed on with [`Action::Next`].
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use std::time::Duration;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN").unwrap());
+/// let anti_raid = handlers::anti_raid::AntiRaid::new(Duration::from_secs(600))
+///     .with_new_account_threshold(7_000_000_000);
+///
+/// let mut router = Router::<()>::new(client)
+///     .with_allowed_updates(["message", "chat_join_request"]);
+///
+/// router
+///     .add_route(Route::ChatJoinRequest(Matcher::Any), anti_raid.handler())
+///     .add_route(Route::Message(Matcher::NewChatMembers), anti_raid.handler())
+///     .add_route(Route::Message(Matcher::BotCommand("raid".into())), move |e: Event, _| {
+///         let anti_raid = anti_raid.clone();
+///         async move {
+///             anti_raid.trigger(&e, e.update.chat_id()?).await?;
+///             Ok(Action::ReplyText("Anti-raid mode is on.".into()))
+///         }
+///     });
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AntiRaid {
+    /// How long anti-raid mode stays on after it's triggered.
+    pub duration: Duration,
+
+    /// The chat's permissions while anti-raid mode is on.
+    pub permissions: ChatPermissions,
+
+    /// If set, members with a user ID above this are banned when they join during the window.
+    /// The Bot API doesn't expose account age, but user IDs are handed out in increasing
+    /// order, so IDs above a threshold are newer accounts.
+    pub new_account_threshold: Option<i64>,
+}
+
+impl AntiRaid {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            permissions: ChatPermissions::none(),
+            new_account_threshold: None,
+        }
+    }
+
+    pub fn with_permissions(mut self, permissions: ChatPermissions) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    pub fn with_new_account_threshold(mut self, user_id: i64) -> Self {
+        self.new_account_threshold = Some(user_id);
+        self
+    }
+
+    /// Returns a handler that shares this component's configuration.
+    pub fn handler<S: BotState>(&self) -> Box<dyn BotHandlerFn<S>> {
+        Box::new(self.clone())
+    }
+
+    /// Turn anti-raid mode on for `chat_id`. If it's already on, the window is extended. It's
+    /// lifted in the background once the window ends.
+    pub async fn trigger(&self, e: &Event, chat_id: i64) -> anyhow::Result<()> {
+        let mut state = e.settings.get::<AntiRaidState>(chat_id).await?;
+        if state.until.is_none() {
+            let chat = e
+                .api
+                .get_chat(&GetChatRequest::new(chat_id.to_string()))
+                .await?;
+            state.saved_permissions = chat.permissions;
+
+            e.api
+                .set_chat_permissions(&SetChatPermissionRequest::new(
+                    chat_id.to_string(),
+                    self.permissions.clone(),
+                    Some(true),
+                ))
+                .await?;
+        }
+
+        let until = e.api.clock().timestamp() + self.duration.as_secs() as i64;
+        state.until = Some(until);
+        e.settings.set(chat_id, &state).await?;
+        info!("Anti-raid mode on in chat {} until {}", chat_id, until);
+
+        let anti_raid = self.clone();
+        let sleep = e.api.clock().sleep(self.duration);
+        e.spawn(move |e| async move {
+            sleep.await;
+
+            // Leave it on if the window was extended in the meantime.
+            let state = e.settings.get::<AntiRaidState>(chat_id).await?;
+            if state.until == Some(until) {
+                anti_raid.lift(&e, chat_id).await?;
+            }
+            Ok(())
+        });
+
+        Ok(())
+    }
+
+    /// Turn anti-raid mode off for `chat_id`, restoring the chat's previous permissions. If
+    /// they weren't known, the chat keeps its anti-raid permissions for an admin to restore.
+    pub async fn lift(&self, e: &Event, chat_id: i64) -> anyhow::Result<()> {
+        let state = e.settings.get::<AntiRaidState>(chat_id).await?;
+        if state.until.is_none() {
+            return Ok(());
+        }
+
+        match state.saved_permissions {
+            Some(permissions) => {
+                e.api
+                    .set_chat_permissions(&SetChatPermissionRequest::new(
+                        chat_id.to_string(),
+                        permissions,
+                        Some(true),
+                    ))
+                    .await?;
+            }
+            None => warn!(
+                "Chat {}'s permissions before anti-raid mode aren't known, leaving them unchanged",
+                chat_id
+            ),
+        }
+        e.settings.reset::<AntiRaidState>(chat_id).await?;
+        info!("Anti-raid mode lifted in chat {}", chat_id);
+        Ok(())
+    }
+
+    /// Returns true if anti-raid mode is on for `chat_id`. Expired windows are lifted.
+    pub async fn is_active(&self, e: &Event, chat_id: i64) -> anyhow::Result<bool> {
+        let state = e.settings.get::<AntiRaidState>(chat_id).await?;
+        match state.until {
+            Some(until) if until <= e.api.clock().timestamp() => {
+                self.lift(e, chat_id).await?;
+                Ok(false)
+            }
+            Some(_) => Ok(true),
+            None => Ok(false),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for AntiRaid {
+    async fn run(&self, e: Event, _: State<S>) -> Result<Action, anyhow::Error> {
+        let Ok(chat_id) = e.update.chat_id() else {
+            return Ok(Action::Next);
+        };
+
+        if !self.is_active(&e, chat_id).await? {
+            return Ok(Action::Next);
+        }
+
+        match &e.update {
+            // Leave it pending for the admins.
+            Update::ChatJoinRequest(_) => Ok(Action::Done),
+            Update::Message(message) => {
+                let Some(members) = &message.new_chat_members else {
+                    return Ok(Action::Next);
+                };
+                let Some(threshold) = self.new_account_threshold else {
+                    return Ok(Action::Next);
+                };
+
+                let user_ids = members
+                    .iter()
+                    .filter(|user| !user.is_bot && user.id > threshold)
+                    .map(|user| user.id)
+                    .collect::<Vec<_>>();
+                for (user_id, result) in e.api.ban_many(chat_id, user_ids).await {
+                    if let Err(err) = result {
+                        warn!("Could not ban {} from chat {}: {}", user_id, chat_id, err);
+                    }
+                }
+                Ok(Action::Done)
+            }
+            _ => Ok(Action::Next),
+        }
+    }
+}
+
+pub fn anti_raid<S: BotState>(duration: Duration) -> Box<dyn BotHandlerFn<S>> {
+    AntiRaid::new(duration).handler()
+}
@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{CopyMessageRequest, GetChatMemberRequest, MessageReactionUpdated},
+    handler::{BotHandlerFn, BotState},
+    Action, ChatSetting, Event, State, Update, Versioned,
+};
+
+/// Per-chat overrides for [`BookmarkBridge`], stored with the chat's [`crate::Settings`].
+/// Fields left unset fall back to the bridge's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkSettings {
+    /// The reaction that bookmarks a message.
+    pub emoji: Option<String>,
+
+    /// The chat (usually a channel) bookmarked messages are copied to.
+    pub archive_chat_id: Option<i64>,
+
+    /// If true, bookmarking is turned off for the chat.
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+impl ChatSetting for BookmarkSettings {
+    const KEY: &'static str = "bookmark";
+}
+
+impl Versioned for BookmarkSettings {}
+
+/// A handler that copies a message to an archive chat when an administrator reacts to it with
+/// the bookmark emoji. Register it for [`crate::Route::MessageReaction`], and request reaction
+/// updates with [`crate::Router::with_allowed_updates`]; the bot must be an administrator in
+/// the chat to receive them.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN").unwrap());
+/// let mut router = Router::<()>::new(client)
+///     .with_allowed_updates(["message", "message_reaction"]);
+///
+/// router.add_route(
+///     Route::MessageReaction(Matcher::Any),
+///     handlers::bookmark::bookmark_bridge(-1001234567890),
+/// );
+/// # }
+/// ```
+///
+/// Chats can pick their own emoji and archive with [`BookmarkSettings`]. Reactions from
+/// non-administrators, and other reactions, are passed on with [`Action::Next`].
+#[derive(Debug, Clone)]
+pub struct BookmarkBridge {
+    /// The default bookmark reaction.
+    pub emoji: String,
+
+    /// The default archive chat. If `None`, only chats that set
+    /// [`BookmarkSettings::archive_chat_id`] are bookmarked.
+    pub archive_chat_id: Option<i64>,
+}
+
+impl BookmarkBridge {
+    pub fn new(archive_chat_id: i64) -> Self {
+        Self {
+            emoji: "🔥".into(),
+            archive_chat_id: Some(archive_chat_id),
+        }
+    }
+
+    /// A bridge without a default archive, for bots where every chat configures its own.
+    pub fn unconfigured() -> Self {
+        Self {
+            archive_chat_id: None,
+            ..Self::new(0)
+        }
+    }
+
+    pub fn with_emoji(mut self, emoji: impl Into<String>) -> Self {
+        self.emoji = emoji.into();
+        self
+    }
+
+    /// Returns true if the reaction was made by an administrator of the chat. Anonymous
+    /// administrators react on behalf of the chat itself.
+    async fn is_admin(e: &Event, reaction: &MessageReactionUpdated) -> anyhow::Result<bool> {
+        if let Some(actor) = &reaction.actor_chat {
+            return Ok(actor.id == reaction.chat.id);
+        }
+
+        let Some(user) = &reaction.user else {
+            return Ok(false);
+        };
+
+        let member = e
+            .api
+            .get_chat_member(&GetChatMemberRequest::new(
+                reaction.chat.id.to_string(),
+                user.id,
+            ))
+            .await?;
+        Ok(member.is_admin())
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for BookmarkBridge {
+    async fn run(&self, e: Event, _: State<S>) -> Result<Action, anyhow::Error> {
+        let Update::MessageReaction(reaction) = &e.update else {
+            return Ok(Action::Next);
+        };
+
+        let settings = e.settings.get::<BookmarkSettings>(reaction.chat.id).await?;
+        let emoji = settings.emoji.as_deref().unwrap_or(&self.emoji);
+        let Some(archive_chat_id) = settings.archive_chat_id.or(self.archive_chat_id) else {
+            return Ok(Action::Next);
+        };
+
+        if settings.disabled
+            || !reaction.added_emoji().contains(&emoji)
+            || !Self::is_admin(&e, reaction).await?
+        {
+            return Ok(Action::Next);
+        }
+
+        e.api
+            .copy_message(&CopyMessageRequest::new(
+                archive_chat_id,
+                reaction.chat.id,
+                reaction.message_id,
+            ))
+            .await?;
+
+        Ok(Action::Done)
+    }
+}
+
+pub fn bookmark_bridge<S: BotState>(archive_chat_id: i64) -> Box<dyn BotHandlerFn<S>> {
+    Box::new(BookmarkBridge::new(archive_chat_id))
+}
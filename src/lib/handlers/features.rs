@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+
+use crate::{
+    handler::{BotHandlerFn, BotState},
+    Action, Event, State,
+};
+
+/// A command handler for the router's feature flags (see [`crate::Features`]):
+///
+/// - `/feature` lists the flags that were set.
+/// - `/feature on <name>` and `/feature off <name>` turn a flag on or off.
+///
+/// The handler doesn't check who's running the command, so wrap it in a filter that does,
+/// e.g. [`crate::filters::when`] with a check against a list of operators:
+///
+/// ```no_run
+/// # use mobot::*;
+/// # let mut router: Router<()> = Router::new(Client::new("token".to_string()));
+/// const OPERATORS: [i64; 1] = [1234];
+///
+/// router.add_route(
+///     Route::Message(Matcher::BotCommand("feature".into())),
+///     filters::when(
+///         |e: Event| async move { Ok(OPERATORS.contains(&e.update.from_user()?.id)) },
+///         handlers::feature_handler(),
+///     ),
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FeatureHandler;
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for FeatureHandler {
+    async fn run(&self, event: Event, _: State<S>) -> Result<Action, anyhow::Error> {
+        let text = event.update.text()?;
+        let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+
+        let reply = match args[..] {
+            [] => {
+                let flags = event.features.flags();
+                if flags.is_empty() {
+                    "No feature flags are set.".to_string()
+                } else {
+                    flags
+                        .iter()
+                        .map(|(name, enabled)| {
+                            format!("{}: {}", name, if *enabled { "on" } else { "off" })
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+            ["on", name] => {
+                event.features.enable(name);
+                format!("Feature {} enabled.", name)
+            }
+            ["off", name] => {
+                event.features.disable(name);
+                format!("Feature {} disabled.", name)
+            }
+            _ => "Usage: /feature [on|off <name>]".to_string(),
+        };
+
+        Ok(Action::ReplyText(reply))
+    }
+}
+
+pub fn feature_handler<S: BotState>() -> Box<dyn BotHandlerFn<S>> {
+    Box::new(FeatureHandler)
+}
@@ -1,4 +1,11 @@
-use crate::{Action, Event, Update};
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    api,
+    handler::{BotHandlerFn, BotState},
+    Action, Event, State, Update,
+};
 
 /// This handler logs every message received.
 pub async fn log_handler<S>(e: Event, _: S) -> Result<Action, anyhow::Error> {
@@ -24,6 +31,296 @@ pub async fn log_handler<S>(e: Event, _: S) -> Result<Action, anyhow::Error> {
 
             Ok(Action::Next)
         }
+        Update::Album(messages) => {
+            let first = messages.first().cloned().unwrap_or_default();
+            let from = first.from.unwrap_or_default();
+
+            info!(
+                "({}) Album of {} from {}",
+                first.chat.id,
+                messages.len(),
+                from.first_name
+            );
+
+            Ok(Action::Next)
+        }
+        Update::MessageReaction(reaction) => {
+            let from = reaction.user.unwrap_or_default();
+            let emoji = reaction
+                .new_reaction
+                .iter()
+                .filter_map(|r| r.emoji.as_deref())
+                .collect::<String>();
+
+            info!(
+                "({}) Reaction from {} to {}: {}",
+                reaction.chat.id, from.first_name, reaction.message_id, emoji
+            );
+
+            Ok(Action::Next)
+        }
+        Update::ChatJoinRequest(request) => {
+            info!(
+                "({}) Join request from {}",
+                request.chat.id, request.from.first_name
+            );
+
+            Ok(Action::Next)
+        }
         _ => Err(anyhow::anyhow!("Unknown message type")),
     }
 }
+
+/// Controls what personal data [`LogHandler`] writes to the logs. The default logs everything;
+/// use [`Redaction::strict`] for production deployments that must not keep message contents or
+/// user identities in their logs.
+#[derive(Debug, Clone, Default)]
+pub struct Redaction {
+    /// Replace message texts, captions, callback data and inline queries with their length.
+    pub strip_text: bool,
+
+    /// Drop first names, last names and usernames.
+    pub strip_names: bool,
+
+    /// Replace user IDs (and private chat IDs, which are the same as the user's ID) with a
+    /// salted hash. The same user always hashes to the same value, so their activity can still
+    /// be correlated across log lines.
+    pub hash_user_ids: bool,
+
+    /// Salt for user ID hashes. User IDs are easy to enumerate, so keep this secret.
+    pub salt: String,
+}
+
+impl Redaction {
+    /// Log everything.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Strip all text and names, and hash user IDs with `salt`.
+    pub fn strict(salt: impl Into<String>) -> Self {
+        Self {
+            strip_text: true,
+            strip_names: true,
+            hash_user_ids: true,
+            salt: salt.into(),
+        }
+    }
+
+    pub fn with_strip_text(mut self, strip_text: bool) -> Self {
+        self.strip_text = strip_text;
+        self
+    }
+
+    pub fn with_strip_names(mut self, strip_names: bool) -> Self {
+        self.strip_names = strip_names;
+        self
+    }
+
+    pub fn with_hashed_user_ids(mut self, salt: impl Into<String>) -> Self {
+        self.hash_user_ids = true;
+        self.salt = salt.into();
+        self
+    }
+
+    fn text(&self, text: &str) -> String {
+        if self.strip_text {
+            format!("<{} chars>", text.chars().count())
+        } else {
+            format!("{:?}", text)
+        }
+    }
+
+    fn user_id(&self, id: i64) -> String {
+        if !self.hash_user_ids {
+            return id.to_string();
+        }
+
+        let digest = Sha256::new()
+            .chain_update(self.salt.as_bytes())
+            .chain_update(id.to_le_bytes())
+            .finalize();
+
+        // 8 bytes is plenty to tell users apart in logs.
+        digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn chat_id(&self, id: i64) -> String {
+        // Group and channel IDs are negative, and don't identify a person.
+        if id > 0 {
+            self.user_id(id)
+        } else {
+            id.to_string()
+        }
+    }
+
+    fn user(&self, user: &api::User) -> String {
+        let mut s = format!("user={}", self.user_id(user.id));
+        if user.is_bot {
+            s.push_str(" bot=true");
+        }
+
+        if !self.strip_names {
+            s.push_str(&format!(" name={:?}", user.first_name));
+            if let Some(username) = &user.username {
+                s.push_str(&format!(" username={:?}", username));
+            }
+        }
+        s
+    }
+}
+
+/// A handler that logs a one-line, structured summary of every update, with personal data
+/// redacted according to its [`Redaction`] settings. It always returns [`Action::Next`], so add
+/// it before the other handlers for a route.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN").unwrap());
+/// let mut router = Router::<()>::new(client);
+///
+/// let salt = std::env::var("LOG_SALT").unwrap();
+/// router.add_route(
+///     Route::Default,
+///     handlers::log::redacting_log_handler(handlers::log::Redaction::strict(salt)),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogHandler {
+    pub redaction: Redaction,
+    pub level: log::Level,
+}
+
+impl Default for LogHandler {
+    fn default() -> Self {
+        Self {
+            redaction: Redaction::default(),
+            level: log::Level::Info,
+        }
+    }
+}
+
+impl LogHandler {
+    pub fn new(redaction: Redaction) -> Self {
+        Self {
+            redaction,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_level(mut self, level: log::Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Returns the summary logged for `update`, e.g.
+    /// `kind=message chat=-100 message_id=5 user=3f2a... text=<12 chars>`.
+    pub fn summarize(&self, update: &Update) -> String {
+        let r = &self.redaction;
+
+        match update {
+            Update::Message(message)
+            | Update::EditedMessage(message)
+            | Update::ChannelPost(message)
+            | Update::EditedChannelPost(message) => {
+                let kind = match update {
+                    Update::Message(_) => "message",
+                    Update::EditedMessage(_) => "edited_message",
+                    Update::ChannelPost(_) => "channel_post",
+                    _ => "edited_channel_post",
+                };
+
+                let mut s = format!(
+                    "kind={} chat={} message_id={}",
+                    kind,
+                    r.chat_id(message.chat.id),
+                    message.message_id
+                );
+                if let Some(from) = &message.from {
+                    s.push_str(&format!(" {}", r.user(from)));
+                }
+                if let Some(text) = &message.text {
+                    s.push_str(&format!(" text={}", r.text(text)));
+                }
+                if let Some(caption) = &message.caption {
+                    s.push_str(&format!(" caption={}", r.text(caption)));
+                }
+                if message.photo.is_some() {
+                    s.push_str(" media=photo");
+                } else if message.document.is_some() {
+                    s.push_str(" media=document");
+                } else {
+                    #[cfg(feature = "stickers")]
+                    if message.sticker.is_some() {
+                        s.push_str(" media=sticker");
+                    }
+                }
+                s
+            }
+            Update::CallbackQuery(query) => {
+                let mut s = String::from("kind=callback_query");
+                if let Some(message) = &query.message {
+                    s.push_str(&format!(" chat={}", r.chat_id(message.chat.id)));
+                }
+                s.push_str(&format!(" {}", r.user(&query.from)));
+                if let Some(data) = &query.data {
+                    s.push_str(&format!(" data={}", r.text(data)));
+                }
+                s
+            }
+            #[cfg(feature = "inline")]
+            Update::InlineQuery(query) => format!(
+                "kind=inline_query {} query={}",
+                r.user(&query.from),
+                r.text(&query.query)
+            ),
+            Update::Album(messages) => {
+                let mut s = String::from("kind=album");
+                if let Some(first) = messages.first() {
+                    s.push_str(&format!(" chat={}", r.chat_id(first.chat.id)));
+                    if let Some(from) = &first.from {
+                        s.push_str(&format!(" {}", r.user(from)));
+                    }
+                }
+                s.push_str(&format!(" count={}", messages.len()));
+                if let Some(caption) = messages.iter().find_map(|m| m.caption.as_ref()) {
+                    s.push_str(&format!(" caption={}", r.text(caption)));
+                }
+                s
+            }
+            Update::MessageReaction(reaction) => {
+                let mut s = format!(
+                    "kind=message_reaction chat={} message_id={}",
+                    r.chat_id(reaction.chat.id),
+                    reaction.message_id
+                );
+                if let Some(user) = &reaction.user {
+                    s.push_str(&format!(" {}", r.user(user)));
+                }
+                s.push_str(&format!(" added={}", reaction.added_emoji().concat()));
+                s
+            }
+            Update::ChatJoinRequest(request) => format!(
+                "kind=chat_join_request chat={} {}",
+                r.chat_id(request.chat.id),
+                r.user(&request.from)
+            ),
+            Update::Unknown => String::from("kind=unknown"),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for LogHandler {
+    async fn run(&self, event: Event, _: State<S>) -> Result<Action, anyhow::Error> {
+        log!(self.level, "{}", self.summarize(&event.update));
+        Ok(Action::Next)
+    }
+}
+
+pub fn redacting_log_handler<S: BotState>(redaction: Redaction) -> Box<dyn BotHandlerFn<S>> {
+    Box::new(LogHandler::new(redaction))
+}
@@ -1,7 +1,19 @@
+pub mod anti_raid;
 pub mod auth;
+pub mod bookmark;
 pub mod done;
+pub mod features;
 pub mod log;
+pub mod settings_transfer;
+#[cfg(feature = "stickers")]
+pub mod sticker_set;
 
-pub use self::log::log_handler;
+pub use self::log::{log_handler, redacting_log_handler};
+pub use anti_raid::anti_raid;
 pub use auth::auth_handler;
+pub use bookmark::bookmark_bridge;
 pub use done::done_handler;
+pub use features::feature_handler;
+pub use settings_transfer::{export_settings_handler, import_settings_handler};
+#[cfg(feature = "stickers")]
+pub use sticker_set::sticker_set_wizard;
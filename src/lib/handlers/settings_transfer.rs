@@ -0,0 +1,202 @@
+use async_trait::async_trait;
+
+use crate::{
+    api::{self, DownloadRequest, GetFileRequest, InputFile, Message, SendDocumentRequest},
+    handler::{BotHandlerFn, BotState},
+    handlers::anti_raid::AntiRaidState,
+    Action, ChatSetting, Event, SettingsExport, State,
+};
+
+/// Settings files larger than this aren't downloaded.
+const MAX_EXPORT_SIZE: i64 = 1024 * 1024;
+
+/// The settings left out of exports and imports by default: state that belongs to what's
+/// happening in the chat right now, rather than its configuration.
+fn default_excluded() -> Vec<String> {
+    vec![
+        AntiRaidState::KEY.to_string(),
+        #[cfg(feature = "stickers")]
+        crate::handlers::sticker_set::StickerSetDraft::KEY.to_string(),
+    ]
+}
+
+/// A handler for `/export_settings`, which sends the chat's [`crate::Settings`] as a JSON file
+/// (see [`SettingsExport`]). Send the file to another chat with `/import_settings` (see
+/// [`ImportSettings`]) to copy the configuration.
+///
+/// The handlers don't check who's running the commands, so wrap them in a filter that does:
+///
+/// ```no_run
+/// # use mobot::*;
+/// # let mut router: Router<()> = Router::new(Client::new("token".to_string()));
+/// let mut router = router.with_caption_matching(true);
+/// router.add_route(
+///     Route::Message(Matcher::BotCommand("export_settings".into())),
+///     filters::when(filters::is_admin, handlers::export_settings_handler()),
+/// );
+/// router.add_route(
+///     Route::Message(Matcher::BotCommand("import_settings".into())),
+///     filters::when(filters::is_admin, handlers::import_settings_handler()),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExportSettings {
+    /// The keys of settings that aren't exported.
+    pub excluded: Vec<String>,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            excluded: default_excluded(),
+        }
+    }
+}
+
+impl ExportSettings {
+    /// Leave the setting `T` out of exports.
+    pub fn with_excluded<T: ChatSetting>(mut self) -> Self {
+        self.excluded.push(T::KEY.to_string());
+        self
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for ExportSettings {
+    async fn run(&self, e: Event, _: State<S>) -> Result<Action, anyhow::Error> {
+        let chat_id = e.update.chat_id()?;
+        let export = without(e.settings.export(chat_id).await?, &self.excluded);
+        if export.settings.is_empty() {
+            return Ok(Action::ReplyText(
+                "This chat has no settings to export.".into(),
+            ));
+        }
+
+        let req = SendDocumentRequest::new(chat_id, "").with_caption(
+            "This chat's settings. Send the file to another chat with /import_settings as its \
+             caption to copy them there.",
+        );
+        let file = InputFile::new(format!("settings-{}.json", chat_id), export.to_json()?)
+            .with_mime_type("application/json");
+        let result = e.api.send_document_file(&req, &file).await;
+        api::with_request(result, "sendDocument", &req)?;
+        Ok(Action::Done)
+    }
+}
+
+/// A handler for `/import_settings`, which replaces the chat's settings with the ones in a file
+/// made by [`ExportSettings`]. The file can be sent with the command as its caption, or the
+/// command can be sent as a reply to the file. The JSON can also follow the command in the
+/// message text.
+///
+/// Settings that aren't in the file are left alone.
+#[derive(Debug, Clone)]
+pub struct ImportSettings {
+    /// The keys of settings that aren't imported.
+    pub excluded: Vec<String>,
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        Self {
+            excluded: default_excluded(),
+        }
+    }
+}
+
+impl ImportSettings {
+    /// Leave the setting `T` out of imports.
+    pub fn with_excluded<T: ChatSetting>(mut self) -> Self {
+        self.excluded.push(T::KEY.to_string());
+        self
+    }
+
+    /// Returns the export's JSON from the message's text, or from the document attached to the
+    /// message or the message it replies to. Problems with what the user sent are returned as
+    /// the reply for them.
+    async fn find_export(
+        &self,
+        e: &Event,
+        message: &Message,
+    ) -> anyhow::Result<Result<String, &'static str>> {
+        let text = message.text_or_caption().unwrap_or_default();
+        let args = text
+            .split_once(char::is_whitespace)
+            .map(|(_, args)| args.trim());
+        if let Some(json) = args.filter(|args| args.starts_with('{')) {
+            return Ok(Ok(json.to_string()));
+        }
+
+        let document = message.document.as_ref().or_else(|| {
+            message
+                .reply_to_message
+                .as_ref()
+                .and_then(|reply| reply.document.as_ref())
+        });
+        let Some(document) = document else {
+            return Ok(Err(
+                "Send a settings file from /export_settings with /import_settings as its \
+                 caption, or reply to one with /import_settings.",
+            ));
+        };
+        if document.file_size.unwrap_or_default() > MAX_EXPORT_SIZE {
+            return Ok(Err("That file is too large to be a settings export."));
+        }
+
+        let file = e
+            .api
+            .get_file(&GetFileRequest::new(document.file_id.clone()))
+            .await?;
+        let Some(file_path) = file.file_path else {
+            return Ok(Err(
+                "Telegram couldn't find that file, please send it again.",
+            ));
+        };
+        let data = e
+            .api
+            .download_file(&DownloadRequest::new(file_path))
+            .await?;
+        Ok(Ok(String::from_utf8_lossy(&data).into_owned()))
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for ImportSettings {
+    async fn run(&self, e: Event, _: State<S>) -> Result<Action, anyhow::Error> {
+        let chat_id = e.update.chat_id()?;
+        let message = e.update.get_message_or_post()?.clone();
+
+        let json = match self.find_export(&e, &message).await? {
+            Ok(json) => json,
+            Err(problem) => return Ok(Action::ReplyText(problem.into())),
+        };
+
+        let export = match SettingsExport::from_json(&json) {
+            Ok(export) => without(export, &self.excluded),
+            Err(err) => return Ok(Action::ReplyText(format!("Can't import that: {}", err))),
+        };
+        if export.settings.is_empty() {
+            return Ok(Action::ReplyText("There are no settings to import.".into()));
+        }
+
+        let keys = e.settings.import(chat_id, &export).await?;
+        Ok(Action::ReplyText(format!(
+            "Imported settings: {}.",
+            keys.join(", ")
+        )))
+    }
+}
+
+fn without(export: SettingsExport, excluded: &[String]) -> SettingsExport {
+    excluded
+        .iter()
+        .fold(export, |export, key| export.without_key(key))
+}
+
+pub fn export_settings_handler<S: BotState>() -> Box<dyn BotHandlerFn<S>> {
+    Box::new(ExportSettings::default())
+}
+
+pub fn import_settings_handler<S: BotState>() -> Box<dyn BotHandlerFn<S>> {
+    Box::new(ImportSettings::default())
+}
@@ -0,0 +1,384 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{
+        AddStickerToSetRequest, ApiError, CreateNewStickerSetRequest, DownloadRequest,
+        GetFileRequest, InputFile, InputSticker, Message, StickerFormat, UploadStickerFileRequest,
+    },
+    handler::{BotHandlerFn, BotState},
+    Action, ChatSetting, Event, State, Update, Versioned,
+};
+
+/// Static sticker files can be at most this large.
+pub const MAX_STICKER_FILE_SIZE: usize = 512 * 1024;
+
+/// Static sticker images are this many pixels on their longest side.
+pub const STICKER_SIZE: u32 = 512;
+
+/// Sticker sets can have at most this many static stickers.
+pub const MAX_STICKERS: usize = 120;
+
+/// Where a chat is in the [`StickerSetWizard`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WizardStep {
+    /// Not making a sticker set.
+    #[default]
+    Idle,
+
+    /// Waiting for the set's title.
+    Title,
+
+    /// Waiting for the set's short name.
+    Name,
+
+    /// Collecting stickers.
+    Stickers,
+}
+
+/// A sticker set in progress, stored with the chat's [`crate::Settings`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StickerSetDraft {
+    pub step: WizardStep,
+
+    /// The set's title.
+    pub title: String,
+
+    /// The set's full short name, ending in `_by_<bot_username>`.
+    pub name: String,
+
+    /// The stickers uploaded so far.
+    pub stickers: Vec<InputSticker>,
+}
+
+impl ChatSetting for StickerSetDraft {
+    const KEY: &'static str = "sticker_set_draft";
+}
+
+impl Versioned for StickerSetDraft {}
+
+/// A handler that walks a user through creating a sticker set: `/newpack` asks for the set's
+/// title and short name, and then collects images sent as files. Each image is checked
+/// (PNG or WEBP, 512 pixels on the longest side, up to 512KB) and uploaded with
+/// `uploadStickerFile`, with the file's caption as its emoji. `/done` creates the set with
+/// `createNewStickerSet` and `addStickerToSet`, and replies with its link; `/cancel` gives
+/// up.
+///
+/// The wizard's progress is kept per chat in the router's [`crate::Settings`], so register it
+/// for private chats. mobot has no dialogue subsystem, and the per-chat [`State`] handlers get
+/// is the bot's own type, which a reusable handler can't add fields to, so the wizard keeps its
+/// own small state machine ([`WizardStep`]) as a chat setting instead. That also means a
+/// wizard in progress survives restarts if the settings are persisted. It needs text messages,
+/// documents and photos:
+///
+/// ```no_run
+/// # use mobot::*;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN").unwrap());
+/// let mut router = Router::<()>::new(client);
+/// for matcher in [Matcher::Any, Matcher::Document, Matcher::Photo] {
+///     router.add_route(
+///         Route::Message(matcher),
+///         filters::private(handlers::sticker_set_wizard()),
+///     );
+/// }
+/// router.start().await;
+/// # }
+/// ```
+///
+/// Messages in chats that aren't making a set are passed on with [`Action::Next`].
+#[derive(Debug, Clone)]
+pub struct StickerSetWizard {
+    /// The command that starts the wizard, without the slash. Defaults to `newpack`.
+    pub command: String,
+
+    /// The emoji for stickers sent without a caption. Defaults to 🙂.
+    pub default_emoji: String,
+}
+
+impl Default for StickerSetWizard {
+    fn default() -> Self {
+        Self {
+            command: "newpack".into(),
+            default_emoji: "🙂".into(),
+        }
+    }
+}
+
+impl StickerSetWizard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_command(mut self, command: impl Into<String>) -> Self {
+        self.command = command.into();
+        self
+    }
+
+    pub fn with_default_emoji(mut self, emoji: impl Into<String>) -> Self {
+        self.default_emoji = emoji.into();
+        self
+    }
+
+    async fn set_name(&self, e: &Event, chat_id: i64, name: &str) -> anyhow::Result<Action> {
+        let me = e.api.me().await?;
+        let name = format!("{}_by_{}", name, me.username.unwrap_or_default());
+        if let Err(problem) = validate_name(&name) {
+            return reply(problem);
+        }
+
+        e.settings
+            .update::<StickerSetDraft>(chat_id, |draft| {
+                draft.name = name;
+                draft.step = WizardStep::Stickers;
+            })
+            .await?;
+        reply(
+            "Now send the stickers as image files: PNG or WEBP, 512 pixels on the longest side, \
+             up to 512KB. Add emoji as the caption. Send /done when you're finished.",
+        )
+    }
+
+    async fn add_sticker(
+        &self,
+        e: &Event,
+        message: &Message,
+        user_id: i64,
+        draft: &StickerSetDraft,
+    ) -> anyhow::Result<Action> {
+        if message.photo.is_some() {
+            return reply("Send the image as a file, so Telegram doesn't compress it.");
+        }
+        let Some(document) = &message.document else {
+            return reply("Send an image file, /done, or /cancel.");
+        };
+        if draft.stickers.len() >= MAX_STICKERS {
+            return reply("The pack is full. Send /done to create it.");
+        }
+        if document.file_size.unwrap_or_default() as usize > MAX_STICKER_FILE_SIZE {
+            return reply("That file is too large, stickers can be up to 512KB.");
+        }
+
+        let file = e
+            .api
+            .get_file(&GetFileRequest::new(document.file_id.clone()))
+            .await?;
+        let Some(file_path) = file.file_path else {
+            return reply("Telegram couldn't find that file, please send it again.");
+        };
+        let data = e
+            .api
+            .download_file(&DownloadRequest::new(file_path))
+            .await?;
+        if let Err(problem) = validate_image(&data) {
+            return reply(&problem);
+        }
+
+        let name = document.file_name.as_deref().unwrap_or("sticker.png");
+        let uploaded = e
+            .api
+            .upload_sticker_file(
+                &UploadStickerFileRequest::new(user_id, StickerFormat::Static),
+                &InputFile::new(name, data),
+            )
+            .await?;
+
+        let mut emoji_list: Vec<String> = message
+            .caption
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .take(20)
+            .map(String::from)
+            .collect();
+        if emoji_list.is_empty() {
+            emoji_list.push(self.default_emoji.clone());
+        }
+
+        let sticker = InputSticker::new(uploaded.file_id, StickerFormat::Static, emoji_list);
+        let draft = e
+            .settings
+            .update::<StickerSetDraft>(message.chat.id, |draft| draft.stickers.push(sticker))
+            .await?;
+        reply(&format!(
+            "Added sticker {}. Send another, or /done.",
+            draft.stickers.len()
+        ))
+    }
+
+    async fn finish(
+        &self,
+        e: &Event,
+        chat_id: i64,
+        user_id: i64,
+        draft: StickerSetDraft,
+    ) -> anyhow::Result<Action> {
+        let Some((first, rest)) = draft.stickers.split_first() else {
+            return reply("Send at least one image first.");
+        };
+
+        let req = CreateNewStickerSetRequest::new(
+            user_id,
+            &draft.name,
+            &draft.title,
+            vec![first.clone()],
+        );
+        if let Err(err) = e.api.create_new_sticker_set(&req).await {
+            let occupied = matches!(err.downcast_ref::<ApiError>(),
+                Some(ApiError::AppError(description)) if description.contains("occupied"));
+            if !occupied {
+                return Err(err);
+            }
+            e.settings
+                .update::<StickerSetDraft>(chat_id, |draft| draft.step = WizardStep::Name)
+                .await?;
+            return reply("That name is taken. Pick another one.");
+        }
+
+        let mut failed = 0;
+        for sticker in rest {
+            let req = AddStickerToSetRequest::new(user_id, &draft.name, sticker.clone());
+            if let Err(err) = e.api.add_sticker_to_set(&req).await {
+                warn!("Can't add sticker to set {}: {}", draft.name, err);
+                failed += 1;
+            }
+        }
+        e.settings.reset::<StickerSetDraft>(chat_id).await?;
+
+        let link = format!("https://t.me/addstickers/{}", draft.name);
+        match failed {
+            0 => reply(&format!("Your sticker pack is ready: {}", link)),
+            _ => reply(&format!(
+                "Your sticker pack is ready: {} ({} stickers couldn't be added)",
+                link, failed
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: BotState> BotHandlerFn<S> for StickerSetWizard {
+    async fn run(&self, e: Event, _: State<S>) -> Result<Action, anyhow::Error> {
+        let Update::Message(message) = &e.update else {
+            return Ok(Action::Next);
+        };
+        let Some(user) = &message.from else {
+            return Ok(Action::Next);
+        };
+        let chat_id = message.chat.id;
+        let text = message.text.as_deref().map(str::trim);
+        let command = text
+            .and_then(|text| text.strip_prefix('/'))
+            .and_then(|command| command.split(['@', ' ']).next());
+
+        if command == Some(self.command.as_str()) {
+            let draft = StickerSetDraft {
+                step: WizardStep::Title,
+                ..Default::default()
+            };
+            e.settings.set(chat_id, &draft).await?;
+            return reply("Let's make a sticker pack! What's its title?");
+        }
+
+        let draft = e.settings.get::<StickerSetDraft>(chat_id).await?;
+        if draft.step == WizardStep::Idle {
+            return Ok(Action::Next);
+        }
+
+        match (draft.step, command, text) {
+            (_, Some("cancel"), _) => {
+                e.settings.reset::<StickerSetDraft>(chat_id).await?;
+                reply("Cancelled, the sticker pack wasn't created.")
+            }
+            (WizardStep::Title, None, Some(title)) if title.chars().count() <= 64 => {
+                e.settings
+                    .update::<StickerSetDraft>(chat_id, |draft| {
+                        draft.title = title.to_string();
+                        draft.step = WizardStep::Name;
+                    })
+                    .await?;
+                reply(
+                    "Now pick a short name for the pack's link: English letters, digits and \
+                     underscores, starting with a letter.",
+                )
+            }
+            (WizardStep::Title, _, _) => reply("Send a title of up to 64 characters, or /cancel."),
+            (WizardStep::Name, None, Some(name)) => self.set_name(&e, chat_id, name).await,
+            (WizardStep::Name, _, _) => reply("Send a short name for the pack, or /cancel."),
+            (WizardStep::Stickers, Some("done"), _) => {
+                self.finish(&e, chat_id, user.id, draft).await
+            }
+            (WizardStep::Stickers, _, _) => self.add_sticker(&e, message, user.id, &draft).await,
+            (WizardStep::Idle, _, _) => Ok(Action::Next),
+        }
+    }
+}
+
+fn reply(text: &str) -> anyhow::Result<Action> {
+    Ok(Action::ReplyText(text.into()))
+}
+
+/// Check a sticker set's full short name against Telegram's rules.
+pub fn validate_name(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("That name is too long. Try a shorter one.");
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err("The name has to start with a letter.");
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') || name.contains("__") {
+        return Err(
+            "The name can only have English letters, digits, and single underscores. Try another.",
+        );
+    }
+    Ok(())
+}
+
+/// Check that `data` is an image Telegram accepts as a static sticker.
+pub fn validate_image(data: &[u8]) -> Result<(), String> {
+    if data.len() > MAX_STICKER_FILE_SIZE {
+        return Err("That file is too large, stickers can be up to 512KB.".into());
+    }
+    let Some((width, height)) = image_size(data) else {
+        return Err("Send a PNG or WEBP image.".into());
+    };
+    if width.max(height) != STICKER_SIZE {
+        return Err(format!(
+            "Stickers have to be 512 pixels on their longest side, that image is {}x{}.",
+            width, height
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the width and height of a PNG or WEBP image, from its header.
+pub fn image_size(data: &[u8]) -> Option<(u32, u32)> {
+    let be32 = |at: usize| Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    let le16 = |at: usize| Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?) as u32);
+    let le24 = |at: usize| {
+        let bytes = data.get(at..at + 3)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+    };
+
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") && data.get(12..16) == Some(b"IHDR") {
+        return Some((be32(16)?, be32(20)?));
+    }
+
+    if !data.starts_with(b"RIFF") || data.get(8..12) != Some(b"WEBP") {
+        return None;
+    }
+    match data.get(12..16)? {
+        b"VP8 " => Some((le16(26)? & 0x3fff, le16(28)? & 0x3fff)),
+        b"VP8L" => {
+            let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+            Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+        }
+        b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+        _ => None,
+    }
+}
+
+pub fn sticker_set_wizard<S: BotState>() -> Box<dyn BotHandlerFn<S>> {
+    Box::new(StickerSetWizard::new())
+}
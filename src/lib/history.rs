@@ -0,0 +1,254 @@
+/// A per-chat read model of recent messages. When enabled with
+/// [`crate::Router::with_message_history`], the router records every incoming message (and its
+/// edits), along with the replies it sends for [`crate::Action`]s, in a [`StateStorage`]. This
+/// is enough for features like `/purge last 20`, or a context window for an AI bot, without
+/// running a separate database.
+///
+/// Messages sent directly through the API (e.g., with [`crate::Event::send_message`]) aren't
+/// seen by the router; call [`MessageHistory::record`] to add them.
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::Mutex;
+
+use crate::{
+    api,
+    storage::{MemoryStorage, NamespacedStorage, StateStorage},
+    UserData,
+};
+
+/// The storage namespace used for message history, keyed by chat ID.
+const NAMESPACE: &str = "history";
+
+/// The storage namespace for the IDs of the chats each user has recorded messages in, keyed by
+/// user ID, so their messages can be found for [`UserData`].
+const USERS_NAMESPACE: &str = "history_users";
+
+/// `HistoryEntry` is a recorded message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub message_id: i64,
+
+    /// Date the message was sent, in Unix time.
+    pub date: i64,
+
+    /// The sender's user ID, if known. Channel posts typically have no sender.
+    pub from_id: Option<i64>,
+
+    /// The sender's first name, if known.
+    pub from_name: Option<String>,
+
+    /// True if the message was sent by a bot (including this one).
+    #[serde(default)]
+    pub from_bot: bool,
+
+    /// The message text, or the caption for media messages.
+    pub text: Option<String>,
+}
+
+impl From<&api::Message> for HistoryEntry {
+    fn from(message: &api::Message) -> Self {
+        Self {
+            message_id: message.message_id,
+            date: message.date,
+            from_id: message.from.as_ref().map(|u| u.id),
+            from_name: message.from.as_ref().map(|u| u.first_name.clone()),
+            from_bot: message.from.as_ref().is_some_and(|u| u.is_bot),
+            text: message.text.clone().or(message.caption.clone()),
+        }
+    }
+}
+
+/// `MessageHistory` keeps the last `capacity` messages of every chat. Clones share the same
+/// storage.
+///
+/// ```no_run
+/// # use mobot::*;
+/// async fn purge(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     let chat_id = e.update.chat_id()?;
+///     let history = e.history.as_ref().ok_or(anyhow::anyhow!("history disabled"))?;
+///
+///     for entry in history.last(chat_id, 20).await? {
+///         e.delete_message(entry.message_id).await?;
+///         history.remove(chat_id, entry.message_id).await?;
+///     }
+///     Ok(Action::Done)
+/// }
+/// ```
+#[derive(Clone)]
+pub struct MessageHistory {
+    storage: Arc<dyn StateStorage>,
+    capacity: usize,
+
+    /// Serializes read-modify-write cycles on a chat's history.
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl Default for MessageHistory {
+    fn default() -> Self {
+        Self::new(MemoryStorage::new())
+    }
+}
+
+impl MessageHistory {
+    /// Create a new `MessageHistory` persisted in `storage`, keeping the last 100 messages per
+    /// chat.
+    pub fn new(storage: impl StateStorage + 'static) -> Self {
+        Self::from_arc(Arc::new(storage))
+    }
+
+    /// Create a new `MessageHistory` persisted in a shared `storage`.
+    pub fn from_arc(storage: Arc<dyn StateStorage>) -> Self {
+        Self {
+            storage,
+            capacity: 100,
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Returns a separate history, kept in the same storage under `prefix`, e.g. for one tenant
+    /// of a multi-tenant router.
+    pub fn with_namespace(&self, prefix: impl Into<String>) -> Self {
+        Self::from_arc(Arc::new(NamespacedStorage::new(
+            Arc::clone(&self.storage),
+            prefix,
+        )))
+        .with_capacity(self.capacity)
+    }
+
+    /// Set the number of messages kept per chat.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Record `message` in its chat's history. If the message is already recorded (i.e., it
+    /// was edited), its entry is replaced.
+    pub async fn record(&self, message: &api::Message) -> Result<()> {
+        let chat_id = message.chat.id;
+        let entry = HistoryEntry::from(message);
+
+        let _guard = self.write_lock.lock().await;
+        let mut entries = self.load(chat_id).await?;
+        match entries
+            .iter_mut()
+            .find(|e| e.message_id == entry.message_id)
+        {
+            Some(existing) => *existing = entry,
+            None => entries.push(entry),
+        }
+
+        if entries.len() > self.capacity {
+            entries.drain(..entries.len() - self.capacity);
+        }
+        self.store(chat_id, entries).await?;
+
+        if let Some(user_id) = message.from.as_ref().map(|u| u.id) {
+            let mut chat_ids = self.chats_of(user_id).await?;
+            if !chat_ids.contains(&chat_id) {
+                chat_ids.push(chat_id);
+                self.storage
+                    .set(USERS_NAMESPACE, user_id, serde_json::to_value(chat_ids)?)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the last `n` messages in the chat, oldest first.
+    pub async fn last(&self, chat_id: i64, n: usize) -> Result<Vec<HistoryEntry>> {
+        let mut entries = self.load(chat_id).await?;
+        entries.drain(..entries.len().saturating_sub(n));
+        Ok(entries)
+    }
+
+    /// Return all recorded messages in the chat, oldest first.
+    pub async fn all(&self, chat_id: i64) -> Result<Vec<HistoryEntry>> {
+        self.load(chat_id).await
+    }
+
+    /// Forget a single message, e.g., after deleting it.
+    pub async fn remove(&self, chat_id: i64, message_id: i64) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let mut entries = self.load(chat_id).await?;
+        entries.retain(|e| e.message_id != message_id);
+        self.store(chat_id, entries).await
+    }
+
+    /// Forget the chat's history.
+    pub async fn clear(&self, chat_id: i64) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        self.storage.delete(NAMESPACE, chat_id).await
+    }
+
+    /// The chats `user_id` may have recorded messages in: the ones they sent messages to, and
+    /// their private chat with the bot.
+    async fn chats_of(&self, user_id: i64) -> Result<Vec<i64>> {
+        match self.storage.get(USERS_NAMESPACE, user_id).await? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn load(&self, chat_id: i64) -> Result<Vec<HistoryEntry>> {
+        match self.storage.get(NAMESPACE, chat_id).await? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn store(&self, chat_id: i64, entries: Vec<HistoryEntry>) -> Result<()> {
+        if entries.is_empty() {
+            self.storage.delete(NAMESPACE, chat_id).await
+        } else {
+            self.storage
+                .set(NAMESPACE, chat_id, serde_json::to_value(entries)?)
+                .await
+        }
+    }
+}
+
+/// A user's history is their private chat with the bot, and the messages they sent in other
+/// chats, keyed by chat ID.
+#[async_trait]
+impl UserData for MessageHistory {
+    async fn export_user(&self, user_id: i64) -> Result<Value> {
+        let mut chat_ids = self.chats_of(user_id).await?;
+        if !chat_ids.contains(&user_id) {
+            chat_ids.push(user_id);
+        }
+
+        let mut export = Map::new();
+        for chat_id in chat_ids {
+            let mut entries = self.load(chat_id).await?;
+            if chat_id != user_id {
+                entries.retain(|e| e.from_id == Some(user_id));
+            }
+            if !entries.is_empty() {
+                export.insert(chat_id.to_string(), serde_json::to_value(entries)?);
+            }
+        }
+
+        Ok(if export.is_empty() {
+            Value::Null
+        } else {
+            Value::Object(export)
+        })
+    }
+
+    async fn delete_user(&self, user_id: i64) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        for chat_id in self.chats_of(user_id).await? {
+            if chat_id != user_id {
+                let mut entries = self.load(chat_id).await?;
+                entries.retain(|e| e.from_id != Some(user_id));
+                self.store(chat_id, entries).await?;
+            }
+        }
+        self.storage.delete(NAMESPACE, user_id).await?;
+        self.storage.delete(USERS_NAMESPACE, user_id).await
+    }
+}
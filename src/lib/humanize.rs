@@ -0,0 +1,119 @@
+/// Natural reply pacing. Replies that arrive the instant a user hits send feel robotic, so some
+/// bots pause before answering, as if typing. A [`Humanizer`] waits in proportion to the
+/// reply's length while showing the "typing…" action, so handlers don't need hand-written
+/// sleeps. Enable it for the router's replies with [`crate::Router::with_humanizer`], or call
+/// [`Humanizer::send_message`] from handlers.
+///
+/// Typing actions are cosmetic: they're resent only as often as Telegram needs to keep them
+/// visible, and if Telegram starts rate limiting the bot, they stop for the rest of the reply
+/// rather than competing with real messages.
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::{
+    api::{self, ApiError, ChatAction, SendChatActionRequest, API},
+    Event, Text,
+};
+
+/// `Humanizer` decides how long a reply takes to "type", and shows the typing action while it
+/// waits.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use std::time::Duration;
+/// async fn handle(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     let humanizer = Humanizer::default().with_chars_per_second(15.0);
+///     humanizer.send_message(&e, "Let me think about that...").await?;
+///     Ok(Action::Done)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Humanizer {
+    /// Typing speed. The delay for a reply is its length divided by this.
+    pub chars_per_second: f64,
+
+    /// The shortest delay, for very short replies.
+    pub min_delay: Duration,
+
+    /// The longest delay, so long replies don't keep users waiting.
+    pub max_delay: Duration,
+
+    /// How often the typing action is resent. Telegram shows it for 5 seconds, or until the
+    /// next message arrives.
+    pub action_interval: Duration,
+}
+
+impl Default for Humanizer {
+    fn default() -> Self {
+        Self {
+            chars_per_second: 30.0,
+            min_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(4),
+            action_interval: Duration::from_millis(4500),
+        }
+    }
+}
+
+impl Humanizer {
+    pub fn with_chars_per_second(mut self, chars_per_second: f64) -> Self {
+        self.chars_per_second = chars_per_second;
+        self
+    }
+
+    pub fn with_delay(mut self, min_delay: Duration, max_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub fn with_action_interval(mut self, interval: Duration) -> Self {
+        self.action_interval = interval;
+        self
+    }
+
+    /// Returns how long typing `text` takes.
+    pub fn delay(&self, text: &str) -> Duration {
+        let secs = text.chars().count() as f64 / self.chars_per_second.max(f64::MIN_POSITIVE);
+        Duration::try_from_secs_f64(secs)
+            .unwrap_or(self.max_delay)
+            .clamp(self.min_delay, self.min_delay.max(self.max_delay))
+    }
+
+    /// Show the typing action in the chat for as long as typing `text` takes. Failures to send
+    /// the action are logged, and don't shorten the wait.
+    pub async fn type_text(&self, api: &API, chat_id: i64, text: &str) {
+        let clock = api.clock();
+        let (start, delay) = (clock.now(), self.delay(text));
+        let req = SendChatActionRequest::new(chat_id, ChatAction::Typing);
+
+        loop {
+            if let Err(err) = api.send_chat_action(&req).await {
+                debug!("Can't show typing action in chat {}: {}", chat_id, err);
+                if err
+                    .downcast_ref::<ApiError>()
+                    .is_some_and(ApiError::is_rate_limit)
+                {
+                    break;
+                }
+            }
+
+            if clock.elapsed(start) + self.action_interval >= delay {
+                break;
+            }
+            clock.sleep(self.action_interval).await;
+        }
+        clock
+            .sleep(delay.saturating_sub(clock.elapsed(start)))
+            .await;
+    }
+
+    /// Show the typing action for as long as typing `text` takes, then send it to the event's
+    /// chat.
+    pub async fn send_message(&self, e: &Event, text: impl Into<Text>) -> Result<api::Message> {
+        let text = text.into();
+        self.type_text(&e.api, e.update.chat_id()?, &text.to_string())
+            .await;
+        e.send_message(text).await
+    }
+}
@@ -0,0 +1,184 @@
+/// A local cache for inline query answers. Telegram caches answers on its side too (see
+/// [`api::AnswerInlineQuery::cache_time`]), but only per exact query, and each query still
+/// reaches the bot the first time a user types it. [`InlineCache`] keeps answers in the bot,
+/// keyed by a normalized query, so popular searches only hit the backend once per TTL.
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::api::{self, API};
+
+/// `InlineCache` serves `answerInlineQuery` results from memory for `ttl` after they're first
+/// computed. Clones share the same entries. Time is measured with the API's clock (see
+/// [`API::with_clock`]). It holds at most 1024 answers by default (see
+/// [`InlineCache::with_capacity`]), evicting the oldest first.
+///
+/// Answers marked [`api::AnswerInlineQuery::is_personal`] are never cached, since they're
+/// specific to the user who asked.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use mobot::inline_cache::InlineCache;
+/// async fn search(e: Event, cache: InlineCache) -> Result<Action, anyhow::Error> {
+///     let query = e.update.get_inline_query()?;
+///     cache
+///         .answer(&e.api, query, || async {
+///             let results = format!("Results for {}", query.query);
+///             Ok(api::AnswerInlineQuery::new(query.id.clone()).with_article_text("Search", results))
+///         })
+///         .await?;
+///     Ok(Action::Done)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InlineCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: Arc<Mutex<Entries>>,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    /// Answers by cache key, with the time they expire (in Unix time).
+    answers: HashMap<String, (i64, api::AnswerInlineQuery)>,
+
+    /// Cache keys with the time they expire, oldest first. Keys that were replaced or dropped
+    /// since are skipped when they reach the front.
+    order: VecDeque<(i64, String)>,
+}
+
+impl Entries {
+    /// Drop the oldest answer. Returns false if there are none left.
+    fn pop_oldest(&mut self) -> bool {
+        while let Some((expires, key)) = self.order.pop_front() {
+            if self.answers.get(&key).is_some_and(|(e, _)| *e == expires) {
+                self.answers.remove(&key);
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl InlineCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            capacity: 1024,
+            entries: Arc::new(Mutex::new(Entries::default())),
+        }
+    }
+
+    /// Set the number of answers kept. Once it's reached, the oldest answer (which is the
+    /// first to expire) is dropped for each new one.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Normalize `query` for use as a cache key: surrounding whitespace is trimmed, runs of
+    /// whitespace are collapsed, and the query is lowercased.
+    pub fn normalize(query: &str) -> String {
+        query
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    }
+
+    /// Answer `query` from the cache if there's a fresh entry for it, or with the answer `f`
+    /// returns (which is cached) otherwise. Returns the result of `answerInlineQuery`.
+    pub async fn answer<F, Fut>(
+        &self,
+        api: &API,
+        query: &api::InlineQuery,
+        f: F,
+    ) -> anyhow::Result<bool>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = anyhow::Result<api::AnswerInlineQuery>>,
+    {
+        let now = api.now();
+        let answer = match self.get(query, now) {
+            Some(answer) => answer,
+            None => {
+                let answer = f().await?;
+                self.insert(query, answer.clone(), now);
+                answer
+            }
+        };
+
+        api.answer_inline_query(&api::AnswerInlineQuery {
+            inline_query_id: query.id.clone(),
+            ..answer
+        })
+        .await
+    }
+
+    /// Returns the cached answer for `query` if it hasn't expired at `now` (in Unix time).
+    pub fn get(&self, query: &api::InlineQuery, now: i64) -> Option<api::AnswerInlineQuery> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .answers
+            .get(&Self::key(query))
+            .filter(|(expires, _)| *expires > now)
+            .map(|(_, answer)| answer.clone())
+    }
+
+    /// Cache `answer` for `query`, as of `now` (in Unix time). Personal answers are ignored.
+    pub fn insert(&self, query: &api::InlineQuery, answer: api::AnswerInlineQuery, now: i64) {
+        if answer.is_personal == Some(true) {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        while entries.answers.len() >= self.capacity && entries.pop_oldest() {}
+
+        // Drop the skipped keys once they're most of the queue, e.g. after a query's answer
+        // was replaced many times.
+        if entries.order.len() >= 2 * self.capacity {
+            let Entries { answers, order } = &mut *entries;
+            order.retain(|(expires, key)| answers.get(key).is_some_and(|(e, _)| e == expires));
+        }
+
+        let key = Self::key(query);
+        let expires = now + self.ttl.as_secs() as i64;
+        entries.order.push_back((expires, key.clone()));
+        entries.answers.insert(key, (expires, answer));
+    }
+
+    /// Drop the cached answers for the query string `query`, for every offset.
+    pub fn invalidate(&self, query: &str) {
+        let prefix = format!("{}\0", Self::normalize(query));
+        self.entries
+            .lock()
+            .unwrap()
+            .answers
+            .retain(|key, _| !key.starts_with(&prefix));
+    }
+
+    /// Drop all cached answers.
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.answers.clear();
+        entries.order.clear();
+    }
+
+    /// Returns the number of cached answers, including expired ones that haven't been pruned.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().answers.len()
+    }
+
+    /// Returns true if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Pages of results are cached separately, so the offset is part of the key.
+    fn key(query: &api::InlineQuery) -> String {
+        format!("{}\0{}", Self::normalize(&query.query), query.offset)
+    }
+}
@@ -0,0 +1,312 @@
+/// Back a Telegram chat with a large language model. [`ChatCompletion`] is the provider
+/// interface, and [`stream_reply`] streams a completion into the chat with a
+/// [`StreamingMessage`]. [`OpenAiChat`] is a provider for OpenAI's chat completions API, and for
+/// compatible servers (e.g., vLLM, Ollama, or LiteLLM); implement `ChatCompletion` to use any
+/// other.
+///
+/// Requires the `llm` feature.
+///
+/// ```no_run
+/// # use mobot::{integrations::llm::*, *};
+/// # use std::sync::Arc;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let llm = Arc::new(OpenAiChat::new(std::env::var("OPENAI_API_KEY").unwrap(), "gpt-4o-mini"));
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN").unwrap());
+/// let mut router = Router::<()>::new(client).with_message_history(MessageHistory::default());
+///
+/// router.add_route(Route::Message(Matcher::Any), move |e: Event, _: State<()>| {
+///     let llm = Arc::clone(&llm);
+///     async move {
+///         // The history includes the message being handled.
+///         let history = e.history.as_ref().unwrap().last(e.update.chat_id()?, 20).await?;
+///         let mut messages = vec![ChatMessage::system("You are a helpful Telegram bot.")];
+///         messages.extend(ChatMessage::from_history(&history));
+///
+///         stream_reply(&e, llm.as_ref(), &messages).await?;
+///         Ok(Action::Done)
+///     }
+/// });
+/// router.start().await;
+/// # }
+/// ```
+use std::collections::VecDeque;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use futures::{stream::BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{api, Event, HistoryEntry, StreamingMessage};
+
+/// The author of a [`ChatMessage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+/// A message in the conversation passed to the model.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new(role: Role, content: impl Into<String>) -> Self {
+        Self {
+            role,
+            content: content.into(),
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new(Role::System, content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new(Role::User, content)
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new(Role::Assistant, content)
+    }
+
+    /// Convert recorded chat history into a conversation. Messages from bots become
+    /// assistant messages, and everything else becomes user messages. Entries without text
+    /// are skipped.
+    pub fn from_history(entries: &[HistoryEntry]) -> Vec<Self> {
+        entries
+            .iter()
+            .filter_map(|entry| {
+                let text = entry.text.clone()?;
+                Some(if entry.from_bot {
+                    Self::assistant(text)
+                } else {
+                    Self::user(text)
+                })
+            })
+            .collect()
+    }
+}
+
+/// A stream of text deltas from the model.
+pub type CompletionStream = BoxStream<'static, Result<String>>;
+
+/// `ChatCompletion` is implemented by LLM providers. Providers without streaming support can
+/// return the whole completion as a single delta.
+#[async_trait]
+pub trait ChatCompletion: Send + Sync {
+    /// Generate a reply to `messages`, streamed as text deltas.
+    async fn stream(&self, messages: &[ChatMessage]) -> Result<CompletionStream>;
+
+    /// Generate a reply to `messages`, and return it once complete.
+    async fn complete(&self, messages: &[ChatMessage]) -> Result<String> {
+        let mut stream = self.stream(messages).await?;
+        let mut reply = String::new();
+        while let Some(delta) = stream.next().await {
+            reply.push_str(&delta?);
+        }
+        Ok(reply)
+    }
+}
+
+/// Stream a reply to `messages` from `llm` into the chat in `e`, and return the full reply. If
+/// the stream fails partway, the text received so far is still shown before the error is
+/// returned.
+pub async fn stream_reply(
+    e: &Event,
+    llm: &dyn ChatCompletion,
+    messages: &[ChatMessage],
+) -> Result<String> {
+    let mut stream = llm.stream(messages).await?;
+    let mut message = StreamingMessage::start(e)?;
+    let mut reply = String::new();
+
+    let mut result = Ok(());
+    while let Some(delta) = stream.next().await {
+        match delta {
+            Ok(delta) => {
+                message.push(&delta).await?;
+                reply.push_str(&delta);
+            }
+            Err(err) => {
+                result = Err(err);
+                break;
+            }
+        }
+    }
+
+    // Record the reply, so it's part of the context next time. Long replies are split across
+    // messages, and each is recorded with its own text.
+    let sent = message.finish_with_texts().await?;
+    if let Some(history) = &e.history
+        && !sent.is_empty()
+    {
+        let chat_id = e.update.chat_id()?;
+        let me = e.api.me().await?;
+        for (message_id, text) in sent {
+            history
+                .record(&api::Message {
+                    message_id,
+                    date: e.api.now(),
+                    chat: api::Chat {
+                        id: chat_id,
+                        ..Default::default()
+                    },
+                    from: Some(me.clone()),
+                    text: Some(text),
+                    ..Default::default()
+                })
+                .await?;
+        }
+    }
+
+    result.map(|_| reply)
+}
+
+/// `OpenAiChat` streams completions from OpenAI's `/chat/completions` endpoint, or from any
+/// server that implements it.
+#[derive(Debug, Clone)]
+pub struct OpenAiChat {
+    http: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    model: String,
+
+    /// Extra request parameters (e.g., `temperature`), merged into every request.
+    params: serde_json::Map<String, Value>,
+}
+
+impl OpenAiChat {
+    pub fn new(api_key: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: "https://api.openai.com/v1".into(),
+            api_key: api_key.into(),
+            model: model.into(),
+            params: serde_json::Map::new(),
+        }
+    }
+
+    /// Use a compatible server instead of OpenAI, e.g. `http://localhost:11434/v1`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Use `http` to make requests, e.g. to configure timeouts or a proxy.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Set a request parameter, e.g. `with_param("temperature", 0.2)`.
+    pub fn with_param(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.params.insert(name.into(), value.into());
+        self
+    }
+}
+
+#[async_trait]
+impl ChatCompletion for OpenAiChat {
+    async fn stream(&self, messages: &[ChatMessage]) -> Result<CompletionStream> {
+        let mut body = json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": true,
+        });
+        body.as_object_mut().unwrap().extend(self.params.clone());
+
+        let response = self
+            .http
+            .post(format!("{}/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            bail!(
+                "Chat completion failed ({}): {}",
+                status,
+                response.text().await?
+            );
+        }
+
+        Ok(sse_deltas(response).boxed())
+    }
+}
+
+/// State for parsing the server-sent events stream of a chat completion.
+struct SseState {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+    deltas: VecDeque<String>,
+    done: bool,
+}
+
+/// Turn a streaming chat completion response into a stream of text deltas.
+fn sse_deltas(response: reqwest::Response) -> impl futures::Stream<Item = Result<String>> {
+    let state = SseState {
+        response,
+        buffer: vec![],
+        deltas: VecDeque::new(),
+        done: false,
+    };
+
+    futures::stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(delta) = state.deltas.pop_front() {
+                return Some((Ok(delta), state));
+            }
+            if state.done {
+                return None;
+            }
+
+            match state.response.chunk().await {
+                Ok(Some(chunk)) => state.buffer.extend_from_slice(&chunk),
+                Ok(None) => state.done = true,
+                Err(err) => {
+                    state.done = true;
+                    return Some((Err(err.into()), state));
+                }
+            }
+
+            // Events are separated by newlines; keep any partial line for the next chunk.
+            while let Some(pos) = state.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = state.buffer.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:") else {
+                    continue;
+                };
+
+                let data = data.trim();
+                if data == "[DONE]" {
+                    state.done = true;
+                    break;
+                }
+
+                match serde_json::from_str::<Value>(data) {
+                    Ok(event) => {
+                        if let Some(delta) = event["choices"][0]["delta"]["content"]
+                            .as_str()
+                            .filter(|delta| !delta.is_empty())
+                        {
+                            state.deltas.push_back(delta.to_string());
+                        }
+                    }
+                    Err(err) => {
+                        state.done = true;
+                        return Some((Err(err.into()), state));
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,5 @@
+//! Optional integrations with third-party services. Each integration is behind its own
+//! feature flag.
+
+#[cfg(feature = "llm")]
+pub mod llm;
@@ -0,0 +1,31 @@
+/// JSON decoding for API responses and updates. By default this uses `serde_json`. Enable the
+/// `simd-json` feature to parse with [simd-json](https://docs.rs/simd-json) instead, which is
+/// considerably faster on large `getUpdates` batches and busy webhooks.
+///
+/// Webhook servers can use [`from_bytes`] to parse request bodies the same way the client
+/// parses responses:
+///
+/// ```
+/// # use mobot::*;
+/// let body = bytes::Bytes::from(r#"{"update_id": 1}"#);
+/// let update: api::Update = json::from_bytes(body).unwrap();
+/// assert_eq!(update.update_id, 1);
+/// ```
+use anyhow::Result;
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+
+/// Parse `T` from raw JSON bytes.
+#[cfg(not(feature = "simd-json"))]
+pub fn from_bytes<T: DeserializeOwned>(data: Bytes) -> Result<T> {
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Parse `T` from raw JSON bytes.
+#[cfg(feature = "simd-json")]
+pub fn from_bytes<T: DeserializeOwned>(data: Bytes) -> Result<T> {
+    // simd-json parses in place, so it needs a mutable buffer. This doesn't copy if `data`
+    // is the only reference to its buffer, which is the case for HTTP bodies.
+    let mut data = Vec::from(data);
+    Ok(simd_json::serde::from_slice(&mut data)?)
+}
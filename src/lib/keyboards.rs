@@ -0,0 +1,306 @@
+/// Remembers the inline keyboard last attached to each message the bot sent, in a
+/// [`StateStorage`]. Handlers change a keyboard with [`KeyboardState::update`], which compares
+/// it to the remembered one and only calls `editMessageReplyMarkup` if a button changed. This
+/// makes checklists and settings menus ("toggle button" UIs) a few lines each, and survives
+/// restarts if the storage does.
+///
+/// Keyboards are remembered when [`KeyboardState::record`] is called with a sent message.
+/// Enable it with [`crate::Router::with_keyboard_state`] to pass it to handlers in
+/// [`crate::Event::keyboards`].
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    api::{self, EditMessageReplyMarkupRequest, InlineKeyboardButton, ReplyMarkup, API},
+    storage::{MemoryStorage, NamespacedStorage, StateStorage},
+};
+
+/// The storage namespace used for keyboards, keyed by chat ID.
+const NAMESPACE: &str = "keyboards";
+
+/// The prefix [`KeyboardState::toggle`] adds to the labels of checked buttons.
+pub const CHECK_MARK: &str = "✅ ";
+
+/// `ButtonChange` is a button that differs between two keyboards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ButtonChange {
+    pub row: usize,
+    pub column: usize,
+
+    /// The button before the change, or `None` if it was added.
+    pub old: Option<InlineKeyboardButton>,
+
+    /// The button after the change, or `None` if it was removed.
+    pub new: Option<InlineKeyboardButton>,
+}
+
+/// Returns the buttons that differ between `old` and `new`, row by row.
+pub fn diff(
+    old: &[Vec<InlineKeyboardButton>],
+    new: &[Vec<InlineKeyboardButton>],
+) -> Vec<ButtonChange> {
+    let mut changes = vec![];
+    for row in 0..old.len().max(new.len()) {
+        let old_row = old.get(row).map(Vec::as_slice).unwrap_or_default();
+        let new_row = new.get(row).map(Vec::as_slice).unwrap_or_default();
+        for column in 0..old_row.len().max(new_row.len()) {
+            let (old, new) = (old_row.get(column), new_row.get(column));
+            if old != new {
+                changes.push(ButtonChange {
+                    row,
+                    column,
+                    old: old.cloned(),
+                    new: new.cloned(),
+                });
+            }
+        }
+    }
+    changes
+}
+
+/// A remembered keyboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    message_id: i64,
+    keyboard: Vec<Vec<InlineKeyboardButton>>,
+}
+
+/// `KeyboardState` keeps the inline keyboards of the last `capacity` messages in every chat.
+/// Clones share the same storage.
+///
+/// ```no_run
+/// # use mobot::*;
+/// // Registered for `Route::CallbackQuery(Matcher::Any)`, on a message sent with buttons
+/// // for each topic, and recorded with `KeyboardState::record`.
+/// async fn subscribe(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     let keyboards = e.keyboards.as_ref().ok_or(anyhow::anyhow!("keyboards disabled"))?;
+///     let (chat_id, message_id) = (e.update.chat_id()?, e.update.message_id()?);
+///
+///     keyboards.toggle(&e.api, chat_id, message_id, e.update.data()?).await?;
+///     let topics = keyboards.checked(chat_id, message_id).await?;
+///     e.acknowledge_callback(Some(format!("Subscribed to {}", topics.join(", ")))).await?;
+///     Ok(Action::Done)
+/// }
+/// ```
+#[derive(Clone)]
+pub struct KeyboardState {
+    storage: Arc<dyn StateStorage>,
+    capacity: usize,
+
+    /// Serializes read-modify-write cycles on a chat's keyboards, including the edits sent to
+    /// Telegram, so quick taps on the same keyboard aren't lost.
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl Default for KeyboardState {
+    fn default() -> Self {
+        Self::new(MemoryStorage::new())
+    }
+}
+
+impl KeyboardState {
+    /// Create a new `KeyboardState` persisted in `storage`, keeping the keyboards of the last
+    /// 50 messages per chat.
+    pub fn new(storage: impl StateStorage + 'static) -> Self {
+        Self::from_arc(Arc::new(storage))
+    }
+
+    /// Create a new `KeyboardState` persisted in a shared `storage`.
+    pub fn from_arc(storage: Arc<dyn StateStorage>) -> Self {
+        Self {
+            storage,
+            capacity: 50,
+            write_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Returns a separate state, kept in the same storage under `prefix`, e.g. for one tenant
+    /// of a multi-tenant router.
+    pub fn with_namespace(&self, prefix: impl Into<String>) -> Self {
+        Self::from_arc(Arc::new(NamespacedStorage::new(
+            Arc::clone(&self.storage),
+            prefix,
+        )))
+        .with_capacity(self.capacity)
+    }
+
+    /// Set the number of messages per chat whose keyboards are kept.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Remember the inline keyboard attached to `message`, e.g. the message returned by
+    /// `sendMessage`. If it has none, the message's keyboard is forgotten.
+    pub async fn record(&self, message: &api::Message) -> Result<()> {
+        match &message.reply_markup {
+            Some(ReplyMarkup::InlineKeyboardMarkup {
+                inline_keyboard, ..
+            }) => {
+                self.set(message.chat.id, message.message_id, inline_keyboard.clone())
+                    .await
+            }
+            _ => self.remove(message.chat.id, message.message_id).await,
+        }
+    }
+
+    /// Remember `keyboard` as the one attached to the message.
+    pub async fn set(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        keyboard: Vec<Vec<InlineKeyboardButton>>,
+    ) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        self.put(chat_id, message_id, keyboard).await
+    }
+
+    /// Return the keyboard attached to the message, if it's remembered.
+    pub async fn get(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+    ) -> Result<Option<Vec<Vec<InlineKeyboardButton>>>> {
+        Ok(self
+            .load(chat_id)
+            .await?
+            .into_iter()
+            .find(|e| e.message_id == message_id)
+            .map(|e| e.keyboard))
+    }
+
+    /// Forget the message's keyboard, e.g., after deleting the message.
+    pub async fn remove(&self, chat_id: i64, message_id: i64) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let mut entries = self.load(chat_id).await?;
+        entries.retain(|e| e.message_id != message_id);
+        self.store(chat_id, entries).await
+    }
+
+    /// Forget all keyboards in the chat.
+    pub async fn clear(&self, chat_id: i64) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        self.storage.delete(NAMESPACE, chat_id).await
+    }
+
+    /// Change the message's keyboard with `f`, and send the result to Telegram if any button
+    /// changed. Returns the changed buttons, which are empty if no edit was sent. Fails if the
+    /// message's keyboard isn't remembered.
+    pub async fn update(
+        &self,
+        api: &API,
+        chat_id: i64,
+        message_id: i64,
+        f: impl FnOnce(&mut Vec<Vec<InlineKeyboardButton>>),
+    ) -> Result<Vec<ButtonChange>> {
+        let _guard = self.write_lock.lock().await;
+        let old = self
+            .get(chat_id, message_id)
+            .await?
+            .ok_or_else(|| anyhow!("No keyboard recorded for message {}", message_id))?;
+
+        let mut new = old.clone();
+        f(&mut new);
+        let changes = diff(&old, &new);
+        if changes.is_empty() {
+            return Ok(changes);
+        }
+
+        let req =
+            EditMessageReplyMarkupRequest::new(ReplyMarkup::inline_keyboard_markup(new.clone()))
+                .with_chat_id(chat_id)
+                .with_message_id(message_id);
+        let result = api.edit_message_reply_markup(&req).await;
+        api::with_request(result, "editMessageReplyMarkup", &req)?;
+
+        self.put(chat_id, message_id, new).await?;
+        Ok(changes)
+    }
+
+    /// Check or uncheck the button with `callback_data` by adding or removing [`CHECK_MARK`]
+    /// from its label, and edit the message. Returns true if the button is now checked.
+    pub async fn toggle(
+        &self,
+        api: &API,
+        chat_id: i64,
+        message_id: i64,
+        callback_data: &str,
+    ) -> Result<bool> {
+        let mut checked = None;
+        self.update(api, chat_id, message_id, |keyboard| {
+            let button = keyboard
+                .iter_mut()
+                .flatten()
+                .find(|b| b.callback_data.as_deref() == Some(callback_data));
+            if let Some(button) = button {
+                match button.text.strip_prefix(CHECK_MARK) {
+                    Some(label) => {
+                        button.text = label.to_string();
+                        checked = Some(false);
+                    }
+                    None => {
+                        button.text = format!("{}{}", CHECK_MARK, button.text);
+                        checked = Some(true);
+                    }
+                }
+            }
+        })
+        .await?;
+
+        checked.ok_or_else(|| anyhow!("No button with callback data {:?}", callback_data))
+    }
+
+    /// Return the callback data of the message's checked buttons (see
+    /// [`KeyboardState::toggle`]), in keyboard order.
+    pub async fn checked(&self, chat_id: i64, message_id: i64) -> Result<Vec<String>> {
+        Ok(self
+            .get(chat_id, message_id)
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .flatten()
+            .filter(|b| b.text.starts_with(CHECK_MARK))
+            .filter_map(|b| b.callback_data)
+            .collect())
+    }
+
+    /// Store the message's keyboard. The caller holds the write lock.
+    async fn put(
+        &self,
+        chat_id: i64,
+        message_id: i64,
+        keyboard: Vec<Vec<InlineKeyboardButton>>,
+    ) -> Result<()> {
+        let mut entries = self.load(chat_id).await?;
+        entries.retain(|e| e.message_id != message_id);
+        entries.push(Entry {
+            message_id,
+            keyboard,
+        });
+
+        if entries.len() > self.capacity {
+            entries.drain(..entries.len() - self.capacity);
+        }
+        self.store(chat_id, entries).await
+    }
+
+    async fn load(&self, chat_id: i64) -> Result<Vec<Entry>> {
+        match self.storage.get(NAMESPACE, chat_id).await? {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn store(&self, chat_id: i64, entries: Vec<Entry>) -> Result<()> {
+        if entries.is_empty() {
+            self.storage.delete(NAMESPACE, chat_id).await
+        } else {
+            self.storage
+                .set(NAMESPACE, chat_id, serde_json::to_value(entries)?)
+                .await
+        }
+    }
+}
@@ -0,0 +1,58 @@
+/// Language-based routing. [`crate::Matcher::Language`] routes updates by the sender's
+/// language: by default the [`crate::api::User::language_code`] reported by their Telegram
+/// client, or, if the router has a [`LanguageDetector`] (see
+/// [`crate::Router::with_language_detector`]), the language detected from the message text.
+use crate::api;
+
+/// `LanguageDetector` guesses the language of a piece of text, returning an IETF language tag
+/// (e.g., "en" or "pt-BR"), or `None` if it can't tell. Closures with the same signature
+/// implement it, so any detection library can be plugged in.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # let client = Client::new("token".to_string());
+/// let router: Router<()> = Router::new(client).with_language_detector(|text: &str| {
+///     text.contains("ñ").then(|| "es".to_string())
+/// });
+/// ```
+pub trait LanguageDetector: Send + Sync {
+    fn detect(&self, text: &str) -> Option<String>;
+}
+
+impl<F> LanguageDetector for F
+where
+    F: Fn(&str) -> Option<String> + Send + Sync,
+{
+    fn detect(&self, text: &str) -> Option<String> {
+        self(text)
+    }
+}
+
+/// Returns the language of `update`: the language `detector` finds in the text of the
+/// message (or inline query), falling back to the sender's `language_code`.
+pub fn language_of(
+    update: &api::Update,
+    detector: Option<&dyn LanguageDetector>,
+) -> Option<String> {
+    let text = update.any_message().and_then(|m| m.text_or_caption());
+    #[cfg(feature = "inline")]
+    let text = text.or(update.inline_query.as_ref().map(|q| q.query.as_str()));
+    let text = text.filter(|text| !text.trim().is_empty());
+
+    detector
+        .zip(text)
+        .and_then(|(detector, text)| detector.detect(text))
+        .or_else(|| update.sender().and_then(|u| u.language_code.clone()))
+}
+
+/// Returns true if `language` matches the language tag `tag`. Tags are compared without case,
+/// and a tag also matches its regional variants, so "pt" matches "pt-BR" (and "pt_br"), but
+/// "pt-BR" doesn't match "pt".
+pub fn matches(tag: &str, language: &str) -> bool {
+    let tag = tag.replace('_', "-").to_lowercase();
+    let language = language.replace('_', "-").to_lowercase();
+    language == tag
+        || language
+            .strip_prefix(&tag)
+            .is_some_and(|rest| rest.starts_with('-'))
+}
@@ -9,33 +9,33 @@ around managing routing and state for your bot.
 The key components of the framework are:
 
 - [`Client`] is the main entry point to the Telegram API. It is used to send
- requests to the Telegram API.
+  requests to the Telegram API.
 
 - [`Router`] is the main entry point to the bot. It is used to register
-handlers for different types of events, and keeps track of the state of
-the bot, passing it to the right handler.
+  handlers for different types of events, and keeps track of the state of
+  the bot, passing it to the right handler.
 
 - [`API`] is used to make direct calls to the Telegram API. An instance of `API` is
-passed to all handlers within the [`Event`] argument.
+  passed to all handlers within the [`Event`] argument.
 
 - [`Handler`]s are functions that handle events. They are registered with
-the [`Router`], and are called when an event is received.
+  the [`Router`], and are called when an event is received.
 
-Each [`Handler`] is passed an [`Event`] and a [`State`], and returns an
-[`Action`].
+  Each [`Handler`] is passed an [`Event`] and a [`State`], and returns an
+  [`Action`].
 
 - [`Action`]s are the result of [`Handler`] calls. They are used to send
-responses to the Telegram API.
+  responses to the Telegram API.
 
 - [`Event`]s are the events that the bot receives. They are passed to
-[`Handler`]s, and can be used to determine what action to take.
+  [`Handler`]s, and can be used to determine what action to take.
 
 - [`State`] is the user-defined state of the bot. It is passed to `Handler`s, as
-a generic parameter and can be used to store information about the bot. `State`
-must implement the [`Default`] and [`Clone`] traits. [`Default`] is used to
-initialize the state of a new chat session, and [`Clone`] is used while passing
-the state to the handlers. `State`s are typically wrapped in an [`std::sync::Arc`], so
-that they can be shared between threads.
+  a generic parameter and can be used to store information about the bot. `State`
+  must implement the [`Default`] and [`Clone`] traits. [`Default`] is used to
+  initialize the state of a new chat session, and [`Clone`] is used while passing
+  the state to the handlers. `State`s are typically wrapped in an [`std::sync::Arc`], so
+  that they can be shared between threads.
 
 ## Example
 
@@ -119,6 +119,34 @@ router
 # }
 ```
 
+# Per-chat settings
+
+Every handler's [`Event`] carries a [`Settings`] store for typed, per-chat configuration. Settings
+are persisted through a [`StateStorage`] backend (in-memory by default, see [`Router::with_settings`]),
+and are read on every access, so changes made by one handler (e.g., an admin command) take effect
+immediately in all other handlers. Use [`Settings::subscribe`] to be notified of changes.
+
+```no_run
+use mobot::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ChatSettings {
+    welcome: Option<String>,
+}
+
+impl ChatSetting for ChatSettings {
+    const KEY: &'static str = "chat";
+}
+
+impl Versioned for ChatSettings {}
+
+async fn welcome(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+    let settings = e.settings.get::<ChatSettings>(e.update.chat_id()?).await?;
+    Ok(Action::ReplyText(settings.welcome.unwrap_or("Welcome!".into())))
+}
+```
+
 # Working with routes
 
 [`Route`]s are used to determine which handler should be called for a given event. Every
@@ -217,35 +245,182 @@ async fn handle_chat_event(e: Event, state: State<()>) -> Result<Action, anyhow:
 MOBOT supports most of the major API calls, however if you need to add more structures or calls, you
 can do it by adding a file to `lib/api`. See `lib/api/sticker.rs` for an example of how `sendSticker` was
 supported.
+
+# Feature flags
+
+- `runtime` (default): everything that runs a bot: the [`Router`], handlers, storage, and
+  the [`fake`] test API. Without it, only [`api`] and the [`Client`] are built, and the
+  crate compiles to `wasm32-unknown-unknown`, so Web App frontends and Workers can make
+  typed requests with the browser's `fetch`:
+  `cargo build --lib --no-default-features --target wasm32-unknown-unknown`. Request
+  deadlines aren't enforced on wasm32, files aren't read from disk, and [`ClientStats`]
+  doesn't count responses by HTTP version.
+- `inline` (default): inline queries, with `api::inline`, `Route::InlineQuery`, and
+  `inline_cache`.
+- `stickers` (default): stickers, with `api::sticker`, `Action::ReplySticker`, and the
+  sticker set wizard.
+- `business` (default): the Telegram Business types, and the business fields of
+  [`api::ChatFullInfo`].
+- `webhooks` (default): the webhook server, with `webhook`, `tenants`, and
+  `Router::with_webhook`. Routers poll for updates without it.
+
+  Leave out the parts of the API a bot doesn't use with `default-features = false`, e.g.
+  `features = ["runtime", "inline"]`, to build less.
+- `notify`: `notify::Notifier`, which sends alerts to a chat, and reading the files it
+  attaches from disk. It doesn't need `runtime`, so apps that only push notifications
+  should turn off the default features to keep compile times and binaries small:
+  `mobot = { version = "0.4", default-features = false, features = ["notify"] }`. It
+  isn't available on wasm32.
+- `simd-json`: parse API responses and updates with [simd-json](https://docs.rs/simd-json)
+  instead of `serde_json`. See [`json`].
+- `sqlite`: an embedded SQLite [`StateStorage`] backend, `storage::SqliteStorage`, for
+  durable state without an external database.
+- `postgres`: a Postgres [`StateStorage`] backend, `storage::PostgresStorage`, built on
+  [sqlx](https://docs.rs/sqlx). The schema migrations ship with the crate.
+- `llm`: `integrations::llm`, for backing a chat with a large language model.
+- `blocking`: `blocking::API`, which makes API calls without async, for scripts and command
+  line tools.
+- `cli`: the `mobot-cli` binary, which sends messages and files, manages the webhook, shows
+  chat info, and downloads files from the command line. Install it with
+  `cargo install mobot --features cli`.
  */
 
 #[macro_use]
 extern crate log;
 
+#[cfg(feature = "runtime")]
 pub mod action;
+#[cfg(feature = "runtime")]
+mod album;
 pub mod api;
+pub mod audit;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(feature = "runtime")]
+pub mod broadcast;
+#[cfg(feature = "runtime")]
+pub mod chat_lock;
+#[cfg(feature = "runtime")]
+pub mod cleanup;
 pub mod client;
+pub mod clock;
+#[cfg(feature = "runtime")]
+pub mod control;
+#[cfg(feature = "runtime")]
+pub mod debounce;
+#[cfg(feature = "runtime")]
 pub mod event;
+#[cfg(feature = "runtime")]
+pub mod extensions;
+#[cfg(feature = "runtime")]
 pub mod fake;
+#[cfg(feature = "runtime")]
+pub mod features;
+#[cfg(feature = "runtime")]
+pub mod filters;
+#[cfg(feature = "runtime")]
+pub mod group;
+#[cfg(feature = "runtime")]
 pub mod handler;
+#[cfg(feature = "runtime")]
 pub mod handlers;
+#[cfg(feature = "runtime")]
+pub mod history;
+#[cfg(feature = "runtime")]
+pub mod humanize;
+#[cfg(all(feature = "runtime", feature = "inline"))]
+pub mod inline_cache;
+#[cfg(feature = "runtime")]
+pub mod integrations;
+pub mod json;
+#[cfg(feature = "runtime")]
+pub mod keyboards;
+#[cfg(feature = "runtime")]
+pub mod language;
+pub mod links;
+#[cfg(feature = "runtime")]
+pub mod metrics;
+#[cfg(feature = "notify")]
+pub mod notify;
+#[cfg(feature = "runtime")]
 pub mod progress;
+pub mod rate_limit;
+#[cfg(feature = "runtime")]
 pub mod router;
+#[cfg(feature = "runtime")]
+pub mod settings;
+#[cfg(feature = "runtime")]
+pub mod storage;
+#[cfg(feature = "runtime")]
+pub mod streaming;
+#[cfg(feature = "runtime")]
+pub mod tasks;
+#[cfg(feature = "webhooks")]
+pub mod tenants;
 pub mod text;
+#[cfg(feature = "runtime")]
 pub mod update;
-
+#[cfg(feature = "runtime")]
+pub mod user_data;
+#[cfg(feature = "runtime")]
+pub mod versioned;
+#[cfg(feature = "webhooks")]
+pub mod webhook;
+
+#[cfg(feature = "runtime")]
 pub use action::Action;
 pub use api::api::*;
-pub use client::{ApiToken, Client};
+#[cfg(feature = "runtime")]
+pub use chat_lock::{ChatGuard, ChatLock};
+#[cfg(feature = "runtime")]
+pub use cleanup::Cleanup;
+pub use client::{ApiToken, CallOptions, Client, ClientStats, HttpConfig, IpPreference};
+#[cfg(feature = "runtime")]
+pub use debounce::CallbackDebounce;
+#[cfg(feature = "runtime")]
 pub use event::Event;
+#[cfg(feature = "runtime")]
+pub use extensions::Extensions;
+#[cfg(feature = "runtime")]
+pub use features::Features;
+#[cfg(feature = "runtime")]
+pub use group::HandlerGroup;
+#[cfg(feature = "runtime")]
 pub use handler::{BotHandler, BotHandlerFn, Handler, State};
-pub use progress::ProgressBar;
+#[cfg(feature = "runtime")]
+pub use history::{HistoryEntry, MessageHistory};
+#[cfg(feature = "runtime")]
+pub use humanize::Humanizer;
+#[cfg(feature = "runtime")]
+pub use keyboards::KeyboardState;
+#[cfg(feature = "runtime")]
+pub use language::LanguageDetector;
+#[cfg(feature = "runtime")]
+pub use metrics::{HandlerMetrics, HandlerStats};
+#[cfg(feature = "runtime")]
+pub use progress::{ProgressBar, ProgressMessage};
+pub use rate_limit::RateLimiter;
+#[cfg(feature = "runtime")]
 pub use router::{Matcher, Route, Router};
+#[cfg(feature = "runtime")]
+pub use settings::{ChatSetting, Settings, SettingsChange, SettingsExport};
+#[cfg(feature = "runtime")]
+pub use storage::{EncryptedStorage, MemoryStorage, NamespacedStorage, StateStorage};
+#[cfg(feature = "runtime")]
+pub use streaming::StreamingMessage;
+#[cfg(feature = "runtime")]
+pub use tasks::Tasks;
 pub use text::Text;
+#[cfg(feature = "runtime")]
 pub use update::Update;
+#[cfg(feature = "runtime")]
+pub use user_data::{UserData, UserDataStores};
+#[cfg(feature = "runtime")]
+pub use versioned::Versioned;
 
 /// Expose mobot_derive macros
 pub use mobot_derive::BotRequest;
+#[cfg(feature = "runtime")]
 pub use mobot_derive::BotState;
 
 /// This method initializes [`env_logger`] from the environment, defaulting to `info` level logging.
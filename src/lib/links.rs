@@ -0,0 +1,168 @@
+/// Link utilities, for moderation bots that police what users post. Use [`crate::api::Message::urls`]
+/// to get the links in a message, then [`is_invite_link`] and [`DomainList`] to decide what to do
+/// with them.
+///
+/// Also builds and decodes the bot's own deep links (see [`start_link`]).
+use reqwest::Url;
+
+/// Hosts that serve Telegram links.
+const TELEGRAM_HOSTS: &[&str] = &["t.me", "telegram.me", "telegram.dog"];
+
+/// Parse `url`, as found in a message. Links in messages often leave out the scheme (e.g.,
+/// "example.com/page"), so `https://` is assumed if there isn't one.
+pub fn parse_url(url: &str) -> Option<Url> {
+    let url = url.trim();
+    if url.contains("://") || url.starts_with("tg:") || url.starts_with("mailto:") {
+        Url::parse(url).ok()
+    } else {
+        Url::parse(&format!("https://{}", url)).ok()
+    }
+}
+
+/// Returns the lowercased host of `url`, without a leading "www.".
+pub fn domain(url: &str) -> Option<String> {
+    let url = parse_url(url)?;
+    let host = url.host_str()?.to_lowercase();
+    Some(host.strip_prefix("www.").map(String::from).unwrap_or(host))
+}
+
+/// Returns true if `url` is a Telegram invite link, i.e., "t.me/+HASH", "t.me/joinchat/HASH"
+/// (or the same on telegram.me and telegram.dog), or "tg://join?invite=HASH".
+pub fn is_invite_link(url: &str) -> bool {
+    let Some(parsed) = parse_url(url) else {
+        return false;
+    };
+
+    if parsed.scheme() == "tg" {
+        return parsed.host_str() == Some("join")
+            && parsed.query_pairs().any(|(key, _)| key == "invite");
+    }
+
+    if !domain(url).is_some_and(|d| TELEGRAM_HOSTS.contains(&d.as_str())) {
+        return false;
+    }
+
+    let mut segments = parsed.path_segments().into_iter().flatten();
+    match segments.next() {
+        // "t.me/+" followed by only digits is a phone number link.
+        Some(path) if path.starts_with('+') => {
+            let hash = &path[1..];
+            !hash.is_empty() && !hash.chars().all(|c| c.is_ascii_digit())
+        }
+        Some("joinchat") => segments.next().is_some_and(|hash| !hash.is_empty()),
+        _ => false,
+    }
+}
+
+/// The longest payload Telegram passes on from a deep link.
+pub const MAX_START_PAYLOAD_LEN: usize = 64;
+
+/// Returns a deep link that opens a chat with the bot `bot_username`, and sends it
+/// `/start payload` when the user taps Start. Returns `None` if the payload is empty, longer
+/// than [`MAX_START_PAYLOAD_LEN`], or uses characters other than `A-Z`, `a-z`, `0-9`, `_` and
+/// `-`.
+///
+/// ```
+/// # use mobot::links::*;
+/// let link = start_link("@my_bot", "invite_42").unwrap();
+/// assert_eq!(link, "https://t.me/my_bot?start=invite_42");
+/// assert_eq!(start_payload("/start invite_42"), Some("invite_42"));
+/// ```
+pub fn start_link(bot_username: &str, payload: &str) -> Option<String> {
+    let valid = !payload.is_empty()
+        && payload.len() <= MAX_START_PAYLOAD_LEN
+        && payload
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then(|| {
+        format!(
+            "https://t.me/{}?start={}",
+            bot_username.trim_start_matches('@'),
+            payload
+        )
+    })
+}
+
+/// Returns the payload of a `/start` command sent by a deep link, e.g. "invite_42" for
+/// "/start invite_42" or "/start@my_bot invite_42".
+pub fn start_payload(text: &str) -> Option<&str> {
+    let (command, payload) = text.trim().split_once(char::is_whitespace)?;
+    let command = command
+        .split_once('@')
+        .map_or(command, |(command, _)| command);
+    if command != "/start" {
+        return None;
+    }
+
+    let payload = payload.trim();
+    (!payload.is_empty()).then_some(payload)
+}
+
+/// `DomainList` matches links against allowed and denied domains. A domain also matches its
+/// subdomains, so "example.com" matches "docs.example.com".
+///
+/// Denied domains are never allowed. If any domains are allowed, links to other domains
+/// aren't either.
+///
+/// ```
+/// # use mobot::links::DomainList;
+/// let list = DomainList::new()
+///     .with_allowed("rust-lang.org")
+///     .with_denied("evil.rust-lang.org");
+///
+/// assert!(list.is_allowed("https://doc.rust-lang.org/std"));
+/// assert!(!list.is_allowed("evil.rust-lang.org"));
+/// assert!(!list.is_allowed("example.com"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DomainList {
+    pub allowed: Vec<String>,
+    pub denied: Vec<String>,
+}
+
+impl DomainList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_allowed(mut self, domain: impl Into<String>) -> Self {
+        self.allowed.push(domain.into().to_lowercase());
+        self
+    }
+
+    pub fn with_denied(mut self, domain: impl Into<String>) -> Self {
+        self.denied.push(domain.into().to_lowercase());
+        self
+    }
+
+    /// Returns true if links to `url` are allowed. Links that can't be parsed are only allowed
+    /// if there's no allow list.
+    pub fn is_allowed(&self, url: &str) -> bool {
+        let Some(domain) = domain(url) else {
+            return self.allowed.is_empty();
+        };
+
+        if Self::matches(&self.denied, &domain) {
+            return false;
+        }
+
+        self.allowed.is_empty() || Self::matches(&self.allowed, &domain)
+    }
+
+    /// Returns the links in `urls` that aren't allowed.
+    pub fn disallowed<'a>(&self, urls: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+        urls.into_iter()
+            .filter(|url| !self.is_allowed(url))
+            .collect()
+    }
+
+    fn matches(domains: &[String], domain: &str) -> bool {
+        domains.iter().any(|d| {
+            let d = d.strip_prefix("www.").unwrap_or(d);
+            domain == d
+                || domain
+                    .strip_suffix(d)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+}
@@ -0,0 +1,113 @@
+/// Per-handler metrics, to find the hot spots in large bots. The router counts the invocations,
+/// errors and latency of every handler, keyed by the path of the handler function (e.g.
+/// `my_bot::report`), or the name given to [`crate::Router::add_named_route`]. Closures and
+/// boxed handlers are keyed by the route they were registered for (e.g.
+/// `Message(BotCommand("report"))`). Read them with [`crate::Router::handler_metrics`], or
+/// [`crate::Event::metrics`] from a handler; the control chat's `/stats` lists the slowest
+/// (see [`crate::control`]). Request counters for the API client are in
+/// [`crate::ClientStats`].
+///
+/// With [`crate::Router::with_slow_handler_warning`], handlers that run longer than a
+/// threshold are also logged as warnings, with a summary of the update they were handling.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// `HandlerStats` is a snapshot of a handler's counters.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HandlerStats {
+    /// Number of times the handler ran.
+    pub invocations: u64,
+
+    /// Number of runs that returned an error (including timeouts).
+    pub errors: u64,
+
+    /// Number of runs longer than the slow handler threshold.
+    pub slow: u64,
+
+    /// Total time spent in the handler.
+    pub total_latency: Duration,
+
+    /// The longest run.
+    pub max_latency: Duration,
+}
+
+impl HandlerStats {
+    /// Average time per run.
+    pub fn avg_latency(&self) -> Duration {
+        if self.invocations == 0 {
+            return Duration::ZERO;
+        }
+        self.total_latency / self.invocations as u32
+    }
+}
+
+/// `HandlerMetrics` collects [`HandlerStats`] per handler. Clones share the same counters.
+#[derive(Debug, Clone, Default)]
+pub struct HandlerMetrics {
+    stats: Arc<Mutex<HashMap<String, HandlerStats>>>,
+
+    /// Runs longer than this are counted as slow, and logged.
+    slow_threshold: Option<Duration>,
+}
+
+impl HandlerMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count runs longer than `threshold` as slow.
+    pub fn with_slow_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_threshold = Some(threshold);
+        self
+    }
+
+    /// Returns the slow handler threshold, if set.
+    pub fn slow_threshold(&self) -> Option<Duration> {
+        self.slow_threshold
+    }
+
+    /// Record a run of `handler` that took `latency`. Returns true if the run was slow.
+    pub fn record(&self, handler: &str, latency: Duration, ok: bool) -> bool {
+        let slow = self.slow_threshold.is_some_and(|t| latency > t);
+
+        let mut stats = self.stats.lock().unwrap();
+        let stats = stats.entry(handler.to_string()).or_default();
+        stats.invocations += 1;
+        stats.errors += u64::from(!ok);
+        stats.slow += u64::from(slow);
+        stats.total_latency += latency;
+        stats.max_latency = stats.max_latency.max(latency);
+        slow
+    }
+
+    /// Returns the counters for `handler`, if it has run.
+    pub fn get(&self, handler: &str) -> Option<HandlerStats> {
+        self.stats.lock().unwrap().get(handler).cloned()
+    }
+
+    /// Returns the counters for every handler that has run, by total time spent in the
+    /// handler, highest first.
+    pub fn snapshot(&self) -> Vec<(String, HandlerStats)> {
+        let mut stats: Vec<_> = self
+            .stats
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.clone()))
+            .collect();
+        stats.sort_by(|(a_name, a), (b_name, b)| {
+            b.total_latency
+                .cmp(&a.total_latency)
+                .then_with(|| a_name.cmp(b_name))
+        });
+        stats
+    }
+
+    /// Reset all counters.
+    pub fn reset(&self) {
+        self.stats.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,68 @@
+/// `Notifier` sends alerts to a chat, for apps that push notifications to Telegram but don't
+/// run a bot. Build mobot without the default features to leave out the router, handlers,
+/// storage, and their dependencies:
+///
+/// ```toml
+/// [dependencies]
+/// mobot = { version = "0.4", default-features = false, features = ["notify"] }
+/// ```
+///
+/// ```no_run
+/// # use mobot::{api::ParseMode, notify::Notifier, Client};
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> anyhow::Result<()> {
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN")?);
+/// let notifier = Notifier::new(client, -1001234567890).with_parse_mode(ParseMode::HTML);
+/// notifier.send("<b>disk</b> is 95% full").await?;
+/// # Ok(())
+/// # }
+/// ```
+use anyhow::Result;
+
+use crate::{
+    api::{InputFile, Message, ParseMode, SendDocumentRequest, SendMessageRequest, API},
+    Client,
+};
+
+#[derive(Clone)]
+pub struct Notifier {
+    /// The API, for calls other than sending alerts.
+    pub api: API,
+
+    /// The chat alerts are sent to.
+    pub chat_id: i64,
+
+    /// How alert text and captions are formatted. Plain text by default.
+    pub parse_mode: Option<ParseMode>,
+}
+
+impl Notifier {
+    pub fn new(client: Client, chat_id: i64) -> Self {
+        Self {
+            api: API::new(client),
+            chat_id,
+            parse_mode: None,
+        }
+    }
+
+    pub fn with_parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.parse_mode = Some(parse_mode);
+        self
+    }
+
+    /// Send `text` to the chat.
+    pub async fn send(&self, text: impl Into<String>) -> Result<Message> {
+        let mut req = SendMessageRequest::new(self.chat_id, text);
+        req.parse_mode = self.parse_mode.clone();
+        self.api.send_message(&req).await
+    }
+
+    /// Upload `file` and send it to the chat, with an optional caption. E.g., to attach a log
+    /// to an alert.
+    pub async fn send_document(&self, file: &InputFile, caption: Option<&str>) -> Result<Message> {
+        let mut req = SendDocumentRequest::new(self.chat_id, "");
+        req.caption = caption.map(String::from);
+        req.parse_mode = self.parse_mode.clone();
+        self.api.send_document_file(&req, file).await
+    }
+}
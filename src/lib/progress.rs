@@ -1,3 +1,7 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+
 use crate::{api, Event};
 
 /// Represent the current state of the progressbar.
@@ -121,14 +125,14 @@ impl ProgressBar {
         while !done {
             tokio::select! {
                 // Update the progress bar.
-                _ = tokio::time::sleep(self.update_interval) => {
+                _ = e.api.clock().sleep(self.update_interval) => {
                     count += 1;
                     message = e.edit_message(message.message_id, progress_str(count, ProgressState::Working)).await?;
                     e.send_chat_action(api::ChatAction::Typing).await?;
                 }
 
                 // Timeout.
-                _ = tokio::time::sleep(std::time::Duration::from_secs(30)) => {
+                _ = e.api.clock().sleep(std::time::Duration::from_secs(30)) => {
                     done = true;
                     message = e.edit_message(message.message_id,
                         format!("{} {}", progress_str(count, ProgressState::Failed(self.failed_str.as_str())),
@@ -150,3 +154,148 @@ impl ProgressBar {
         Ok(result)
     }
 }
+
+/// A progress message for long operations that report their own progress. `ProgressMessage`
+/// sends a single message, and edits it with a progress bar and percentage as work completes.
+/// Edits are throttled to at most one per `interval` (and skipped if nothing visible changed),
+/// so tight loops can report progress freely without hitting Telegram's flood limits.
+///
+/// ```no_run
+/// # use mobot::*;
+/// async fn handle_import(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     let items = vec!["a", "b", "c"];
+///     let mut progress = ProgressMessage::start(&e, "Importing...").await?;
+///
+///     for (i, _item) in items.iter().enumerate() {
+///         // ... do the work ...
+///         progress.update(i as u64 + 1, items.len() as u64).await?;
+///     }
+///
+///     progress.finish(format!("Imported {} items.", items.len())).await?;
+///     Ok(Action::Done)
+/// }
+/// ```
+pub struct ProgressMessage {
+    api: Arc<api::API>,
+    chat_id: i64,
+    message_id: i64,
+
+    /// Shown above the progress bar.
+    title: String,
+
+    /// Minimum time between edits.
+    interval: Duration,
+
+    /// Width of the progress bar, in characters.
+    width: usize,
+
+    /// When the message was last edited, by the API's clock.
+    last_edit: DateTime<Utc>,
+    last_text: String,
+}
+
+impl ProgressMessage {
+    /// Send the progress message to the chat in `e`. Edits are throttled to one every three
+    /// seconds by default, see [`ProgressMessage::with_interval`].
+    pub async fn start(e: &Event, title: impl Into<String>) -> anyhow::Result<Self> {
+        Self::send(Arc::clone(&e.api), e.update.chat_id()?, title).await
+    }
+
+    /// Send the progress message to `chat_id`.
+    pub async fn send(
+        api: Arc<api::API>,
+        chat_id: i64,
+        title: impl Into<String>,
+    ) -> anyhow::Result<Self> {
+        let title = title.into();
+        let message = api
+            .send_message(&api::SendMessageRequest::new(chat_id, title.clone()))
+            .await?;
+        let last_edit = api.clock().now();
+
+        Ok(Self {
+            api,
+            chat_id,
+            message_id: message.message_id,
+            last_text: title.clone(),
+            title,
+            interval: Duration::from_secs(3),
+            width: 10,
+            last_edit,
+        })
+    }
+
+    /// Set the minimum time between edits.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the width of the progress bar, in characters.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width.max(1);
+        self
+    }
+
+    /// The ID of the progress message.
+    pub fn message_id(&self) -> i64 {
+        self.message_id
+    }
+
+    /// Report that `done` out of `total` units of work are complete. The message is only edited
+    /// if `interval` has passed since the last edit. Returns true if the message was edited.
+    pub async fn update(&mut self, done: u64, total: u64) -> anyhow::Result<bool> {
+        if self.api.clock().elapsed(self.last_edit) < self.interval {
+            return Ok(false);
+        }
+
+        let text = format!("{}\n{}", self.title, self.render(done, total));
+        self.edit(text).await
+    }
+
+    /// Replace the progress bar with `summary`. This is never throttled.
+    pub async fn finish(mut self, summary: impl Into<String>) -> anyhow::Result<()> {
+        self.edit(summary.into()).await?;
+        Ok(())
+    }
+
+    /// Render the progress bar, e.g. `▓▓▓▓░░░░░░ 40% (4/10)`.
+    fn render(&self, done: u64, total: u64) -> String {
+        let done = done.min(total);
+        let fraction = if total == 0 {
+            1.0
+        } else {
+            done as f64 / total as f64
+        };
+
+        let filled = (fraction * self.width as f64).round() as usize;
+        format!(
+            "{}{} {}% ({}/{})",
+            "\u{2593}".repeat(filled),
+            "\u{2591}".repeat(self.width - filled),
+            (fraction * 100.0).floor() as u64,
+            done,
+            total
+        )
+    }
+
+    async fn edit(&mut self, text: String) -> anyhow::Result<bool> {
+        // Telegram rejects edits that don't change the message.
+        if text == self.last_text {
+            return Ok(false);
+        }
+
+        self.api
+            .edit_message_text(&api::EditMessageTextRequest {
+                base: api::EditMessageBase::new()
+                    .with_chat_id(self.chat_id)
+                    .with_message_id(self.message_id),
+                text: text.clone(),
+            })
+            .await?;
+
+        self.last_text = text;
+        self.last_edit = self.api.clock().now();
+        Ok(true)
+    }
+}
@@ -0,0 +1,62 @@
+/// Client-side rate limiting. Telegram throttles bots that send too many requests (roughly 30
+/// per second overall, and fewer for actions in a single group), answering with `429 Too Many
+/// Requests`. A [`RateLimiter`] spaces requests out so bursts, like cleaning up after a raid,
+/// stay under those limits.
+use std::{sync::Arc, time::Duration};
+
+use tokio::{sync::Mutex, time::Instant};
+
+/// `RateLimiter` lets callers through at most once per interval, in the order they arrive.
+/// Clones share the same schedule.
+///
+/// ```
+/// # use mobot::*;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let limiter = RateLimiter::per_second(20);
+/// for _ in 0..3 {
+///     limiter.acquire().await;
+///     // ... make a request
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    interval: Duration,
+
+    /// The earliest time the next caller can go.
+    next: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimiter {
+    /// Allow one caller through every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Allow `rate` callers through per second.
+    pub fn per_second(rate: u32) -> Self {
+        Self::new(Duration::from_secs(1) / rate.max(1))
+    }
+
+    /// Returns the time between callers.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Wait for this caller's turn.
+    pub async fn acquire(&self) {
+        let slot = {
+            let mut next = self.next.lock().await;
+            let now = Instant::now();
+            let slot = next.map_or(now, |next| next.max(now));
+            *next = Some(slot + self.interval);
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
+}
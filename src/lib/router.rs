@@ -8,23 +8,45 @@
 ///
 /// User handlers are called for every message that is sent to the bot from any specific
 /// user.
-use std::{cmp::max, collections::HashMap, sync::Arc, time::Duration};
+use std::{cell::OnceCell, cmp::max, collections::HashMap, sync::Arc, time::Duration};
 
 use futures::{future::BoxFuture, Future};
+use lazy_static::lazy_static;
 use tokio::sync::{mpsc, Notify, RwLock};
+#[cfg(feature = "webhooks")]
+use tokio::net::TcpListener;
 
 use crate::{
-    api::{self, GetUpdatesRequest, SendMessageRequest, SendStickerRequest, API},
-    handler::{BotHandler, BotState},
-    Action, Client, Event, State, Update,
+    album::AlbumBuffer,
+    api::{self, ApiError, DeleteWebhookRequest, GetUpdatesRequest, SendMessageRequest, API},
+    clock::Clock,
+    control::{ControlChat, ControlEvent},
+    handler::{handler_name, BotHandler, BotState},
+    handlers::log::{LogHandler, Redaction},
+    language::{self, LanguageDetector},
+    Action, CallOptions, CallbackDebounce, ChatLock, Cleanup, Client, Event, Extensions, Features,
+    HandlerGroup, HandlerMetrics, Humanizer, KeyboardState, MessageHistory, Settings, State, Tasks,
+    Update, UserData, UserDataStores,
+};
+#[cfg(feature = "webhooks")]
+use crate::{
+    tenants::{Tenant, TenantResolver},
+    webhook::{self, WebhookConfig},
+    RateLimiter,
 };
 
 use anyhow::anyhow;
+use async_trait::async_trait;
 
 type Arw<T> = Arc<RwLock<T>>;
-type HandlerMap<S> = HashMap<Route, Vec<(Matcher, Box<dyn BotHandler<S>>)>>;
+/// Handlers by route, each with its matcher, priority and name (for metrics), in the order
+/// they run.
+type HandlerMap<S> = HashMap<Route, Vec<(Matcher, i32, String, Box<dyn BotHandler<S>>)>>;
+type UpdateFilter = Box<dyn Fn(&api::Update) -> bool + Send + Sync>;
+type Dispatch = Arc<dyn Fn(api::Update, Update) + Send + Sync>;
 type ErrorHandler<S> =
     Box<dyn Fn(Arc<API>, i64, State<S>, anyhow::Error) -> BoxFuture<'static, ()> + Send + Sync>;
+type StateExport<S> = Arc<dyn Fn(&S) -> anyhow::Result<serde_json::Value> + Send + Sync>;
 
 /// `Matcher` is used to match a message against a route. It is used to determine
 /// which handler should be called for a given message.
@@ -53,6 +75,35 @@ pub enum Matcher {
 
     /// Match messages that represent a video
     Video,
+
+    /// Match service messages announcing new chat members
+    NewChatMembers,
+
+    /// Match updates in the given language (e.g., "de", or "pt-BR"). A language also matches
+    /// its regional variants, so "pt" matches "pt-BR". The language is the sender's
+    /// `language_code`, unless the router has a detector (see
+    /// [`Router::with_language_detector`]).
+    Language(String),
+}
+
+lazy_static! {
+    /// Compiled `Matcher::Regex` patterns, so each pattern is only compiled once rather than
+    /// on every update.
+    static ref REGEX_CACHE: std::sync::RwLock<HashMap<String, regex::Regex>> =
+        std::sync::RwLock::new(HashMap::new());
+}
+
+fn cached_regex(pattern: &str) -> regex::Regex {
+    if let Some(re) = REGEX_CACHE.read().unwrap().get(pattern) {
+        return re.clone();
+    }
+
+    let re = regex::Regex::new(pattern).unwrap();
+    REGEX_CACHE
+        .write()
+        .unwrap()
+        .insert(pattern.to_string(), re.clone());
+    re
 }
 
 impl Matcher {
@@ -61,9 +112,13 @@ impl Matcher {
             Self::Any => true,
             Self::Exact(m) => s == m,
             Self::Prefix(m) => s.starts_with(m),
-            Self::Regex(m) => regex::Regex::new(m).unwrap().is_match(s),
+            Self::Regex(m) => cached_regex(m).is_match(s),
             Self::BotCommand(m) => s.starts_with(&format!("/{}", m)),
-            Self::Document | Self::Photo | Self::Video => false,
+            Self::Document
+            | Self::Photo
+            | Self::Video
+            | Self::NewChatMembers
+            | Self::Language(_) => false,
         }
     }
 }
@@ -78,7 +133,10 @@ impl From<Route> for Matcher {
             Route::ChannelPost(matcher) => matcher,
             Route::EditedChannelPost(matcher) => matcher,
             Route::CallbackQuery(matcher) => matcher,
+            #[cfg(feature = "inline")]
             Route::InlineQuery(matcher) => matcher,
+            Route::MessageReaction(matcher) => matcher,
+            Route::ChatJoinRequest(matcher) => matcher,
         }
     }
 }
@@ -107,8 +165,17 @@ pub enum Route {
     /// Handle callback queries from inline keyboards
     CallbackQuery(Matcher),
 
+    #[cfg(feature = "inline")]
     /// Handle inline queries
     InlineQuery(Matcher),
+
+    /// Handle reactions to messages. The matcher is tested against each newly added emoji.
+    /// Telegram only sends reactions if they're requested with
+    /// [`Router::with_allowed_updates`].
+    MessageReaction(Matcher),
+
+    /// Handle requests to join the chat. The matcher is tested against the user's bio.
+    ChatJoinRequest(Matcher),
 }
 
 fn get_update_parts(update: &api::Update) -> anyhow::Result<(i64, Route)> {
@@ -130,10 +197,18 @@ fn get_update_parts(update: &api::Update) -> anyhow::Result<(i64, Route)> {
             q.message.as_ref().map(|m| m.chat.id).unwrap_or(0),
             Route::CallbackQuery(Matcher::Any),
         ))
-    } else if let Some(ref q) = update.inline_query {
-        debug!("Inline query: {:#?}", q);
-        Ok((q.from.id, Route::InlineQuery(Matcher::Any)))
+    } else if let Some(ref r) = update.message_reaction {
+        debug!("Message reaction: {:#?}", r);
+        Ok((r.chat.id, Route::MessageReaction(Matcher::Any)))
+    } else if let Some(ref r) = update.chat_join_request {
+        debug!("Chat join request: {:#?}", r);
+        Ok((r.chat.id, Route::ChatJoinRequest(Matcher::Any)))
     } else {
+        #[cfg(feature = "inline")]
+        if let Some(ref q) = update.inline_query {
+            debug!("Inline query: {:#?}", q);
+            return Ok((q.from.id, Route::InlineQuery(Matcher::Any)));
+        }
         anyhow::bail!("Unknown update type")
     }
 }
@@ -148,7 +223,10 @@ impl Route {
             Self::ChannelPost(_) => Self::ChannelPost(Matcher::Any),
             Self::EditedChannelPost(_) => Self::EditedChannelPost(Matcher::Any),
             Self::CallbackQuery(_) => Self::CallbackQuery(Matcher::Any),
+            #[cfg(feature = "inline")]
             Self::InlineQuery(_) => Self::InlineQuery(Matcher::Any),
+            Self::MessageReaction(_) => Self::MessageReaction(Matcher::Any),
+            Self::ChatJoinRequest(_) => Self::ChatJoinRequest(Matcher::Any),
         }
     }
 
@@ -161,11 +239,41 @@ impl Route {
             Self::ChannelPost(_) => Self::ChannelPost(matcher.clone()),
             Self::EditedChannelPost(_) => Self::EditedChannelPost(matcher.clone()),
             Self::CallbackQuery(_) => Self::CallbackQuery(matcher.clone()),
+            #[cfg(feature = "inline")]
             Self::InlineQuery(_) => Self::InlineQuery(matcher.clone()),
+            Self::MessageReaction(_) => Self::MessageReaction(matcher.clone()),
+            Self::ChatJoinRequest(_) => Self::ChatJoinRequest(matcher.clone()),
         }
     }
 
     pub fn match_update(&self, update: &api::Update) -> bool {
+        let language = language::language_of(update, None);
+        self.matches(update, false, language.as_deref())
+    }
+
+    /// Like [`Route::match_update`], but text matchers are also tested against the captions of
+    /// media messages, so e.g. a photo captioned "/scan" matches `Matcher::BotCommand("scan")`.
+    pub fn match_update_or_caption(&self, update: &api::Update) -> bool {
+        let language = language::language_of(update, None);
+        self.matches(update, true, language.as_deref())
+    }
+
+    /// Returns true if the route matches `update`. `Matcher::Language` routes are tested
+    /// against `language`.
+    fn matches(&self, update: &api::Update, captions: bool, language: Option<&str>) -> bool {
+        if let Matcher::Language(tag) = Matcher::from(self.clone()) {
+            return self.is_route_for(update)
+                && language.is_some_and(|language| language::matches(&tag, language));
+        }
+
+        let text = |m: &api::Message| {
+            if captions {
+                m.text_or_caption().map(String::from)
+            } else {
+                m.text.clone()
+            }
+        };
+
         match self {
             Self::Message(m) => match m {
                 Matcher::Photo => update
@@ -183,61 +291,90 @@ impl Route {
                     .as_ref()
                     .and_then(|m| m.video.as_ref())
                     .is_some(),
+                Matcher::NewChatMembers => update
+                    .message
+                    .as_ref()
+                    .and_then(|m| m.new_chat_members.as_ref())
+                    .is_some(),
                 _ => update
                     .message
                     .as_ref()
-                    .and_then(|m| m.text.as_ref())
-                    .map_or(false, |t| m.match_str(t)),
+                    .and_then(text)
+                    .is_some_and(|t| m.match_str(&t)),
             },
             Self::EditedMessage(m) => update
                 .edited_message
                 .as_ref()
-                .and_then(|m| m.text.as_ref())
-                .map_or(false, |t| m.match_str(t)),
+                .and_then(text)
+                .is_some_and(|t| m.match_str(&t)),
             Self::ChannelPost(m) => update
                 .channel_post
                 .as_ref()
-                .and_then(|m| m.text.as_ref())
-                .map_or(false, |t| m.match_str(t)),
+                .and_then(text)
+                .is_some_and(|t| m.match_str(&t)),
             Self::EditedChannelPost(m) => update
                 .edited_channel_post
                 .as_ref()
-                .and_then(|m| m.text.as_ref())
-                .map_or(false, |t| m.match_str(t)),
+                .and_then(text)
+                .is_some_and(|t| m.match_str(&t)),
             Self::CallbackQuery(m) => update
                 .callback_query
                 .as_ref()
                 .and_then(|m| m.data.as_ref())
-                .map_or(false, |t| m.match_str(t)),
+                .is_some_and(|t| m.match_str(t)),
+            #[cfg(feature = "inline")]
             Self::InlineQuery(m) => update
                 .inline_query
                 .as_ref()
-                .map_or(false, |t| m.match_str(&t.query)),
+                .is_some_and(|t| m.match_str(&t.query)),
+            Self::MessageReaction(m) => update.message_reaction.as_ref().is_some_and(|r| {
+                matches!(m, Matcher::Any) || r.added_emoji().iter().any(|e| m.match_str(e))
+            }),
+            Self::ChatJoinRequest(m) => update.chat_join_request.as_ref().is_some_and(|r| {
+                matches!(m, Matcher::Any) || r.bio.as_ref().is_some_and(|bio| m.match_str(bio))
+            }),
             Self::Any(matcher) => {
                 let mut matched = false;
                 if let Some(ref m) = update.message {
-                    matched |= m.text.as_ref().map_or(false, |t| matcher.match_str(t));
+                    matched |= text(m).is_some_and(|t| matcher.match_str(&t));
                 }
                 if let Some(ref m) = update.edited_message {
-                    matched |= m.text.as_ref().map_or(false, |t| matcher.match_str(t));
+                    matched |= text(m).is_some_and(|t| matcher.match_str(&t));
                 }
                 if let Some(ref m) = update.channel_post {
-                    matched |= m.text.as_ref().map_or(false, |t| matcher.match_str(t));
+                    matched |= text(m).is_some_and(|t| matcher.match_str(&t));
                 }
                 if let Some(ref m) = update.edited_channel_post {
-                    matched |= m.text.as_ref().map_or(false, |t| matcher.match_str(t));
+                    matched |= text(m).is_some_and(|t| matcher.match_str(&t));
                 }
                 if let Some(ref q) = update.callback_query {
-                    matched |= q.data.as_ref().map_or(false, |t| matcher.match_str(t));
+                    matched |= q.data.as_ref().is_some_and(|t| matcher.match_str(t));
                 }
+                #[cfg(feature = "inline")]
                 if let Some(ref q) = update.inline_query {
                     matched |= matcher.match_str(&q.query);
                 }
+                if let Some(ref r) = update.message_reaction {
+                    matched |= matches!(matcher, Matcher::Any)
+                        || r.added_emoji().iter().any(|e| matcher.match_str(e));
+                }
+                if let Some(ref r) = update.chat_join_request {
+                    matched |= matches!(matcher, Matcher::Any)
+                        || r.bio.as_ref().is_some_and(|bio| matcher.match_str(bio));
+                }
                 matched
             }
             Self::Default => true,
         }
     }
+
+    /// Returns true if `update` is the kind of update this route handles.
+    fn is_route_for(&self, update: &api::Update) -> bool {
+        match self {
+            Self::Default | Self::Any(_) => true,
+            _ => get_update_parts(update).is_ok_and(|(_, route)| route == Route::any(self)),
+        }
+    }
 }
 
 pub struct Router<S: BotState> {
@@ -251,15 +388,295 @@ pub struct Router<S: BotState> {
     handlers: Arw<HandlerMap<S>>,
     handler_state: Arw<HashMap<i64, State<S>>>,
 
+    /// If set, per-chat handler state is included in user data exports.
+    state_export: Option<StateExport<S>>,
+
+    /// Per-chat settings, passed to every handler in the `Event`.
+    settings: Settings,
+
+    /// Per-chat locks, held by handlers while they run if `serialize_chats` is set.
+    chat_lock: ChatLock,
+
+    /// If true, updates for the same chat are handled one at a time.
+    serialize_chats: bool,
+
+    /// User data stores registered with `with_user_data`, in addition to the router's own.
+    user_data: UserDataStores,
+
+    /// If set, recent messages per chat are recorded here.
+    history: Option<MessageHistory>,
+
+    /// If set, passed to handlers to remember the inline keyboards of sent messages.
+    keyboards: Option<KeyboardState>,
+
+    /// If set, repeated taps on the same inline keyboard are dropped.
+    callback_debounce: Option<CallbackDebounce>,
+
+    /// If set, text replies are delayed while showing the typing action.
+    humanizer: Option<Humanizer>,
+
+    /// If set, passed to handlers to delete ephemeral messages after a TTL.
+    cleanup: Option<Cleanup>,
+
+    /// Feature flags, passed to every handler in the `Event`.
+    features: Features,
+
+    /// Invocation counts and latency per handler, passed to every handler in the `Event`.
+    metrics: HandlerMetrics,
+
+    /// If set, errors and dead letters are reported here.
+    control: Option<ControlChat>,
+
+    /// Background tasks spawned by handlers, and how long to wait for them on shutdown.
+    tasks: Tasks,
+    shutdown_grace_period: Duration,
+
+    /// Pre-filters applied to every update before it's dispatched.
+    update_filters: Vec<UpdateFilter>,
+
     /// Telegram getUpdates HTTP poll timeout
     timeout_s: i64,
 
+    /// If true, discard updates that arrived while the bot was offline.
+    drop_pending_updates: bool,
+
+    /// How long to wait after getUpdates fails with a conflict, doubling for each conflict in
+    /// a row up to the maximum.
+    conflict_backoff: Duration,
+    max_conflict_backoff: Duration,
+
+    /// If true, call deleteWebhook after a conflict.
+    delete_webhook_on_conflict: bool,
+
+    /// If set, updates are received with a webhook while it works, and polled otherwise.
+    #[cfg(feature = "webhooks")]
+    webhook: Option<WebhookConfig>,
+
+    /// If set, the router serves many bots from its webhook, and this finds the bot each
+    /// update belongs to.
+    #[cfg(feature = "webhooks")]
+    tenants: Option<Arc<dyn TenantResolver>>,
+
+    /// If set, album parts are buffered for this long after the last part arrives, and
+    /// dispatched together as an `Update::Album`.
+    album_window: Option<Duration>,
+
+    /// If true, text matchers also match the captions of media messages.
+    match_captions: bool,
+
+    /// If set, `Matcher::Language` routes use the language detected in the message text.
+    language_detector: Option<Arc<dyn LanguageDetector>>,
+
+    /// Update types requested from getUpdates. `None` requests Telegram's default set.
+    allowed_updates: Option<Vec<String>>,
+
+    /// Updates with messages older than this are acknowledged, but not dispatched.
+    max_update_age: Option<Duration>,
+
+    /// If set, handlers running longer than this are stopped.
+    handler_timeout: Option<Duration>,
+
+    /// If true, drop updates sent by bots.
+    ignore_bots: bool,
+
+    /// If true, drop updates sent by this bot. `bot_id` is looked up when the router starts.
+    ignore_self: bool,
+    bot_id: Option<i64>,
+
     /// Shutdown notifier
     shutdown: Arc<Notify>,
     shutdown_tx: Arc<mpsc::Sender<()>>,
     shutdown_rx: mpsc::Receiver<()>,
 }
 
+/// Everything the router passes to handlers in an [`Event`], besides the update.
+#[derive(Clone)]
+struct EventContext {
+    api: Arc<API>,
+    settings: Settings,
+    tasks: Tasks,
+    chat_lock: ChatLock,
+    serialize_chats: bool,
+    user_data: UserDataStores,
+    history: Option<MessageHistory>,
+    keyboards: Option<KeyboardState>,
+    features: Features,
+    metrics: HandlerMetrics,
+    control: Option<ControlChat>,
+    handler_timeout: Option<Duration>,
+    callback_debounce: Option<CallbackDebounce>,
+    humanizer: Option<Humanizer>,
+    cleanup: Option<Cleanup>,
+
+    /// The tenant the update belongs to, for multi-tenant routers.
+    tenant: Option<String>,
+
+    /// If set, updates wait their turn here before they're handled.
+    #[cfg(feature = "webhooks")]
+    update_limiter: Option<RateLimiter>,
+}
+
+impl EventContext {
+    /// Returns the context for `tenant`'s updates: its API, and settings, history and chat
+    /// locks of its own. The control chat only gets reports for the router's own updates.
+    #[cfg(feature = "webhooks")]
+    fn for_tenant(&self, tenant: &Tenant) -> Self {
+        let prefix = format!("tenant/{}", tenant.id);
+        Self {
+            api: Arc::clone(&tenant.api),
+            settings: self.settings.with_namespace(&prefix),
+            chat_lock: ChatLock::new(),
+            history: self
+                .history
+                .as_ref()
+                .map(|history| history.with_namespace(&prefix)),
+            keyboards: self
+                .keyboards
+                .as_ref()
+                .map(|keyboards| keyboards.with_namespace(&prefix)),
+            cleanup: self
+                .cleanup
+                .as_ref()
+                .map(|cleanup| cleanup.with_namespace(&prefix)),
+            control: None,
+            callback_debounce: self
+                .callback_debounce
+                .as_ref()
+                .map(CallbackDebounce::cleared),
+            tenant: Some(tenant.id.clone()),
+            update_limiter: tenant.update_limiter.clone(),
+            ..self.clone()
+        }
+    }
+
+    fn event(&self, update: Update) -> Event {
+        Event::new(Arc::clone(&self.api), update)
+            .with_tenant(self.tenant.clone())
+            .with_settings(self.settings.clone())
+            .with_tasks(self.tasks.clone())
+            .with_chat_lock(self.chat_lock.clone())
+            .with_user_data(self.user_data.clone())
+            .with_history(self.history.clone())
+            .with_keyboards(self.keyboards.clone())
+            .with_cleanup(self.cleanup.clone())
+            .with_features(self.features.clone())
+            .with_metrics(self.metrics.clone())
+    }
+
+    /// Record `message` in the message history, if enabled. Failures are logged, and don't
+    /// stop the update from being handled.
+    async fn record(&self, message: &api::Message) {
+        if let Some(history) = &self.history
+            && let Err(err) = history.record(message).await
+        {
+            error!("Can't record message in history: {}", err);
+        }
+    }
+
+    /// Run `handler` for `event`, stopping it and cancelling its API calls if it runs past the
+    /// handler timeout.
+    async fn run<S: BotState>(
+        &self,
+        handler: &dyn BotHandler<S>,
+        event: Event,
+        state: State<S>,
+    ) -> anyhow::Result<Action> {
+        let Some(timeout) = self.handler_timeout else {
+            return handler.run(event, state).await;
+        };
+
+        let cancellation = event.cancellation.clone();
+        let options = CallOptions::new()
+            .with_timeout(timeout)
+            .with_cancellation_token(cancellation.clone());
+        match tokio::time::timeout(timeout, options.scope(handler.run(event, state))).await {
+            Ok(result) => result,
+            Err(_) => {
+                cancellation.cancel();
+                Err(anyhow!("Handler timed out after {:?}", timeout))
+            }
+        }
+    }
+
+    /// Record a run of `handler` in the metrics, and warn if it was slow.
+    fn observe(&self, handler: &str, update: &Update, latency: Duration, ok: bool) {
+        if self.metrics.record(handler, latency, ok) {
+            let redaction = Redaction {
+                strip_text: true,
+                strip_names: true,
+                ..Default::default()
+            };
+            warn!(
+                "Slow handler for {}: took {}ms ({})",
+                handler,
+                latency.as_millis(),
+                LogHandler::new(redaction).summarize(update)
+            );
+        }
+    }
+
+    /// Report `event` to the control chat, if there is one.
+    async fn report(&self, event: impl FnOnce() -> ControlEvent) {
+        if let Some(control) = &self.control {
+            control.report(&self.api, event()).await;
+        }
+    }
+}
+
+/// A tenant's context, chat states, and the dispatcher and album buffer that use them.
+#[cfg(feature = "webhooks")]
+struct TenantRoute<S: BotState> {
+    context: EventContext,
+    handler_state: Arw<HashMap<i64, State<S>>>,
+    dispatch: Dispatch,
+    albums: Option<AlbumBuffer>,
+}
+
+/// How routes are matched against updates.
+#[derive(Clone)]
+struct MatchOptions {
+    /// If true, text matchers also match the captions of media messages.
+    captions: bool,
+
+    /// Detects the language for `Matcher::Language` routes.
+    language_detector: Option<Arc<dyn LanguageDetector>>,
+}
+
+impl MatchOptions {
+    fn language(&self, update: &api::Update) -> Option<String> {
+        language::language_of(update, self.language_detector.as_deref())
+    }
+}
+
+/// Per-chat handler state, exposed as a [`UserData`] store. `S` isn't required to be
+/// serializable, so without `export`, a user's state is only reported as present.
+struct HandlerStates<S: BotState> {
+    states: Arw<HashMap<i64, State<S>>>,
+    export: Option<StateExport<S>>,
+}
+
+#[async_trait]
+impl<S: BotState> UserData for HandlerStates<S> {
+    async fn export_user(&self, user_id: i64) -> anyhow::Result<serde_json::Value> {
+        let Some(state) = self.states.read().await.get(&user_id).cloned() else {
+            return Ok(serde_json::Value::Null);
+        };
+
+        let state = state.get().read().await;
+        match &self.export {
+            Some(export) => export(&state),
+            None => Ok(serde_json::Value::String(
+                "present, but not exportable (see Router::with_state_export)".into(),
+            )),
+        }
+    }
+
+    async fn delete_user(&self, user_id: i64) -> anyhow::Result<()> {
+        self.states.write().await.remove(&user_id);
+        Ok(())
+    }
+}
+
 async fn default_error_handler<S: BotState>(
     api: Arc<API>,
     chat_id: i64,
@@ -294,23 +711,360 @@ impl<S: BotState> Router<S> {
             init_handlers: Some(HashMap::new()),
             handlers: Arc::new(RwLock::new(HashMap::new())),
             handler_state: Arc::new(RwLock::new(HashMap::new())),
+            state_export: None,
+            settings: Settings::default(),
+            chat_lock: ChatLock::new(),
+            serialize_chats: false,
+            user_data: UserDataStores::new(),
+            history: None,
+            keyboards: None,
+            callback_debounce: None,
+            humanizer: None,
+            cleanup: None,
+            features: Features::new(),
+            metrics: HandlerMetrics::new(),
+            control: None,
+            tasks: Tasks::new(),
+            shutdown_grace_period: Duration::from_secs(5),
+            update_filters: vec![],
             timeout_s: 60,
+            drop_pending_updates: false,
+            max_update_age: None,
+            handler_timeout: None,
+            conflict_backoff: Duration::from_secs(1),
+            max_conflict_backoff: Duration::from_secs(30),
+            delete_webhook_on_conflict: false,
+            #[cfg(feature = "webhooks")]
+            webhook: None,
+            #[cfg(feature = "webhooks")]
+            tenants: None,
+            album_window: None,
+            match_captions: false,
+            language_detector: None,
+            allowed_updates: None,
+            ignore_bots: false,
+            ignore_self: false,
+            bot_id: None,
             shutdown: Arc::new(Notify::new()),
             shutdown_tx: Arc::new(shutdown_tx),
             shutdown_rx,
         }
     }
 
+    /// Use `api` for all requests, instead of the default API created from the router's client.
+    /// Useful to configure the API (e.g., with [`API::with_audit_sink`]) before starting the router.
+    pub fn with_api(mut self, api: API) -> Self {
+        self.api = Arc::new(api);
+        self
+    }
+
     pub fn with_poll_timeout_s(mut self, timeout_s: i64) -> Self {
         self.timeout_s = timeout_s;
         self
     }
 
+    /// If `drop` is true, updates that arrived while the bot was offline are discarded when the
+    /// router starts, so a bot restarted after downtime doesn't replay stale commands. This
+    /// also removes any webhook set for the bot.
+    pub fn with_drop_pending_updates(mut self, drop: bool) -> Self {
+        self.drop_pending_updates = drop;
+        self
+    }
+
+    /// Don't dispatch updates whose message is older than `max_age`, according to the API's
+    /// clock (see [`API::with_clock`]). This keeps the bot from replying to hours-old commands
+    /// after an outage. Callback and inline queries carry no date, and are always dispatched.
+    pub fn with_max_update_age(mut self, max_age: Duration) -> Self {
+        self.max_update_age = Some(max_age);
+        self
+    }
+
+    /// Stop handlers that run longer than `timeout`, and pass a timeout error to the error
+    /// handler. The handler's API calls are cancelled with it, including calls from tasks it
+    /// spawned with the event's cancellation token (see [`crate::CallOptions`]).
+    pub fn with_handler_timeout(mut self, timeout: Duration) -> Self {
+        self.handler_timeout = Some(timeout);
+        self
+    }
+
+    /// If polling fails with a conflict (HTTP 409), which happens when another instance of the
+    /// bot is polling at the same time (e.g., while a deploy overlaps the old and new instance),
+    /// wait `backoff` before polling again, doubling the wait for each conflict in a row, up
+    /// to `max_backoff`. Defaults to 1 and 30 seconds.
+    pub fn with_conflict_backoff(mut self, backoff: Duration, max_backoff: Duration) -> Self {
+        self.conflict_backoff = backoff;
+        self.max_conflict_backoff = max_backoff;
+        self
+    }
+
+    /// If `delete` is true, call deleteWebhook after a conflict, in case the conflict is caused
+    /// by a webhook set for the bot. Pending updates are kept.
+    pub fn with_delete_webhook_on_conflict(mut self, delete: bool) -> Self {
+        self.delete_webhook_on_conflict = delete;
+        self
+    }
+
+    /// Receive updates with the webhook `config`, and fall back to polling while Telegram can't
+    /// deliver to it. See [`crate::webhook`].
+    #[cfg(feature = "webhooks")]
+    pub fn with_webhook(mut self, config: WebhookConfig) -> Self {
+        self.webhook = Some(config);
+        self
+    }
+
+    /// Serve many bots from the webhook (see [`Router::with_webhook`], which is required), with
+    /// `resolver` finding the bot each update belongs to from the path it was posted to. The
+    /// router doesn't poll, or set a webhook for its own client. Each tenant's handlers get its
+    /// own API, settings, message history and chat state. See [`crate::tenants`].
+    #[cfg(feature = "webhooks")]
+    pub fn with_tenants(mut self, resolver: impl TenantResolver + 'static) -> Self {
+        self.tenants = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Create a router that only serves the tenants found by `resolver` (see
+    /// [`Router::with_tenants`]). It has no bot of its own, so its [`Router::api`] has no
+    /// token, and calls made with it fail.
+    #[cfg(feature = "webhooks")]
+    pub fn for_tenants(resolver: impl TenantResolver + 'static) -> Self {
+        Self::new(Client::new(String::new())).with_tenants(resolver)
+    }
+
+    /// Deliver albums as a single [`Update::Album`], instead of one update per photo or video.
+    /// Album parts are held back until none have arrived for `window` (Telegram sends them in
+    /// quick succession, so a second or so is plenty), and routed using the first part, so match
+    /// them with [`Matcher::Photo`], [`Matcher::Video`] or [`Matcher::Document`].
+    pub fn with_album_aggregation(mut self, window: Duration) -> Self {
+        self.album_window = Some(window);
+        self
+    }
+
+    /// If `enabled` is true, text routes (`Exact`, `Prefix`, `Regex` and `BotCommand`) also match
+    /// the captions of photos, videos and documents, so commands sent as a caption work. Off by
+    /// default, since handlers for those routes usually expect `message.text` to be set.
+    pub fn with_caption_matching(mut self, enabled: bool) -> Self {
+        self.match_captions = enabled;
+        self
+    }
+
+    /// Route [`Matcher::Language`] by the language `detector` finds in the text of messages
+    /// and inline queries, instead of the sender's `language_code` (which is the language of
+    /// their Telegram app, not necessarily the one they write in). Updates without text, or
+    /// whose language isn't detected, fall back to `language_code`.
+    pub fn with_language_detector(mut self, detector: impl LanguageDetector + 'static) -> Self {
+        self.language_detector = Some(Arc::new(detector));
+        self
+    }
+
+    /// Only receive the listed update types, e.g. `["message", "callback_query",
+    /// "message_reaction"]`. By default Telegram sends every type except `chat_member`,
+    /// `message_reaction` and `message_reaction_count`, so those must be requested here.
+    pub fn with_allowed_updates<T: Into<String>>(
+        mut self,
+        allowed_updates: impl IntoIterator<Item = T>,
+    ) -> Self {
+        self.allowed_updates = Some(allowed_updates.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// If `ignore` is true, updates sent by other bots are dropped without running any handlers.
+    pub fn with_ignore_bots(mut self, ignore: bool) -> Self {
+        self.ignore_bots = ignore;
+        self
+    }
+
+    /// If `ignore` is true, updates sent by this bot (e.g., its own messages in a channel it
+    /// posts to) are dropped without running any handlers.
+    pub fn with_ignore_self(mut self, ignore: bool) -> Self {
+        self.ignore_self = ignore;
+        self
+    }
+
     pub fn with_state(mut self, state: S) -> Self {
         self.state = Some(Arc::new(RwLock::new(state)));
         self
     }
 
+    /// Use `settings` as the settings store passed to handlers. Defaults to an in-memory store.
+    pub fn with_settings(mut self, settings: Settings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Return a handle to the router's settings store. Useful for subscribing to changes
+    /// before the router is started.
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// How long to wait for background tasks (see [`Event::spawn`]) to finish when the router
+    /// shuts down, before cancelling them. Defaults to 5 seconds.
+    pub fn with_shutdown_grace_period(mut self, grace_period: Duration) -> Self {
+        self.shutdown_grace_period = grace_period;
+        self
+    }
+
+    /// Register `store` under `name`, so it's included in user data exports and deletions.
+    pub fn with_user_data(
+        mut self,
+        name: impl Into<String>,
+        store: impl UserData + 'static,
+    ) -> Self {
+        self.user_data = self.user_data.with_store(name, store);
+        self
+    }
+
+    /// Return the router's user data stores: the per-chat handler state (as `"state"`, which
+    /// is only exported with [`Router::with_state_export`]), the settings store (as
+    /// `"settings"`), the message history if it's enabled (as `"history"`), and any stores
+    /// registered with [`Router::with_user_data`]. A user's chat state and settings are those
+    /// of their private chat with the bot.
+    ///
+    /// Tenants of a multi-tenant router get the same stores over their own state, settings
+    /// and history. Stores registered with [`Router::with_user_data`] are shared by all
+    /// tenants.
+    pub fn user_data(&self) -> UserDataStores {
+        self.user_data_over(
+            Arc::clone(&self.handler_state),
+            &self.settings,
+            self.history.as_ref(),
+        )
+    }
+
+    fn user_data_over(
+        &self,
+        handler_state: Arw<HashMap<i64, State<S>>>,
+        settings: &Settings,
+        history: Option<&MessageHistory>,
+    ) -> UserDataStores {
+        let mut stores = UserDataStores::new()
+            .with_store(
+                "state",
+                HandlerStates {
+                    states: handler_state,
+                    export: self.state_export.clone(),
+                },
+            )
+            .with_store("settings", settings.clone());
+        if let Some(history) = history {
+            stores = stores.with_store("history", history.clone());
+        }
+
+        for (name, store) in self.user_data.stores() {
+            stores = stores.with_arc(name, store);
+        }
+        stores
+    }
+
+    /// Record recent messages per chat in `history`, and pass it to handlers in
+    /// [`Event::history`].
+    pub fn with_message_history(mut self, history: MessageHistory) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Pass `keyboards` to handlers in [`Event::keyboards`], to remember the inline keyboards
+    /// of the messages they send.
+    pub fn with_keyboard_state(mut self, keyboards: KeyboardState) -> Self {
+        self.keyboards = Some(keyboards);
+        self
+    }
+
+    /// Drop callback queries from users tapping the same message's buttons again while the
+    /// first tap is handled, or soon after (see [`CallbackDebounce`]). Dropped queries are
+    /// answered with the debounce's toast, and never reach handlers.
+    pub fn with_callback_debounce(mut self, debounce: CallbackDebounce) -> Self {
+        self.callback_debounce = Some(debounce);
+        self
+    }
+
+    /// Pace the router's text replies (`Action::ReplyText` and `Action::ReplyMarkdown`) with
+    /// `humanizer`, showing the typing action for a while before sending them.
+    pub fn with_humanizer(mut self, humanizer: Humanizer) -> Self {
+        self.humanizer = Some(humanizer);
+        self
+    }
+
+    /// Pass `cleanup` to handlers in [`Event::cleanup`], to delete ephemeral messages after a
+    /// TTL. Deletions left pending by an earlier run are rescheduled when the router starts.
+    pub fn with_cleanup(mut self, cleanup: Cleanup) -> Self {
+        self.cleanup = Some(cleanup);
+        self
+    }
+
+    /// Use `features` as the router's feature flags, e.g. to load them from the bot's config.
+    pub fn with_features(mut self, features: Features) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Return a handle to the router's feature flags, to turn features on and off while the
+    /// router is running.
+    pub fn features(&self) -> &Features {
+        &self.features
+    }
+
+    /// Log a warning, with a summary of the update, when a handler runs longer than
+    /// `threshold`. Slow runs are also counted in [`Router::handler_metrics`].
+    pub fn with_slow_handler_warning(mut self, threshold: Duration) -> Self {
+        self.metrics = self.metrics.with_slow_threshold(threshold);
+        self
+    }
+
+    /// Return a handle to the router's per-handler metrics.
+    pub fn handler_metrics(&self) -> &HandlerMetrics {
+        &self.metrics
+    }
+
+    /// Report handler errors, rate limit errors, and dead letters to `control`. See
+    /// [`ControlChat`].
+    pub fn with_control_chat(mut self, control: ControlChat) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// Handle updates for the same chat one at a time, in the order they arrive. By default,
+    /// handlers for the same chat run concurrently.
+    pub fn with_chat_serialization(mut self) -> Self {
+        self.serialize_chats = true;
+        self
+    }
+
+    /// Return a handle to the router's per-chat locks. With [`Router::with_chat_serialization`],
+    /// updates for a chat are only dispatched while its lock is free, so code outside handlers
+    /// can take the lock to coordinate with them.
+    pub fn chat_lock(&self) -> &ChatLock {
+        &self.chat_lock
+    }
+
+    /// Return a handle to the router's background tasks.
+    pub fn tasks(&self) -> &Tasks {
+        &self.tasks
+    }
+
+    /// Add a pre-filter for incoming updates. Filters are cheap synchronous checks that run in
+    /// the polling loop, before an update is cloned and spawned into a handler task. Updates for
+    /// which any filter returns `false` are dropped without running any handlers.
+    ///
+    /// ```no_run
+    /// # use mobot::*;
+    /// # let client = Client::new("token".to_string());
+    /// // Ignore messages forwarded from channels.
+    /// let router: Router<()> = Router::new(client).with_update_filter(|update| {
+    ///     update
+    ///         .message
+    ///         .as_ref()
+    ///         .is_none_or(|m| m.forward_from_chat.is_none())
+    /// });
+    /// ```
+    pub fn with_update_filter(
+        mut self,
+        filter: impl Fn(&api::Update) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.update_filters.push(Box::new(filter));
+        self
+    }
+
     pub fn with_error_handler<Func, Fut>(mut self, func: Func) -> Self
     where
         Func: Send + Sync + 'static + Fn(Arc<API>, i64, State<S>, anyhow::Error) -> Fut,
@@ -323,20 +1077,88 @@ impl<S: BotState> Router<S> {
     /// Add a handler for messages matching a route in a chat. The handler is called with current
     /// state of the chat ID or the user ID, depending on the update.
     pub fn add_route(&mut self, r: Route, h: impl Into<Box<dyn BotHandler<S>>>) -> &mut Self {
-        let mut h: Box<dyn BotHandler<S>> = h.into();
+        self.add_route_with_priority(r, 0, h)
+    }
+
+    /// Like [`Router::add_route`], but handlers with a higher `priority` run before those with
+    /// a lower one of the same kind of route, regardless of the order they were added in: e.g.
+    /// a `Route::Message` handler only runs ahead of other `Route::Message` handlers. Handlers
+    /// with the same priority run in the order they were added. [`Router::add_route`] uses
+    /// priority 0.
+    ///
+    /// Priority doesn't move handlers between kinds of routes. `Route::Default` (and
+    /// `Route::Any`) handlers only run for updates that have no handlers for their own kind of
+    /// route, whatever their priority.
+    ///
+    /// Use it to let several handlers observe the same update, e.g. a logger that returns
+    /// [`Action::Next`] (or `ControlFlow::Continue`, see [`Action`]) ahead of commands:
+    ///
+    /// ```no_run
+    /// # use mobot::*;
+    /// # async fn help(e: Event, _: State<()>) -> Result<Action, anyhow::Error> { unimplemented!() }
+    /// # let mut router: Router<()> = Router::new(Client::new("token".to_string()));
+    /// router
+    ///     .add_route(Route::Message(Matcher::BotCommand("help".into())), help)
+    ///     .add_route_with_priority(Route::Message(Matcher::Any), 100, handlers::log_handler);
+    /// ```
+    pub fn add_route_with_priority<H: Into<Box<dyn BotHandler<S>>>>(
+        &mut self,
+        r: Route,
+        priority: i32,
+        h: H,
+    ) -> &mut Self {
+        self.insert_route(r, priority, handler_name::<H>(), h.into())
+    }
+
+    /// Like [`Router::add_route`], but the handler's metrics (see [`Router::handler_metrics`])
+    /// are kept under `name`. Use it to tell closures on the same route apart: without a name,
+    /// they're kept under the route.
+    pub fn add_named_route(
+        &mut self,
+        name: impl Into<String>,
+        r: Route,
+        h: impl Into<Box<dyn BotHandler<S>>>,
+    ) -> &mut Self {
+        self.insert_route(r, 0, Some(name.into()), h.into())
+    }
+
+    /// Add the handler `h`, keeping its metrics under `name`, or the route if it has none.
+    fn insert_route(
+        &mut self,
+        r: Route,
+        priority: i32,
+        name: Option<String>,
+        mut h: Box<dyn BotHandler<S>>,
+    ) -> &mut Self {
+        let name = name.unwrap_or_else(|| format!("{:?}", r));
 
         if let Some(state) = &self.state {
             h.set_state(Arc::clone(state));
         }
 
         // Note that Route::Default gets converted to Route::Any(Matcher::Any)
-        self.init_handlers
+        let handlers = self
+            .init_handlers
             .as_mut()
             .expect("Can't call add_chat_route after start()")
             .entry(Route::any(&r))
-            .or_default()
-            .push((r.into(), h));
+            .or_default();
 
+        // Insert after all handlers with the same or higher priority.
+        let index = handlers
+            .iter()
+            .position(|(_, p, _, _)| *p < priority)
+            .unwrap_or(handlers.len());
+        handlers.insert(index, (r.into(), priority, name, h));
+
+        self
+    }
+
+    /// Add the routes of `group` (see [`HandlerGroup`]) after any routes already added.
+    pub fn mount(&mut self, group: HandlerGroup<S>) -> &mut Self {
+        for (route, priority, name, handler) in group.into_routes() {
+            self.insert_route(route, priority, name, handler);
+        }
         self
     }
 
@@ -352,71 +1174,543 @@ impl<S: BotState> Router<S> {
         // to other tasks.
         self.handlers = Arc::new(RwLock::new(self.init_handlers.take().unwrap()));
 
+        if self.drop_pending_updates {
+            last_update_id = self.drop_pending_updates().await;
+        }
+
+        if self.ignore_self && self.bot_id.is_none() {
+            match self.api.me().await {
+                Ok(me) => self.bot_id = Some(me.id),
+                Err(err) => error!("Can't get bot user, not ignoring own messages: {}", err),
+            }
+        }
+
+        if let Some(cleanup) = &self.cleanup {
+            match cleanup.resume(&self.api, &self.tasks).await {
+                Ok(0) => {}
+                Ok(count) => info!("Rescheduled {} pending message deletions", count),
+                Err(err) => error!("Can't reschedule pending message deletions: {}", err),
+            }
+        }
+
+        let context = EventContext {
+            api: Arc::clone(&self.api),
+            settings: self.settings.clone(),
+            tasks: self.tasks.clone(),
+            chat_lock: self.chat_lock.clone(),
+            serialize_chats: self.serialize_chats,
+            user_data: self.user_data(),
+            history: self.history.clone(),
+            keyboards: self.keyboards.clone(),
+            features: self.features.clone(),
+            metrics: self.metrics.clone(),
+            control: self.control.clone(),
+            handler_timeout: self.handler_timeout,
+            callback_debounce: self.callback_debounce.clone(),
+            humanizer: self.humanizer.clone(),
+            cleanup: self.cleanup.clone(),
+            tenant: None,
+            #[cfg(feature = "webhooks")]
+            update_limiter: None,
+        };
+
+        #[cfg(feature = "webhooks")]
+        if let Some(resolver) = self.tenants.clone() {
+            self.serve_tenants(context, resolver).await;
+            self.tasks.shutdown(self.shutdown_grace_period).await;
+            self.shutdown.notify_waiters();
+            return;
+        }
+
+        let dispatch = self.dispatcher(context, Arc::clone(&self.handler_state));
+        let albums = self.album_buffer(&dispatch, self.api.clock());
+
+        // Updates posted to the webhook server, if there is one. While the webhook is active,
+        // the router waits for them instead of polling.
+        #[cfg(feature = "webhooks")]
+        let (webhook_tx, mut webhook_rx) = mpsc::channel(100);
+        #[cfg(feature = "webhooks")]
+        let mut webhook = None;
+        #[cfg(feature = "webhooks")]
+        if let Some(config) = self.webhook.clone() {
+            match TcpListener::bind(config.listen).await {
+                Ok(listener) => {
+                    info!("Listening for webhook updates on {}", config.listen);
+                    let server =
+                        tokio::spawn(webhook::serve(listener, config.secret.clone(), webhook_tx));
+                    let since = self.set_webhook(&config).await;
+                    webhook = Some((config, server, since));
+                }
+                Err(err) => error!(
+                    "Can't listen on {}, polling instead: {}",
+                    config.listen, err
+                ),
+            }
+        }
+        #[cfg(feature = "webhooks")]
+        let check_interval = webhook
+            .as_ref()
+            .map_or(Duration::from_secs(60), |(config, _, _)| {
+                config.check_interval
+            });
+        #[cfg(feature = "webhooks")]
+        let clock = Arc::clone(self.api.clock());
+        #[cfg(feature = "webhooks")]
+        let mut last_check = clock.now();
+
+        // The number of conflicts in a row, for backing off.
+        let mut conflicts = 0;
+
         loop {
+            // Wait for webhook updates while the webhook is active, and fall back to polling
+            // if Telegram can't deliver them.
+            #[cfg(feature = "webhooks")]
+            if let Some((config, _, since)) = &mut webhook
+                && let Some(active_since) = *since
+            {
+                tokio::select! {
+                    _ = self.shutdown_rx.recv() => {
+                        info!("Received shutdown signal");
+                        break;
+                    }
+                    Some(update) = webhook_rx.recv() => {
+                        last_update_id = max(last_update_id, update.update_id);
+                        self.process_update(update, &dispatch, albums.as_ref());
+                    }
+                    _ = clock.sleep(check_interval.saturating_sub(clock.elapsed(last_check))) => {
+                        last_check = clock.now();
+                        match self.api.get_webhook_info().await {
+                            Ok(info) if webhook::is_failing(&info, active_since) => {
+                                warn!(
+                                    "Webhook {} is failing ({}), polling instead",
+                                    config.url,
+                                    info.last_error_message.unwrap_or_default()
+                                );
+                                if let Err(err) =
+                                    self.api.delete_webhook(&DeleteWebhookRequest::new()).await
+                                {
+                                    error!("Can't delete webhook: {}", err);
+                                }
+                                *since = None;
+                            }
+                            Ok(_) => {}
+                            Err(err) => warn!("Can't check webhook: {}", err),
+                        }
+                    }
+                }
+                continue;
+            }
+
             if self.shutdown_rx.try_recv().is_ok() {
                 info!("Received shutdown signal");
                 break;
             }
 
+            // Switch back to the webhook once its URL is reachable again. Updates that were
+            // posted to the webhook before it was deleted are handled here.
+            #[cfg(feature = "webhooks")]
+            {
+                while let Ok(update) = webhook_rx.try_recv() {
+                    last_update_id = max(last_update_id, update.update_id);
+                    self.process_update(update, &dispatch, albums.as_ref());
+                }
+                if let Some((config, _, since)) = &mut webhook
+                    && clock.elapsed(last_check) >= check_interval
+                {
+                    last_check = clock.now();
+                    if webhook::is_reachable(&config.url).await {
+                        *since = self.set_webhook(config).await;
+                        if since.is_some() {
+                            continue;
+                        }
+                    }
+                }
+            }
+
             debug!(
                 "Polling /getUpdates with last_update_id = {} timeout = {}s",
                 last_update_id, self.timeout_s
             );
 
-            let updates = match self
-                .api
-                .get_updates(
-                    &GetUpdatesRequest::new()
-                        .with_timeout(self.timeout_s)
-                        .with_offset(last_update_id + 1),
-                )
-                .await
-            {
-                Ok(updates) => updates,
+            let mut req = GetUpdatesRequest::new()
+                .with_timeout(self.timeout_s)
+                .with_offset(last_update_id + 1);
+            if let Some(allowed_updates) = &self.allowed_updates {
+                req = req.with_allowed_updates(allowed_updates.clone());
+            }
+
+            let updates = match self.api.get_updates(&req).await {
+                Ok(updates) => {
+                    conflicts = 0;
+                    updates
+                }
+                Err(err)
+                    if err
+                        .downcast_ref::<ApiError>()
+                        .is_some_and(ApiError::is_conflict) =>
+                {
+                    conflicts += 1;
+                    if !self.recover_from_conflict(conflicts, &err).await {
+                        info!("Received shutdown signal");
+                        break;
+                    }
+                    continue;
+                }
                 Err(err) => {
                     error!("Error polling /getUpdates: {}", err);
-                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    if !self.back_off(Duration::from_secs(1)).await {
+                        info!("Received shutdown signal");
+                        break;
+                    }
                     continue;
                 }
             };
 
             for update in updates {
-                debug!("Received update: {:#?}", update);
                 last_update_id = max(last_update_id, update.update_id);
-
-                let chat_update = update.clone();
-                let handlers = Arc::clone(&self.handlers);
-                let error_handler = Arc::clone(&self.error_handler);
-                let handler_state = Arc::clone(&self.handler_state);
-                let api = Arc::clone(&self.api);
-                tokio::spawn(async move {
-                    if let Err(err) = Self::handle_chat_update(
-                        api,
-                        handler_state,
-                        handlers,
-                        error_handler,
-                        chat_update,
-                    )
-                    .await
-                    {
-                        error!("Error handling chat update: {}", err);
-                    }
-                });
+                self.process_update(update, &dispatch, albums.as_ref());
             }
         }
 
+        #[cfg(feature = "webhooks")]
+        if let Some((_, server, _)) = webhook {
+            server.abort();
+        }
+
+        self.tasks.shutdown(self.shutdown_grace_period).await;
         self.shutdown.notify_waiters();
     }
 
+    /// Drop `update` if it's too old, from an ignored sender, or filtered out, and dispatch it
+    /// otherwise. Album parts are buffered until the album is complete.
+    fn process_update(
+        &self,
+        update: api::Update,
+        dispatch: &Dispatch,
+        albums: Option<&AlbumBuffer>,
+    ) {
+        debug!("Received update: {:#?}", update);
+
+        if self.is_too_old(&update) {
+            debug!("Update {} dropped: too old", update.update_id);
+            return;
+        }
+
+        if self.is_ignored_sender(&update) {
+            debug!("Update {} dropped: ignored sender", update.update_id);
+            return;
+        }
+
+        if !self.update_filters.iter().all(|filter| filter(&update)) {
+            debug!("Update {} dropped by filter", update.update_id);
+            return;
+        }
+
+        let update = match albums {
+            Some(albums) => match albums.push(update) {
+                Some(update) => update,
+                None => return,
+            },
+            None => update,
+        };
+
+        let event = update.clone().into();
+        dispatch(update, event);
+    }
+
+    /// Register the webhook with Telegram. Returns the time it was set (in Unix time), or
+    /// `None` if it couldn't be set, in which case the router keeps polling.
+    #[cfg(feature = "webhooks")]
+    async fn set_webhook(&self, config: &WebhookConfig) -> Option<i64> {
+        let mut req = config.request();
+        if let Some(allowed_updates) = &self.allowed_updates {
+            req = req.with_allowed_updates(allowed_updates.clone());
+        }
+
+        let since = self.api.now();
+        match self.api.set_webhook(&req).await {
+            Ok(_) => {
+                info!("Receiving updates with webhook {}", config.url);
+                Some(since)
+            }
+            Err(err) => {
+                warn!("Can't set webhook {}, polling instead: {}", config.url, err);
+                None
+            }
+        }
+    }
+
+    /// Receive tenants' updates on the webhook server until the router is shut down, and
+    /// dispatch them with each tenant's context.
+    #[cfg(feature = "webhooks")]
+    async fn serve_tenants(&mut self, context: EventContext, resolver: Arc<dyn TenantResolver>) {
+        let Some(config) = self.webhook.clone() else {
+            error!("Multi-tenant routers need a webhook, see Router::with_webhook");
+            return;
+        };
+        let listener = match TcpListener::bind(config.listen).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!("Can't listen on {}: {}", config.listen, err);
+                return;
+            }
+        };
+        info!("Listening for tenant webhook updates on {}", config.listen);
+        let (tx, mut rx) = mpsc::channel(100);
+        let server = tokio::spawn(webhook::serve_tenants(
+            listener,
+            config.secret.clone(),
+            resolver,
+            tx,
+        ));
+
+        // Each tenant's context and dispatcher, created on its first update. The dispatcher is
+        // replaced if the tenant's API changes (e.g., its token is rotated).
+        let mut tenants: HashMap<String, TenantRoute<S>> = HashMap::new();
+
+        loop {
+            let (tenant, update) = tokio::select! {
+                _ = self.shutdown_rx.recv() => {
+                    info!("Received shutdown signal");
+                    break;
+                }
+                Some(received) = rx.recv() => received,
+            };
+
+            let route = match tenants.remove(&tenant.id) {
+                Some(route) if Arc::ptr_eq(&route.context.api, &tenant.api) => route,
+                Some(route) => {
+                    let context = EventContext {
+                        api: Arc::clone(&tenant.api),
+                        update_limiter: tenant.update_limiter.clone(),
+                        ..route.context
+                    };
+                    self.tenant_route(context, route.handler_state)
+                }
+                None => {
+                    let handler_state = Arc::new(RwLock::new(HashMap::new()));
+                    let mut context = context.for_tenant(&tenant);
+                    context.user_data = self.user_data_over(
+                        Arc::clone(&handler_state),
+                        &context.settings,
+                        context.history.as_ref(),
+                    );
+                    self.tenant_route(context, handler_state)
+                }
+            };
+
+            self.process_update(update, &route.dispatch, route.albums.as_ref());
+            tenants.insert(tenant.id, route);
+        }
+
+        server.abort();
+    }
+
+    #[cfg(feature = "webhooks")]
+    fn tenant_route(
+        &self,
+        context: EventContext,
+        handler_state: Arw<HashMap<i64, State<S>>>,
+    ) -> TenantRoute<S> {
+        let dispatch = self.dispatcher(context.clone(), Arc::clone(&handler_state));
+        TenantRoute {
+            albums: self.album_buffer(&dispatch, context.api.clock()),
+            dispatch,
+            context,
+            handler_state,
+        }
+    }
+
+    /// Return a buffer that dispatches albums as a single update once all their parts have
+    /// arrived, if albums are enabled. The first part is used for routing.
+    fn album_buffer(&self, dispatch: &Dispatch, clock: &Arc<dyn Clock>) -> Option<AlbumBuffer> {
+        self.album_window.map(|window| {
+            let dispatch = Arc::clone(dispatch);
+            AlbumBuffer::new(
+                window,
+                Arc::new(move |mut parts: Vec<api::Update>| {
+                    let first = parts[0].clone();
+                    let messages = parts
+                        .iter_mut()
+                        .filter_map(|u| u.message.take().or(u.channel_post.take()))
+                        .collect();
+                    dispatch(first, Update::Album(messages));
+                }),
+                Arc::clone(clock),
+            )
+        })
+    }
+
+    /// Return a function that handles an update on a new task, with the chat states in
+    /// `handler_state`. `event` is the update passed to handlers, and `update` is used for
+    /// routing.
+    fn dispatcher(
+        &self,
+        context: EventContext,
+        handler_state: Arw<HashMap<i64, State<S>>>,
+    ) -> Dispatch {
+        let handlers = Arc::clone(&self.handlers);
+        let error_handler = Arc::clone(&self.error_handler);
+        let match_options = MatchOptions {
+            captions: self.match_captions,
+            language_detector: self.language_detector.clone(),
+        };
+
+        Arc::new(move |update: api::Update, event: Update| {
+            let handlers = Arc::clone(&handlers);
+            let error_handler = Arc::clone(&error_handler);
+            let handler_state = Arc::clone(&handler_state);
+            let context = context.clone();
+            let match_options = match_options.clone();
+            tokio::spawn(async move {
+                // Wait for the tenant's turn. Other tenants' updates are handled meanwhile.
+                #[cfg(feature = "webhooks")]
+                if let Some(limiter) = &context.update_limiter {
+                    limiter.acquire().await;
+                }
+
+                if let Err(err) = Self::handle_chat_update(
+                    context,
+                    handler_state,
+                    handlers,
+                    error_handler,
+                    update,
+                    event,
+                    match_options,
+                )
+                .await
+                {
+                    error!("Error handling chat update: {}", err);
+                }
+            });
+        })
+    }
+
+    /// Back off after the `conflicts`th conflict in a row, and delete the webhook if enabled.
+    /// Returns false if the router was shut down while backing off.
+    async fn recover_from_conflict(&mut self, conflicts: u32, err: &anyhow::Error) -> bool {
+        let backoff = self
+            .conflict_backoff
+            .saturating_mul(1 << (conflicts - 1).min(16))
+            .min(self.max_conflict_backoff);
+        warn!(
+            "Conflict polling /getUpdates (is another instance running?), retrying in {:?}: {}",
+            backoff, err
+        );
+
+        if self.delete_webhook_on_conflict
+            && let Err(err) = self.api.delete_webhook(&DeleteWebhookRequest::new()).await
+        {
+            warn!("Can't delete webhook after conflict: {}", err);
+        }
+
+        self.back_off(backoff).await
+    }
+
+    /// Wait for `backoff` on the API's clock before polling again. Returns false if the router
+    /// was shut down in the meantime.
+    async fn back_off(&mut self, backoff: Duration) -> bool {
+        tokio::select! {
+            _ = self.api.clock().sleep(backoff) => true,
+            _ = self.shutdown_rx.recv() => false,
+        }
+    }
+
+    fn is_too_old(&self, update: &api::Update) -> bool {
+        match (self.max_update_age, update.date()) {
+            (Some(max_age), Some(date)) => self.api.now() - date > max_age.as_secs() as i64,
+            _ => false,
+        }
+    }
+
+    fn is_ignored_sender(&self, update: &api::Update) -> bool {
+        let Some(sender) = update.sender() else {
+            return false;
+        };
+
+        (self.ignore_bots && sender.is_bot) || (self.ignore_self && self.bot_id == Some(sender.id))
+    }
+
+    /// Discard pending updates, and return the update ID to continue polling from. This asks
+    /// Telegram to drop them with `deleteWebhook`, and if that fails, fast-forwards past them
+    /// by fetching only the most recent update.
+    async fn drop_pending_updates(&self) -> i64 {
+        let req = DeleteWebhookRequest::new().with_drop_pending_updates(true);
+        match self.api.delete_webhook(&req).await {
+            Ok(_) => {
+                info!("Dropped pending updates");
+                return 0;
+            }
+            Err(err) => warn!("Can't drop pending updates with /deleteWebhook: {}", err),
+        }
+
+        let req = GetUpdatesRequest::new().with_offset(-1).with_timeout(0);
+        match self.api.get_updates(&req).await {
+            Ok(updates) => {
+                let last_update_id = updates.iter().map(|u| u.update_id).max().unwrap_or(0);
+                info!("Skipped pending updates up to {}", last_update_id);
+                last_update_id
+            }
+            Err(err) => {
+                error!("Can't skip pending updates: {}", err);
+                0
+            }
+        }
+    }
+
     async fn handle_chat_update(
-        api: Arc<API>,
+        context: EventContext,
         handler_state: Arc<RwLock<HashMap<i64, State<S>>>>,
         handlers: Arw<HandlerMap<S>>,
         error_handler: Arc<ErrorHandler<S>>,
         update: api::Update,
+        message_event: Update,
+        match_options: MatchOptions,
     ) -> anyhow::Result<()> {
         let (chat_id, route) = get_update_parts(&update)?;
-        let message_event: Update = update.clone().into();
+        let api = Arc::clone(&context.api);
+
+        // Drop repeated taps on the same message's buttons. The guard is held until the
+        // handlers are done.
+        let _debounce = match (&context.callback_debounce, &update.callback_query) {
+            (Some(debounce), Some(query)) => match debounce.start(query, api.clock()) {
+                Some(guard) => Some(guard),
+                None => {
+                    debug!(
+                        "Dropping repeated callback query from user {}",
+                        query.from.id
+                    );
+                    let req = api::AnswerCallbackQueryRequest::new(query.id.clone())
+                        .with_text(debounce.text());
+                    if let Err(err) = api.answer_callback_query(&req).await {
+                        warn!("Can't answer repeated callback query: {}", err);
+                    }
+                    return Ok(());
+                }
+            },
+            _ => None,
+        };
+
+        // If enabled, handle updates for the same chat one at a time. Queries without a chat
+        // (chat_id 0) aren't serialized.
+        let _guard = if context.serialize_chats && chat_id != 0 {
+            Some(context.chat_lock.lock(chat_id).await)
+        } else {
+            None
+        };
+
+        match &message_event {
+            Update::Album(messages) => {
+                for message in messages {
+                    context.record(message).await;
+                }
+            }
+            _ => {
+                if let Some(message) = update.any_message() {
+                    context.record(message).await;
+                }
+            }
+        }
 
         let mut handler_groups = vec![];
         let h = handlers.read().await;
@@ -434,6 +1728,12 @@ impl<S: BotState> Router<S> {
         if handler_groups.is_empty() {
             // No default handler installed, so we can't do anything with this message. Call
             // the error handler.
+            context
+                .report(|| ControlEvent::DeadLetter {
+                    chat_id,
+                    route: format!("{:?}", route),
+                })
+                .await;
             error_handler(
                 Arc::clone(&api),
                 chat_id,
@@ -443,11 +1743,24 @@ impl<S: BotState> Router<S> {
             .await;
         }
 
+        // The update's language is only looked up if a route needs it.
+        let language = OnceCell::new();
+
+        // Shared by all handlers that run for this update.
+        let extensions = Extensions::new();
+
         // Go through each handler in the stack and see if it matches the update.
         'top: for handler_group in handler_groups {
             for matcher_handler in handler_group {
-                let (matcher, handler) = matcher_handler;
-                if !route.with(matcher).match_update(&update) {
+                let (matcher, _, name, handler) = matcher_handler;
+                let route = route.with(matcher);
+                let language = match matcher {
+                    Matcher::Language(_) => language
+                        .get_or_init(|| match_options.language(&update))
+                        .as_deref(),
+                    _ => None,
+                };
+                if !route.matches(&update, match_options.captions, language) {
                     // Route doesn't match, so skip this handler.
                     continue;
                 }
@@ -463,15 +1776,28 @@ impl<S: BotState> Router<S> {
                 };
 
                 // Run the handler
-                let reply = handler
+                let started = api.clock().now();
+                let reply = context
                     .run(
-                        Event::new(Arc::clone(&api), message_event.clone()),
+                        handler.as_ref(),
+                        context
+                            .event(message_event.clone())
+                            .with_extensions(extensions.clone()),
                         state.clone(),
                     )
                     .await;
+                context.observe(
+                    name,
+                    &message_event,
+                    api.clock().elapsed(started),
+                    reply.is_ok(),
+                );
 
                 // Handler failed, run the default error handler
                 if let Err(err) = reply {
+                    context
+                        .report(|| ControlEvent::from_error(chat_id, &err))
+                        .await;
                     error_handler(Arc::clone(&api), chat_id, state, err).await;
                     return Ok(());
                 }
@@ -487,33 +1813,46 @@ impl<S: BotState> Router<S> {
 
                     // Handler returned Reply, send the message to the chat, and stop running handlers.
                     Action::ReplyText(text) => {
-                        api.send_message(&SendMessageRequest {
+                        if let Some(humanizer) = &context.humanizer {
+                            humanizer.type_text(&api, chat_id, &text).await;
+                        }
+                        let req = SendMessageRequest {
                             chat_id,
                             text,
                             ..Default::default()
-                        })
-                        .await?;
+                        };
+                        let result = api.send_message(&req).await;
+                        let reply = api::with_request(result, "sendMessage", &req)?;
+                        context.record(&reply).await;
                         break 'top;
                     }
 
                     // Handler returned ReplyMarkdown, send the MarkDown message to the chat, and
                     // stop running handlers.
                     Action::ReplyMarkdown(text) => {
-                        api.send_message(&SendMessageRequest {
+                        if let Some(humanizer) = &context.humanizer {
+                            humanizer.type_text(&api, chat_id, &text).await;
+                        }
+                        let req = SendMessageRequest {
                             chat_id,
                             text,
                             parse_mode: Some(api::ParseMode::MarkdownV2),
                             ..Default::default()
-                        })
-                        .await?;
+                        };
+                        let result = api.send_message(&req).await;
+                        let reply = api::with_request(result, "sendMessage", &req)?;
+                        context.record(&reply).await;
                         break 'top;
                     }
 
                     // Handler returned ReplySticker, send the sticker to the chat, and stop running
                     // handlers.
+                    #[cfg(feature = "stickers")]
                     Action::ReplySticker(sticker) => {
-                        api.send_sticker(&SendStickerRequest::new(chat_id, sticker))
-                            .await?;
+                        let req = api::SendStickerRequest::new(chat_id, sticker);
+                        let result = api.send_sticker(&req).await;
+                        let reply = api::with_request(result, "sendSticker", &req)?;
+                        context.record(&reply).await;
                         break 'top;
                     }
                 }
@@ -522,3 +1861,12 @@ impl<S: BotState> Router<S> {
         Ok(())
     }
 }
+
+impl<S: BotState + serde::Serialize> Router<S> {
+    /// Include the per-chat handler state in user data exports (see [`Router::user_data`]),
+    /// serialized as JSON.
+    pub fn with_state_export(mut self) -> Self {
+        self.state_export = Some(Arc::new(|state: &S| Ok(serde_json::to_value(state)?)));
+        self
+    }
+}
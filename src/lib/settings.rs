@@ -0,0 +1,315 @@
+/// Typed, per-chat settings backed by a [`StateStorage`]. Settings are read from the store on
+/// every access, so a change made by one handler (e.g., an admin running `/setwelcome`) is
+/// immediately visible to every other handler, without restarting the bot.
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::sync::{broadcast, Mutex};
+
+use crate::{
+    storage::{MemoryStorage, NamespacedStorage, StateStorage},
+    user_data::UserData,
+    versioned::{self, Versioned},
+};
+
+/// The storage namespace used for settings. Each chat's settings are stored as a single JSON
+/// object, keyed by [`ChatSetting::KEY`].
+const NAMESPACE: &str = "settings";
+
+/// Implement `ChatSetting` for any type you want to store with [`Settings`]. `KEY` must be
+/// unique across all setting types used by the bot.
+///
+/// ```
+/// # use mobot::*;
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// struct ChatSettings {
+///     welcome: Option<String>,
+/// }
+///
+/// impl ChatSetting for ChatSettings {
+///     const KEY: &'static str = "chat";
+/// }
+///
+/// impl Versioned for ChatSettings {}
+/// ```
+///
+/// Settings are persisted, so changing a setting type's fields can break reading data saved by
+/// an earlier release. Settings are [`Versioned`]: to evolve a setting, bump its
+/// [`Versioned::VERSION`] and upgrade older data in [`Versioned::migrate`].
+pub trait ChatSetting: Versioned + Default + Send + Sync + 'static {
+    const KEY: &'static str;
+}
+
+/// `SettingsChange` is broadcast to all subscribers every time a setting is written or reset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsChange {
+    /// The chat whose settings changed.
+    pub chat_id: i64,
+
+    /// The [`ChatSetting::KEY`] of the setting that changed.
+    pub key: String,
+}
+
+/// `Settings` is a typed settings store. Clones share the same storage and change
+/// notifications. An instance is passed to every handler in [`crate::Event::settings`].
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use serde::{Deserialize, Serialize};
+/// # #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// # struct ChatSettings { welcome: Option<String> }
+/// # impl ChatSetting for ChatSettings { const KEY: &'static str = "chat"; }
+/// # impl Versioned for ChatSettings {}
+/// async fn set_welcome(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     let chat_id = e.update.chat_id()?;
+///     let welcome = e.update.text()?.trim_start_matches("/setwelcome").trim().to_string();
+///
+///     e.settings
+///         .update::<ChatSettings>(chat_id, |s| s.welcome = Some(welcome))
+///         .await?;
+///     Ok(Action::ReplyText("Welcome message updated.".into()))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Settings {
+    storage: Arc<dyn StateStorage>,
+
+    /// Serializes read-modify-write cycles, since all settings for a chat share one entry.
+    write_lock: Arc<Mutex<()>>,
+
+    /// Change notifications.
+    changes: broadcast::Sender<SettingsChange>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new(MemoryStorage::new())
+    }
+}
+
+impl Settings {
+    /// Create a new `Settings` store persisted in `storage`.
+    pub fn new(storage: impl StateStorage + 'static) -> Self {
+        Self::from_arc(Arc::new(storage))
+    }
+
+    /// Create a new `Settings` store persisted in a shared `storage`.
+    pub fn from_arc(storage: Arc<dyn StateStorage>) -> Self {
+        let (changes, _) = broadcast::channel(64);
+        Self {
+            storage,
+            write_lock: Arc::new(Mutex::new(())),
+            changes,
+        }
+    }
+
+    /// Returns a separate settings store, kept in the same storage under `prefix`, e.g. for
+    /// one tenant of a multi-tenant router. It has its own change notifications.
+    pub fn with_namespace(&self, prefix: impl Into<String>) -> Self {
+        Self::new(NamespacedStorage::new(Arc::clone(&self.storage), prefix))
+    }
+
+    /// Subscribe to change notifications. Notifications are only delivered for changes made
+    /// after this call.
+    pub fn subscribe(&self) -> broadcast::Receiver<SettingsChange> {
+        self.changes.subscribe()
+    }
+
+    /// Return the setting `T` for `chat_id`, or `T::default()` if it was never set.
+    pub async fn get<T: ChatSetting>(&self, chat_id: i64) -> Result<T> {
+        let settings = self.load(chat_id).await?;
+        match settings.get(T::KEY) {
+            Some(value) => versioned::decode(value.clone()),
+            None => Ok(T::default()),
+        }
+    }
+
+    /// Replace the setting `T` for `chat_id` with `value`, and notify subscribers.
+    pub async fn set<T: ChatSetting>(&self, chat_id: i64, value: &T) -> Result<()> {
+        let value = versioned::encode(value)?;
+        self.modify(chat_id, T::KEY, |settings| {
+            settings.insert(T::KEY.to_string(), value);
+        })
+        .await
+    }
+
+    /// Apply `f` to the current setting `T` for `chat_id`, store it, and return the new value.
+    pub async fn update<T: ChatSetting>(&self, chat_id: i64, f: impl FnOnce(&mut T)) -> Result<T> {
+        let _guard = self.write_lock.lock().await;
+        let mut settings = self.load(chat_id).await?;
+        let mut current: T = match settings.get(T::KEY) {
+            Some(value) => versioned::decode(value.clone())?,
+            None => T::default(),
+        };
+
+        f(&mut current);
+        settings.insert(T::KEY.to_string(), versioned::encode(&current)?);
+        self.store(chat_id, settings).await?;
+        self.notify(chat_id, T::KEY);
+        Ok(current)
+    }
+
+    /// Remove the setting `T` for `chat_id`, so subsequent reads return `T::default()`.
+    pub async fn reset<T: ChatSetting>(&self, chat_id: i64) -> Result<()> {
+        self.modify(chat_id, T::KEY, |settings| {
+            settings.remove(T::KEY);
+        })
+        .await
+    }
+
+    async fn modify<F>(&self, chat_id: i64, key: &str, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Map<String, Value>),
+    {
+        let _guard = self.write_lock.lock().await;
+        let mut settings = self.load(chat_id).await?;
+        f(&mut settings);
+        self.store(chat_id, settings).await?;
+        self.notify(chat_id, key);
+        Ok(())
+    }
+
+    async fn load(&self, chat_id: i64) -> Result<Map<String, Value>> {
+        match self.storage.get(NAMESPACE, chat_id).await? {
+            Some(Value::Object(settings)) => Ok(settings),
+            Some(_) => anyhow::bail!("Corrupt settings for chat {}", chat_id),
+            None => Ok(Map::new()),
+        }
+    }
+
+    async fn store(&self, chat_id: i64, settings: Map<String, Value>) -> Result<()> {
+        if settings.is_empty() {
+            self.storage.delete(NAMESPACE, chat_id).await
+        } else {
+            self.storage
+                .set(NAMESPACE, chat_id, Value::Object(settings))
+                .await
+        }
+    }
+
+    fn notify(&self, chat_id: i64, key: &str) {
+        // Sending only fails if there are no subscribers, which is fine.
+        _ = self.changes.send(SettingsChange {
+            chat_id,
+            key: key.to_string(),
+        });
+    }
+}
+
+/// `SettingsExport` is a chat's settings in a portable form, made by [`Settings::export`] and
+/// applied to another chat with [`Settings::import`], e.g. to copy a group's configuration to a
+/// new group. Each setting is kept as it's stored, with its version, so an export made by an
+/// older release is migrated when it's read back like any other setting.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # async fn copy(settings: Settings) -> anyhow::Result<()> {
+/// let json = settings.export(-1001).await?.to_json()?;
+/// let export = SettingsExport::from_json(&json)?;
+/// settings.import(-1002, &export).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SettingsExport {
+    /// The version of the export format.
+    pub format: u32,
+
+    /// The settings, by [`ChatSetting::KEY`].
+    pub settings: Map<String, Value>,
+}
+
+impl SettingsExport {
+    /// The current version of the export format.
+    pub const FORMAT: u32 = 1;
+
+    pub fn new(settings: Map<String, Value>) -> Self {
+        Self {
+            format: Self::FORMAT,
+            settings,
+        }
+    }
+
+    /// Parse an export, checking that it's in a format this release understands.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let export: SettingsExport = serde_json::from_str(json)
+            .map_err(|err| anyhow::anyhow!("Not a settings export: {}", err))?;
+        if export.format == 0 || export.format > Self::FORMAT {
+            anyhow::bail!("Unsupported settings export format {}", export.format);
+        }
+        Ok(export)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Leave the setting `T` out of the export.
+    pub fn without<T: ChatSetting>(self) -> Self {
+        self.without_key(T::KEY)
+    }
+
+    /// Leave the setting stored under `key` out of the export.
+    pub fn without_key(mut self, key: &str) -> Self {
+        self.settings.remove(key);
+        self
+    }
+
+    /// Returns the keys of the settings in the export.
+    pub fn keys(&self) -> Vec<String> {
+        self.settings.keys().cloned().collect()
+    }
+}
+
+impl Settings {
+    /// Export all of `chat_id`'s settings. See [`SettingsExport`].
+    pub async fn export(&self, chat_id: i64) -> Result<SettingsExport> {
+        Ok(SettingsExport::new(self.load(chat_id).await?))
+    }
+
+    /// Replace `chat_id`'s settings with the ones in `export`, and notify subscribers.
+    /// Settings that aren't in the export are left alone. Returns the keys of the settings that
+    /// were imported.
+    pub async fn import(&self, chat_id: i64, export: &SettingsExport) -> Result<Vec<String>> {
+        let _guard = self.write_lock.lock().await;
+        let mut settings = self.load(chat_id).await?;
+        for (key, value) in &export.settings {
+            settings.insert(key.clone(), value.clone());
+        }
+        self.store(chat_id, settings).await?;
+
+        let keys = export.keys();
+        for key in &keys {
+            self.notify(chat_id, key);
+        }
+        Ok(keys)
+    }
+}
+
+/// A user's settings are the settings of their private chat with the bot, whose ID is the
+/// user's ID.
+#[async_trait]
+impl UserData for Settings {
+    async fn export_user(&self, user_id: i64) -> Result<Value> {
+        let settings = self.load(user_id).await?;
+        Ok(if settings.is_empty() {
+            Value::Null
+        } else {
+            Value::Object(settings)
+        })
+    }
+
+    async fn delete_user(&self, user_id: i64) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let keys: Vec<String> = self.load(user_id).await?.keys().cloned().collect();
+        self.storage.delete(NAMESPACE, user_id).await?;
+        for key in keys {
+            self.notify(user_id, &key);
+        }
+        Ok(())
+    }
+}
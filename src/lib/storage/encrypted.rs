@@ -0,0 +1,165 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{Map, Value};
+
+use super::StateStorage;
+use crate::UserData;
+
+/// Prefix for encrypted values, so the format can change without breaking existing data.
+const PREFIX: &str = "enc:v1:";
+
+/// AES-GCM nonces are 96 bits.
+const NONCE_LEN: usize = 12;
+
+/// `EncryptedStorage` encrypts values at rest with AES-256-GCM before handing them to the
+/// wrapped [`StateStorage`]. Each value is stored as a string holding a random nonce and the
+/// ciphertext. The namespace and ID are authenticated along with the value, so ciphertexts
+/// can't be swapped between entries.
+///
+/// Namespaces and IDs are stored in the clear.
+///
+/// ```
+/// # use mobot::*;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// // Load the key from your secret store. Losing it means losing the data.
+/// let key = EncryptedStorage::<MemoryStorage>::generate_key();
+/// let settings = Settings::new(EncryptedStorage::new(MemoryStorage::new(), &key));
+/// # Ok(())
+/// # }
+/// ```
+pub struct EncryptedStorage<S: StateStorage> {
+    inner: S,
+    cipher: Aes256Gcm,
+}
+
+impl<S: StateStorage> EncryptedStorage<S> {
+    /// Wrap `inner`, encrypting values with the 256-bit `key`.
+    pub fn new(inner: S, key: &[u8; 32]) -> Self {
+        Self {
+            inner,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    /// Wrap `inner`, with a base64-encoded 256-bit key (e.g., from an environment variable).
+    pub fn from_base64_key(inner: S, key: &str) -> Result<Self> {
+        let key: [u8; 32] = BASE64
+            .decode(key.trim())?
+            .try_into()
+            .map_err(|_| anyhow!("Encryption key must be 32 bytes"))?;
+        Ok(Self::new(inner, &key))
+    }
+
+    /// Generate a new random key.
+    pub fn generate_key() -> [u8; 32] {
+        Aes256Gcm::generate_key(OsRng).into()
+    }
+
+    /// Return the wrapped storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn encrypt(&self, namespace: &str, id: i64, value: &Value) -> Result<Value> {
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &serde_json::to_vec(value)?,
+                    aad: &aad(namespace, id),
+                },
+            )
+            .map_err(|_| anyhow!("Can't encrypt {}/{}", namespace, id))?;
+
+        let mut data = nonce.to_vec();
+        data.extend(ciphertext);
+        Ok(Value::String(format!("{}{}", PREFIX, BASE64.encode(data))))
+    }
+
+    fn decrypt(&self, namespace: &str, id: i64, value: Value) -> Result<Value> {
+        let Some(encoded) = value.as_str().and_then(|s| s.strip_prefix(PREFIX)) else {
+            bail!("Value for {}/{} is not encrypted", namespace, id);
+        };
+
+        let data = BASE64.decode(encoded)?;
+        if data.len() < NONCE_LEN {
+            bail!("Encrypted value for {}/{} is truncated", namespace, id);
+        }
+
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad(namespace, id),
+                },
+            )
+            .map_err(|_| {
+                anyhow!(
+                    "Can't decrypt {}/{}: wrong key or corrupt data",
+                    namespace,
+                    id
+                )
+            })?;
+
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+}
+
+fn aad(namespace: &str, id: i64) -> Vec<u8> {
+    format!("{}/{}", namespace, id).into_bytes()
+}
+
+#[async_trait]
+impl<S: StateStorage> StateStorage for EncryptedStorage<S> {
+    async fn get(&self, namespace: &str, id: i64) -> Result<Option<Value>> {
+        match self.inner.get(namespace, id).await? {
+            Some(value) => Ok(Some(self.decrypt(namespace, id, value)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn set(&self, namespace: &str, id: i64, value: Value) -> Result<()> {
+        let value = self.encrypt(namespace, id, &value)?;
+        self.inner.set(namespace, id, value).await
+    }
+
+    async fn delete(&self, namespace: &str, id: i64) -> Result<()> {
+        self.inner.delete(namespace, id).await
+    }
+}
+
+/// Exports and deletes what the wrapped store holds about a user, decrypted. The wrapped
+/// store's export must map namespaces to the values stored under the user's ID, like
+/// [`super::MemoryStorage`]'s.
+#[async_trait]
+impl<S: StateStorage + UserData> UserData for EncryptedStorage<S> {
+    async fn export_user(&self, user_id: i64) -> Result<Value> {
+        let Value::Object(data) = self.inner.export_user(user_id).await? else {
+            return Ok(Value::Null);
+        };
+
+        let data = data
+            .into_iter()
+            .map(|(namespace, value)| {
+                let value = self.decrypt(&namespace, user_id, value)?;
+                Ok((namespace, value))
+            })
+            .collect::<Result<Map<_, _>>>()?;
+        Ok(Value::Object(data))
+    }
+
+    async fn delete_user(&self, user_id: i64) -> Result<()> {
+        self.inner.delete_user(user_id).await
+    }
+}
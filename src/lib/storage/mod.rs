@@ -0,0 +1,129 @@
+/// Pluggable persistence for bot state. [`StateStorage`] is a small key-value interface
+/// that framework components (e.g., [`crate::Settings`]) use to persist JSON values, so
+/// that the backing store can be swapped without touching the components.
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::RwLock;
+
+pub mod encrypted;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use encrypted::EncryptedStorage;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresStorage;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
+
+/// `StateStorage` stores JSON values keyed by a namespace and a numeric ID (typically a
+/// chat ID or user ID).
+#[async_trait]
+pub trait StateStorage: Send + Sync {
+    /// Return the value stored under `namespace`/`id`, if any.
+    async fn get(&self, namespace: &str, id: i64) -> Result<Option<Value>>;
+
+    /// Store `value` under `namespace`/`id`, replacing any existing value.
+    async fn set(&self, namespace: &str, id: i64, value: Value) -> Result<()>;
+
+    /// Remove the value stored under `namespace`/`id`. Removing a missing value is not an error.
+    async fn delete(&self, namespace: &str, id: i64) -> Result<()>;
+}
+
+/// `MemoryStorage` is an in-process [`StateStorage`]. Values are lost when the bot exits. Clones
+/// share the same underlying map.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    data: Arc<RwLock<HashMap<(String, i64), Value>>>,
+}
+
+impl MemoryStorage {
+    /// Create a new, empty `MemoryStorage`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return a copy of every stored value, keyed by namespace and ID.
+    pub async fn entries(&self) -> Vec<((String, i64), Value)> {
+        self.data
+            .read()
+            .await
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect()
+    }
+
+    /// Keep only the values for which `f(namespace, id)` returns true.
+    pub async fn retain(&self, mut f: impl FnMut(&str, i64) -> bool) {
+        self.data
+            .write()
+            .await
+            .retain(|(namespace, id), _| f(namespace, *id));
+    }
+}
+
+#[async_trait]
+impl StateStorage for MemoryStorage {
+    async fn get(&self, namespace: &str, id: i64) -> Result<Option<Value>> {
+        Ok(self
+            .data
+            .read()
+            .await
+            .get(&(namespace.to_string(), id))
+            .cloned())
+    }
+
+    async fn set(&self, namespace: &str, id: i64, value: Value) -> Result<()> {
+        self.data
+            .write()
+            .await
+            .insert((namespace.to_string(), id), value);
+        Ok(())
+    }
+
+    async fn delete(&self, namespace: &str, id: i64) -> Result<()> {
+        self.data.write().await.remove(&(namespace.to_string(), id));
+        Ok(())
+    }
+}
+
+/// `NamespacedStorage` keeps its values apart from other users of the wrapped
+/// [`StateStorage`], by prefixing every namespace with `prefix`. Multi-tenant routers use it
+/// to give each tenant its own state (see [`crate::tenants`]).
+#[derive(Clone)]
+pub struct NamespacedStorage {
+    inner: Arc<dyn StateStorage>,
+    prefix: String,
+}
+
+impl NamespacedStorage {
+    pub fn new(inner: Arc<dyn StateStorage>, prefix: impl Into<String>) -> Self {
+        Self {
+            inner,
+            prefix: prefix.into(),
+        }
+    }
+
+    fn namespace(&self, namespace: &str) -> String {
+        format!("{}/{}", self.prefix, namespace)
+    }
+}
+
+#[async_trait]
+impl StateStorage for NamespacedStorage {
+    async fn get(&self, namespace: &str, id: i64) -> Result<Option<Value>> {
+        self.inner.get(&self.namespace(namespace), id).await
+    }
+
+    async fn set(&self, namespace: &str, id: i64, value: Value) -> Result<()> {
+        self.inner.set(&self.namespace(namespace), id, value).await
+    }
+
+    async fn delete(&self, namespace: &str, id: i64) -> Result<()> {
+        self.inner.delete(&self.namespace(namespace), id).await
+    }
+}
@@ -0,0 +1,129 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use sqlx::{migrate::Migrator, postgres::PgPool};
+
+use super::StateStorage;
+use crate::user_data::UserData;
+
+/// `PostgresStorage` is a [`StateStorage`] backed by Postgres, for teams that already run it.
+/// Values are stored as `JSONB` in the `mobot_state` table.
+///
+/// The schema ships with the crate (in `migrations/postgres`) and is applied by
+/// [`PostgresStorage::connect`] or [`PostgresStorage::migrate`]. Migrations are tracked in
+/// sqlx's `_sqlx_migrations` table, and can share it with the application's own sqlx
+/// migrations.
+///
+/// Requires the `postgres` feature.
+///
+/// ```no_run
+/// # use mobot::{storage::PostgresStorage, *};
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let storage = PostgresStorage::connect(&std::env::var("DATABASE_URL")?).await?;
+/// let settings = Settings::new(storage);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    /// Connect to the database at `url`, and apply any pending migrations.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let storage = Self::from_pool(PgPool::connect(url).await?);
+        storage.migrate().await?;
+        Ok(storage)
+    }
+
+    /// Use an existing connection pool. Migrations are not applied; call
+    /// [`PostgresStorage::migrate`] if needed.
+    pub fn from_pool(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Return the connection pool.
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Apply the crate's pending migrations.
+    pub async fn migrate(&self) -> Result<()> {
+        Self::migrator().run(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Return the crate's migrations. They ignore migrations they don't know about, so that
+    /// they can share a database with the application's own.
+    pub fn migrator() -> Migrator {
+        let mut migrator = sqlx::migrate!("./migrations/postgres");
+        migrator.set_ignore_missing(true);
+        migrator
+    }
+}
+
+#[async_trait]
+impl StateStorage for PostgresStorage {
+    async fn get(&self, namespace: &str, id: i64) -> Result<Option<Value>> {
+        let value: Option<(Value,)> =
+            sqlx::query_as("SELECT value FROM mobot_state WHERE namespace = $1 AND id = $2")
+                .bind(namespace)
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(value.map(|(v,)| v))
+    }
+
+    async fn set(&self, namespace: &str, id: i64, value: Value) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO mobot_state (namespace, id, value) VALUES ($1, $2, $3)
+             ON CONFLICT (namespace, id) DO UPDATE SET value = excluded.value, updated_at = now()",
+        )
+        .bind(namespace)
+        .bind(id)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, namespace: &str, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM mobot_state WHERE namespace = $1 AND id = $2")
+            .bind(namespace)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Like [`super::MemoryStorage`], `PostgresStorage` exports and deletes the values stored
+/// under `user_id` in every namespace.
+#[async_trait]
+impl UserData for PostgresStorage {
+    async fn export_user(&self, user_id: i64) -> Result<Value> {
+        let rows: Vec<(String, Value)> = sqlx::query_as(
+            "SELECT namespace, value FROM mobot_state WHERE id = $1 ORDER BY namespace",
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        if rows.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        Ok(Value::Object(rows.into_iter().collect::<Map<_, _>>()))
+    }
+
+    async fn delete_user(&self, user_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM mobot_state WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
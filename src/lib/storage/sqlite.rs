@@ -0,0 +1,166 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::{Map, Value};
+
+use super::StateStorage;
+use crate::user_data::UserData;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS mobot_state (
+        namespace TEXT NOT NULL,
+        id INTEGER NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (namespace, id)
+    );
+    CREATE INDEX IF NOT EXISTS mobot_state_id ON mobot_state (id);
+";
+
+/// `SqliteStorage` is a [`StateStorage`] backed by an embedded SQLite database, for bots that
+/// need their state to survive restarts without running a database server. Values are stored
+/// as JSON text in a single `mobot_state` table, which is created if it doesn't exist.
+///
+/// SQLite calls are blocking, so they run on tokio's blocking thread pool. Clones share the
+/// same connection.
+///
+/// Requires the `sqlite` feature.
+///
+/// ```no_run
+/// # use mobot::{storage::SqliteStorage, *};
+/// # fn main() -> anyhow::Result<()> {
+/// let settings = Settings::new(SqliteStorage::open("bot.db")?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct SqliteStorage {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    /// Open (or create) the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+
+        // Write-ahead logging lets readers proceed while a write is in progress.
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::from_connection(conn)
+    }
+
+    /// Create a database that lives only in memory. Useful for tests.
+    pub fn memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Use an existing connection, creating the `mobot_state` table if needed.
+    pub fn from_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Run `f` with the connection on the blocking thread pool.
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T> + Send + 'static,
+    {
+        let conn = Arc::clone(&self.conn);
+        tokio::task::spawn_blocking(move || {
+            let conn = conn
+                .lock()
+                .map_err(|_| anyhow!("SQLite connection poisoned"))?;
+            f(&conn)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl StateStorage for SqliteStorage {
+    async fn get(&self, namespace: &str, id: i64) -> Result<Option<Value>> {
+        let namespace = namespace.to_string();
+        let value: Option<String> = self
+            .with_conn(move |conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT value FROM mobot_state WHERE namespace = ?1 AND id = ?2",
+                        params![namespace, id],
+                        |row| row.get(0),
+                    )
+                    .optional()?)
+            })
+            .await?;
+
+        Ok(value.map(|v| serde_json::from_str(&v)).transpose()?)
+    }
+
+    async fn set(&self, namespace: &str, id: i64, value: Value) -> Result<()> {
+        let namespace = namespace.to_string();
+        let value = serde_json::to_string(&value)?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT INTO mobot_state (namespace, id, value) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (namespace, id) DO UPDATE SET value = excluded.value",
+                params![namespace, id, value],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete(&self, namespace: &str, id: i64) -> Result<()> {
+        let namespace = namespace.to_string();
+        self.with_conn(move |conn| {
+            conn.execute(
+                "DELETE FROM mobot_state WHERE namespace = ?1 AND id = ?2",
+                params![namespace, id],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+}
+
+/// Like [`super::MemoryStorage`], `SqliteStorage` exports and deletes the values stored under
+/// `user_id` in every namespace.
+#[async_trait]
+impl UserData for SqliteStorage {
+    async fn export_user(&self, user_id: i64) -> Result<Value> {
+        let rows: Vec<(String, String)> = self
+            .with_conn(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT namespace, value FROM mobot_state WHERE id = ?1 ORDER BY namespace",
+                )?;
+                let rows = stmt
+                    .query_map(params![user_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<rusqlite::Result<_>>()?;
+                Ok(rows)
+            })
+            .await?;
+
+        if rows.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        let mut data = Map::new();
+        for (namespace, value) in rows {
+            data.insert(namespace, serde_json::from_str(&value)?);
+        }
+        Ok(Value::Object(data))
+    }
+
+    async fn delete_user(&self, user_id: i64) -> Result<()> {
+        self.with_conn(move |conn| {
+            conn.execute("DELETE FROM mobot_state WHERE id = ?1", params![user_id])?;
+            Ok(())
+        })
+        .await
+    }
+}
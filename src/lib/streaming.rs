@@ -0,0 +1,166 @@
+/// Incrementally streamed messages. [`StreamingMessage`] shows text as it's produced (e.g.,
+/// tokens from an LLM) by editing a single message in place, throttled to stay clear of
+/// Telegram's flood limits.
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::{api, Event};
+
+/// Telegram's maximum message length, in characters.
+const MAX_MESSAGE_LEN: usize = 4096;
+
+/// `StreamingMessage` accumulates text with [`StreamingMessage::push`], and shows it in the
+/// chat by sending a message and then editing it at most once every `interval`. Text that
+/// outgrows a single message continues in a new one. Call [`StreamingMessage::finish`] to
+/// flush whatever is left.
+///
+/// ```no_run
+/// # use mobot::*;
+/// async fn handle(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     let mut stream = StreamingMessage::start(&e)?;
+///     for word in ["Streaming", " one", " word", " at", " a", " time."] {
+///         stream.push(word).await?;
+///     }
+///     stream.finish().await?;
+///     Ok(Action::Done)
+/// }
+/// ```
+pub struct StreamingMessage {
+    api: Arc<api::API>,
+    chat_id: i64,
+
+    /// Minimum time between edits.
+    interval: Duration,
+
+    /// The message being streamed into, once it's been sent.
+    message_id: Option<i64>,
+
+    /// Text of the current message, and how much of it is shown in the chat.
+    text: String,
+    shown: String,
+
+    /// IDs of all messages sent so far, in order, and the text last shown in each.
+    message_ids: Vec<i64>,
+    texts: Vec<String>,
+
+    /// When the message was last edited, by the API's clock.
+    last_edit: Option<DateTime<Utc>>,
+}
+
+impl StreamingMessage {
+    /// Stream into the chat in `e`. Nothing is sent until text is pushed.
+    pub fn start(e: &Event) -> Result<Self> {
+        Ok(Self::new(Arc::clone(&e.api), e.update.chat_id()?))
+    }
+
+    /// Stream into `chat_id`. Nothing is sent until text is pushed.
+    pub fn new(api: Arc<api::API>, chat_id: i64) -> Self {
+        Self {
+            api,
+            chat_id,
+            interval: Duration::from_secs(1),
+            message_id: None,
+            text: String::new(),
+            shown: String::new(),
+            message_ids: vec![],
+            texts: vec![],
+            last_edit: None,
+        }
+    }
+
+    /// Set the minimum time between edits. Defaults to one second.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Returns the IDs of the messages sent so far.
+    pub fn message_ids(&self) -> &[i64] {
+        &self.message_ids
+    }
+
+    /// Append `delta` to the message. The chat is updated if `interval` has passed since the
+    /// last update.
+    pub async fn push(&mut self, delta: &str) -> Result<()> {
+        self.text.push_str(delta);
+
+        // Finish off full messages, and carry the rest over into a new one.
+        while self.text.chars().count() > MAX_MESSAGE_LEN {
+            let split = self
+                .text
+                .char_indices()
+                .nth(MAX_MESSAGE_LEN)
+                .map(|(i, _)| i)
+                .unwrap_or(self.text.len());
+            let rest = self.text.split_off(split);
+            self.flush().await?;
+
+            self.text = rest;
+            self.message_id = None;
+            self.shown.clear();
+        }
+
+        if self
+            .last_edit
+            .is_none_or(|last_edit| self.api.clock().elapsed(last_edit) >= self.interval)
+        {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Show all the text pushed so far. Returns the IDs of all messages sent.
+    pub async fn finish(mut self) -> Result<Vec<i64>> {
+        self.flush().await?;
+        Ok(self.message_ids)
+    }
+
+    /// Like [`StreamingMessage::finish`], but returns the ID of each message sent along with
+    /// its text.
+    pub async fn finish_with_texts(mut self) -> Result<Vec<(i64, String)>> {
+        self.flush().await?;
+        Ok(self.message_ids.into_iter().zip(self.texts).collect())
+    }
+
+    /// Show the current text, sending the message if it hasn't been sent yet.
+    async fn flush(&mut self) -> Result<()> {
+        // Telegram rejects empty messages, and edits that don't change anything.
+        if self.text.trim().is_empty() || self.text == self.shown {
+            return Ok(());
+        }
+
+        match self.message_id {
+            None => {
+                let message = self
+                    .api
+                    .send_message(&api::SendMessageRequest::new(
+                        self.chat_id,
+                        self.text.clone(),
+                    ))
+                    .await?;
+                self.message_id = Some(message.message_id);
+                self.message_ids.push(message.message_id);
+                self.texts.push(self.text.clone());
+            }
+            Some(message_id) => {
+                self.api
+                    .edit_message_text(&api::EditMessageTextRequest {
+                        base: api::EditMessageBase::new()
+                            .with_chat_id(self.chat_id)
+                            .with_message_id(message_id),
+                        text: self.text.clone(),
+                    })
+                    .await?;
+                if let Some(text) = self.texts.last_mut() {
+                    *text = self.text.clone();
+                }
+            }
+        }
+
+        self.shown = self.text.clone();
+        self.last_edit = Some(self.api.clock().now());
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+/// Background tasks tied to the router's lifecycle. Handlers can spawn fire-and-forget work
+/// with [`crate::Event::spawn`]. When the router shuts down, tasks are signalled to stop (see
+/// [`Tasks::cancelled`]), given a grace period to finish, and then cancelled.
+use std::{future::Future, time::Duration};
+
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+/// `Tasks` tracks background tasks spawned by handlers. Clones share the same set of tasks.
+#[derive(Debug, Clone, Default)]
+pub struct Tasks {
+    tracker: TaskTracker,
+
+    /// Cancelled when shutdown starts. Tasks can watch this to wrap up early.
+    shutdown: CancellationToken,
+
+    /// Cancelled when the grace period expires. Tasks still running are dropped.
+    abort: CancellationToken,
+}
+
+impl Tasks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn `task` in the background. Errors returned by the task are logged. The task is
+    /// dropped if it's still running when the shutdown grace period expires.
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let abort = self.abort.clone();
+        self.tracker.spawn(async move {
+            tokio::select! {
+                result = task => {
+                    if let Err(err) = result {
+                        error!("Background task failed: {}", err);
+                    }
+                }
+                _ = abort.cancelled() => {
+                    warn!("Background task cancelled at shutdown");
+                }
+            }
+        });
+    }
+
+    /// Returns the number of tasks still running.
+    pub fn len(&self) -> usize {
+        self.tracker.len()
+    }
+
+    /// Returns true if no tasks are running.
+    pub fn is_empty(&self) -> bool {
+        self.tracker.is_empty()
+    }
+
+    /// Returns true once shutdown has started.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// Completes when shutdown starts. Long-running tasks can `select!` on this to stop
+    /// cleanly.
+    pub async fn cancelled(&self) {
+        self.shutdown.cancelled().await
+    }
+
+    /// Signal all tasks to stop, wait up to `grace_period` for them to finish, and cancel any
+    /// that are still running.
+    pub async fn shutdown(&self, grace_period: Duration) {
+        self.shutdown.cancel();
+        self.tracker.close();
+
+        if !self.tracker.is_empty() {
+            info!("Waiting for {} background tasks...", self.tracker.len());
+        }
+
+        if tokio::time::timeout(grace_period, self.tracker.wait())
+            .await
+            .is_err()
+        {
+            self.abort.cancel();
+            self.tracker.wait().await;
+        }
+    }
+}
@@ -0,0 +1,139 @@
+/// Multi-tenant routing, for platforms that host many customer bots in one process. Each
+/// tenant is a bot with its own API token. Telegram delivers every tenant's updates to the
+/// router's webhook server, each at its own path, and a [`TenantResolver`] maps the path to the
+/// tenant. Handlers for a tenant's updates get an [`crate::Event`] whose `api` uses the
+/// tenant's token, and whose settings and chat state are kept apart from other tenants'.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use mobot::tenants::{Tenant, Tenants};
+/// # use mobot::webhook::WebhookConfig;
+/// # #[tokio::main]
+/// # async fn main() -> anyhow::Result<()> {
+/// let webhook = WebhookConfig::new("https://bots.example.com/telegram", ([0, 0, 0, 0], 8080));
+/// let tenants = Tenants::new();
+///
+/// // E.g., for each customer bot in the platform's database:
+/// let tenant = Tenant::new("acme", Client::new(std::env::var("ACME_TOKEN")?))
+///     .with_update_limiter(RateLimiter::per_second(10));
+/// tenant.set_webhook(&webhook).await?;
+/// tenants.add(tenant);
+///
+/// let mut router = Router::<()>::for_tenants(tenants).with_webhook(webhook);
+/// router.add_route(Route::Default, |_, _| async move {
+///     Ok(Action::ReplyText("Hello from your bot!".into()))
+/// });
+/// router.start().await;
+/// # Ok(())
+/// # }
+/// ```
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::{api::API, webhook::WebhookConfig, Client, RateLimiter};
+
+/// `Tenant` is one bot served by a multi-tenant router.
+#[derive(Clone)]
+pub struct Tenant {
+    /// Identifies the tenant. It's the last segment of the tenant's webhook path, and the
+    /// namespace of its state, so it must be unique, and shouldn't contain `/`.
+    pub id: String,
+
+    /// The API, with the tenant's token. Use [`Client::with_rate_limiter`] on the tenant's
+    /// client to space out its requests.
+    pub api: Arc<API>,
+
+    /// If set, the tenant's updates wait their turn here before they're handled, so a busy
+    /// tenant can't take over the router. Other tenants' updates are handled in the meantime.
+    pub update_limiter: Option<RateLimiter>,
+}
+
+impl Tenant {
+    pub fn new(id: impl Into<String>, client: Client) -> Self {
+        Self {
+            id: id.into(),
+            api: Arc::new(API::new(client)),
+            update_limiter: None,
+        }
+    }
+
+    /// Handle at most as many of the tenant's updates as `limiter` lets through.
+    pub fn with_update_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.update_limiter = Some(limiter);
+        self
+    }
+
+    /// Returns the URL Telegram delivers the tenant's updates to: the webhook's URL, followed
+    /// by the tenant's ID.
+    pub fn webhook_url(&self, config: &WebhookConfig) -> String {
+        format!("{}/{}", config.url.trim_end_matches('/'), self.id)
+    }
+
+    /// Register the tenant's webhook (see [`Tenant::webhook_url`]) with Telegram, with the
+    /// webhook's current secret token.
+    pub async fn set_webhook(&self, config: &WebhookConfig) -> Result<bool> {
+        let mut req = config.request();
+        req.url = self.webhook_url(config);
+        self.api.set_webhook(&req).await
+    }
+}
+
+impl std::fmt::Debug for Tenant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tenant").field("id", &self.id).finish()
+    }
+}
+
+/// `TenantResolver` finds the tenant an update belongs to, from the path it was posted to.
+/// Implement it to look tenants up in a database; [`Tenants`] is a simple registry.
+#[async_trait]
+pub trait TenantResolver: Send + Sync {
+    /// Returns the tenant for webhook requests to `path`, or `None` if there isn't one, in
+    /// which case the request is rejected.
+    async fn resolve(&self, path: &str) -> Result<Option<Tenant>>;
+}
+
+/// `Tenants` is a [`TenantResolver`] with a fixed set of tenants, resolved by the last segment
+/// of the path. Clones share the same tenants, so they can be added and removed while the
+/// router is running.
+#[derive(Debug, Clone, Default)]
+pub struct Tenants {
+    tenants: Arc<RwLock<HashMap<String, Tenant>>>,
+}
+
+impl Tenants {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `tenant`, replacing any tenant with the same ID.
+    pub fn add(&self, tenant: Tenant) {
+        self.tenants
+            .write()
+            .unwrap()
+            .insert(tenant.id.clone(), tenant);
+    }
+
+    /// Remove the tenant `id`. Its updates are rejected from now on.
+    pub fn remove(&self, id: &str) -> Option<Tenant> {
+        self.tenants.write().unwrap().remove(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Tenant> {
+        self.tenants.read().unwrap().get(id).cloned()
+    }
+}
+
+#[async_trait]
+impl TenantResolver for Tenants {
+    async fn resolve(&self, path: &str) -> Result<Option<Tenant>> {
+        let path = path.split('?').next().unwrap_or_default();
+        let id = path.trim_end_matches('/').rsplit('/').next();
+        Ok(id.and_then(|id| self.get(id)))
+    }
+}
@@ -10,25 +10,42 @@ pub enum Update {
     ChannelPost(api::Message),
     EditedChannelPost(api::Message),
     CallbackQuery(api::CallbackQuery),
+    #[cfg(feature = "inline")]
     InlineQuery(api::InlineQuery),
+
+    /// All the messages of a media group (album), in order. Only delivered if album
+    /// aggregation is enabled with [`crate::Router::with_album_aggregation`].
+    Album(Vec<api::Message>),
+
+    /// A user changed their reaction to a message.
+    MessageReaction(api::MessageReactionUpdated),
+
+    /// A user asked to join the chat.
+    ChatJoinRequest(api::ChatJoinRequest),
     Unknown,
 }
 
 impl From<api::Update> for Update {
     fn from(update: api::Update) -> Self {
-        if let Some(ref m) = update.message {
-            Self::Message(m.clone())
-        } else if let Some(ref m) = update.edited_message {
-            Self::EditedMessage(m.clone())
-        } else if let Some(ref m) = update.channel_post {
-            Self::ChannelPost(m.clone())
-        } else if let Some(ref m) = update.edited_channel_post {
-            Self::EditedChannelPost(m.clone())
-        } else if let Some(ref c) = update.callback_query {
-            Self::CallbackQuery(c.clone())
-        } else if let Some(ref c) = update.inline_query {
-            Self::InlineQuery(c.clone())
+        if let Some(m) = update.message {
+            Self::Message(m)
+        } else if let Some(m) = update.edited_message {
+            Self::EditedMessage(m)
+        } else if let Some(m) = update.channel_post {
+            Self::ChannelPost(m)
+        } else if let Some(m) = update.edited_channel_post {
+            Self::EditedChannelPost(m)
+        } else if let Some(c) = update.callback_query {
+            Self::CallbackQuery(c)
+        } else if let Some(r) = update.message_reaction {
+            Self::MessageReaction(r)
+        } else if let Some(r) = update.chat_join_request {
+            Self::ChatJoinRequest(r)
         } else {
+            #[cfg(feature = "inline")]
+            if let Some(c) = update.inline_query {
+                return Self::InlineQuery(c);
+            }
             Self::Unknown
         }
     }
@@ -44,7 +61,10 @@ impl From<Update> for api::Message {
             ChannelPost(msg) => msg,
             EditedChannelPost(msg) => msg,
             CallbackQuery(query) => query.message.unwrap(),
-            InlineQuery(_) | Unknown => {
+            Album(mut messages) => messages.remove(0),
+            #[cfg(feature = "inline")]
+            InlineQuery(_) => panic!("Bad Message::Unknown"),
+            MessageReaction(_) | ChatJoinRequest(_) | Unknown => {
                 panic!("Bad Message::Unknown")
             }
         }
@@ -70,7 +90,26 @@ impl fmt::Display for Update {
             ChannelPost(msg) => write!(f, "{}", msg.text.clone().unwrap()),
             EditedChannelPost(msg) => write!(f, "{}", msg.text.clone().unwrap()),
             CallbackQuery(query) => write!(f, "{}", query.data.clone().unwrap()),
+            #[cfg(feature = "inline")]
             InlineQuery(query) => write!(f, "{}", query.query.clone()),
+            Album(messages) => write!(
+                f,
+                "{}",
+                messages
+                    .iter()
+                    .find_map(|msg| msg.caption.clone())
+                    .unwrap_or_default()
+            ),
+            MessageReaction(reaction) => write!(
+                f,
+                "{}",
+                reaction
+                    .new_reaction
+                    .iter()
+                    .filter_map(|r| r.emoji.clone())
+                    .collect::<String>()
+            ),
+            ChatJoinRequest(request) => write!(f, "{}", request.bio.clone().unwrap_or_default()),
             Unknown => {
                 panic!("Bad Message::Unknown")
             }
@@ -111,6 +150,31 @@ impl Update {
         .ok_or(anyhow!("message is not an EditedPost"))
     }
 
+    /// Return the messages of an album, in order.
+    pub fn get_album(&self) -> anyhow::Result<&Vec<api::Message>> {
+        match self {
+            Update::Album(messages) => Some(messages),
+            _ => None,
+        }
+        .ok_or(anyhow!("message is not an Album"))
+    }
+
+    pub fn get_message_reaction(&self) -> anyhow::Result<&api::MessageReactionUpdated> {
+        match self {
+            Update::MessageReaction(reaction) => Some(reaction),
+            _ => None,
+        }
+        .ok_or(anyhow!("message is not a MessageReaction"))
+    }
+
+    pub fn get_chat_join_request(&self) -> anyhow::Result<&api::ChatJoinRequest> {
+        match self {
+            Update::ChatJoinRequest(request) => Some(request),
+            _ => None,
+        }
+        .ok_or(anyhow!("message is not a ChatJoinRequest"))
+    }
+
     pub fn get_callback_query(&self) -> anyhow::Result<&api::CallbackQuery> {
         match self {
             Update::CallbackQuery(query) => Some(query),
@@ -119,6 +183,15 @@ impl Update {
         .ok_or(anyhow!("message is not a CallbackQuery"))
     }
 
+    #[cfg(feature = "inline")]
+    pub fn get_inline_query(&self) -> anyhow::Result<&api::InlineQuery> {
+        match self {
+            Update::InlineQuery(query) => Some(query),
+            _ => None,
+        }
+        .ok_or(anyhow!("message is not an InlineQuery"))
+    }
+
     pub fn get_message_or_post(&self) -> anyhow::Result<&api::Message> {
         match self {
             Update::Message(msg) => Some(msg),
@@ -154,18 +227,33 @@ impl Update {
             Update::EditedMessage(msg) => Some(msg),
             Update::ChannelPost(msg) => Some(msg),
             Update::EditedChannelPost(msg) => Some(msg),
-            Update::CallbackQuery(query) => Some(query.message.as_ref().unwrap()),
-            Update::InlineQuery(_) | Update::Unknown => None,
+            Update::CallbackQuery(query) => query.message.as_ref(),
+            Update::Album(messages) => messages.first(),
+            #[cfg(feature = "inline")]
+            Update::InlineQuery(_) => None,
+            Update::MessageReaction(_) | Update::ChatJoinRequest(_) | Update::Unknown => None,
         }
         .ok_or(anyhow!("message is not a api::Message"))
     }
 
+    /// The chat the update happened in.
+    pub fn chat(&self) -> anyhow::Result<&api::Chat> {
+        match self {
+            Update::MessageReaction(reaction) => Ok(&reaction.chat),
+            Update::ChatJoinRequest(request) => Ok(&request.chat),
+            _ => self.message().map(|msg| &msg.chat),
+        }
+    }
+
     pub fn chat_id(&self) -> anyhow::Result<i64> {
-        self.message().map(|msg| msg.chat.id)
+        self.chat().map(|chat| chat.id)
     }
 
     pub fn message_id(&self) -> anyhow::Result<i64> {
-        self.message().map(|msg| msg.message_id)
+        match self {
+            Update::MessageReaction(reaction) => Ok(reaction.message_id),
+            _ => self.message().map(|msg| msg.message_id),
+        }
     }
 
     pub fn query_id(&self) -> anyhow::Result<&str> {
@@ -206,6 +294,9 @@ impl Update {
                 msg.from.as_ref()
             }
             CallbackQuery(query) => Some(&query.from),
+            Album(messages) => messages.first().and_then(|msg| msg.from.as_ref()),
+            MessageReaction(reaction) => reaction.user.as_ref(),
+            ChatJoinRequest(request) => Some(&request.from),
             _ => None,
         }
         .ok_or(anyhow!("message has no user"))
@@ -0,0 +1,122 @@
+/// Uniform access to the data a bot keeps about a user, for answering data protection requests
+/// (e.g., GDPR's right of access and right to erasure). Each store that holds user data
+/// implements [`UserData`], and [`UserDataStores`] fans export and delete requests out to all of
+/// them.
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+
+use crate::storage::MemoryStorage;
+
+/// Implement `UserData` for every store that keeps data about users.
+#[async_trait]
+pub trait UserData: Send + Sync {
+    /// Return everything this store holds about `user_id`, or [`Value::Null`] if it holds
+    /// nothing.
+    async fn export_user(&self, user_id: i64) -> Result<Value>;
+
+    /// Remove everything this store holds about `user_id`. Deleting a user with no data is not
+    /// an error.
+    async fn delete_user(&self, user_id: i64) -> Result<()>;
+}
+
+/// `MemoryStorage` exports and deletes the values stored under `user_id` in every namespace.
+#[async_trait]
+impl UserData for MemoryStorage {
+    async fn export_user(&self, user_id: i64) -> Result<Value> {
+        let data: Map<String, Value> = self
+            .entries()
+            .await
+            .into_iter()
+            .filter(|((_, id), _)| *id == user_id)
+            .map(|((namespace, _), value)| (namespace, value))
+            .collect();
+
+        Ok(if data.is_empty() {
+            Value::Null
+        } else {
+            Value::Object(data)
+        })
+    }
+
+    async fn delete_user(&self, user_id: i64) -> Result<()> {
+        self.retain(|_, id| id != user_id).await;
+        Ok(())
+    }
+}
+
+/// `UserDataStores` is a named set of [`UserData`] stores. Clones share the same stores. The
+/// router keeps one with its own stores registered (see [`crate::Router::user_data`]), and
+/// passes it to every handler in [`crate::Event::user_data`].
+///
+/// ```no_run
+/// # use mobot::*;
+/// async fn forget_me(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+///     e.user_data.delete(e.update.from_user()?.id).await?;
+///     Ok(Action::ReplyText("All your data has been deleted.".into()))
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct UserDataStores {
+    stores: Vec<(String, Arc<dyn UserData>)>,
+}
+
+impl UserDataStores {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `store` under `name`. Its data appears under `name` in exports.
+    pub fn with_store(self, name: impl Into<String>, store: impl UserData + 'static) -> Self {
+        self.with_arc(name, Arc::new(store))
+    }
+
+    /// Add a shared `store` under `name`.
+    pub fn with_arc(mut self, name: impl Into<String>, store: Arc<dyn UserData>) -> Self {
+        self.stores.push((name.into(), store));
+        self
+    }
+
+    /// The names of the registered stores, in registration order.
+    pub fn names(&self) -> Vec<&str> {
+        self.stores.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    pub(crate) fn stores(&self) -> impl Iterator<Item = (String, Arc<dyn UserData>)> + '_ {
+        self.stores
+            .iter()
+            .map(|(name, store)| (name.clone(), Arc::clone(store)))
+    }
+
+    /// Export everything held about `user_id`, as a JSON object keyed by store name. Stores
+    /// with no data for the user are left out.
+    pub async fn export(&self, user_id: i64) -> Result<Value> {
+        let mut export = Map::new();
+        for (name, store) in &self.stores {
+            let data = store.export_user(user_id).await?;
+            if !data.is_null() {
+                export.insert(name.clone(), data);
+            }
+        }
+
+        Ok(Value::Object(export))
+    }
+
+    /// Delete everything held about `user_id` from every store. A failing store doesn't stop
+    /// the others from being cleared; the first error is returned once all stores have run.
+    pub async fn delete(&self, user_id: i64) -> Result<()> {
+        let mut result = Ok(());
+        for (name, store) in &self.stores {
+            if let Err(err) = store.delete_user(user_id).await {
+                error!("Failed to delete user data from {}: {}", name, err);
+                if result.is_ok() {
+                    result = Err(err.context(format!("deleting user data from {}", name)));
+                }
+            }
+        }
+
+        result
+    }
+}
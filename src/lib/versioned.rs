@@ -0,0 +1,116 @@
+/// Versioned serialization for persisted state. Values are stored with the version of the type
+/// that wrote them, and older data is passed through a migration hook when it's read back, so a
+/// bot's state types can evolve between releases without corrupting what's already persisted.
+use anyhow::{bail, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+
+/// Envelope field holding the version. Data written without an envelope is version 0.
+const VERSION_KEY: &str = "__mobot_version";
+
+/// Envelope field holding the serialized value.
+const VALUE_KEY: &str = "value";
+
+/// Implement `Versioned` for state that's persisted across releases. `VERSION` starts at 0; bump
+/// it whenever the serialized form changes, and teach `migrate` to upgrade data from older
+/// versions.
+///
+/// ```
+/// # use mobot::*;
+/// # use serde::{Deserialize, Serialize};
+/// # use serde_json::{json, Value};
+/// // Version 0 stored `name`; version 1 splits it in two.
+/// #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+/// struct Profile {
+///     first_name: String,
+///     last_name: String,
+/// }
+///
+/// impl Versioned for Profile {
+///     const VERSION: u32 = 1;
+///
+///     fn migrate(old_version: u32, mut value: Value) -> anyhow::Result<Value> {
+///         if old_version == 0 {
+///             let name = value["name"].as_str().unwrap_or_default().to_string();
+///             let (first, last) = name.split_once(' ').unwrap_or((&name, ""));
+///             value = json!({"first_name": first, "last_name": last});
+///         }
+///         Ok(value)
+///     }
+/// }
+///
+/// let profile: Profile = versioned::decode(json!({"name": "Ada Lovelace"})).unwrap();
+/// assert_eq!(profile.last_name, "Lovelace");
+/// ```
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// The current version of the serialized form.
+    const VERSION: u32 = 0;
+
+    /// Upgrade `value`, written by version `old_version`, to the current version. This is only
+    /// called for versions older than [`Versioned::VERSION`]. The default has no migrations,
+    /// and fails.
+    fn migrate(old_version: u32, value: Value) -> Result<Value> {
+        _ = value;
+        bail!(
+            "No migration from version {} to {}",
+            old_version,
+            Self::VERSION
+        )
+    }
+}
+
+/// Serialize `value`, tagged with its version.
+pub fn encode<T: Versioned>(value: &T) -> Result<Value> {
+    Ok(wrap(T::VERSION, serde_json::to_value(value)?))
+}
+
+/// Deserialize a value written by [`encode`] (or untagged, as version 0), migrating it if it
+/// was written by an older version. Values from newer versions are rejected rather than
+/// misread.
+pub fn decode<T: Versioned>(value: Value) -> Result<T> {
+    Ok(serde_json::from_value(upgrade(
+        value,
+        T::VERSION,
+        T::migrate,
+    )?)?)
+}
+
+/// Tag `value` with `version`. Version 0 values are stored as is.
+pub(crate) fn wrap(version: u32, value: Value) -> Value {
+    if version == 0 {
+        value
+    } else {
+        json!({ VERSION_KEY: version, VALUE_KEY: value })
+    }
+}
+
+/// Split a value into its version and the untagged value.
+pub(crate) fn unwrap(value: Value) -> (u32, Value) {
+    if let Value::Object(mut map) = value {
+        if let (Some(version), true) = (
+            map.get(VERSION_KEY).and_then(Value::as_u64),
+            map.len() == 2 && map.contains_key(VALUE_KEY),
+        ) {
+            return (version as u32, map.remove(VALUE_KEY).unwrap_or_default());
+        }
+        return (0, Value::Object(map));
+    }
+    (0, value)
+}
+
+/// Untag `value` and bring it up to `current` with `migrate`.
+pub(crate) fn upgrade(
+    value: Value,
+    current: u32,
+    migrate: impl FnOnce(u32, Value) -> Result<Value>,
+) -> Result<Value> {
+    match unwrap(value) {
+        (version, value) if version == current => Ok(value),
+        (version, value) if version < current => migrate(version, value),
+        (version, _) => bail!(
+            "Data was written by version {}, which is newer than this version ({})",
+            version,
+            current
+        ),
+    }
+}
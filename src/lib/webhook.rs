@@ -0,0 +1,497 @@
+/// Webhook delivery with automatic failover to long polling. With a [`WebhookConfig`] (see
+/// [`crate::Router::with_webhook`]), the router listens for updates from Telegram on a local
+/// address, and registers its public URL with `setWebhook`. It keeps an eye on the webhook
+/// with `getWebhookInfo`, and if Telegram can't deliver to it, deletes the webhook and falls
+/// back to polling `getUpdates`. While polling, it checks if the public URL is reachable
+/// again, and switches back to the webhook when it is.
+///
+/// The built-in server speaks plain HTTP/1.1, and only handles what Telegram sends. Telegram
+/// only delivers to HTTPS URLs, so put it behind a reverse proxy that terminates TLS. Request
+/// lines, headers and bodies are size-limited, and a client that takes longer than 30 seconds
+/// to send a request is disconnected.
+use std::{
+    net::SocketAddr,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+
+use crate::{
+    api::{self, WebhookInfo, API},
+    clock::Clock,
+    tenants::{Tenant, TenantResolver},
+};
+
+/// The header Telegram sends the secret token in.
+const SECRET_TOKEN_HEADER: &str = "x-telegram-bot-api-secret-token";
+
+/// Telegram's secret tokens are at most this long. Tokens are compared over this many bytes,
+/// so the comparison takes the same time however much of the token matches.
+const MAX_SECRET_TOKEN_LENGTH: usize = 256;
+
+/// Requests with larger bodies are rejected.
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/// Requests with longer request or header lines are rejected.
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+
+/// Requests with more headers are rejected.
+const MAX_HEADERS: usize = 64;
+
+/// How long a client has to send each request, including the time it idles on a kept-alive
+/// connection before starting it. Connections that take longer are closed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `WebhookConfig` describes how the router receives updates with a webhook.
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use mobot::webhook::WebhookConfig;
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = Client::new(std::env::var("TELEGRAM_TOKEN").unwrap());
+/// let webhook = WebhookConfig::new("https://bot.example.com/telegram", ([0, 0, 0, 0], 8080))
+///     .with_secret_token(std::env::var("WEBHOOK_SECRET").unwrap());
+/// Router::<()>::new(client).with_webhook(webhook).start().await;
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// The public URL Telegram sends updates to.
+    pub url: String,
+
+    /// The local address to listen on.
+    pub listen: SocketAddr,
+
+    /// The secret token requests must carry in the `X-Telegram-Bot-Api-Secret-Token` header.
+    /// Requests are accepted without one if it's not set.
+    pub secret: WebhookSecret,
+
+    /// How often to check the webhook's health with `getWebhookInfo` while it's in use, and
+    /// how often to check if `url` is reachable while polling. Defaults to 60 seconds.
+    pub check_interval: Duration,
+}
+
+impl WebhookConfig {
+    pub fn new(url: impl Into<String>, listen: impl Into<SocketAddr>) -> Self {
+        Self {
+            url: url.into(),
+            listen: listen.into(),
+            secret: WebhookSecret::default(),
+            check_interval: Duration::from_secs(60),
+        }
+    }
+
+    pub fn with_secret_token(mut self, secret_token: impl Into<String>) -> Self {
+        self.secret = WebhookSecret::new(secret_token);
+        self
+    }
+
+    pub fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+        self
+    }
+
+    /// Returns the `setWebhook` request for this webhook, with the current secret token.
+    pub fn request(&self) -> api::SetWebhookRequest {
+        let req = api::SetWebhookRequest::new(&self.url);
+        match self.secret.current() {
+            Some(secret_token) => req.with_secret_token(secret_token),
+            None => req,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tokens {
+    /// The token Telegram is told to send. `None` accepts any request.
+    current: Option<String>,
+
+    /// The token before the last rotation.
+    previous: Option<Previous>,
+}
+
+/// A rotated-out token, accepted until the end of its grace period.
+#[derive(Debug)]
+struct Previous {
+    token: Option<String>,
+
+    /// The end of the grace period, by the clock of the API that rotated the token.
+    until: DateTime<Utc>,
+    clock: Arc<dyn Clock>,
+}
+
+/// `WebhookSecret` holds the webhook's secret token, and checks it on incoming requests.
+/// Clones share the same token, so the token can be rotated while the router is running with
+/// [`WebhookSecret::rotate`]:
+///
+/// ```no_run
+/// # use mobot::*;
+/// # use mobot::webhook::WebhookConfig;
+/// # use std::time::Duration;
+/// # async fn run(client: Client) -> anyhow::Result<()> {
+/// let webhook = WebhookConfig::new("https://bot.example.com/telegram", ([0, 0, 0, 0], 8080))
+///     .with_secret_token("first-secret");
+/// let secret = webhook.secret.clone();
+/// let router = Router::<()>::new(client).with_webhook(webhook.clone());
+///
+/// // Later, e.g. from a scheduled task:
+/// secret
+///     .rotate(&router.api, &webhook, "second-secret", Duration::from_secs(60))
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct WebhookSecret {
+    tokens: Arc<RwLock<Tokens>>,
+}
+
+impl WebhookSecret {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            tokens: Arc::new(RwLock::new(Tokens {
+                current: Some(token.into()),
+                previous: None,
+            })),
+        }
+    }
+
+    /// Returns the current token, if one is set.
+    pub fn current(&self) -> Option<String> {
+        self.tokens.read().unwrap().current.clone()
+    }
+
+    /// Returns true if a request carrying `token` (or no token) is accepted: if it matches the
+    /// current token, or the previous one during its grace period.
+    pub fn accepts(&self, token: Option<&str>) -> bool {
+        let tokens = self.tokens.read().unwrap();
+        let matches = |expected: &Option<String>| match (expected, token) {
+            (None, _) => true,
+            (Some(expected), Some(token)) => constant_time_eq(expected, token),
+            (Some(_), None) => false,
+        };
+
+        matches(&tokens.current)
+            || tokens
+                .previous
+                .as_ref()
+                .is_some_and(|p| p.clock.now() < p.until && matches(&p.token))
+    }
+
+    /// Switch to `new_token`: tell Telegram to send it with `setWebhook`, and keep accepting
+    /// the old token for `grace`, for requests that were already on their way. Both tokens are
+    /// accepted while `setWebhook` is in flight. If it fails, the old token stays current.
+    pub async fn rotate(
+        &self,
+        api: &API,
+        config: &WebhookConfig,
+        new_token: impl Into<String>,
+        grace: Duration,
+    ) -> Result<()> {
+        let new_token = new_token.into();
+        let grace_period = chrono::Duration::from_std(grace)?;
+        let clock = Arc::clone(api.clock());
+        let (old_token, old_previous) = {
+            let mut tokens = self.tokens.write().unwrap();
+            let old_token = tokens.current.replace(new_token.clone());
+            let old_previous = tokens.previous.replace(Previous {
+                token: old_token.clone(),
+                until: clock.now() + chrono::Duration::hours(1),
+                clock: Arc::clone(&clock),
+            });
+            (old_token, old_previous)
+        };
+
+        let req = api::SetWebhookRequest::new(&config.url).with_secret_token(&new_token);
+        if let Err(err) = api.set_webhook(&req).await {
+            let mut tokens = self.tokens.write().unwrap();
+            tokens.current = old_token;
+            tokens.previous = old_previous;
+            return Err(err);
+        }
+
+        info!(
+            "Rotated webhook secret token, the old one expires in {:?}",
+            grace
+        );
+        self.tokens.write().unwrap().previous = Some(Previous {
+            token: old_token,
+            until: clock.now() + grace_period,
+            clock,
+        });
+        Ok(())
+    }
+}
+
+/// Returns true if Telegram failed to deliver to the webhook since `since` (in Unix time), and
+/// there are updates waiting for it.
+pub fn is_failing(info: &WebhookInfo, since: i64) -> bool {
+    info.last_error_date.is_some_and(|date| date >= since) && info.pending_update_count > 0
+}
+
+/// Returns true if `url` answers a `GET` request successfully. The router's webhook server
+/// answers `GET` requests on any path, so this checks the whole path from the internet to the
+/// bot.
+pub async fn is_reachable(url: &str) -> bool {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build();
+    match client {
+        Ok(client) => client
+            .get(url)
+            .send()
+            .await
+            .is_ok_and(|response| response.status().is_success()),
+        Err(_) => false,
+    }
+}
+
+/// Where a webhook server sends the updates posted to it.
+#[derive(Clone)]
+enum Sink {
+    Updates(mpsc::Sender<api::Update>),
+    Tenants(Arc<dyn TenantResolver>, mpsc::Sender<(Tenant, api::Update)>),
+}
+
+impl Sink {
+    async fn closed(&self) {
+        match self {
+            Sink::Updates(tx) => tx.closed().await,
+            Sink::Tenants(_, tx) => tx.closed().await,
+        }
+    }
+
+    /// Send the update in `body`, posted to `path`, on. Returns the response's status.
+    async fn deliver(&self, path: &str, body: Vec<u8>) -> Result<&'static str> {
+        let parse = |body: Vec<u8>| match crate::json::from_bytes::<api::Update>(body.into()) {
+            Ok(update) => Some(update),
+            Err(err) => {
+                warn!("Can't parse webhook update: {}", err);
+                None
+            }
+        };
+
+        match self {
+            Sink::Updates(tx) => {
+                let Some(update) = parse(body) else {
+                    return Ok("400 Bad Request");
+                };
+                tx.send(update).await?;
+            }
+            Sink::Tenants(resolver, tx) => {
+                let Some(tenant) = resolver.resolve(path).await? else {
+                    return Ok("404 Not Found");
+                };
+                let Some(update) = parse(body) else {
+                    return Ok("400 Bad Request");
+                };
+                tx.send((tenant, update)).await?;
+            }
+        }
+        Ok("200 OK")
+    }
+}
+
+/// Accept connections on `listener`, and send the updates posted to it with a token `secret`
+/// accepts to `tx`. Runs until `tx` is closed.
+pub async fn serve(listener: TcpListener, secret: WebhookSecret, tx: mpsc::Sender<api::Update>) {
+    serve_sink(listener, secret, Sink::Updates(tx)).await
+}
+
+/// Like [`serve`], but for a multi-tenant router: updates are sent to `tx` along with the
+/// tenant `resolver` finds for the path they were posted to. Requests to paths without a
+/// tenant are rejected with `404 Not Found`. See [`crate::tenants`].
+pub async fn serve_tenants(
+    listener: TcpListener,
+    secret: WebhookSecret,
+    resolver: Arc<dyn TenantResolver>,
+    tx: mpsc::Sender<(Tenant, api::Update)>,
+) {
+    serve_sink(listener, secret, Sink::Tenants(resolver, tx)).await
+}
+
+/// Returns true if `a` and `b` are equal, without returning early at the first difference, so
+/// the time taken doesn't reveal how much of a secret token a request got right.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let len = MAX_SECRET_TOKEN_LENGTH.max(a.len()).max(b.len());
+    let diff = (0..len).fold((a.len() != b.len()) as u8, |diff, i| {
+        diff | (a.get(i).unwrap_or(&0) ^ b.get(i).unwrap_or(&0))
+    });
+    diff == 0
+}
+
+async fn serve_sink(listener: TcpListener, secret: WebhookSecret, sink: Sink) {
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    warn!("Can't accept webhook connection: {}", err);
+                    continue;
+                }
+            },
+            _ = sink.closed() => return,
+        };
+
+        let secret = secret.clone();
+        let sink = sink.clone();
+        tokio::spawn(async move {
+            if let Err(err) = handle_connection(stream, &secret, &sink).await {
+                debug!("Webhook connection from {} failed: {}", addr, err);
+            }
+        });
+    }
+}
+
+/// A request read by [`read_request`].
+struct Request {
+    method: String,
+    path: String,
+    token: Option<String>,
+    close: bool,
+    body: Vec<u8>,
+}
+
+/// A request [`read_request`] rejected for being too large, with the response's status. The
+/// connection is closed after the response.
+#[derive(Debug, thiserror::Error)]
+#[error("Request too large: {0}")]
+struct TooLarge(&'static str);
+
+/// Handle requests on `stream` until the client closes the connection, or takes longer than
+/// [`REQUEST_TIMEOUT`] to send a request.
+async fn handle_connection(stream: TcpStream, secret: &WebhookSecret, sink: &Sink) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+
+    loop {
+        let Ok(request) = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream)).await
+        else {
+            bail!("Timed out reading request");
+        };
+        let request = match request {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()),
+            Err(err) => match err.downcast_ref::<TooLarge>() {
+                Some(TooLarge(status)) => {
+                    respond(&mut stream, status).await?;
+                    return linger(stream).await;
+                }
+                None => return Err(err),
+            },
+        };
+
+        let status = match request.method.as_str() {
+            "GET" | "HEAD" => "200 OK",
+            "POST" if !secret.accepts(request.token.as_deref()) => "401 Unauthorized",
+            "POST" => sink.deliver(&request.path, request.body).await?,
+            _ => "405 Method Not Allowed",
+        };
+        respond(&mut stream, status).await?;
+
+        if request.close {
+            return Ok(());
+        }
+    }
+}
+
+/// Read the next request on `stream`. Returns `None` if the client closed the connection
+/// before sending one.
+async fn read_request(stream: &mut BufReader<TcpStream>) -> Result<Option<Request>> {
+    let Some(request_line) = read_line(stream).await? else {
+        return Ok(None);
+    };
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+
+    let mut content_length = 0;
+    let mut token = None;
+    let mut close = false;
+    let mut headers = 0;
+    loop {
+        let Some(line) = read_line(stream).await? else {
+            bail!("Connection closed in headers");
+        };
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        headers += 1;
+        if headers > MAX_HEADERS {
+            bail!(TooLarge("431 Request Header Fields Too Large"));
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_lowercase().as_str() {
+            "content-length" => content_length = value.parse()?,
+            "connection" => close = value.eq_ignore_ascii_case("close"),
+            SECRET_TOKEN_HEADER => token = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        bail!(TooLarge("413 Payload Too Large"));
+    }
+    let mut body = vec![0; content_length];
+    stream.read_exact(&mut body).await?;
+
+    Ok(Some(Request {
+        method,
+        path,
+        token,
+        close,
+        body,
+    }))
+}
+
+/// Read a line of at most [`MAX_LINE_LENGTH`] bytes from `stream`. Returns `None` at the end
+/// of the stream.
+async fn read_line(stream: &mut BufReader<TcpStream>) -> Result<Option<String>> {
+    let mut line = Vec::new();
+    let read = (&mut *stream)
+        .take(MAX_LINE_LENGTH as u64)
+        .read_until(b'\n', &mut line)
+        .await?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with(b"\n") {
+        if read == MAX_LINE_LENGTH {
+            bail!(TooLarge("431 Request Header Fields Too Large"));
+        }
+        bail!("Connection closed mid-line");
+    }
+    Ok(Some(String::from_utf8(line)?))
+}
+
+/// Close `stream` after a response, without losing it to a reset: stop writing, and discard
+/// what the client is still sending for a little while, so the client reads the response
+/// before the connection closes.
+async fn linger(mut stream: BufReader<TcpStream>) -> Result<()> {
+    stream.get_mut().shutdown().await?;
+    let mut rest = (&mut stream).take(MAX_BODY_SIZE as u64);
+    let _ = tokio::time::timeout(
+        Duration::from_secs(1),
+        tokio::io::copy(&mut rest, &mut tokio::io::sink()),
+    )
+    .await;
+    Ok(())
+}
+
+async fn respond(stream: &mut BufReader<TcpStream>, status: &str) -> Result<()> {
+    let response = format!("HTTP/1.1 {}\r\ncontent-length: 0\r\n\r\n", status);
+    stream.get_mut().write_all(response.as_bytes()).await?;
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use mobot::{
+    api::API,
+    clock::FakeClock,
+    handlers::anti_raid::{AntiRaid, AntiRaidState},
+    *,
+};
+
+type Calls = Arc<Mutex<Vec<(String, serde_json::Value)>>>;
+
+const CHAT_ID: i64 = -100;
+
+/// Returns an API for a group whose members can only send messages, and the calls (method and
+/// request) it received.
+fn recording_api(clock: FakeClock) -> (Arc<API>, Calls) {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&calls);
+    let client = Client::new("token".to_string()).with_post_handler_fn(
+        move |method: String, req: String| {
+            let response = match method.as_str() {
+                "getChat" => format!(
+                    r#"{{"ok": true, "result": {{"id": {}, "type": "supergroup", "accent_color_id": 0, "max_reaction_count": 11, "permissions": {{"can_send_messages": true}}}}}}"#,
+                    CHAT_ID
+                ),
+                _ => r#"{"ok": true, "result": true}"#.to_string(),
+            };
+            recorded
+                .lock()
+                .unwrap()
+                .push((method, serde_json::from_str(&req).unwrap()));
+            Ok(response)
+        },
+    );
+
+    (Arc::new(API::new(client).with_clock(clock)), calls)
+}
+
+fn user(id: i64) -> api::User {
+    api::User {
+        id,
+        first_name: "raider".into(),
+        ..Default::default()
+    }
+}
+
+fn join_request(user_id: i64) -> Update {
+    Update::ChatJoinRequest(api::ChatJoinRequest {
+        chat: api::Chat {
+            id: CHAT_ID,
+            ..Default::default()
+        },
+        from: user(user_id),
+        ..Default::default()
+    })
+}
+
+fn new_members(user_ids: &[i64]) -> Update {
+    Update::Message(api::Message {
+        chat: api::Chat {
+            id: CHAT_ID,
+            ..Default::default()
+        },
+        new_chat_members: Some(user_ids.iter().map(|id| user(*id)).collect()),
+        ..Default::default()
+    })
+}
+
+fn methods(calls: &Calls) -> Vec<String> {
+    calls.lock().unwrap().drain(..).map(|(m, _)| m).collect()
+}
+
+#[tokio::test]
+async fn anti_raid() {
+    let clock = FakeClock::at(1_700_000_000);
+    let (api, calls) = recording_api(clock.clone());
+    let settings = Settings::new(MemoryStorage::new());
+    let event = |update| Event::new(Arc::clone(&api), update).with_settings(settings.clone());
+
+    let anti_raid = AntiRaid::new(Duration::from_secs(1)).with_new_account_threshold(1000);
+
+    // Nothing happens outside the raid window.
+    let action = BotHandlerFn::<()>::run(&anti_raid, event(join_request(5)), State::default())
+        .await
+        .unwrap();
+    assert!(matches!(action, Action::Next));
+    assert!(methods(&calls).is_empty());
+
+    let e = event(new_members(&[]));
+    anti_raid.trigger(&e, CHAT_ID).await.unwrap();
+    {
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls[0].0, "getChat");
+        assert_eq!(calls[1].0, "setChatPermissions");
+        assert_eq!(calls[1].1["permissions"]["can_send_messages"], false);
+    }
+    methods(&calls);
+    assert!(anti_raid.is_active(&e, CHAT_ID).await.unwrap());
+
+    // Join requests are left pending, and not passed on.
+    let action = BotHandlerFn::<()>::run(&anti_raid, event(join_request(5)), State::default())
+        .await
+        .unwrap();
+    assert!(matches!(action, Action::Done));
+    assert!(methods(&calls).is_empty());
+
+    // New members above the threshold are banned.
+    BotHandlerFn::<()>::run(&anti_raid, event(new_members(&[5, 5000])), State::default())
+        .await
+        .unwrap();
+    {
+        let mut calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "banChatMember");
+        assert_eq!(calls[0].1["user_id"], 5000);
+        calls.clear();
+    }
+
+    // Once the window ends, the saved permissions are restored.
+    clock.advance(Duration::from_secs(1));
+    e.tasks.shutdown(Duration::from_secs(5)).await;
+    {
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].0, "setChatPermissions");
+        assert_eq!(calls[0].1["permissions"]["can_send_messages"], true);
+    }
+    assert!(!anti_raid.is_active(&e, CHAT_ID).await.unwrap());
+    assert!(settings
+        .get::<AntiRaidState>(CHAT_ID)
+        .await
+        .unwrap()
+        .until
+        .is_none());
+}
+
+#[tokio::test]
+async fn unknown_permissions() {
+    let clock = FakeClock::at(1_700_000_000);
+    let (api, calls) = recording_api(clock.clone());
+    let settings = Settings::new(MemoryStorage::new());
+    let e = Event::new(Arc::clone(&api), new_members(&[])).with_settings(settings.clone());
+
+    // If the permissions before the raid weren't saved, lifting it doesn't change them.
+    settings
+        .set(
+            CHAT_ID,
+            &AntiRaidState {
+                until: Some(1_700_000_000),
+                saved_permissions: None,
+            },
+        )
+        .await
+        .unwrap();
+    let anti_raid = AntiRaid::new(Duration::from_secs(1));
+    assert!(!anti_raid.is_active(&e, CHAT_ID).await.unwrap());
+    assert!(methods(&calls).is_empty());
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use mobot::{api::API, audit::MemoryAuditSink, clock::FakeClock, *};
+
+#[tokio::test]
+async fn records_moderation_actions() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let sink = MemoryAuditSink::new();
+    let api = API::new(Client::new("token".to_string()).with_post_handler(fakeserver.clone()))
+        .with_audit_sink(sink.clone());
+
+    api.ban_chat_member(&api::BanChatMemberRequest::new(
+        "-100".into(),
+        42,
+        None,
+        None,
+    ))
+    .await
+    .unwrap();
+    api.set_chat_permissions(&api::SetChatPermissionRequest::new(
+        "-100".into(),
+        api::ChatPermissions {
+            can_send_messages: Some(false),
+            ..Default::default()
+        },
+        None,
+    ))
+    .await
+    .unwrap();
+
+    let entries = sink.entries().await;
+    assert_eq!(entries.len(), 2);
+
+    assert_eq!(entries[0].method, "banChatMember");
+    assert_eq!(entries[0].chat_id, "-100");
+    assert_eq!(entries[0].user_id, Some(42));
+    assert_eq!(entries[0].actor, Some(0));
+    assert_eq!(entries[0].old.as_ref().unwrap()["status"], "member");
+    assert_eq!(entries[0].new["user_id"], 42);
+
+    assert_eq!(entries[1].method, "setChatPermissions");
+    assert_eq!(entries[1].user_id, None);
+    assert_eq!(entries[1].old.as_ref().unwrap()["can_send_messages"], true);
+    assert_eq!(entries[1].new["permissions"]["can_send_messages"], false);
+}
+
+#[tokio::test]
+async fn no_sink_no_snapshots() {
+    // Without a sink, moderation calls don't make extra requests.
+    let client = Client::new("token".to_string()).with_post_handler_fn(|method: String, _| {
+        assert_eq!(method, "banChatMember");
+        Ok(r#"{"ok": true, "result": true}"#.to_string())
+    });
+
+    assert!(API::new(client)
+        .ban_chat_member(&api::BanChatMemberRequest::new(
+            "-100".into(),
+            42,
+            None,
+            None
+        ))
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn timestamps_use_clock() {
+    let fakeserver = fake::FakeAPI::new();
+    let sink = MemoryAuditSink::new();
+    let clock = FakeClock::at(1_700_000_000);
+    let api = API::new(Client::new("token".to_string()).with_post_handler(fakeserver.clone()))
+        .with_audit_sink(sink.clone())
+        .with_clock(clock.clone());
+
+    assert_eq!(api.until_date(Duration::from_secs(3600)), 1_700_003_600);
+
+    clock.advance(Duration::from_secs(60));
+    api.unban_chat_member(&api::UnbanChatMemberRequest::new("-100".into(), 42, None))
+        .await
+        .unwrap();
+
+    assert_eq!(sink.entries().await[0].timestamp, 1_700_000_060);
+}
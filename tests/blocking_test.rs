@@ -0,0 +1,48 @@
+#![cfg(feature = "blocking")]
+
+use std::sync::{Arc, Mutex};
+
+use mobot::{
+    api::{DeleteMessageRequest, SendMessageRequest},
+    blocking, Client,
+};
+
+#[test]
+fn blocking_api() {
+    let sent = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&sent);
+    let client = Client::new("token".to_string()).with_post_handler_fn(
+        move |method: String, req: String| {
+            let req: serde_json::Value = serde_json::from_str(&req).unwrap();
+            let response = match method.as_str() {
+                "sendMessage" => serde_json::json!({
+                    "ok": true,
+                    "result": {
+                        "message_id": 7,
+                        "date": 0,
+                        "chat": {"id": req["chat_id"], "type": "private"},
+                        "text": req["text"],
+                    }
+                }),
+                _ => serde_json::json!({"ok": true, "result": true}),
+            };
+            recorded.lock().unwrap().push(method);
+            Ok(response.to_string())
+        },
+    );
+
+    let api = blocking::API::new(client).unwrap();
+    let message = api
+        .send_message(&SendMessageRequest::new(42, "Done"))
+        .unwrap();
+    assert_eq!(message.message_id, 7);
+    assert_eq!(message.chat.id, 42);
+    assert_eq!(message.text.as_deref(), Some("Done"));
+
+    let deleted = api
+        .clone()
+        .call(|api| async move { api.delete_message(&DeleteMessageRequest::new(42, 7)).await })
+        .unwrap();
+    assert!(deleted);
+    assert_eq!(*sent.lock().unwrap(), ["sendMessage", "deleteMessage"]);
+}
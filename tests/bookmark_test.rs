@@ -0,0 +1,101 @@
+use mobot::{handlers::bookmark::BookmarkSettings, *};
+
+fn reaction(chat_id: i64, message_id: i64, user_id: i64, emoji: &[&str]) -> Update {
+    Update::MessageReaction(api::MessageReactionUpdated {
+        chat: api::Chat {
+            id: chat_id,
+            ..Default::default()
+        },
+        message_id,
+        user: Some(api::User {
+            id: user_id,
+            first_name: "qubyte".into(),
+            ..Default::default()
+        }),
+        new_reaction: emoji.iter().map(|e| api::ReactionType::emoji(*e)).collect(),
+        ..Default::default()
+    })
+}
+
+#[tokio::test]
+async fn bookmark_bridge() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    fakeserver.admins.lock().await.insert(1);
+
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_allowed_updates(["message", "message_reaction"]);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    let archive = fakeserver.create_chat("archive").await;
+    let settings = router.settings().clone();
+
+    router.add_route(
+        Route::MessageReaction(Matcher::Any),
+        handlers::bookmark_bridge(archive.chat_id),
+    );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    // Only reactions from admins with the bookmark emoji are archived.
+    chat.send_update(reaction(chat.chat_id, 10, 2, &["🔥"]))
+        .await
+        .unwrap();
+    chat.send_update(reaction(chat.chat_id, 11, 1, &["👍"]))
+        .await
+        .unwrap();
+    chat.send_update(reaction(chat.chat_id, 12, 1, &["👍", "🔥"]))
+        .await
+        .unwrap();
+    assert_eq!(
+        archive.recv_update().await.unwrap().to_string(),
+        format!("copy of {}/12", chat.chat_id)
+    );
+
+    // Chats can choose their own emoji.
+    settings
+        .set(
+            chat.chat_id,
+            &BookmarkSettings {
+                emoji: Some("🏆".into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    chat.send_update(reaction(chat.chat_id, 13, 1, &["🔥"]))
+        .await
+        .unwrap();
+    chat.send_update(reaction(chat.chat_id, 14, 1, &["🏆"]))
+        .await
+        .unwrap();
+    assert_eq!(
+        archive.recv_update().await.unwrap().to_string(),
+        format!("copy of {}/14", chat.chat_id)
+    );
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[test]
+fn added_emoji() {
+    let Update::MessageReaction(mut reaction) = reaction(1, 1, 1, &["👍", "🔥"]) else {
+        unreachable!()
+    };
+    reaction.old_reaction = vec![api::ReactionType::emoji("👍")];
+    assert_eq!(reaction.added_emoji(), vec!["🔥"]);
+
+    let route = Route::MessageReaction(Matcher::Exact("🔥".into()));
+    let update = api::Update {
+        message_reaction: Some(reaction),
+        ..Default::default()
+    };
+    assert!(route.match_update(&update));
+    assert!(!Route::MessageReaction(Matcher::Exact("👍".into())).match_update(&update));
+}
@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+
+use mobot::{
+    broadcast::{BroadcastStats, BroadcastTracker},
+    *,
+};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn broadcast() {
+    let sent = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&sent);
+    let client = Client::new("token".to_string())
+        .with_rate_limiter(RateLimiter::per_second(1000))
+        .with_post_handler_fn(move |method: String, req: String| {
+            if method == "getMe" {
+                return Ok(json!({"ok": true, "result": {
+                    "id": 1, "is_bot": true, "first_name": "Bot", "username": "news_bot",
+                }})
+                .to_string());
+            }
+
+            let req: Value = serde_json::from_str(&req).unwrap();
+            recorded.lock().unwrap().push(req.clone());
+            if req["chat_id"] == 103 {
+                return Ok(json!({"ok": false, "error_code": 403,
+                    "description": "Forbidden: bot was blocked by the user"})
+                .to_string());
+            }
+            Ok(json!({"ok": true, "result": {
+                "message_id": 1, "date": 0, "chat": {"id": req["chat_id"], "type": "private"},
+            }})
+            .to_string())
+        });
+    let api = API::new(client);
+
+    let tracker = BroadcastTracker::default();
+    let stats = tracker
+        .send(&api, 7, [101, 102, 103], "Maintenance tonight", "Got it")
+        .await
+        .unwrap();
+    assert_eq!((stats.delivered, stats.failed), (2, 1));
+
+    // Each recipient gets their own link.
+    let sent = sent.lock().unwrap().clone();
+    assert_eq!(sent.len(), 3);
+    assert_eq!(
+        sent[1]["reply_markup"]["inline_keyboard"][0][0]["url"],
+        "https://t.me/news_bot?start=ack_7_102"
+    );
+
+    let click = |from: &str, user_id: i64, text: &str| {
+        let mut message = api::Message::new(from, text);
+        message.from.as_mut().unwrap().id = user_id;
+        message
+    };
+
+    // Recipients reading their own copy, once each, and a forwarded copy.
+    let recorded = tracker
+        .record_click(&click("a", 101, "/start ack_7_101"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!((recorded.broadcast_id, recorded.recipient_id), (7, 101));
+    tracker
+        .record_click(&click("a", 101, "/start ack_7_101"))
+        .await
+        .unwrap();
+    tracker
+        .record_click(&click("c", 104, "/start ack_7_102"))
+        .await
+        .unwrap();
+
+    // Other /start commands aren't clicks.
+    assert!(tracker
+        .record_click(&click("d", 105, "/start ref_5"))
+        .await
+        .unwrap()
+        .is_none());
+
+    assert_eq!(
+        tracker.stats(7).await.unwrap(),
+        BroadcastStats {
+            delivered: 2,
+            failed: 1,
+            read: 1,
+            forwarded_reads: 1,
+        }
+    );
+    assert_eq!(tracker.clicks(7).await.unwrap().len(), 2);
+}
@@ -0,0 +1,97 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use mobot::{clock::FakeClock, *};
+use serde_json::Value;
+
+/// The (chat ID, message ID) of each deleted message.
+type Deleted = Arc<Mutex<Vec<(i64, i64)>>>;
+
+/// Returns an API that records the messages it's asked to delete.
+fn api(clock: FakeClock) -> (Arc<API>, Deleted) {
+    let deleted = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&deleted);
+    let client = Client::new("token".to_string()).with_post_handler_fn(
+        move |method: String, req: String| {
+            assert_eq!(method, "deleteMessage");
+            let req: Value = serde_json::from_str(&req).unwrap();
+            recorded.lock().unwrap().push((
+                req["chat_id"].as_i64().unwrap(),
+                req["message_id"].as_i64().unwrap(),
+            ));
+            Ok(r#"{"ok": true, "result": true}"#.to_string())
+        },
+    );
+    (Arc::new(API::new(client).with_clock(clock)), deleted)
+}
+
+fn message(message_id: i64) -> api::Message {
+    let mut message = api::Message::fake("qubyte");
+    message.message_id = message_id;
+    message
+}
+
+async fn wait(tasks: &Tasks) {
+    while !tasks.is_empty() {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+}
+
+#[tokio::test]
+async fn cleanup() {
+    let clock = FakeClock::at(1_000_000);
+    let (api, deleted) = api(clock.clone());
+    let tasks = Tasks::new();
+    let cleanup = Cleanup::default().with_ttl(Duration::from_millis(20));
+
+    let (menu, notice) = (message(1), message(2));
+    let chat_id = menu.chat.id;
+    cleanup.schedule(&api, &tasks, &menu).await.unwrap();
+    cleanup.schedule(&api, &tasks, &notice).await.unwrap();
+    assert_eq!(cleanup.pending(chat_id).await.unwrap(), vec![1, 2]);
+
+    // Kept messages aren't deleted.
+    cleanup.keep(chat_id, 1).await.unwrap();
+    clock.advance(Duration::from_millis(20));
+    wait(&tasks).await;
+    assert_eq!(*deleted.lock().unwrap(), vec![(chat_id, 2)]);
+    assert!(cleanup.pending(chat_id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn resume() {
+    let storage: Arc<dyn StateStorage> = Arc::new(MemoryStorage::new());
+    let clock = FakeClock::at(1_000_000);
+    let (api, deleted) = api(clock.clone());
+
+    // The router shuts down before the TTL is up.
+    let tasks = Tasks::new();
+    let cleanup = Cleanup::from_arc(Arc::clone(&storage)).with_ttl(Duration::from_secs(60));
+    let notice = message(3);
+    cleanup.schedule(&api, &tasks, &notice).await.unwrap();
+    tasks.shutdown(Duration::from_secs(1)).await;
+    assert!(deleted.lock().unwrap().is_empty());
+
+    // After a restart, messages past their TTL are deleted right away.
+    clock.advance(Duration::from_secs(61));
+    let tasks = Tasks::new();
+    let cleanup = Cleanup::from_arc(storage);
+    assert_eq!(cleanup.resume(&api, &tasks).await.unwrap(), 1);
+    wait(&tasks).await;
+    assert_eq!(*deleted.lock().unwrap(), vec![(notice.chat.id, 3)]);
+    assert!(cleanup.pending(notice.chat.id).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn ephemeral_without_cleanup() {
+    // The API panics on anything but deleteMessage, so nothing may be sent.
+    let (api, _) = api(FakeClock::at(1_000_000));
+    let e = Event::new(api, Update::Message(message(1)));
+    let err = e
+        .send_ephemeral("Only admins can do that.")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Router::with_cleanup"));
+}
@@ -0,0 +1,148 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    process::{Command, Output},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use serde_json::{json, Value};
+
+/// The (path, body) of each request the test server got.
+type Requests = Arc<Mutex<Vec<(String, String)>>>;
+
+/// A Bot API server that answers with canned responses, and records the requests it gets.
+fn serve() -> (String, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&requests);
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let path = line.split(' ').nth(1).unwrap().to_string();
+
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let body = String::from_utf8(body).unwrap();
+
+            let response = match path.rsplit('/').next().unwrap() {
+                "sendMessage" => {
+                    let req: Value = serde_json::from_str(&body).unwrap();
+                    json!({"ok": true, "result": {
+                        "message_id": 7,
+                        "date": 0,
+                        "chat": {"id": req["chat_id"], "type": "private"},
+                        "text": req["text"],
+                    }})
+                    .to_string()
+                }
+                "getFile" => json!({"ok": true, "result": {
+                    "file_id": "abc",
+                    "file_path": "documents/report.txt",
+                }})
+                .to_string(),
+                "report.txt" => "quarterly numbers".to_string(),
+                "setWebhook" => {
+                    json!({"ok": false, "description": "Bad Request: bad webhook"}).to_string()
+                }
+                _ => json!({"ok": true, "result": true}).to_string(),
+            };
+            recorded.lock().unwrap().push((path, body));
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+        }
+    });
+
+    (url, requests)
+}
+
+fn cli(server: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_mobot-cli"))
+        .args(["--token", "123:abc", "--server", server])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn send() {
+    let (server, requests) = serve();
+    let output = cli(
+        &server,
+        &["send", "42", "<b>Done</b>", "--parse-mode", "html"],
+    );
+    assert!(output.status.success());
+
+    let message: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(message["message_id"], 7);
+    assert_eq!(message["chat"]["id"], 42);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].0, "/bot123:abc/sendMessage");
+    let req: Value = serde_json::from_str(&requests[0].1).unwrap();
+    assert_eq!(req["text"], "<b>Done</b>");
+    assert_eq!(req["parse_mode"], "HTML");
+}
+
+#[test]
+fn download() {
+    let (server, requests) = serve();
+    let dir = std::env::temp_dir().join(format!("mobot-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let file = dir.join("report.txt");
+
+    let output = cli(
+        &server,
+        &["download", "abc", "--output", file.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "quarterly numbers");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let paths: Vec<_> = requests
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(path, _)| path.clone())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "/bot123:abc/getFile",
+            "/file/bot123:abc/documents/report.txt"
+        ]
+    );
+}
+
+#[test]
+fn api_errors() {
+    let (server, _) = serve();
+    let output = cli(&server, &["set-webhook", "https://example.com/telegram"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Bad Request: bad webhook"));
+}
@@ -27,7 +27,252 @@ async fn it_works() {
 
     println!(
         "api = {:#?}",
-        api.send_sticker(&api::SendStickerRequest::new(1, "2".to_string()))
+        api.send_message(&api::SendMessageRequest::new(1, "2"))
             .await
     );
 }
+
+#[tokio::test]
+async fn dry_run() {
+    let client = Client::new("token".to_string())
+        .with_dry_run(true)
+        .with_post_handler_fn(|method: String, _| {
+            assert_eq!(method, "sendMessage");
+            Ok(r#"{"ok": true, "result": {"message_id": 1, "date": 0, "chat": {"id": 1, "type": "private"}}}"#.to_string())
+        });
+    let api = API::new(client);
+
+    // Destructive methods are not sent to the server.
+    assert!(api
+        .ban_chat_member(&api::BanChatMemberRequest::new("1".into(), 2, None, None))
+        .await
+        .unwrap());
+    assert!(api
+        .delete_message(&api::DeleteMessageRequest::new(1, 1))
+        .await
+        .unwrap());
+
+    // Everything else is.
+    assert_eq!(
+        api.send_message(&api::SendMessageRequest::new(1, "hi"))
+            .await
+            .unwrap()
+            .message_id,
+        1
+    );
+}
+
+#[tokio::test]
+async fn stats() {
+    let client = Client::new("token".to_string()).with_post_handler_fn(|method: String, _| {
+        if method == "sendMessage" {
+            Ok(r#"{"ok": true, "result": {"message_id": 1, "date": 0, "chat": {"id": 1, "type": "private"}}}"#.to_string())
+        } else {
+            Ok(r#"{"ok": false, "description": "Bad Request: chat not found"}"#.to_string())
+        }
+    });
+    let api = API::new(client);
+
+    api.send_message(&api::SendMessageRequest::new(1, "hi"))
+        .await
+        .unwrap();
+    api.send_message(&api::SendMessageRequest::new(1, "there"))
+        .await
+        .unwrap();
+    assert!(api
+        .delete_message(&api::DeleteMessageRequest::new(1, 2))
+        .await
+        .is_err());
+
+    let stats = api.client.stats();
+    assert_eq!(stats.requests, 3);
+    assert_eq!(stats.failures, 1);
+    assert_eq!(stats.in_flight, 0);
+
+    // Fake post handlers don't go over HTTP.
+    assert_eq!(stats.http1_responses + stats.http2_responses, 0);
+}
+
+#[tokio::test]
+async fn clone_api() {
+    let fakeserver = fake::FakeAPI::new();
+    let api = API::new(Client::new("token".to_string()).with_post_handler(fakeserver));
+
+    // Clones can be moved into background tasks, and share the client and the cached bot user.
+    let background = api.clone();
+    let me = tokio::spawn(async move { background.me().await.unwrap() })
+        .await
+        .unwrap();
+
+    assert_eq!(me.username, api.me().await.unwrap().username);
+    assert_eq!(api.client.stats().requests, 1);
+}
+
+#[test]
+fn resolver_options() {
+    let telegram = std::net::IpAddr::from([149, 154, 167, 220]);
+    let config = HttpConfig::default()
+        .with_ip_preference(IpPreference::Ipv4Only)
+        .with_dns_override("API.telegram.org", telegram);
+    assert_eq!(config.dns_overrides["api.telegram.org"], vec![telegram]);
+
+    let client = Client::new("token".to_string()).with_http_config(config);
+    assert_eq!(client.http_config().ip_preference, IpPreference::Ipv4Only);
+}
+
+#[tokio::test]
+async fn request_logging() {
+    mobot::init_logger();
+    let client = Client::new("token".to_string())
+        .with_request_logging(true)
+        .with_post_handler_fn(|_: String, _: String| {
+            Ok(r#"{"ok": false, "description": "Bad Request: message text is empty"}"#.to_string())
+        });
+    let api = API::new(client);
+
+    // Failures are logged, and still returned.
+    let err = api
+        .send_message(&api::SendMessageRequest::new(1, ""))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Telegram error: Bad Request: message text is empty"
+    );
+}
+
+#[tokio::test]
+async fn api_version_gating() {
+    use std::sync::{Arc, Mutex};
+
+    let sent = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&sent);
+    let client = Client::new("token".to_string())
+        .with_api_version("7.0".parse().unwrap())
+        .with_post_handler_fn(move |method: String, req: String| {
+            recorded.lock().unwrap().push((method.clone(), req));
+            Ok(match method.as_str() {
+                "getMe" => r#"{"ok": true, "result": {"id": 1, "is_bot": true, "first_name": "bot", "can_connect_to_business": false}}"#.to_string(),
+                _ => serde_json::to_string(&api::ApiResponse::Ok(api::Message::fake("bot"))).unwrap(),
+            })
+        });
+    let api = API::new(client);
+
+    // Methods the server doesn't have aren't sent.
+    let err = api
+        .set_user_emoji_status(&api::SetUserEmojiStatusRequest::new(1, "123"))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "setUserEmojiStatus requires Bot API 8.0, but the server runs 7.0"
+    );
+    assert!(sent.lock().unwrap().is_empty());
+
+    // Fields it doesn't know are dropped.
+    api.send_message(&api::SendMessageRequest {
+        message_effect_id: Some("5104841245755180586".into()),
+        ..api::SendMessageRequest::new(1, "hi")
+    })
+    .await
+    .unwrap();
+    let req: serde_json::Value = serde_json::from_str(&sent.lock().unwrap()[0].1).unwrap();
+    assert_eq!(req["text"], "hi");
+    assert!(req.get("message_effect_id").is_none());
+
+    // getMe has 7.2's fields, but not 8.0's, and the configured version is older.
+    let version = api.check_api_version().await.unwrap();
+    assert_eq!(version, api::BotApiVersion::new(7, 0));
+    assert_eq!(
+        api.detect_api_version().await.unwrap(),
+        api::BotApiVersion::new(7, 11)
+    );
+
+    // Without a configured version, the detected version is used.
+    api.client.set_api_version(None);
+    assert_eq!(
+        api.check_api_version().await.unwrap(),
+        api::BotApiVersion::new(7, 11)
+    );
+    assert_eq!(
+        api.client.api_version(),
+        Some(api::BotApiVersion::new(7, 11))
+    );
+}
+
+/// Answers every request after `delay`.
+struct SlowServer {
+    delay: std::time::Duration,
+}
+
+#[async_trait::async_trait]
+impl client::Post for SlowServer {
+    async fn post(&self, _method: String, _req: String) -> Result<String> {
+        tokio::time::sleep(self.delay).await;
+        Ok(r#"{"ok": true, "result": true}"#.to_string())
+    }
+}
+
+#[tokio::test]
+async fn call_options() {
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+
+    let client = Client::new("token".to_string())
+        .with_post_handler(SlowServer {
+            delay: Duration::from_millis(200),
+        })
+        .with_method_timeout("sendChatAction", Duration::from_millis(20));
+    let api = API::new(client);
+    let typing = api::SendChatActionRequest::new(1, api::ChatAction::Typing);
+
+    // Per-method timeouts.
+    let err = api.send_chat_action(&typing).await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<api::ApiError>(),
+        Some(api::ApiError::Timeout(method)) if method == "sendChatAction"
+    ));
+
+    // Scoped deadlines.
+    let err = CallOptions::new()
+        .with_timeout(Duration::from_millis(20))
+        .scope(api.delete_message(&api::DeleteMessageRequest::new(1, 1)))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<api::ApiError>(),
+        Some(api::ApiError::Timeout(_))
+    ));
+
+    // Cancellation, including of calls in nested scopes.
+    let token = CancellationToken::new();
+    let call = CallOptions::new()
+        .with_cancellation_token(token.clone())
+        .scope(async {
+            CallOptions::new()
+                .with_timeout(Duration::from_secs(10))
+                .scope(api.delete_message(&api::DeleteMessageRequest::new(1, 1)))
+                .await
+        });
+    let cancel = async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        token.cancel();
+    };
+    let (result, _) = tokio::join!(call, cancel);
+    assert!(matches!(
+        result.unwrap_err().downcast_ref::<api::ApiError>(),
+        Some(api::ApiError::Cancelled(_))
+    ));
+
+    // Abandoned requests aren't left in flight, and count as failures.
+    let stats = api.client.stats();
+    assert_eq!(stats.requests, 3);
+    assert_eq!(stats.in_flight, 0);
+    assert_eq!(stats.failures, 3);
+
+    // Calls within their bounds go through.
+    assert!(api
+        .delete_message(&api::DeleteMessageRequest::new(1, 1))
+        .await
+        .unwrap());
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use mobot::{
+    api::ApiError,
+    control::{ControlChat, ControlEvent},
+    *,
+};
+
+#[test]
+fn rate_limit_errors() {
+    let err = ApiError::AppError("Too Many Requests: retry after 35".into());
+    assert!(err.is_rate_limit());
+    assert_eq!(err.retry_after(), Some(Duration::from_secs(35)));
+
+    let err = ApiError::AppError("Bad Request: message text is empty".into());
+    assert!(!err.is_rate_limit());
+    assert_eq!(err.retry_after(), None);
+
+    assert_eq!(
+        ControlEvent::from_error(1, &anyhow::anyhow!("boom")),
+        ControlEvent::Error {
+            chat_id: 1,
+            error: "boom".into()
+        }
+    );
+}
+
+#[tokio::test]
+async fn control_chat() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let user = fakeserver.create_chat("qubyte").await;
+    let operators = fakeserver.create_chat("operator").await;
+
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let control = ControlChat::new(operators.chat_id);
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_control_chat(control.clone());
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_route_with_priority(Route::Message(Matcher::Any), 100, control.handler())
+        .add_route_with_priority(Route::Message(Matcher::Photo), 100, control.handler())
+        .add_route(
+            Route::Message(Matcher::Photo),
+            |_: Event, _: State<()>| async move { Ok(Action::ReplyText("photo".into())) },
+        )
+        .add_route(
+            Route::Message(Matcher::Exact("fail".into())),
+            |_: Event, _: State<()>| async move { Err(anyhow::anyhow!("boom")) },
+        )
+        .add_route(
+            Route::Message(Matcher::Exact("flood".into())),
+            |_: Event, _: State<()>| async move {
+                Err(ApiError::AppError("Too Many Requests: retry after 5".into()).into())
+            },
+        )
+        .add_route(
+            Route::Message(Matcher::Any),
+            |_: Event, _: State<()>| async move { Ok(Action::ReplyText("echo".into())) },
+        );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    // Errors are reported to the control chat, and handled as usual.
+    user.send_text("fail").await.unwrap();
+    assert_eq!(
+        operators.recv_update().await.unwrap().to_string(),
+        format!("Error in chat {}: boom", user.chat_id)
+    );
+    assert_eq!(
+        user.recv_update().await.unwrap().to_string(),
+        "Handler error: boom"
+    );
+
+    user.send_text("flood").await.unwrap();
+    assert_eq!(
+        operators.recv_update().await.unwrap().to_string(),
+        format!("Rate limited in chat {}, retry after 5s", user.chat_id)
+    );
+    user.recv_update().await.unwrap();
+
+    // Dead letters too.
+    user.send_callback_query("data").await.unwrap();
+    assert_eq!(
+        operators.recv_update().await.unwrap().to_string(),
+        format!(
+            "No handlers for CallbackQuery(Any) in chat {}",
+            user.chat_id
+        )
+    );
+    user.recv_update().await.unwrap();
+
+    // Commands only work in the control chat.
+    user.send_text("/stats").await.unwrap();
+    assert_eq!(user.recv_update().await.unwrap().to_string(), "echo");
+
+    // Messages without text in the control chat are passed on.
+    let mut message = api::Message::fake("operator");
+    message.chat.id = operators.chat_id;
+    message.photo = Some(vec![]);
+    operators
+        .send_update(Update::Message(message))
+        .await
+        .unwrap();
+    assert_eq!(operators.recv_update().await.unwrap().to_string(), "photo");
+
+    operators.send_text("/toggle ai").await.unwrap();
+    assert_eq!(
+        operators.recv_update().await.unwrap().to_string(),
+        "Feature ai disabled."
+    );
+
+    operators.send_text("/stats").await.unwrap();
+    let stats = operators.recv_update().await.unwrap().to_string();
+    for line in [
+        "Errors: 1",
+        "Rate limits: 1",
+        "Dead letters: 1",
+        "Feature ai: off",
+    ] {
+        assert!(stats.lines().any(|l| l == line), "{}", stats);
+    }
+    assert!(
+        stats
+            .lines()
+            .any(|l| l.starts_with("Handler Message(Exact(\"fail\")): 1 runs")),
+        "{}",
+        stats
+    );
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
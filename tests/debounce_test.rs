@@ -0,0 +1,125 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use mobot::{clock::*, *};
+
+fn tap(id: &str, message: &api::Message) -> api::CallbackQuery {
+    api::CallbackQuery {
+        id: id.into(),
+        from: "qubyte".into(),
+        message: Some(message.clone()),
+        data: Some("pay".into()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn debounce() {
+    let clock = FakeClock::at(1_000_000);
+    let shared: Arc<dyn Clock> = Arc::new(clock.clone());
+    let debounce = CallbackDebounce::new(Duration::from_secs(2));
+    let message = api::Message::fake("qubyte");
+    let other = api::Message::fake("qubyte");
+
+    // Taps on the same message are dropped while the first is handled...
+    let guard = debounce.start(&tap("1", &message), &shared).unwrap();
+    assert!(debounce.start(&tap("2", &message), &shared).is_none());
+    let other_guard = debounce.start(&tap("3", &other), &shared);
+    assert!(other_guard.is_some());
+    drop(guard);
+
+    // ... and for the window after.
+    clock.advance(Duration::from_secs(1));
+    assert!(debounce.start(&tap("4", &message), &shared).is_none());
+    clock.advance(Duration::from_secs(2));
+    assert!(debounce.start(&tap("5", &message), &shared).is_some());
+}
+
+/// A server that returns two taps on the same message from the first getUpdates call, and
+/// records the toasts it's asked to show.
+#[derive(Clone, Default)]
+struct DoubleTapServer {
+    polls: Arc<AtomicUsize>,
+    toasts: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait::async_trait]
+impl client::Post for DoubleTapServer {
+    async fn post(&self, method: String, req: String) -> Result<String> {
+        match method.as_str() {
+            "getUpdates" if self.polls.fetch_add(1, Ordering::SeqCst) == 0 => {
+                let message = api::Message::fake("qubyte");
+                let updates: Vec<_> = (1..=2)
+                    .map(|i| api::Update {
+                        update_id: i,
+                        callback_query: Some(tap(&i.to_string(), &message)),
+                        ..Default::default()
+                    })
+                    .collect();
+                Ok(serde_json::json!({"ok": true, "result": updates}).to_string())
+            }
+            "getUpdates" => {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                Ok(r#"{"ok": true, "result": []}"#.into())
+            }
+            "answerCallbackQuery" => {
+                let req: serde_json::Value = serde_json::from_str(&req)?;
+                self.toasts
+                    .lock()
+                    .unwrap()
+                    .push(req["text"].as_str().unwrap_or_default().into());
+                Ok(r#"{"ok": true, "result": true}"#.into())
+            }
+            _ => Ok(r#"{"ok": true, "result": true}"#.into()),
+        }
+    }
+}
+
+#[tokio::test]
+async fn router_debounce() {
+    mobot::init_logger();
+    let server = DoubleTapServer::default();
+    let client = Client::new("token".to_string()).with_post_handler(server.clone());
+    let mut router: Router<()> = Router::new(client).with_callback_debounce(
+        CallbackDebounce::new(Duration::from_secs(60)).with_text("Still paying…"),
+    );
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    let payments = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&payments);
+    router.add_route(
+        Route::CallbackQuery(Matcher::Any),
+        move |e: Event, _: State<()>| {
+            let counter = Arc::clone(&counter);
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                e.acknowledge_callback(Some("Paid".into())).await?;
+                Ok(Action::Done)
+            }
+        },
+    );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    while server.toasts.lock().unwrap().len() < 2 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    // The handler ran once, and the second tap got the debounce toast.
+    assert_eq!(payments.load(Ordering::SeqCst), 1);
+    let mut toasts = server.toasts.lock().unwrap().clone();
+    toasts.sort();
+    assert_eq!(toasts, ["Paid", "Still paying…"]);
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
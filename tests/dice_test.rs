@@ -0,0 +1,83 @@
+use mobot::api::{Dice, DiceEmoji, DiceOutcome, Message, SlotSymbol};
+
+#[test]
+fn dice_emoji() {
+    assert_eq!("🎰".parse::<DiceEmoji>().unwrap(), DiceEmoji::SlotMachine);
+    assert_eq!(
+        "⚽\u{fe0f}".parse::<DiceEmoji>().unwrap(),
+        DiceEmoji::Football
+    );
+    assert!("🃏".parse::<DiceEmoji>().is_err());
+
+    for emoji in DiceEmoji::ALL {
+        assert_eq!(emoji.to_string().parse::<DiceEmoji>().unwrap(), emoji);
+        assert!(emoji.outcome(0).is_none());
+        assert!(emoji.outcome(emoji.max_value()).is_some());
+        assert!(emoji.outcome(emoji.max_value() + 1).is_none());
+    }
+}
+
+#[test]
+fn dice_outcomes() {
+    let outcome = |emoji, value| Dice::new(emoji, value).outcome().unwrap();
+
+    assert_eq!(outcome("🎲", 4), DiceOutcome::Roll(4));
+    assert_eq!(outcome("🎯", 1), DiceOutcome::Miss);
+    assert_eq!(outcome("🎯", 2), DiceOutcome::Ring(4));
+    assert_eq!(outcome("🎯", 5), DiceOutcome::Ring(1));
+    assert_eq!(outcome("🎯", 6), DiceOutcome::Bullseye);
+    assert_eq!(outcome("🎳", 1), DiceOutcome::Miss);
+    assert_eq!(outcome("🎳", 2), DiceOutcome::Pins(1));
+    assert_eq!(outcome("🎳", 5), DiceOutcome::Pins(5));
+    assert_eq!(outcome("🎳", 6), DiceOutcome::Strike);
+    assert_eq!(outcome("🏀", 3), DiceOutcome::Miss);
+    assert_eq!(outcome("🏀", 4), DiceOutcome::Score);
+    assert_eq!(outcome("⚽", 2), DiceOutcome::Miss);
+    assert_eq!(outcome("⚽", 3), DiceOutcome::Score);
+
+    assert!(Dice::new("🃏", 1).outcome().is_none());
+    assert!(Dice::new("🎲", 7).outcome().is_none());
+}
+
+#[test]
+fn slot_machine() {
+    let reels = |value| match Dice::new("🎰", value).outcome().unwrap() {
+        DiceOutcome::Slots(reels) => reels,
+        outcome => panic!("unexpected outcome {:?}", outcome),
+    };
+
+    assert_eq!(reels(1), [SlotSymbol::Bar; 3]);
+    assert_eq!(reels(22), [SlotSymbol::Grapes; 3]);
+    assert_eq!(reels(43), [SlotSymbol::Lemon; 3]);
+    assert_eq!(reels(64), [SlotSymbol::Seven; 3]);
+    assert_eq!(
+        reels(2),
+        [SlotSymbol::Grapes, SlotSymbol::Bar, SlotSymbol::Bar]
+    );
+    assert_eq!(
+        reels(17),
+        [SlotSymbol::Bar, SlotSymbol::Bar, SlotSymbol::Grapes]
+    );
+
+    let wins: Vec<i64> = (1..=64)
+        .filter(|value| Dice::new("🎰", *value).outcome().unwrap().is_win())
+        .collect();
+    assert_eq!(wins, [1, 22, 43, 64]);
+    assert!(Dice::new("🎰", 64).outcome().unwrap().is_jackpot());
+    assert!(!Dice::new("🎰", 1).outcome().unwrap().is_jackpot());
+}
+
+#[test]
+fn dice_message() {
+    let message: Message = serde_json::from_value(serde_json::json!({
+        "message_id": 1,
+        "date": 0,
+        "chat": {"id": 1, "type": "private"},
+        "dice": {"emoji": "🏀", "value": 5}
+    }))
+    .unwrap();
+
+    let dice = message.dice.unwrap();
+    assert_eq!(dice.kind(), Some(DiceEmoji::Basketball));
+    assert_eq!(dice.outcome(), Some(DiceOutcome::Score));
+}
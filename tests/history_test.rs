@@ -0,0 +1,79 @@
+use mobot::*;
+
+#[tokio::test]
+async fn history() {
+    let history = MessageHistory::default().with_capacity(3);
+
+    let mut message = api::Message::fake("qubyte");
+    message.chat.id = 5;
+    for i in 1..=4 {
+        message.message_id = i;
+        message.text = Some(format!("message {}", i));
+        history.record(&message).await.unwrap();
+    }
+
+    // Only the last three are kept, oldest first.
+    let ids = |entries: Vec<HistoryEntry>| entries.iter().map(|e| e.message_id).collect::<Vec<_>>();
+    assert_eq!(ids(history.all(5).await.unwrap()), vec![2, 3, 4]);
+    assert_eq!(ids(history.last(5, 2).await.unwrap()), vec![3, 4]);
+    assert_eq!(ids(history.last(5, 10).await.unwrap()), vec![2, 3, 4]);
+
+    // Edits replace the existing entry.
+    message.message_id = 3;
+    message.text = Some("edited".into());
+    history.record(&message).await.unwrap();
+    let entries = history.all(5).await.unwrap();
+    assert_eq!(entries[1].text.as_deref(), Some("edited"));
+    assert_eq!(entries[1].from_name.as_deref(), Some("qubyte"));
+
+    history.remove(5, 3).await.unwrap();
+    assert_eq!(ids(history.all(5).await.unwrap()), vec![2, 4]);
+
+    history.clear(5).await.unwrap();
+    assert!(history.all(5).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn router_history() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let history = MessageHistory::default();
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_message_history(history.clone());
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router.add_route(Route::Default, |e: Event, _: State<()>| async move {
+        let chat_id = e.update.chat_id()?;
+        let history = e.history.as_ref().unwrap();
+
+        // The current message is recorded before handlers run.
+        let texts: Vec<String> = history
+            .last(chat_id, 10)
+            .await?
+            .into_iter()
+            .filter_map(|e| e.text)
+            .collect();
+        Ok(Action::ReplyText(texts.join(",")))
+    });
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("one").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "one");
+
+    // Replies are recorded too.
+    chat.send_text("two").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "one,one,two");
+
+    let entries = history.all(chat.chat_id).await.unwrap();
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries[3].from_name.as_deref(), Some("mobot"));
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
@@ -0,0 +1,81 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use mobot::{clock::FakeClock, *};
+
+#[test]
+fn delay() {
+    let humanizer = Humanizer::default()
+        .with_chars_per_second(10.0)
+        .with_delay(Duration::from_millis(200), Duration::from_secs(2));
+
+    assert_eq!(humanizer.delay("hi"), Duration::from_millis(200));
+    assert_eq!(humanizer.delay("hello world"), Duration::from_millis(1100));
+    assert_eq!(humanizer.delay(&"a".repeat(100)), Duration::from_secs(2));
+}
+
+/// Returns an API that counts typing actions, answering them with `response`.
+fn typing_api(response: &'static str) -> (API, Arc<AtomicUsize>) {
+    let actions = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&actions);
+    let client =
+        Client::new("token".to_string()).with_post_handler_fn(move |method: String, _: String| {
+            assert_eq!(method, "sendChatAction");
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(response.to_string())
+        });
+    (API::new(client), actions)
+}
+
+#[tokio::test]
+async fn type_text() {
+    let humanizer = Humanizer::default()
+        .with_chars_per_second(100.0)
+        .with_delay(Duration::ZERO, Duration::from_secs(1))
+        .with_action_interval(Duration::from_millis(40));
+
+    // 10 characters take 100ms, with the typing action resent every 40ms.
+    let (api, actions) = typing_api(r#"{"ok": true, "result": true}"#);
+    let start = Instant::now();
+    humanizer.type_text(&api, 1, "0123456789").await;
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert!((2..=3).contains(&actions.load(Ordering::SeqCst)));
+
+    // Rate limited typing actions aren't retried, but the reply still waits.
+    let (api, actions) = typing_api(
+        r#"{"ok": false, "description": "Too Many Requests: retry after 5", "error_code": 429}"#,
+    );
+    let start = Instant::now();
+    humanizer.type_text(&api, 1, "0123456789").await;
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(actions.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn fake_clock() {
+    let clock = FakeClock::at(1_000_000);
+    let humanizer = Humanizer::default()
+        .with_chars_per_second(100.0)
+        .with_delay(Duration::ZERO, Duration::from_secs(1))
+        .with_action_interval(Duration::from_millis(40));
+    let (api, actions) = typing_api(r#"{"ok": true, "result": true}"#);
+    let api = api.with_clock(clock.clone());
+    let typing = tokio::spawn(async move { humanizer.type_text(&api, 1, "0123456789").await });
+
+    // The reply only waits for the API's clock.
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(actions.load(Ordering::SeqCst), 1);
+    assert!(!typing.is_finished());
+
+    for _ in 0..3 {
+        clock.advance(Duration::from_millis(40));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(typing.is_finished());
+    assert_eq!(actions.load(Ordering::SeqCst), 3);
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "inline")]
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use mobot::{api::API, clock::FakeClock, inline_cache::InlineCache, *};
+
+fn query(id: &str, text: &str) -> api::InlineQuery {
+    api::InlineQuery {
+        id: id.into(),
+        from: api::User::default(),
+        query: text.into(),
+        offset: "".into(),
+    }
+}
+
+#[tokio::test]
+async fn inline_cache() {
+    let answers = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&answers);
+    let client =
+        Client::new("token".to_string()).with_post_handler_fn(move |_: String, req: String| {
+            recorded
+                .lock()
+                .unwrap()
+                .push(serde_json::from_str::<serde_json::Value>(&req).unwrap());
+            Ok(r#"{"ok": true, "result": true}"#.to_string())
+        });
+    let clock = FakeClock::at(1_700_000_000);
+    let api = API::new(client).with_clock(clock.clone());
+
+    let cache = InlineCache::new(Duration::from_secs(60));
+    let lookups = AtomicUsize::new(0);
+    let answer = |q: api::InlineQuery| {
+        let (cache, api, lookups) = (&cache, &api, &lookups);
+        async move {
+            cache
+                .answer(api, &q, || async {
+                    let n = lookups.fetch_add(1, Ordering::SeqCst);
+                    Ok(api::AnswerInlineQuery::new(q.id.clone())
+                        .with_article_text("Result", format!("lookup {}", n)))
+                })
+                .await
+                .unwrap()
+        }
+    };
+
+    // Queries that normalize to the same key share an answer, sent with their own query ID.
+    answer(query("1", "Rust  Books")).await;
+    answer(query("2", " rust books ")).await;
+    assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    {
+        let answers = answers.lock().unwrap();
+        assert_eq!(answers[1]["inline_query_id"], "2");
+        assert_eq!(
+            answers[1]["results"][0]["input_message_content"]["message_text"],
+            "lookup 0"
+        );
+    }
+
+    // Other queries and other pages are looked up.
+    answer(query("3", "rust")).await;
+    let mut next_page = query("4", "rust books");
+    next_page.offset = "10".into();
+    answer(next_page).await;
+    assert_eq!(lookups.load(Ordering::SeqCst), 3);
+    assert_eq!(cache.len(), 3);
+
+    // Answers expire after the TTL.
+    clock.advance(Duration::from_secs(60));
+    answer(query("5", "rust books")).await;
+    assert_eq!(lookups.load(Ordering::SeqCst), 4);
+
+    // Invalidating a query drops all its pages.
+    cache.invalidate("RUST BOOKS");
+    assert_eq!(cache.len(), 1);
+    answer(query("6", "rust books")).await;
+    assert_eq!(lookups.load(Ordering::SeqCst), 5);
+}
+
+#[test]
+fn personal_answers_not_cached() {
+    let cache = InlineCache::new(Duration::from_secs(60));
+    let q = query("1", "me");
+    cache.insert(
+        &q,
+        api::AnswerInlineQuery {
+            is_personal: Some(true),
+            ..api::AnswerInlineQuery::new("1".into())
+        },
+        0,
+    );
+    assert!(cache.is_empty());
+
+    cache.insert(&q, api::AnswerInlineQuery::new("1".into()), 0);
+    assert!(cache.get(&q, 59).is_some());
+    assert!(cache.get(&q, 60).is_none());
+}
+
+#[test]
+fn capacity() {
+    let cache = InlineCache::new(Duration::from_secs(60)).with_capacity(2);
+    for (i, text) in ["a", "b", "c"].into_iter().enumerate() {
+        cache.insert(
+            &query("1", text),
+            api::AnswerInlineQuery::new("1".into()),
+            i as i64,
+        );
+    }
+
+    // The oldest answer was dropped to make room.
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(&query("1", "a"), 2).is_none());
+    assert!(cache.get(&query("1", "b"), 2).is_some());
+    assert!(cache.get(&query("1", "c"), 2).is_some());
+
+    // Replacing an answer makes it the newest.
+    cache.insert(&query("1", "b"), api::AnswerInlineQuery::new("1".into()), 3);
+    cache.insert(&query("1", "d"), api::AnswerInlineQuery::new("1".into()), 4);
+    assert!(cache.get(&query("1", "b"), 4).is_some());
+    assert!(cache.get(&query("1", "c"), 4).is_none());
+
+    // Expired answers are the oldest, so they're dropped first.
+    cache.insert(
+        &query("1", "e"),
+        api::AnswerInlineQuery::new("1".into()),
+        63,
+    );
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(&query("1", "d"), 63).is_some());
+}
@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use mobot::{api::*, keyboards, Client, KeyboardState};
+use serde_json::{json, Value};
+
+fn menu() -> Vec<Vec<InlineKeyboardButton>> {
+    vec![
+        vec![
+            InlineKeyboardButton::from("News").with_callback_data("news"),
+            InlineKeyboardButton::from("Sports").with_callback_data("sports"),
+        ],
+        vec![InlineKeyboardButton::from("Done").with_callback_data("done")],
+    ]
+}
+
+#[test]
+fn diff() {
+    let mut new = menu();
+    new[0][1].text = "✅ Sports".into();
+    new[1].pop();
+
+    let changes = keyboards::diff(&menu(), &new);
+    assert_eq!(changes.len(), 2);
+    assert_eq!((changes[0].row, changes[0].column), (0, 1));
+    assert_eq!(changes[0].new.as_ref().unwrap().text, "✅ Sports");
+    assert_eq!((changes[1].row, changes[1].column), (1, 0));
+    assert_eq!(changes[1].new, None);
+
+    assert!(keyboards::diff(&menu(), &menu()).is_empty());
+}
+
+#[tokio::test]
+async fn toggle() {
+    let edits = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&edits);
+    let client = Client::new("token".to_string()).with_post_handler_fn(
+        move |method: String, req: String| {
+            let mut req: Value = serde_json::from_str(&req).unwrap();
+            // Reply markup is sent as a JSON-encoded string.
+            req["reply_markup"] =
+                serde_json::from_str(req["reply_markup"].as_str().unwrap()).unwrap();
+            let response = json!({"ok": true, "result": {
+                "message_id": req["message_id"],
+                "date": 0,
+                "chat": {"id": req["chat_id"], "type": "private"},
+                "reply_markup": req["reply_markup"],
+            }});
+            recorded.lock().unwrap().push((method, req));
+            Ok(response.to_string())
+        },
+    );
+    let api = API::new(client);
+
+    let keyboards = KeyboardState::default();
+    let mut message = Message::fake("qubyte");
+    message.message_id = 7;
+    message.reply_markup = Some(ReplyMarkup::inline_keyboard_markup(menu()));
+    keyboards.record(&message).await.unwrap();
+    let chat_id = message.chat.id;
+
+    assert!(keyboards.toggle(&api, chat_id, 7, "sports").await.unwrap());
+    assert!(keyboards.toggle(&api, chat_id, 7, "news").await.unwrap());
+    assert!(!keyboards.toggle(&api, chat_id, 7, "sports").await.unwrap());
+    assert_eq!(keyboards.checked(chat_id, 7).await.unwrap(), vec!["news"]);
+    assert!(keyboards.toggle(&api, chat_id, 7, "weather").await.is_err());
+
+    {
+        let edits = edits.lock().unwrap();
+        assert_eq!(edits.len(), 3);
+        assert_eq!(edits[0].0, "editMessageReplyMarkup");
+        assert_eq!(edits[0].1["message_id"], 7);
+        assert_eq!(
+            edits[0].1["reply_markup"]["inline_keyboard"][0][1]["text"],
+            "✅ Sports"
+        );
+    }
+
+    // Unchanged keyboards aren't sent.
+    let changes = keyboards
+        .update(&api, chat_id, 7, |keyboard| {
+            keyboard[1][0].text = "Done".into()
+        })
+        .await
+        .unwrap();
+    assert!(changes.is_empty());
+    assert_eq!(edits.lock().unwrap().len(), 3);
+
+    // Keyboards of unknown messages can't be updated.
+    assert!(keyboards.update(&api, chat_id, 8, |_| ()).await.is_err());
+
+    // Recording a message without a keyboard forgets it.
+    message.reply_markup = None;
+    keyboards.record(&message).await.unwrap();
+    assert_eq!(keyboards.get(chat_id, 7).await.unwrap(), None);
+}
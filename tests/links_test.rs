@@ -0,0 +1,90 @@
+use mobot::{
+    links::{self, DomainList},
+    *,
+};
+
+#[test]
+fn message_urls() {
+    let message: api::Message = serde_json::from_str(
+        r#"{
+            "message_id": 1, "date": 0, "chat": {"id": -1, "type": "group"},
+            "text": "🎉 see example.com/a and this, or t.me/+AbCdEf",
+            "entities": [
+                {"type": "url", "offset": 7, "length": 13},
+                {"type": "text_link", "offset": 25, "length": 4, "url": "https://evil.test/"},
+                {"type": "bold", "offset": 0, "length": 2},
+                {"type": "url", "offset": 34, "length": 12}
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        message.urls(),
+        vec!["example.com/a", "https://evil.test/", "t.me/+AbCdEf"]
+    );
+    assert_eq!(message.invite_links(), vec!["t.me/+AbCdEf"]);
+    assert!(api::Message::new("qubyte", "no links").urls().is_empty());
+}
+
+#[test]
+fn invite_links() {
+    assert!(links::is_invite_link("https://t.me/+AbCdEf123"));
+    assert!(links::is_invite_link("t.me/joinchat/AbCdEf123"));
+    assert!(links::is_invite_link("http://www.telegram.me/+AbCdEf123"));
+    assert!(links::is_invite_link("tg://join?invite=AbCdEf123"));
+
+    assert!(!links::is_invite_link("t.me/+15551234567"));
+    assert!(!links::is_invite_link("t.me/mobot"));
+    assert!(!links::is_invite_link("t.me/joinchat"));
+    assert!(!links::is_invite_link("example.com/+AbCdEf123"));
+    assert!(!links::is_invite_link("tg://resolve?domain=mobot"));
+}
+
+#[test]
+fn domains() {
+    assert_eq!(
+        links::domain("HTTPS://WWW.Example.com/a?b").as_deref(),
+        Some("example.com")
+    );
+    assert_eq!(links::domain("docs.rs").as_deref(), Some("docs.rs"));
+    assert_eq!(links::domain("mailto:me@example.com"), None);
+
+    let deny = DomainList::new().with_denied("example.com");
+    assert!(!deny.is_allowed("example.com"));
+    assert!(!deny.is_allowed("https://a.b.example.com/x"));
+    assert!(deny.is_allowed("notexample.com"));
+    assert!(deny.is_allowed("mailto:me@example.com"));
+
+    let allow = DomainList::new()
+        .with_allowed("www.rust-lang.org")
+        .with_denied("evil.rust-lang.org");
+    assert!(allow.is_allowed("rust-lang.org"));
+    assert!(allow.is_allowed("doc.rust-lang.org/std"));
+    assert!(!allow.is_allowed("evil.rust-lang.org"));
+    assert!(!allow.is_allowed("mailto:me@rust-lang.org"));
+    assert_eq!(
+        allow.disallowed(["rust-lang.org", "example.com"]),
+        vec!["example.com"]
+    );
+}
+
+#[test]
+fn start_links() {
+    assert_eq!(
+        links::start_link("@my_bot", "ref-42_a").as_deref(),
+        Some("https://t.me/my_bot?start=ref-42_a")
+    );
+    assert_eq!(links::start_link("my_bot", "no spaces"), None);
+    assert_eq!(links::start_link("my_bot", &"a".repeat(65)), None);
+    assert_eq!(links::start_link("my_bot", ""), None);
+
+    assert_eq!(links::start_payload("/start ref-42_a"), Some("ref-42_a"));
+    assert_eq!(
+        links::start_payload("/start@my_bot  ref-42_a "),
+        Some("ref-42_a")
+    );
+    assert_eq!(links::start_payload("/start"), None);
+    assert_eq!(links::start_payload("/started ref"), None);
+    assert_eq!(links::start_payload("hello /start ref"), None);
+}
@@ -0,0 +1,199 @@
+#![cfg(feature = "llm")]
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use mobot::{integrations::llm::*, *};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Replies by echoing the last message back, a word at a time.
+struct Echo;
+
+#[async_trait]
+impl ChatCompletion for Echo {
+    async fn stream(&self, messages: &[ChatMessage]) -> anyhow::Result<CompletionStream> {
+        let last = messages.last().unwrap().content.clone();
+        let words: Vec<anyhow::Result<String>> =
+            format!("echo: {} ({} messages)", last, messages.len())
+                .split_inclusive(' ')
+                .map(|w| Ok(w.to_string()))
+                .collect();
+        Ok(futures::stream::iter(words).boxed())
+    }
+}
+
+#[tokio::test]
+async fn stream_reply_with_history() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_message_history(MessageHistory::default());
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    let llm = Arc::new(Echo);
+    router.add_route(Route::Default, move |e: Event, _: State<()>| {
+        let llm = Arc::clone(&llm);
+        async move {
+            let history = e.history.as_ref().unwrap().all(e.update.chat_id()?).await?;
+            let mut messages = vec![ChatMessage::system("Be brief.")];
+            messages.extend(ChatMessage::from_history(&history));
+            stream_reply(&e, llm.as_ref(), &messages).await?;
+            Ok(Action::Done)
+        }
+    });
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("hello").await.unwrap();
+
+    // The first word is sent, and the rest arrives in a single edit.
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "echo: ");
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "echo: hello (2 messages)"
+    );
+
+    // The reply was recorded as an assistant message.
+    chat.send_text("again").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "echo: ");
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "echo: again (4 messages)"
+    );
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+/// Replies with `deltas`, and then fails if `fail` is set.
+struct Canned {
+    deltas: Vec<String>,
+    fail: bool,
+}
+
+#[async_trait]
+impl ChatCompletion for Canned {
+    async fn stream(&self, _: &[ChatMessage]) -> anyhow::Result<CompletionStream> {
+        let mut deltas: Vec<anyhow::Result<String>> = self.deltas.iter().cloned().map(Ok).collect();
+        if self.fail {
+            deltas.push(Err(anyhow::anyhow!("connection lost")));
+        }
+        Ok(futures::stream::iter(deltas).boxed())
+    }
+}
+
+#[tokio::test]
+async fn stream_reply_split_and_failed() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let history = MessageHistory::default();
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_message_history(history.clone());
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router.add_route(Route::Default, move |e: Event, _: State<()>| async move {
+        let llm = match e.update.text()? {
+            "long" => Canned {
+                deltas: vec!["a".repeat(5000)],
+                fail: false,
+            },
+            _ => Canned {
+                deltas: vec!["partial".into(), " reply".into()],
+                fail: true,
+            },
+        };
+        stream_reply(&e, &llm, &[]).await?;
+        Ok(Action::ReplyText("done".into()))
+    });
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    // Each part of a long reply is recorded with its own text.
+    chat.send_text("long").await.unwrap();
+    chat.recv_update().await.unwrap();
+    chat.recv_update().await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done");
+    let lengths: Vec<usize> = history
+        .all(chat.chat_id)
+        .await
+        .unwrap()
+        .iter()
+        .filter_map(|entry| entry.text.as_ref().filter(|text| text.starts_with('a')))
+        .map(|text| text.chars().count())
+        .collect();
+    assert_eq!(lengths, vec![4096, 904]);
+
+    // If the stream fails, what arrived is shown before the error is handled.
+    chat.send_text("fail").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "partial");
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "partial reply"
+    );
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "Handler error: connection lost"
+    );
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn openai_stream() {
+    // Serve a canned streaming response, split mid-event to exercise buffering.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = vec![0; 8192];
+        let n = socket.read(&mut request).await.unwrap();
+        let request = String::from_utf8_lossy(&request[..n]).to_string();
+        assert!(request.starts_with("POST /v1/chat/completions"));
+        assert!(request.contains("authorization: Bearer sk-test"));
+
+        let events = concat!(
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"lo!\"}}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let (first, second) = events.split_at(70);
+        socket
+            .write_all(
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\n\r\n{}",
+                    events.len(),
+                    first
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        socket.flush().await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        socket.write_all(second.as_bytes()).await.unwrap();
+    });
+
+    let llm = OpenAiChat::new("sk-test", "test-model").with_base_url(format!("http://{}/v1", addr));
+    let deltas: Vec<String> = llm
+        .stream(&[ChatMessage::user("hi")])
+        .await
+        .unwrap()
+        .map(|d| d.unwrap())
+        .collect()
+        .await;
+    assert_eq!(deltas, vec!["Hel", "lo!"]);
+}
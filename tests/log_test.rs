@@ -0,0 +1,89 @@
+use mobot::{
+    handlers::log::{LogHandler, Redaction},
+    *,
+};
+
+fn private_message(user_id: i64, text: &str) -> Update {
+    Update::Message(
+        serde_json::from_value(serde_json::json!({
+            "message_id": 7, "date": 0,
+            "chat": {"id": user_id, "type": "private"},
+            "from": {"id": user_id, "is_bot": false, "first_name": "Alice", "username": "alice"},
+            "text": text,
+        }))
+        .unwrap(),
+    )
+}
+
+#[test]
+fn summarize() {
+    let summary = LogHandler::new(Redaction::none()).summarize(&private_message(42, "hi there"));
+    assert_eq!(
+        summary,
+        r#"kind=message chat=42 message_id=7 user=42 name="Alice" username="alice" text="hi there""#
+    );
+}
+
+#[test]
+fn redacted_summary() {
+    let handler = LogHandler::new(Redaction::strict("pepper"));
+    let summary = handler.summarize(&private_message(987654321, "my secret"));
+
+    assert!(summary.starts_with("kind=message chat="));
+    assert!(summary.ends_with("text=<9 chars>"));
+    for pii in ["987654321", "Alice", "alice", "secret"] {
+        assert!(!summary.contains(pii), "{} leaked in: {}", pii, summary);
+    }
+
+    // Hashes are stable for a user and salt, so activity can still be correlated.
+    assert_eq!(
+        summary,
+        handler.summarize(&private_message(987654321, "the codes"))
+    );
+    assert_ne!(
+        summary,
+        handler.summarize(&private_message(987654322, "the codes"))
+    );
+    assert_ne!(
+        summary,
+        LogHandler::new(Redaction::strict("salt"))
+            .summarize(&private_message(987654321, "the codes"))
+    );
+
+    // Group IDs aren't personal, and are left alone.
+    let mut update = private_message(42, "hello");
+    if let Update::Message(message) = &mut update {
+        message.chat.id = -100;
+    }
+    assert!(handler.summarize(&update).contains("chat=-100 "));
+}
+
+#[tokio::test]
+async fn redacting_log_handler() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client).with_poll_timeout_s(1);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    // The log handler passes every update on to the next handler.
+    router
+        .add_route(
+            Route::Default,
+            handlers::redacting_log_handler(Redaction::strict("pepper")),
+        )
+        .add_route(Route::Default, |_, _: State<()>| async move {
+            Ok(Action::ReplyText("logged".into()))
+        });
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "logged");
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use mobot::{api::API, *};
+
+#[tokio::test]
+async fn send_with_spoiler() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let api = Arc::new(API::new(
+        Client::new("token".to_string()).with_post_handler(fakeserver.clone()),
+    ));
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    let sent = api
+        .send_photo(
+            &api::SendPhotoRequest::new(chat.chat_id, "photo-id")
+                .with_caption("not safe for work")
+                .with_spoiler(true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(sent.has_media_spoiler, Some(true));
+
+    let Update::Message(received) = chat.recv_update().await.unwrap() else {
+        panic!("expected a message");
+    };
+    assert_eq!(received.photo.unwrap()[0].file_id, "photo-id");
+    assert_eq!(received.caption.as_deref(), Some("not safe for work"));
+    assert_eq!(received.has_media_spoiler, Some(true));
+
+    api.send_video(&api::SendVideoRequest::new(chat.chat_id, "video-id"))
+        .await
+        .unwrap();
+    let Update::Message(received) = chat.recv_update().await.unwrap() else {
+        panic!("expected a message");
+    };
+    assert_eq!(received.video.unwrap().file_id, "video-id");
+    assert_eq!(received.has_media_spoiler, None);
+}
+
+#[test]
+fn spoiler_serialization() {
+    let req = api::SendVideoRequest::new(1, "video-id").with_spoiler(true);
+    let json = serde_json::to_value(&req).unwrap();
+    assert_eq!(json["has_spoiler"], true);
+    assert!(json.get("caption").is_none());
+}
+
+#[tokio::test]
+async fn upload_progress() {
+    let fakeserver = fake::FakeAPI::new();
+    let api = API::new(Client::new("token".to_string()).with_post_handler(fakeserver.clone()));
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    let progress = Arc::new(std::sync::Mutex::new(vec![]));
+    let reports = Arc::clone(&progress);
+    let file = api::InputFile::new("video.mp4", vec![0; 150 * 1024])
+        .with_mime_type("video/mp4")
+        .with_progress(move |sent, total| reports.lock().unwrap().push((sent, total)));
+
+    api.send_video_file(&api::SendVideoRequest::new(chat.chat_id, "video-id"), &file)
+        .await
+        .unwrap();
+    let Update::Message(received) = chat.recv_update().await.unwrap() else {
+        panic!("expected a message");
+    };
+    assert!(received.video.is_some());
+
+    // Reported after each 64KiB chunk.
+    let total = 150 * 1024;
+    assert_eq!(
+        *progress.lock().unwrap(),
+        vec![(64 * 1024, total), (128 * 1024, total), (total, total)]
+    );
+}
+
+#[tokio::test]
+async fn local_server() {
+    let dir = std::env::temp_dir().join(format!("mobot-{}", rand::random::<u64>()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let video = dir.join("video.mp4");
+    std::fs::write(&video, b"video").unwrap();
+
+    let sent = Arc::new(std::sync::Mutex::new(vec![]));
+    let requests = Arc::clone(&sent);
+    let client = Client::new("123:secret".to_string())
+        .with_server_url("http://localhost:8081/")
+        .with_local_mode(true)
+        .with_post_handler_fn(move |_, req: String| {
+            requests.lock().unwrap().push(req);
+            Ok(r#"{"ok": true, "result": {"message_id": 1, "date": 0, "chat": {"id": 1, "type": "private"}}}"#.to_string())
+        });
+    assert!(client
+        .curl_command("getMe", &serde_json::json!({}))
+        .starts_with("curl -X POST 'http://localhost:8081/bot<TOKEN>/getMe'"));
+    let api = API::new(client);
+
+    // Files on disk are passed by path, without reading them.
+    api.send_video_file(
+        &api::SendVideoRequest::new(1, ""),
+        &api::InputFile::path(&video),
+    )
+    .await
+    .unwrap();
+    let req: serde_json::Value = serde_json::from_str(&sent.lock().unwrap()[0]).unwrap();
+    assert_eq!(req["video"], format!("file://{}", video.display()));
+
+    // getFile returns absolute paths, which are read from disk.
+    let path = video.display().to_string();
+    let data = api
+        .download_file(&api::DownloadRequest::new(path.clone()))
+        .await
+        .unwrap();
+    assert_eq!(&data[..], b"video");
+    let data = api
+        .download_file(&api::DownloadRequest::new(format!("file://{}", path)))
+        .await
+        .unwrap();
+    assert_eq!(&data[..], b"video");
+
+    // Telegram's servers take uploads up to 50MB.
+    let large = dir.join("large.mp4");
+    std::fs::File::create(&large)
+        .unwrap()
+        .set_len(client::MAX_UPLOAD_SIZE + 1)
+        .unwrap();
+    let cloud = API::new(
+        Client::new("token".to_string()).with_post_handler_fn(|_, _| {
+            panic!("large uploads shouldn't be sent");
+        }),
+    );
+    let err = cloud
+        .send_video_file(
+            &api::SendVideoRequest::new(1, ""),
+            &api::InputFile::path(&large),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("local Bot API server"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn reupload() {
+    let dir = std::env::temp_dir().join(format!("mobot-{}", rand::random::<u64>()));
+    for kind in ["photos", "documents"] {
+        std::fs::create_dir_all(dir.join(kind)).unwrap();
+    }
+    std::fs::write(dir.join("photos/file_0.jpg"), b"photo").unwrap();
+    std::fs::write(dir.join("documents/file_1.pdf"), b"document").unwrap();
+
+    let sent = Arc::new(std::sync::Mutex::new(vec![]));
+    let requests = Arc::clone(&sent);
+    let files = dir.clone();
+    let client = Client::new("token".to_string())
+        .with_local_mode(true)
+        .with_post_handler_fn(move |method: String, req: String| {
+            let req: serde_json::Value = serde_json::from_str(&req).unwrap();
+            if method == "getFile" {
+                let path = match req["file_id"].as_str().unwrap() {
+                    "photo-id" => files.join("photos/file_0.jpg"),
+                    _ => files.join("documents/file_1.pdf"),
+                };
+                return Ok(serde_json::json!({
+                    "ok": true,
+                    "result": {"file_id": req["file_id"], "file_path": path}
+                })
+                .to_string());
+            }
+            requests.lock().unwrap().push((method, req));
+            Ok(r#"{"ok": true, "result": {"message_id": 1, "date": 0, "chat": {"id": 2, "type": "private"}}}"#.to_string())
+        });
+    let api = API::new(client);
+
+    api.reupload("photo-id", 2).await.unwrap();
+    api.reupload("document-id", 2).await.unwrap();
+
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent[0].0, "sendPhoto");
+    assert_eq!(sent[0].1["chat_id"], 2);
+    assert_eq!(
+        sent[0].1["photo"],
+        format!("file://{}", dir.join("photos/file_0.jpg").display())
+    );
+    assert_eq!(sent[1].0, "sendDocument");
+    assert_eq!(
+        sent[1].1["document"],
+        format!("file://{}", dir.join("documents/file_1.pdf").display())
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
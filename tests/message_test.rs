@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use mobot::{api::API, *};
+
+fn group_message(json: &str) -> api::Message {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn entity_text() {
+    // Offsets and lengths are in UTF-16 code units, so the emoji counts as two.
+    let message = group_message(
+        r#"{
+            "message_id": 1, "date": 0, "chat": {"id": -1, "type": "group"},
+            "text": "🎉 hey @MoBot and @other",
+            "entities": [
+                {"type": "mention", "offset": 7, "length": 6},
+                {"type": "mention", "offset": 18, "length": 6}
+            ]
+        }"#,
+    );
+
+    let text = message.text.as_ref().unwrap();
+    let entities = message.entities.as_ref().unwrap();
+    assert_eq!(entities[0].text(text).unwrap(), "@MoBot");
+    assert_eq!(entities[1].text(text).unwrap(), "@other");
+
+    assert!(message.mentions_bot("mobot"));
+    assert!(message.mentions_bot("@mobot"));
+    assert!(!message.mentions_bot("somebot"));
+}
+
+#[test]
+fn bot_command_mention() {
+    let message = group_message(
+        r#"{
+            "message_id": 1, "date": 0, "chat": {"id": -1, "type": "group"},
+            "text": "/start@mobot now",
+            "entities": [{"type": "bot_command", "offset": 0, "length": 12}]
+        }"#,
+    );
+    assert!(message.mentions_bot("mobot"));
+
+    // A command without a username isn't addressed to any bot in particular.
+    let message = group_message(
+        r#"{
+            "message_id": 1, "date": 0, "chat": {"id": -1, "type": "group"},
+            "text": "/start",
+            "entities": [{"type": "bot_command", "offset": 0, "length": 6}]
+        }"#,
+    );
+    assert!(!message.mentions_bot("mobot"));
+}
+
+#[tokio::test]
+async fn addressed_to_bot() {
+    mobot::init_logger();
+
+    // The FakeAPI bot is "mobot", with user ID 0.
+    let fakeserver = fake::FakeAPI::new();
+    let api = Arc::new(API::new(
+        Client::new("token".to_string()).with_post_handler(fakeserver),
+    ));
+
+    let mention = group_message(
+        r#"{
+            "message_id": 2, "date": 0, "chat": {"id": -1, "type": "group"},
+            "text": "@mobot hi",
+            "entities": [{"type": "mention", "offset": 0, "length": 6}]
+        }"#,
+    );
+    let e = Event::new(Arc::clone(&api), Update::Message(mention));
+    assert!(e.mentions_bot().await.unwrap());
+    assert!(!e.is_reply_to_bot().await.unwrap());
+
+    let reply = group_message(
+        r#"{
+            "message_id": 3, "date": 0, "chat": {"id": -1, "type": "group"},
+            "text": "thanks",
+            "reply_to_message": {
+                "message_id": 1, "date": 0, "chat": {"id": -1, "type": "group"},
+                "from": {"id": 0, "is_bot": true, "first_name": "mobot"},
+                "text": "hello"
+            }
+        }"#,
+    );
+    let e = Event::new(Arc::clone(&api), Update::Message(reply));
+    assert!(!e.mentions_bot().await.unwrap());
+    assert!(e.is_reply_to_bot().await.unwrap());
+
+    // The bot user is cached after the first call.
+    assert_eq!(api.client.stats().requests, 1);
+}
+
+#[tokio::test]
+async fn failed_request_in_error() {
+    let client = Client::new("token".to_string()).with_post_handler_fn(|_, _| {
+        Ok(r#"{"ok": false, "description": "Bad Request: can't parse entities"}"#.to_string())
+    });
+    let api = Arc::new(API::new(client));
+
+    let message = group_message(
+        r#"{"message_id": 1, "date": 0, "chat": {"id": -1, "type": "group"}, "text": "hi"}"#,
+    );
+    let e = Event::new(api, Update::Message(message));
+    let err = e.send_message("*unclosed").await.unwrap_err();
+
+    // The error shows what was sent, and the API error can still be inspected.
+    let failed = err.downcast_ref::<api::FailedRequest>().unwrap();
+    assert_eq!(failed.method, "sendMessage");
+    assert_eq!(failed.request["chat_id"], -1);
+    assert_eq!(failed.request["text"], "*unclosed");
+    assert!(err.to_string().contains("can't parse entities"));
+    assert!(err.to_string().contains("*unclosed"));
+    assert!(matches!(
+        err.downcast_ref::<api::ApiError>(),
+        Some(api::ApiError::AppError(_))
+    ));
+}
+
+#[test]
+fn caption_mention() {
+    let message = group_message(
+        r#"{
+            "message_id": 1, "date": 0, "chat": {"id": -1, "type": "group"},
+            "photo": [],
+            "caption": "look @mobot",
+            "caption_entities": [{"type": "mention", "offset": 5, "length": 6}],
+            "show_caption_above_media": true
+        }"#,
+    );
+
+    assert_eq!(message.text_or_caption(), Some("look @mobot"));
+    assert_eq!(message.show_caption_above_media, Some(true));
+    assert!(message.mentions_bot("mobot"));
+}
+
+#[tokio::test]
+async fn message_effect() {
+    let fakeserver = fake::FakeAPI::new();
+    let api = API::new(Client::new("token".to_string()).with_post_handler(fakeserver.clone()));
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    let sent = api
+        .send_message(
+            &api::SendMessageRequest::new(chat.chat_id, "congrats!")
+                .with_message_effect_id("5046509860389126442"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(sent.effect_id.as_deref(), Some("5046509860389126442"));
+
+    #[cfg(feature = "stickers")]
+    {
+        let req = api::SendStickerRequest::new(chat.chat_id, "sticker-id".into())
+            .with_message_effect_id("5046509860389126442");
+        let json = serde_json::to_value(&req).unwrap();
+        assert_eq!(json["message_effect_id"], "5046509860389126442");
+    }
+}
+
+#[tokio::test]
+async fn emoji_status() {
+    let fakeserver = fake::FakeAPI::new();
+    let api = API::new(Client::new("token".to_string()).with_post_handler(fakeserver));
+
+    assert_eq!(api.get_user_emoji_status(7).await.unwrap(), None);
+
+    assert!(api
+        .set_user_emoji_status(
+            &api::SetUserEmojiStatusRequest::new(7, "emoji-id").with_expiration_date(1_800_000_000)
+        )
+        .await
+        .unwrap());
+    assert_eq!(
+        api.get_user_emoji_status(7).await.unwrap(),
+        Some(api::EmojiStatus {
+            custom_emoji_id: "emoji-id".into(),
+            expiration_date: Some(1_800_000_000),
+        })
+    );
+
+    api.set_user_emoji_status(&api::SetUserEmojiStatusRequest::clear(7))
+        .await
+        .unwrap();
+    assert_eq!(api.get_user_emoji_status(7).await.unwrap(), None);
+}
+
+#[test]
+fn update_date() {
+    let message = group_message(
+        r#"{"message_id": 1, "date": 1000, "edit_date": 2000, "chat": {"id": -1, "type": "group"}}"#,
+    );
+
+    // Edits are dated by when they were made, not when the message was first sent.
+    let edited = api::Update {
+        edited_message: Some(message.clone()),
+        ..Default::default()
+    };
+    assert_eq!(edited.date(), Some(2000));
+
+    let sent = api::Update {
+        message: Some(api::Message {
+            edit_date: None,
+            ..message
+        }),
+        ..Default::default()
+    };
+    assert_eq!(sent.date(), Some(1000));
+}
@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use mobot::*;
+
+#[test]
+fn handler_metrics() {
+    let metrics = HandlerMetrics::new().with_slow_threshold(Duration::from_millis(100));
+    assert!(!metrics.record("fast", Duration::from_millis(10), true));
+    assert!(!metrics.record("fast", Duration::from_millis(30), false));
+    assert!(metrics.record("slow", Duration::from_millis(200), true));
+
+    let fast = metrics.get("fast").unwrap();
+    assert_eq!(fast.invocations, 2);
+    assert_eq!(fast.errors, 1);
+    assert_eq!(fast.slow, 0);
+    assert_eq!(fast.avg_latency(), Duration::from_millis(20));
+    assert_eq!(fast.max_latency, Duration::from_millis(30));
+
+    // Handlers that take the most time come first.
+    let names: Vec<_> = metrics.snapshot().into_iter().map(|(n, _)| n).collect();
+    assert_eq!(names, vec!["slow", "fast"]);
+
+    metrics.reset();
+    assert!(metrics.get("fast").is_none());
+}
+
+#[tokio::test]
+async fn slow_handlers() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_slow_handler_warning(Duration::from_millis(50));
+    let metrics = router.handler_metrics().clone();
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_named_route(
+            "slow",
+            Route::Message(Matcher::Exact("slow".into())),
+            |_: Event, _: State<()>| async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(Action::ReplyText("done".into()))
+            },
+        )
+        .add_named_route(
+            "runs",
+            Route::Message(Matcher::Any),
+            |e: Event, _: State<()>| async move {
+                let runs = e.metrics.get("runs").map_or(0, |s| s.invocations);
+                Ok(Action::ReplyText(format!("runs: {}", runs)))
+            },
+        );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    chat.send_text("slow").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done");
+
+    // Handlers see the counters so far.
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "runs: 0");
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "runs: 1");
+
+    let slow = metrics.get("slow").unwrap();
+    assert_eq!(slow.invocations, 1);
+    assert_eq!(slow.slow, 1);
+    assert!(slow.max_latency >= Duration::from_millis(100));
+
+    let fast = metrics.get("runs").unwrap();
+    assert_eq!(fast.invocations, 2);
+    assert_eq!(fast.slow, 0);
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+async fn first(_: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+    Ok(Action::Next)
+}
+
+async fn second(_: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+    Ok(Action::ReplyText("done".into()))
+}
+
+#[tokio::test]
+async fn handlers_on_one_route() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client).with_poll_timeout_s(1);
+    let metrics = router.handler_metrics().clone();
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_route(Route::Message(Matcher::Any), first)
+        .add_route(Route::Message(Matcher::Any), second);
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done");
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done");
+
+    // Each handler has counters of its own, named after its function.
+    assert_eq!(metrics.get("metrics_test::first").unwrap().invocations, 2);
+    assert_eq!(metrics.get("metrics_test::second").unwrap().invocations, 2);
+    assert!(metrics.get("Message(Any)").is_none());
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
@@ -0,0 +1,166 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use mobot::{api::API, *};
+
+type Calls = Arc<Mutex<Vec<(String, serde_json::Value)>>>;
+
+/// Returns an API that answers every call with `true`, and the calls (method and request) it
+/// received.
+fn recording_api() -> (API, Calls) {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&calls);
+    let client = Client::new("token".to_string()).with_post_handler_fn(
+        move |method: String, req: String| {
+            recorded
+                .lock()
+                .unwrap()
+                .push((method, serde_json::from_str(&req).unwrap()));
+            Ok(r#"{"ok": true, "result": true}"#.to_string())
+        },
+    );
+
+    (API::new(client), calls)
+}
+
+#[tokio::test]
+async fn kick() {
+    let (api, calls) = recording_api();
+    assert!(api.kick_chat_member(-100, 42).await.unwrap());
+
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].0, "banChatMember");
+    assert_eq!(calls[0].1["chat_id"], "-100");
+    assert_eq!(calls[0].1["user_id"], 42);
+    assert_eq!(calls[1].0, "unbanChatMember");
+    assert_eq!(calls[1].1["only_if_banned"], true);
+}
+
+#[tokio::test]
+async fn kick_unban_failure() {
+    let client = Client::new("token".to_string()).with_post_handler_fn(|method: String, _| {
+        Ok(match method.as_str() {
+            "banChatMember" => r#"{"ok": true, "result": true}"#,
+            _ => r#"{"ok": false, "description": "Bad Request: not enough rights"}"#,
+        }
+        .to_string())
+    });
+
+    let err = API::new(client)
+        .kick_chat_member("@group", 42)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("couldn't be unbanned"));
+}
+
+#[tokio::test]
+async fn mute_and_unmute() {
+    let (api, calls) = recording_api();
+    let api = api.with_clock(clock::FakeClock::at(1_000_000));
+
+    api.mute_chat_member(-100, 42, Some(Duration::from_secs(3600)))
+        .await
+        .unwrap();
+    api.mute_chat_member(-100, 42, None).await.unwrap();
+    api.unmute_chat_member(-100, 42).await.unwrap();
+
+    let calls = calls.lock().unwrap();
+    assert!(calls
+        .iter()
+        .all(|(method, _)| method == "restrictChatMember"));
+
+    let muted = &calls[0].1;
+    assert_eq!(muted["until_date"], 1_003_600);
+    assert_eq!(muted["use_independent_chat_permissions"], true);
+    let permissions = muted["permissions"].as_object().unwrap();
+    assert_eq!(permissions.len(), 14);
+    assert!(permissions.values().all(|v| v == false));
+
+    assert!(calls[1].1.get("until_date").is_none());
+
+    let unmuted = calls[2].1["permissions"].as_object().unwrap();
+    assert_eq!(unmuted.len(), 14);
+    assert!(unmuted.values().all(|v| v == true));
+}
+
+#[tokio::test]
+async fn ban_many() {
+    let (api, calls) = recording_api();
+    let results = api.ban_many(-100, [1, 2, 3]).await;
+
+    assert_eq!(
+        results.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        vec![1, 2, 3]
+    );
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls.len(), 3);
+    assert!(calls.iter().all(|(method, _)| method == "banChatMember"));
+}
+
+#[tokio::test]
+async fn bulk_short_circuits_on_permission_errors() {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&calls);
+    let client = Client::new("token".to_string()).with_post_handler_fn(
+        move |_: String, req: String| {
+            let req: serde_json::Value = serde_json::from_str(&req).unwrap();
+            let user_id = req["user_id"].as_i64().unwrap();
+            recorded.lock().unwrap().push(user_id);
+
+            Ok(match user_id {
+                2 => r#"{"ok": false, "description": "Bad Request: user not found"}"#,
+                3 => r#"{"ok": false, "description": "Bad Request: not enough rights to restrict/unrestrict chat member"}"#,
+                _ => r#"{"ok": true, "result": true}"#,
+            }
+            .to_string())
+        },
+    );
+
+    let results = API::new(client)
+        .restrict_many(-100, [1, 2, 3, 4, 5], api::ChatPermissions::none(), None)
+        .await;
+
+    // Other errors only fail their own user; permission errors skip the rest.
+    assert_eq!(*calls.lock().unwrap(), vec![1, 2, 3]);
+    assert_eq!(results.len(), 5);
+    assert!(results[0].1.is_ok());
+    assert!(results[1]
+        .1
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("not found"));
+    assert!(results[2]
+        .1
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("not enough rights"));
+    assert!(results[3]
+        .1
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .contains("Skipped"));
+    assert!(results[4].1.is_err());
+}
+
+#[tokio::test]
+async fn rate_limited_client() {
+    let client = Client::new("token".to_string())
+        .with_rate_limiter(RateLimiter::per_second(20))
+        .with_post_handler_fn(|_: String, _| Ok(r#"{"ok": true, "result": true}"#.to_string()));
+    let api = API::new(client);
+
+    let start = Instant::now();
+    let results = api.ban_many(-100, [1, 2, 3, 4]).await;
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+
+    // The first request goes out immediately, and the rest 50ms apart.
+    assert!(start.elapsed() >= Duration::from_millis(150));
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "notify")]
+
+use std::sync::{Arc, Mutex};
+
+use mobot::{
+    api::{InputFile, ParseMode},
+    notify::Notifier,
+    Client,
+};
+use serde_json::{json, Value};
+
+#[tokio::test(flavor = "current_thread")]
+async fn notifier() {
+    let sent = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&sent);
+    let client = Client::new("token".to_string()).with_post_handler_fn(
+        move |method: String, req: String| {
+            let req: Value = serde_json::from_str(&req).unwrap();
+            let response = json!({"ok": true, "result": {
+                "message_id": 1,
+                "date": 0,
+                "chat": {"id": req["chat_id"], "type": "supergroup"},
+            }});
+            recorded.lock().unwrap().push((method, req));
+            Ok(response.to_string())
+        },
+    );
+
+    let notifier = Notifier::new(client, -100).with_parse_mode(ParseMode::HTML);
+    let message = notifier.send("<b>disk</b> is 95% full").await.unwrap();
+    assert_eq!(message.chat.id, -100);
+    notifier
+        .send_document(&InputFile::new("df.txt", "/dev/sda1 95%"), Some("df"))
+        .await
+        .unwrap();
+
+    // Files attached by path are read from disk.
+    let path = std::env::temp_dir().join(format!("mobot-notify-{}.log", std::process::id()));
+    std::fs::write(&path, "disk full").unwrap();
+    let sent_file = notifier.send_document(&InputFile::path(&path), None).await;
+    std::fs::remove_file(&path).unwrap();
+    sent_file.unwrap();
+
+    let sent = sent.lock().unwrap();
+    assert_eq!(sent[0].0, "sendMessage");
+    assert_eq!(sent[0].1["text"], "<b>disk</b> is 95% full");
+    assert_eq!(sent[0].1["parse_mode"], "HTML");
+    assert_eq!(sent[1].0, "sendDocument");
+    assert_eq!(sent[1].1["caption"], "df");
+    assert_eq!(sent[2].0, "sendDocument");
+}
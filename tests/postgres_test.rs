@@ -0,0 +1,41 @@
+#![cfg(feature = "postgres")]
+
+use mobot::{storage::PostgresStorage, *};
+use serde_json::json;
+
+#[test]
+fn migrations() {
+    let migrator = PostgresStorage::migrator();
+    assert!(migrator.iter().any(|m| m.description == "mobot state"));
+}
+
+/// Runs against the database in `MOBOT_TEST_POSTGRES_URL`, and is skipped if it's not set.
+#[tokio::test]
+async fn postgres_storage() {
+    let Ok(url) = std::env::var("MOBOT_TEST_POSTGRES_URL") else {
+        eprintln!("MOBOT_TEST_POSTGRES_URL not set, skipping");
+        return;
+    };
+
+    let storage = PostgresStorage::connect(&url).await.unwrap();
+
+    // Migrations are idempotent.
+    storage.migrate().await.unwrap();
+
+    let id = rand::random::<i32>() as i64;
+    assert_eq!(storage.get("notes", id).await.unwrap(), None);
+    storage.set("notes", id, json!({"a": 1})).await.unwrap();
+    storage.set("notes", id, json!({"a": 2})).await.unwrap();
+    storage.set("warnings", id, json!(3)).await.unwrap();
+    assert_eq!(
+        storage.get("notes", id).await.unwrap(),
+        Some(json!({"a": 2}))
+    );
+
+    assert_eq!(
+        storage.export_user(id).await.unwrap(),
+        json!({"notes": {"a": 2}, "warnings": 3})
+    );
+    storage.delete_user(id).await.unwrap();
+    assert_eq!(storage.get("notes", id).await.unwrap(), None);
+}
@@ -0,0 +1,62 @@
+use std::{sync::Arc, time::Duration};
+
+use mobot::{api::API, *};
+
+#[tokio::test]
+async fn progress_message() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let api = Arc::new(API::new(
+        Client::new("token".to_string()).with_post_handler(fakeserver.clone()),
+    ));
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    let mut progress = ProgressMessage::send(Arc::clone(&api), chat.chat_id, "Working")
+        .await
+        .unwrap()
+        .with_interval(Duration::from_millis(200));
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "Working");
+
+    // Updates within the interval are dropped.
+    for i in 1..=10 {
+        assert!(!progress.update(i, 10).await.unwrap());
+    }
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(progress.update(4, 10).await.unwrap());
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "Working\n▓▓▓▓░░░░░░ 40% (4/10)"
+    );
+
+    // Finishing ignores the interval.
+    progress.finish("Done: 10 items").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "Done: 10 items"
+    );
+
+    // One send, and two edits.
+    assert_eq!(api.client.stats().requests, 3);
+}
+
+#[tokio::test]
+async fn progress_message_unchanged() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let api = Arc::new(API::new(
+        Client::new("token".to_string()).with_post_handler(fakeserver.clone()),
+    ));
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    let mut progress = ProgressMessage::send(Arc::clone(&api), chat.chat_id, "Working")
+        .await
+        .unwrap()
+        .with_interval(Duration::ZERO)
+        .with_width(4);
+
+    assert!(progress.update(1, 2).await.unwrap());
+    assert!(!progress.update(1, 2).await.unwrap());
+    assert!(progress.update(2, 2).await.unwrap());
+    assert_eq!(api.client.stats().requests, 3);
+}
@@ -354,3 +354,999 @@ async fn push_buttons() {
     shutdown_tx.send(()).await.unwrap();
     shutdown_notifier.notified().await;
 }
+
+#[tokio::test]
+async fn update_filter() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+
+    // Drop anything starting with "ignore" before it reaches the handlers.
+    let mut router = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_update_filter(|update| {
+            update
+                .message
+                .as_ref()
+                .and_then(|m| m.text.as_ref())
+                .is_none_or(|t| !t.starts_with("ignore"))
+        });
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    router.add_route(Route::Default, handle_chat_event);
+
+    tokio::spawn(async move {
+        info!("Starting router...");
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    // The filtered message never reaches the handler, so the counter isn't incremented.
+    chat.send_text("ignore me").await.unwrap();
+    chat.send_text("ping1").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "pong(1): ping1"
+    );
+
+    assert!(
+        tokio::time::timeout(Duration::from_millis(500), chat.recv_update())
+            .await
+            .is_err()
+    );
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn drop_pending_updates() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_drop_pending_updates(true);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    router.add_route(Route::Default, handle_chat_event);
+
+    // These arrive while the bot is "offline".
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("stale1").await.unwrap();
+    chat.send_text("stale2").await.unwrap();
+
+    tokio::spawn(async move {
+        info!("Starting router...");
+        router.start().await;
+    });
+
+    // Give the router a moment to drop the stale messages.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    chat.send_text("ping1").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "pong(1): ping1"
+    );
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn max_update_age() {
+    mobot::init_logger();
+    // The chat's messages are all dated 1000000, 30s before the bot's clock.
+    let fakeserver = fake::FakeAPI::new().with_clock(clock::FakeClock::at(1_000_000));
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let clock = clock::FakeClock::at(1_000_030);
+    let mut router = Router::new(Client::new("token".to_string()))
+        .with_api(API::new(client).with_clock(clock.clone()))
+        .with_poll_timeout_s(1)
+        .with_max_update_age(Duration::from_secs(60));
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    router.add_route(Route::Default, handle_chat_event);
+
+    tokio::spawn(async move {
+        info!("Starting router...");
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("ping1").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "pong(1): ping1"
+    );
+
+    // Messages are now 90s old, so they're dropped.
+    clock.advance(Duration::from_secs(60));
+    chat.send_text("ping2").await.unwrap();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(500), chat.recv_update())
+            .await
+            .is_err()
+    );
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn ignore_bots_and_self() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_ignore_bots(true)
+        .with_ignore_self(true);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    router.add_route(Route::Default, handle_chat_event);
+
+    tokio::spawn(async move {
+        info!("Starting router...");
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    let message_from = |user: api::User, text: &str| {
+        Update::Message(api::Message {
+            from: Some(user),
+            chat: api::Chat {
+                id: chat.chat_id,
+                ..Default::default()
+            },
+            text: Some(text.into()),
+            ..Default::default()
+        })
+    };
+
+    // The FakeAPI bot's user ID is 0.
+    let me = api::User {
+        id: 0,
+        first_name: "mobot".into(),
+        ..Default::default()
+    };
+    let other_bot = api::User {
+        id: 42,
+        is_bot: true,
+        first_name: "otherbot".into(),
+        ..Default::default()
+    };
+
+    chat.send_update(message_from(me, "from me")).await.unwrap();
+    chat.send_update(message_from(other_bot, "from bot"))
+        .await
+        .unwrap();
+    chat.send_text("ping1").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "pong(1): ping1"
+    );
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn background_tasks() {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_shutdown_grace_period(Duration::from_millis(500));
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    let tasks = router.tasks().clone();
+
+    let drained = Arc::new(AtomicBool::new(false));
+    let finished = Arc::clone(&drained);
+    router.add_route(Route::Default, move |e: Event, _: State<()>| {
+        let finished = Arc::clone(&finished);
+        async move {
+            // This task notices the shutdown and wraps up.
+            e.spawn(|e| async move {
+                e.tasks.cancelled().await;
+                finished.store(true, Ordering::SeqCst);
+                Ok(())
+            });
+
+            // This one never finishes, and is cancelled after the grace period.
+            e.spawn(|_| futures::future::pending());
+            Ok(Action::ReplyText("spawned".into()))
+        }
+    });
+
+    tokio::spawn(async move {
+        info!("Starting router...");
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("go").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "spawned");
+    assert_eq!(tasks.len(), 2);
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+
+    assert!(drained.load(Ordering::SeqCst));
+    assert!(tasks.is_empty());
+}
+
+#[tokio::test]
+async fn chat_lock() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_chat_serialization();
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    let chat_lock = router.chat_lock().clone();
+
+    // Track how many handlers are running at once.
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let (r, m) = (Arc::clone(&running), Arc::clone(&max_running));
+    router.add_route(Route::Default, move |e: Event, _: State<()>| {
+        let (running, max_running) = (Arc::clone(&r), Arc::clone(&m));
+        async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(Action::ReplyText(format!(
+                "done: {}",
+                e.update.get_message()?.text.clone().unwrap()
+            )))
+        }
+    });
+
+    tokio::spawn(async move {
+        info!("Starting router...");
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    // Handlers for a chat don't run while its lock is held elsewhere.
+    let guard = chat_lock.lock(chat.chat_id).await;
+    chat.send_text("one").await.unwrap();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(300), chat.recv_update())
+            .await
+            .is_err()
+    );
+    drop(guard);
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done: one");
+
+    // Updates for the same chat are handled one at a time.
+    chat.send_text("two").await.unwrap();
+    chat.send_text("three").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done: two");
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done: three");
+    assert_eq!(max_running.load(Ordering::SeqCst), 1);
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn concurrent_chat_handlers() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client).with_poll_timeout_s(1);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    // Track how many handlers are running at once.
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let (r, m) = (Arc::clone(&running), Arc::clone(&max_running));
+    router.add_route(Route::Default, move |_: Event, _: State<()>| {
+        let (running, max_running) = (Arc::clone(&r), Arc::clone(&m));
+        async move {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+            Ok(Action::ReplyText("done".into()))
+        }
+    });
+
+    tokio::spawn(async move {
+        info!("Starting router...");
+        router.start().await;
+    });
+
+    // By default, handlers for the same chat run concurrently.
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("one").await.unwrap();
+    chat.send_text("two").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done");
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done");
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn album_aggregation() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_album_aggregation(Duration::from_millis(200));
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_route(
+            Route::Message(Matcher::Photo),
+            |e: Event, _: State<()>| async move {
+                let messages = e.update.get_album()?;
+                Ok(Action::ReplyText(format!(
+                    "album of {}: {} ({})",
+                    messages.len(),
+                    e.update,
+                    messages
+                        .iter()
+                        .map(|m| m.message_id.to_string())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )))
+            },
+        )
+        .add_route(
+            Route::Message(Matcher::Any),
+            |e: Event, _: State<()>| async move {
+                Ok(Action::ReplyText(format!("single: {}", e.update)))
+            },
+        );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    let photo = |id: i64, group: &str, caption: Option<&str>| {
+        let mut message = api::Message::fake("qubyte");
+        message.chat.id = chat.chat_id;
+        message.message_id = id;
+        message.media_group_id = Some(group.into());
+        message.caption = caption.map(String::from);
+        message.photo = Some(vec![]);
+        Update::Message(message)
+    };
+
+    chat.send_update(photo(1, "a", Some("holiday")))
+        .await
+        .unwrap();
+    chat.send_update(photo(2, "a", None)).await.unwrap();
+    chat.send_update(photo(3, "a", None)).await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "album of 3: holiday (1,2,3)"
+    );
+
+    // Messages outside albums aren't held back.
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "single: hello"
+    );
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn caption_matching() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_caption_matching(true);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_route(
+            Route::Message(Matcher::BotCommand("scan".into())),
+            |e: Event, _: State<()>| async move {
+                let message = e.update.get_message()?;
+                Ok(Action::ReplyText(format!(
+                    "scanning: {}",
+                    message.text_or_caption().unwrap()
+                )))
+            },
+        )
+        .add_route(
+            Route::Message(Matcher::Any),
+            |_: Event, _: State<()>| async move { Ok(Action::ReplyText("not a command".into())) },
+        );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    let mut photo = api::Message::fake("qubyte");
+    photo.chat.id = chat.chat_id;
+    photo.photo = Some(vec![]);
+    photo.caption = Some("/scan receipt".into());
+    chat.send_update(Update::Message(photo)).await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "scanning: /scan receipt"
+    );
+
+    chat.send_text("/scan").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "scanning: /scan"
+    );
+
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "not a command"
+    );
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn language_routing() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_language_detector(|text: &str| text.contains("¿").then(|| "es".to_string()));
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_route(
+            Route::Message(Matcher::Language("pt".into())),
+            |_: Event, _: State<()>| async move { Ok(Action::ReplyText("olá".into())) },
+        )
+        .add_route(
+            Route::Message(Matcher::Language("es".into())),
+            |_: Event, _: State<()>| async move { Ok(Action::ReplyText("hola".into())) },
+        )
+        .add_route(
+            Route::Message(Matcher::Any),
+            |_: Event, _: State<()>| async move { Ok(Action::ReplyText("hello".into())) },
+        );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    let send = |text: &str, language_code: Option<&str>| {
+        let mut message = api::Message::new("qubyte", text);
+        message.chat.id = chat.chat_id;
+        message.from.as_mut().unwrap().language_code = language_code.map(String::from);
+        chat.send_update(Update::Message(message))
+    };
+
+    // The sender's language code is used, unless the detector finds a language.
+    send("hi", Some("pt-BR")).await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "olá");
+
+    send("¿qué?", Some("pt-BR")).await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "hola");
+
+    send("hi", Some("en")).await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "hello");
+
+    send("hi", None).await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "hello");
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[test]
+fn language_matcher() {
+    assert!(mobot::language::matches("pt", "pt-BR"));
+    assert!(mobot::language::matches("pt-br", "pt_BR"));
+    assert!(!mobot::language::matches("pt-BR", "pt"));
+    assert!(!mobot::language::matches("p", "pt"));
+
+    let mut message = api::Message::new("qubyte", "hi");
+    message.from.as_mut().unwrap().language_code = Some("de".into());
+    let update = api::Update {
+        message: Some(message),
+        ..Default::default()
+    };
+    assert!(Route::Message(Matcher::Language("de".into())).match_update(&update));
+    assert!(Route::Any(Matcher::Language("de".into())).match_update(&update));
+    assert!(!Route::EditedMessage(Matcher::Language("de".into())).match_update(&update));
+    assert!(!Route::Message(Matcher::Language("fr".into())).match_update(&update));
+}
+
+#[tokio::test]
+async fn chat_type_filters() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client).with_poll_timeout_s(1);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_route(
+            Route::Message(Matcher::Any),
+            filters::private(|_: Event, _: State<()>| async move {
+                Ok(Action::ReplyText("private".into()))
+            }),
+        )
+        .add_route(
+            Route::Message(Matcher::Any),
+            filters::groups(|e: Event, _: State<()>| async move {
+                Ok(Action::ReplyText(e.update.chat()?.chat_type.to_string()))
+            }),
+        );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    let send = |chat_type: api::ChatType| {
+        let mut message = api::Message::new("qubyte", "hi");
+        message.chat.id = chat.chat_id;
+        message.chat.chat_type = chat_type;
+        chat.send_update(Update::Message(message))
+    };
+
+    send(api::ChatType::Private).await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "private");
+
+    send(api::ChatType::Supergroup).await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "supergroup");
+
+    // Channel posts sent as messages match neither filter.
+    send(api::ChatType::Channel).await.unwrap();
+    send(api::ChatType::Group).await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "group");
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn handler_groups() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client).with_poll_timeout_s(1);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    let mut bans = HandlerGroup::new();
+    bans.add_route(
+        Route::Message(Matcher::BotCommand("ban".into())),
+        |_: Event, _: State<()>| async move { Ok(Action::ReplyText("banned".into())) },
+    );
+
+    let mut admin = HandlerGroup::new();
+    admin.mount(bans.with_prefix("user_")).add_route(
+        Route::Message(Matcher::Any),
+        |e: Event, _: State<()>| async move {
+            Ok(Action::ReplyText(format!("admin: {}", e.update.text()?)))
+        },
+    );
+    let admin = admin
+        .with_prefix("/admin_")
+        .filtered(|e: Event| async move { Ok(e.update.from_user()?.id == 1) });
+    assert_eq!(admin.len(), 2);
+
+    router.mount(admin).add_route(
+        Route::Message(Matcher::Any),
+        |_: Event, _: State<()>| async move { Ok(Action::ReplyText("fallback".into())) },
+    );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    let send = |user_id: i64, text: &str| {
+        let mut message = api::Message::new("qubyte", text);
+        message.chat.id = chat.chat_id;
+        message.from.as_mut().unwrap().id = user_id;
+        chat.send_update(Update::Message(message))
+    };
+
+    // Prefixes nest: "/admin_" + "user_" + "ban".
+    send(1, "/admin_user_ban").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "banned");
+
+    send(1, "/admin_status").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "admin: /admin_status"
+    );
+
+    // Commands without the prefix, or from users that don't pass the filter, fall through.
+    send(1, "/ban").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "fallback");
+
+    send(2, "/admin_user_ban").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "fallback");
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn handler_priority() {
+    use std::{ops::ControlFlow, sync::Arc};
+    use tokio::sync::Mutex;
+
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client).with_poll_timeout_s(1);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    let seen = Arc::new(Mutex::new(vec![]));
+    let observer = |name: &'static str, flow: ControlFlow<()>| {
+        let seen = Arc::clone(&seen);
+        move |_: Event, _: State<()>| {
+            let seen = Arc::clone(&seen);
+            async move {
+                seen.lock().await.push(name);
+                Ok(flow.into())
+            }
+        }
+    };
+
+    router
+        .add_route(
+            Route::Message(Matcher::Any),
+            |_: Event, _: State<()>| async move { Ok(Action::ReplyText("reply".into())) },
+        )
+        .add_route_with_priority(
+            Route::Message(Matcher::Any),
+            -1,
+            observer("never", ControlFlow::Continue(())),
+        )
+        .add_route_with_priority(
+            Route::Message(Matcher::Any),
+            10,
+            observer("audit", ControlFlow::Continue(())),
+        )
+        .add_route_with_priority(
+            Route::Message(Matcher::Any),
+            100,
+            observer("log", ControlFlow::Continue(())),
+        )
+        .add_route_with_priority(
+            Route::Message(Matcher::Exact("stop".into())),
+            10,
+            observer("stop", ControlFlow::Break(())),
+        );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("hi").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "reply");
+    assert_eq!(*seen.lock().await, vec!["log", "audit"]);
+
+    // Handlers with the same priority run in the order they were added.
+    seen.lock().await.clear();
+    chat.send_text("stop").await.unwrap();
+    chat.send_text("hi").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "reply");
+    assert_eq!(
+        *seen.lock().await,
+        vec!["log", "audit", "stop", "log", "audit"]
+    );
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn extensions() {
+    #[derive(Clone)]
+    struct Locale(String);
+
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client).with_poll_timeout_s(1);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_route(
+            Route::Message(Matcher::Any),
+            |e: Event, _: State<()>| async move {
+                let locale = e.extensions.get::<Locale>();
+                Ok(Action::ReplyText(
+                    locale.map_or("unknown".into(), |locale| locale.0),
+                ))
+            },
+        )
+        .add_route_with_priority(
+            Route::Message(Matcher::Prefix("hallo".into())),
+            10,
+            |e: Event, _: State<()>| async move {
+                e.extensions.insert(Locale("de".into()));
+                Ok(Action::Next)
+            },
+        );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("hallo").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "de");
+
+    // Each update starts with empty extensions.
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "unknown");
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn feature_flags() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_features(Features::new().with_flag("beta", false));
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    let features = router.features().clone();
+
+    router
+        .add_route(
+            Route::Message(Matcher::BotCommand("feature".into())),
+            handlers::feature_handler(),
+        )
+        .add_route(
+            Route::Message(Matcher::Any),
+            filters::feature("ai", |_: Event, _: State<()>| async move {
+                Ok(Action::ReplyText("ai".into()))
+            }),
+        )
+        .add_route_with_priority(
+            Route::Message(Matcher::Any),
+            -1,
+            |_: Event, _: State<()>| async move { Ok(Action::ReplyText("fallback".into())) },
+        );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("hi").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "ai");
+
+    // Flags can be toggled with the command...
+    chat.send_text("/feature off ai").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "Feature ai disabled."
+    );
+    chat.send_text("hi").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "fallback");
+
+    chat.send_text("/feature").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "ai: off\nbeta: off"
+    );
+
+    // ...or through the router's handle, e.g. on a config reload.
+    features.load([("ai", true)]);
+    chat.send_text("hi").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "ai");
+
+    // Registered defaults survive a reload that leaves the flag out.
+    assert!(!features.is_enabled("beta"));
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+/// A server that answers the first `conflicts` polls with a conflict, as if another instance of
+/// the bot was polling, and counts requests by method.
+#[derive(Clone, Default)]
+struct ConflictingServer {
+    conflicts: usize,
+    calls: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+impl ConflictingServer {
+    fn count(&self, method: &str) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| *m == method)
+            .count()
+    }
+}
+
+#[async_trait::async_trait]
+impl client::Post for ConflictingServer {
+    async fn post(&self, method: String, _: String) -> Result<String> {
+        self.calls.lock().unwrap().push(method.clone());
+        if method != "getUpdates" {
+            return Ok(r#"{"ok": true, "result": true}"#.into());
+        }
+
+        if self.count("getUpdates") <= self.conflicts {
+            return Ok(r#"{"ok": false, "error_code": 409, "description": "Conflict: terminated by other getUpdates request; make sure that only one bot instance is running"}"#.into());
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        Ok(r#"{"ok": true, "result": []}"#.into())
+    }
+}
+
+#[tokio::test]
+async fn recovers_from_conflicts() {
+    mobot::init_logger();
+    let server = ConflictingServer {
+        conflicts: 3,
+        ..Default::default()
+    };
+    let client = Client::new("token".to_string()).with_post_handler(server.clone());
+    let mut router: Router<()> = Router::new(client)
+        .with_conflict_backoff(Duration::from_millis(20), Duration::from_millis(40))
+        .with_delete_webhook_on_conflict(true);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    let start = std::time::Instant::now();
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    while server.count("getUpdates") < 5 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    // Backs off for 20ms, 40ms, and 40ms, deleting the webhook each time, then keeps polling.
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(server.count("deleteWebhook"), 3);
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn conflict_backoff_on_clock() {
+    mobot::init_logger();
+    let server = ConflictingServer {
+        conflicts: 3,
+        ..Default::default()
+    };
+    let clock = clock::FakeClock::at(1_000_000);
+    let client = Client::new("token".to_string()).with_post_handler(server.clone());
+    let mut router: Router<()> = Router::new(Client::new("token".to_string()))
+        .with_api(API::new(client).with_clock(clock.clone()))
+        .with_conflict_backoff(Duration::from_secs(60), Duration::from_secs(60));
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    // The backoff waits for the API's clock...
+    while server.count("getUpdates") < 1 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(server.count("getUpdates"), 1);
+    clock.advance(Duration::from_secs(60));
+    while server.count("getUpdates") < 2 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    // ...and is cut short by a shutdown.
+    shutdown_tx.send(()).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(1), shutdown_notifier.notified())
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn handler_timeout() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let mut router = Router::new(client)
+        .with_poll_timeout_s(1)
+        .with_handler_timeout(Duration::from_millis(50));
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    let (tx, mut rx) = tokio::sync::mpsc::channel(1);
+    router.add_route(Route::Default, move |e: Event, _: State<()>| {
+        let tx = tx.clone();
+        async move {
+            if e.update.text()? == "quick" {
+                return Ok(Action::ReplyText("done".into()));
+            }
+
+            // A task that makes its call after the handler has timed out.
+            let options = CallOptions::new().with_cancellation_token(e.cancellation.clone());
+            let api = e.api.clone();
+            tokio::spawn(options.scope(async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let result = api
+                    .send_message(&api::SendMessageRequest::new(1, "too late"))
+                    .await;
+                tx.send(result.is_err()).await.unwrap();
+            }));
+
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(Action::ReplyText("never sent".into()))
+        }
+    });
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    chat.send_text("slow").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "Handler error: Handler timed out after 50ms"
+    );
+    assert!(rx.recv().await.unwrap());
+
+    // Handlers that finish in time aren't affected.
+    chat.send_text("quick").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "done");
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
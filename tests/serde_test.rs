@@ -0,0 +1,342 @@
+/// Serialization tests for the `api` module. Incoming types are round-tripped through the JSON
+/// fixtures in `tests/fixtures/types`, and outgoing requests are compared against the snapshots
+/// in `tests/fixtures/requests`.
+///
+/// To regenerate the request snapshots after an intentional change, run:
+///
+///     MOBOT_UPDATE_SNAPSHOTS=1 cargo test --test serde_test
+use std::{fs, path::PathBuf};
+
+use mobot::api::*;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+fn fixture_path(kind: &str, name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(kind)
+        .join(format!("{}.json", name))
+}
+
+/// Asserts that every field in `expected` is present in `actual` with the same value. Fields in
+/// `actual` that are missing from `expected` (e.g., unset optional fields) are ignored.
+fn assert_subset(expected: &Value, actual: &Value, path: &str) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let path = format!("{}.{}", path, key);
+                match actual.get(key) {
+                    Some(actual) => assert_subset(value, actual, &path),
+                    None => panic!("{}: missing after round trip", path),
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            assert_eq!(expected.len(), actual.len(), "{}: length mismatch", path);
+            for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                assert_subset(expected, actual, &format!("{}[{}]", path, i));
+            }
+        }
+        _ => assert_eq!(expected, actual, "{}: value mismatch", path),
+    }
+}
+
+/// Deserializes the fixture `name` into `T`, serializes it back, and checks that no fields were
+/// lost or renamed along the way.
+fn assert_round_trip<T: DeserializeOwned + Serialize>(name: &str) {
+    let data = fs::read_to_string(fixture_path("types", name)).unwrap();
+    let fixture: Value = serde_json::from_str(&data).unwrap();
+
+    let parsed: T = serde_json::from_str(&data)
+        .unwrap_or_else(|e| panic!("{}: can't deserialize: {}", name, e));
+    let output = serde_json::to_value(&parsed).unwrap();
+
+    assert_subset(&fixture, &output, name);
+}
+
+/// Serializes `req` and compares it against the snapshot `name`. If `MOBOT_UPDATE_SNAPSHOTS`
+/// is set, the snapshot is rewritten instead.
+fn assert_snapshot(name: &str, req: &impl Serialize) {
+    let path = fixture_path("requests", name);
+    let output = serde_json::to_value(req).unwrap();
+
+    if std::env::var_os("MOBOT_UPDATE_SNAPSHOTS").is_some() {
+        let data = serde_json::to_string_pretty(&output).unwrap();
+        fs::write(&path, data + "\n").unwrap();
+        return;
+    }
+
+    let data = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}: can't read snapshot ({}), regenerate it?", name, e));
+    let snapshot: Value = serde_json::from_str(&data).unwrap();
+
+    assert_eq!(snapshot, output, "{}: snapshot mismatch", name);
+}
+
+#[test]
+fn types() {
+    mobot::init_logger();
+
+    assert_round_trip::<Update>("update_message");
+    assert_round_trip::<Update>("update_reply");
+    assert_round_trip::<Update>("update_photo");
+    assert_round_trip::<Update>("update_document");
+    #[cfg(feature = "stickers")]
+    assert_round_trip::<Update>("update_media");
+    assert_round_trip::<Update>("update_channel_post");
+    assert_round_trip::<Update>("update_callback_query");
+    #[cfg(feature = "inline")]
+    assert_round_trip::<Update>("update_inline_query");
+    assert_round_trip::<Update>("update_chat_background");
+    #[cfg(feature = "business")]
+    assert_round_trip::<ChatFullInfo>("chat_full_info");
+    assert_round_trip::<ChatMember>("chat_member");
+    assert_round_trip::<ChatMemberAdministrator>("chat_member_administrator");
+    assert_round_trip::<File>("file");
+    assert_round_trip::<Vec<BotCommand>>("bot_commands");
+    assert_round_trip::<ApiResponse<Vec<Update>>>("response_ok");
+    assert_round_trip::<ApiResponse<()>>("response_error");
+}
+
+#[test]
+fn incoming_reply_markup() {
+    mobot::init_logger();
+
+    let data = fs::read_to_string(fixture_path("types", "update_callback_query")).unwrap();
+    let update: Update = serde_json::from_str(&data).unwrap();
+    let message = update.callback_query.unwrap().message.unwrap();
+
+    match message.reply_markup {
+        Some(ReplyMarkup::InlineKeyboardMarkup {
+            inline_keyboard, ..
+        }) => {
+            assert_eq!(inline_keyboard[0][1].text, "No");
+            assert_eq!(inline_keyboard[0][1].callback_data.as_deref(), Some("no"));
+        }
+        other => panic!("unexpected reply markup: {:?}", other),
+    }
+}
+
+#[test]
+fn chat_appearance() {
+    mobot::init_logger();
+
+    let data = fs::read_to_string(fixture_path("types", "update_chat_background")).unwrap();
+    let update: Update = serde_json::from_str(&data).unwrap();
+    let background = update.message.unwrap().chat_background_set.unwrap();
+
+    match background.type_ {
+        BackgroundType::Pattern {
+            fill, intensity, ..
+        } => {
+            assert_eq!(intensity, 50);
+            assert!(matches!(
+                fill,
+                BackgroundFill::Gradient {
+                    rotation_angle: 45,
+                    ..
+                }
+            ));
+        }
+        other => panic!("unexpected background: {:?}", other),
+    }
+
+    // The Bot API calls the accent color `accent_color_id`.
+    let chat: ChatFullInfo =
+        serde_json::from_str(r#"{"id": 1, "type": "private", "accent_color_id": 5}"#).unwrap();
+    assert_eq!(chat.accent_color, Some(5));
+}
+
+#[test]
+fn business_info() {
+    mobot::init_logger();
+
+    let data = fs::read_to_string(fixture_path("types", "chat_full_info")).unwrap();
+    let chat: ChatFullInfo = serde_json::from_str(&data).unwrap();
+
+    assert_eq!(
+        chat.birthdate,
+        Some(Birthdate {
+            day: 29,
+            month: 2,
+            year: Some(1996)
+        })
+    );
+    assert_eq!(chat.personal_chat.unwrap().id, 2222222);
+
+    // Open Mondays 9:00-18:00, and Sunday 21:00 to Monday 3:00.
+    #[cfg(feature = "business")]
+    {
+        let hours = chat.business_opening_hours.unwrap();
+        assert!(hours.is_open(9 * 60));
+        assert!(!hours.is_open(18 * 60));
+        assert!(hours.is_open(6 * 24 * 60 + 22 * 60));
+        assert!(hours.is_open(2 * 60));
+        assert!(!hours.is_open(3 * 60));
+    }
+}
+
+#[test]
+fn chat_type() {
+    let chat: Chat = serde_json::from_str(r#"{"id": -1, "type": "supergroup"}"#).unwrap();
+    assert_eq!(chat.chat_type, ChatType::Supergroup);
+    assert!(chat.is_group());
+    assert!(!chat.is_private() && !chat.is_channel());
+    assert_eq!(serde_json::to_value(&chat).unwrap()["type"], "supergroup");
+
+    // Chat types still compare with their names.
+    assert!(chat.chat_type == "supergroup");
+    assert_eq!("channel".parse::<ChatType>().unwrap(), ChatType::Channel);
+    assert_eq!(ChatType::Private.to_string(), "private");
+
+    // Chat types added to the Bot API later don't break parsing, and keep their names.
+    let chat: Chat = serde_json::from_str(r#"{"id": -1, "type": "sender"}"#).unwrap();
+    assert_eq!(chat.chat_type, ChatType::Unknown("sender".into()));
+    assert_eq!(serde_json::to_value(&chat).unwrap()["type"], "sender");
+    assert_eq!("sender".parse::<ChatType>().unwrap(), chat.chat_type);
+    assert!(chat.chat_type == "sender");
+}
+
+#[test]
+fn chat_member_status() {
+    let member: ChatMember = serde_json::from_str(
+        r#"{"status": "creator", "user": {"id": 1, "is_bot": false, "first_name": "a"}}"#,
+    )
+    .unwrap();
+    assert_eq!(member.status(), ChatMemberStatus::Creator);
+    assert!(member.is_admin());
+    assert_eq!(serde_json::to_value(&member).unwrap()["status"], "creator");
+
+    // Code written when `status` was a string still compiles.
+    #[allow(deprecated)]
+    {
+        let status: String = member.status.clone();
+        assert_eq!(status, "creator");
+        let member = ChatMemberAdministrator {
+            status: "administrator".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(member.status(), ChatMemberStatus::Administrator);
+    }
+    assert!(member.status() == "creator");
+    assert_eq!(ChatMemberStatus::from("kicked"), ChatMemberStatus::Kicked);
+    assert_eq!(
+        ChatMemberStatus::from("banished"),
+        ChatMemberStatus::Unknown
+    );
+}
+
+#[test]
+fn requests() {
+    mobot::init_logger();
+
+    assert_snapshot(
+        "send_message",
+        &SendMessageRequest::new(1111111, "Hello *world*")
+            .with_message_thread_id(1300)
+            .with_parse_mode(ParseMode::MarkdownV2)
+            .with_reply_markup(ReplyMarkup::inline_keyboard_markup(vec![vec![
+                InlineKeyboardButton::from("Yes").with_callback_data("yes"),
+                InlineKeyboardButton::from("No").with_callback_data("no"),
+            ]])),
+    );
+    assert_snapshot(
+        "send_message_keyboard",
+        &SendMessageRequest::new(1111111, "Pick one").with_reply_markup(
+            ReplyMarkup::reply_keyboard_markup(vec![vec!["A".into(), "B".into()]]),
+        ),
+    );
+    assert_snapshot(
+        "send_message_remove_keyboard",
+        &SendMessageRequest::new(1111111, "Done")
+            .with_reply_markup(ReplyMarkup::reply_keyboard_remove()),
+    );
+    assert_snapshot(
+        "edit_message_text",
+        &EditMessageTextRequest::new("Updated".into())
+            .with_chat_id(1111111)
+            .with_message_id(1365),
+    );
+    assert_snapshot(
+        "edit_message_reply_markup",
+        &EditMessageReplyMarkupRequest::new(ReplyMarkup::force_reply())
+            .with_chat_id(1111111)
+            .with_message_id(1365),
+    );
+    assert_snapshot("delete_message", &DeleteMessageRequest::new(1111111, 1365));
+    assert_snapshot(
+        "set_message_reaction",
+        &MessageReactionRequest::new(
+            1111111,
+            1365,
+            Some(vec![ReactionType::new(
+                "emoji".into(),
+                Some("👍".into()),
+                None,
+            )]),
+            Some(true),
+        ),
+    );
+    assert_snapshot(
+        "send_chat_action",
+        &SendChatActionRequest::new(1111111, ChatAction::Typing),
+    );
+    #[cfg(feature = "stickers")]
+    assert_snapshot(
+        "send_sticker",
+        &SendStickerRequest::new(1111111, "CAACAgIAAxkBAAIBaSticker".into()),
+    );
+    assert_snapshot(
+        "answer_callback_query",
+        &AnswerCallbackQueryRequest::new("4382bfdwdsb323b2d9".into())
+            .with_text("Thanks!")
+            .with_show_alert(true),
+    );
+    #[cfg(feature = "inline")]
+    assert_snapshot(
+        "answer_inline_query",
+        &AnswerInlineQuery::new("134567890097".into()).with_article_text("Cats", "Meow"),
+    );
+    assert_snapshot(
+        "get_updates",
+        &GetUpdatesRequest::new()
+            .with_offset(10001)
+            .with_limit(100)
+            .with_timeout(30),
+    );
+    assert_snapshot(
+        "ban_chat_member",
+        &BanChatMemberRequest::new("-1001234567890".into(), 1111111, Some(1441649999), None),
+    );
+    assert_snapshot(
+        "restrict_chat_member",
+        &RestrictChatMemberRequest::new(
+            "-1001234567890".into(),
+            1111111,
+            ChatPermissions {
+                can_send_messages: Some(false),
+                ..Default::default()
+            },
+            Some(true),
+            None,
+        ),
+    );
+    assert_snapshot(
+        "promote_chat_member",
+        &PromoteChatMemberRequest {
+            can_delete_messages: Some(true),
+            can_restrict_members: Some(true),
+            ..PromoteChatMemberRequest::new("-1001234567890".into(), 3333333)
+        },
+    );
+    assert_snapshot(
+        "set_my_commands",
+        &SetMyCommandsRequest {
+            commands: vec![BotCommand {
+                command: "start".into(),
+                description: "Start the bot".into(),
+            }],
+            ..Default::default()
+        },
+    );
+}
@@ -0,0 +1,237 @@
+use anyhow::Result;
+use log::*;
+use mobot::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct ChatSettings {
+    welcome: Option<String>,
+}
+
+impl ChatSetting for ChatSettings {
+    const KEY: &'static str = "chat";
+}
+
+impl Versioned for ChatSettings {}
+
+/// Admin command that updates the welcome message for the chat.
+async fn set_welcome(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+    let chat_id = e.update.chat_id()?;
+    let welcome = e
+        .update
+        .text()?
+        .trim_start_matches("/setwelcome")
+        .trim()
+        .to_string();
+
+    e.settings
+        .update::<ChatSettings>(chat_id, |s| s.welcome = Some(welcome))
+        .await?;
+    Ok(Action::ReplyText("ok".into()))
+}
+
+/// Replies with the current welcome message for the chat.
+async fn welcome(e: Event, _: State<()>) -> Result<Action, anyhow::Error> {
+    let settings = e.settings.get::<ChatSettings>(e.update.chat_id()?).await?;
+    Ok(Action::ReplyText(
+        settings.welcome.unwrap_or("default".into()),
+    ))
+}
+
+#[tokio::test]
+async fn get_set_reset() {
+    let settings = Settings::default();
+    let mut changes = settings.subscribe();
+
+    assert_eq!(
+        settings.get::<ChatSettings>(1).await.unwrap(),
+        ChatSettings::default()
+    );
+
+    settings
+        .set(
+            1,
+            &ChatSettings {
+                welcome: Some("hi".into()),
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        changes.recv().await.unwrap(),
+        SettingsChange {
+            chat_id: 1,
+            key: "chat".into()
+        }
+    );
+    assert_eq!(
+        settings
+            .get::<ChatSettings>(1)
+            .await
+            .unwrap()
+            .welcome
+            .unwrap(),
+        "hi"
+    );
+
+    // Other chats are unaffected.
+    assert_eq!(
+        settings.get::<ChatSettings>(2).await.unwrap(),
+        ChatSettings::default()
+    );
+
+    settings.reset::<ChatSettings>(1).await.unwrap();
+    assert_eq!(changes.recv().await.unwrap().chat_id, 1);
+    assert_eq!(
+        settings.get::<ChatSettings>(1).await.unwrap(),
+        ChatSettings::default()
+    );
+}
+
+#[tokio::test]
+async fn hot_reload() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+
+    let mut router = Router::new(client).with_poll_timeout_s(1);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_route(
+            Route::Message(Matcher::BotCommand("setwelcome".into())),
+            set_welcome,
+        )
+        .add_route(Route::Message(Matcher::Any), welcome);
+
+    tokio::spawn(async move {
+        info!("Starting router...");
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "default");
+
+    chat.send_text("/setwelcome Hi there!").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "ok");
+
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "Hi there!");
+
+    info!("Shutting down...");
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[cfg(feature = "stickers")]
+#[tokio::test]
+async fn export_import() {
+    let settings = Settings::default();
+    settings
+        .set(
+            1,
+            &ChatSettings {
+                welcome: Some("hi".into()),
+            },
+        )
+        .await
+        .unwrap();
+    settings
+        .set(1, &handlers::sticker_set::StickerSetDraft::default())
+        .await
+        .unwrap();
+
+    let export = settings
+        .export(1)
+        .await
+        .unwrap()
+        .without::<handlers::sticker_set::StickerSetDraft>();
+    assert_eq!(export.keys(), ["chat"]);
+
+    let export = SettingsExport::from_json(&export.to_json().unwrap()).unwrap();
+    let mut changes = settings.subscribe();
+    assert_eq!(settings.import(2, &export).await.unwrap(), ["chat"]);
+    assert_eq!(
+        changes.recv().await.unwrap(),
+        SettingsChange {
+            chat_id: 2,
+            key: "chat".into()
+        }
+    );
+    assert_eq!(
+        settings.get::<ChatSettings>(2).await.unwrap().welcome,
+        Some("hi".into())
+    );
+
+    assert!(SettingsExport::from_json("{}").is_err());
+    assert!(SettingsExport::from_json(r#"{"format": 2, "settings": {}}"#).is_err());
+}
+
+#[tokio::test]
+async fn import_settings_handler() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+
+    let mut router = Router::new(client).with_poll_timeout_s(1);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    router
+        .add_route(
+            Route::Message(Matcher::BotCommand("import_settings".into())),
+            handlers::import_settings_handler(),
+        )
+        .add_route(Route::Message(Matcher::Any), welcome);
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let other = Settings::default();
+    other
+        .set(
+            1,
+            &ChatSettings {
+                welcome: Some("Hi there!".into()),
+            },
+        )
+        .await
+        .unwrap();
+    let json = other.export(1).await.unwrap().to_json().unwrap();
+
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    chat.send_text("/import_settings").await.unwrap();
+    assert!(chat
+        .recv_update()
+        .await
+        .unwrap()
+        .to_string()
+        .starts_with("Send a settings file"));
+
+    chat.send_text("/import_settings {\"settings\": {}}")
+        .await
+        .unwrap();
+    assert!(chat
+        .recv_update()
+        .await
+        .unwrap()
+        .to_string()
+        .starts_with("Can't import that"));
+
+    chat.send_text(format!("/import_settings {}", json))
+        .await
+        .unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "Imported settings: chat."
+    );
+
+    chat.send_text("hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "Hi there!");
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "sqlite")]
+
+use mobot::{storage::SqliteStorage, *};
+use serde_json::json;
+
+#[tokio::test]
+async fn sqlite_storage() {
+    let storage = SqliteStorage::memory().unwrap();
+
+    assert_eq!(storage.get("notes", 1).await.unwrap(), None);
+    storage.set("notes", 1, json!({"a": 1})).await.unwrap();
+    storage.set("notes", 1, json!({"a": 2})).await.unwrap();
+    storage.set("warnings", 1, json!(3)).await.unwrap();
+    storage.set("notes", 2, json!("other")).await.unwrap();
+    assert_eq!(
+        storage.get("notes", 1).await.unwrap(),
+        Some(json!({"a": 2}))
+    );
+
+    assert_eq!(
+        storage.export_user(1).await.unwrap(),
+        json!({"notes": {"a": 2}, "warnings": 3})
+    );
+    storage.delete_user(1).await.unwrap();
+    assert_eq!(
+        storage.export_user(1).await.unwrap(),
+        serde_json::Value::Null
+    );
+    assert_eq!(storage.get("notes", 2).await.unwrap(), Some(json!("other")));
+
+    storage.delete("notes", 2).await.unwrap();
+    assert_eq!(storage.get("notes", 2).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn sqlite_survives_restart() {
+    let path = std::env::temp_dir().join(format!("mobot-{}.db", rand::random::<u64>()));
+
+    {
+        let settings = Settings::new(SqliteStorage::open(&path).unwrap());
+        settings.set(7, &Welcome("hi".into())).await.unwrap();
+    }
+
+    let settings = Settings::new(SqliteStorage::open(&path).unwrap());
+    assert_eq!(
+        settings.get::<Welcome>(7).await.unwrap(),
+        Welcome("hi".into())
+    );
+
+    for suffix in ["", "-wal", "-shm"] {
+        _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Welcome(String);
+
+impl ChatSetting for Welcome {
+    const KEY: &'static str = "welcome";
+}
+
+impl Versioned for Welcome {}
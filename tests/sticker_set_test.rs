@@ -0,0 +1,190 @@
+#![cfg(feature = "stickers")]
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use mobot::{handlers::sticker_set, *};
+
+/// A FakeAPI that also serves files from `dir`, and records sticker set requests.
+#[derive(Clone)]
+struct StickerServer {
+    fake: fake::FakeAPI,
+    dir: PathBuf,
+    requests: Arc<Mutex<Vec<(String, serde_json::Value)>>>,
+}
+
+#[async_trait::async_trait]
+impl client::Post for StickerServer {
+    async fn post(&self, method: String, req: String) -> Result<String> {
+        let body: serde_json::Value = serde_json::from_str(&req)?;
+        let result = match method.as_str() {
+            "getFile" => serde_json::json!({
+                "file_id": body["file_id"],
+                "file_path": self.dir.join(body["file_id"].as_str().unwrap()),
+            }),
+            "uploadStickerFile" => serde_json::json!({"file_id": "uploaded"}),
+            "createNewStickerSet" | "addStickerToSet" => serde_json::json!(true),
+            _ => return self.fake.post(method, req).await,
+        };
+        self.requests.lock().unwrap().push((method, body));
+        Ok(serde_json::json!({"ok": true, "result": result}).to_string())
+    }
+}
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+    data.extend(width.to_be_bytes());
+    data.extend(height.to_be_bytes());
+    data
+}
+
+fn webp(width: u32, height: u32) -> Vec<u8> {
+    let mut data = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+    data.extend(&(width - 1).to_le_bytes()[..3]);
+    data.extend(&(height - 1).to_le_bytes()[..3]);
+    data
+}
+
+fn document(chat_id: i64, file_id: &str, caption: Option<&str>) -> Update {
+    Update::Message(api::Message {
+        text: None,
+        caption: caption.map(String::from),
+        document: Some(api::Document {
+            file_id: file_id.into(),
+            thumbnail: None,
+            file_name: Some(format!("{}.png", file_id)),
+            mime_type: Some("image/png".into()),
+            file_size: Some(100),
+        }),
+        ..fake::FakeMessage::text(chat_id, "qubyte", "").into()
+    })
+}
+
+#[test]
+fn image_sizes() {
+    assert_eq!(sticker_set::image_size(&png(512, 256)), Some((512, 256)));
+    assert_eq!(sticker_set::image_size(&webp(300, 512)), Some((300, 512)));
+    assert_eq!(sticker_set::image_size(b"GIF89a"), None);
+
+    assert!(sticker_set::validate_image(&png(512, 512)).is_ok());
+    assert!(sticker_set::validate_image(&png(100, 100)).is_err());
+
+    assert!(sticker_set::validate_name("cats_by_mobot").is_ok());
+    assert!(sticker_set::validate_name("1cats_by_mobot").is_err());
+    assert!(sticker_set::validate_name("cats__by_mobot").is_err());
+    assert!(sticker_set::validate_name("cäts_by_mobot").is_err());
+}
+
+#[tokio::test]
+async fn sticker_set_wizard() {
+    mobot::init_logger();
+    let dir = std::env::temp_dir().join(format!("mobot-{}", rand::random::<u64>()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("small"), png(100, 100)).unwrap();
+    std::fs::write(dir.join("cat"), png(512, 256)).unwrap();
+    std::fs::write(dir.join("dog"), webp(512, 512)).unwrap();
+
+    let fakeserver = fake::FakeAPI::new();
+    let server = StickerServer {
+        fake: fakeserver.clone(),
+        dir: dir.clone(),
+        requests: Arc::new(Mutex::new(vec![])),
+    };
+    let client = Client::new("token".to_string())
+        .with_local_mode(true)
+        .with_post_handler(server.clone());
+    let mut router: Router<()> = Router::new(client).with_poll_timeout_s(1);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    for matcher in [Matcher::Any, Matcher::Document] {
+        router.add_route(Route::Message(matcher), handlers::sticker_set_wizard());
+    }
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    let say = |text: &'static str| {
+        let chat = &chat;
+        async move {
+            chat.send_text(text).await.unwrap();
+            chat.recv_update().await.unwrap().to_string()
+        }
+    };
+
+    assert_eq!(
+        say("/newpack").await,
+        "Let's make a sticker pack! What's its title?"
+    );
+    assert!(say("Cats").await.starts_with("Now pick a short name"));
+    assert!(say("cats").await.starts_with("Now send the stickers"));
+
+    // Images are checked before they're uploaded.
+    chat.send_update(document(chat.chat_id, "small", None))
+        .await
+        .unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "Stickers have to be 512 pixels on their longest side, that image is 100x100."
+    );
+    chat.send_update(document(chat.chat_id, "cat", Some("🐱")))
+        .await
+        .unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "Added sticker 1. Send another, or /done."
+    );
+    chat.send_update(document(chat.chat_id, "dog", None))
+        .await
+        .unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        "Added sticker 2. Send another, or /done."
+    );
+
+    assert_eq!(
+        say("/done").await,
+        "Your sticker pack is ready: https://t.me/addstickers/cats_by_mobot"
+    );
+
+    let requests = server.requests.lock().unwrap().clone();
+    let methods: Vec<_> = requests.iter().map(|(method, _)| method.as_str()).collect();
+    assert_eq!(
+        methods,
+        [
+            "getFile",
+            "getFile",
+            "uploadStickerFile",
+            "getFile",
+            "uploadStickerFile",
+            "createNewStickerSet",
+            "addStickerToSet"
+        ]
+    );
+    let create = &requests[5].1;
+    assert_eq!(create["name"], "cats_by_mobot");
+    assert_eq!(create["title"], "Cats");
+    assert_eq!(
+        create["stickers"][0]["emoji_list"],
+        serde_json::json!(["🐱"])
+    );
+    assert_eq!(
+        requests[6].1["sticker"]["emoji_list"],
+        serde_json::json!(["🙂"])
+    );
+
+    // The wizard is done, so other messages are passed on.
+    chat.send_text("hello").await.unwrap();
+    assert!(
+        tokio::time::timeout(std::time::Duration::from_millis(200), chat.recv_update())
+            .await
+            .is_err()
+    );
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,68 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use mobot::*;
+use serde_json::json;
+
+#[tokio::test]
+async fn encrypted_storage() {
+    let key = EncryptedStorage::<MemoryStorage>::generate_key();
+    let inner = MemoryStorage::new();
+    let storage = EncryptedStorage::new(inner.clone(), &key);
+
+    let value = json!({"diagnosis": "very secret"});
+    storage.set("notes", 1, value.clone()).await.unwrap();
+    assert_eq!(storage.get("notes", 1).await.unwrap(), Some(value.clone()));
+    assert_eq!(storage.get("notes", 2).await.unwrap(), None);
+
+    // The wrapped store only sees ciphertext.
+    let raw = inner.get("notes", 1).await.unwrap().unwrap();
+    assert!(raw.as_str().unwrap().starts_with("enc:v1:"));
+    assert!(!raw.to_string().contains("secret"));
+
+    // Values are encrypted with a fresh nonce every time.
+    storage.set("notes", 1, value.clone()).await.unwrap();
+    assert_ne!(inner.get("notes", 1).await.unwrap().unwrap(), raw);
+
+    // Ciphertexts are bound to their namespace and ID.
+    inner.set("notes", 2, raw.clone()).await.unwrap();
+    assert!(storage.get("notes", 2).await.is_err());
+
+    // The wrong key can't decrypt.
+    let other = EncryptedStorage::new(
+        inner.clone(),
+        &EncryptedStorage::<MemoryStorage>::generate_key(),
+    );
+    assert!(other.get("notes", 1).await.is_err());
+
+    // Neither can anything read unencrypted values.
+    inner.set("notes", 3, json!("plain")).await.unwrap();
+    assert!(storage.get("notes", 3).await.is_err());
+
+    storage.delete("notes", 1).await.unwrap();
+    assert_eq!(inner.get("notes", 1).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn encrypted_settings() {
+    let key = BASE64.encode(EncryptedStorage::<MemoryStorage>::generate_key());
+    let settings =
+        Settings::new(EncryptedStorage::from_base64_key(MemoryStorage::new(), &key).unwrap());
+
+    #[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Secret {
+        token: Option<String>,
+    }
+
+    impl ChatSetting for Secret {
+        const KEY: &'static str = "secret";
+    }
+
+    impl Versioned for Secret {}
+
+    let secret = Secret {
+        token: Some("hunter2".into()),
+    };
+    settings.set(5, &secret).await.unwrap();
+    assert_eq!(settings.get::<Secret>(5).await.unwrap(), secret);
+
+    assert!(EncryptedStorage::from_base64_key(MemoryStorage::new(), "c2hvcnQ=").is_err());
+}
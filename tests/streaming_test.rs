@@ -0,0 +1,72 @@
+use std::{sync::Arc, time::Duration};
+
+use mobot::{api::API, *};
+
+#[tokio::test]
+async fn streaming_message() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let api = Arc::new(API::new(
+        Client::new("token".to_string()).with_post_handler(fakeserver.clone()),
+    ));
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    let mut stream = StreamingMessage::new(Arc::clone(&api), chat.chat_id)
+        .with_interval(Duration::from_secs(60));
+
+    // Whitespace alone isn't sent.
+    stream.push(" ").await.unwrap();
+    assert_eq!(api.client.stats().requests, 0);
+
+    // The first text is sent right away, and the rest is held back until the interval passes.
+    stream.push("Hello").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), " Hello");
+    for word in [",", " world", "!"] {
+        stream.push(word).await.unwrap();
+    }
+    assert_eq!(api.client.stats().requests, 1);
+
+    let ids = stream.finish().await.unwrap();
+    assert_eq!(ids.len(), 1);
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        " Hello, world!"
+    );
+    assert_eq!(api.client.stats().requests, 2);
+}
+
+#[tokio::test]
+async fn streaming_message_overflow() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let api = Arc::new(API::new(
+        Client::new("token".to_string()).with_post_handler(fakeserver.clone()),
+    ));
+    let chat = fakeserver.create_chat("qubyte").await;
+
+    let mut stream =
+        StreamingMessage::new(Arc::clone(&api), chat.chat_id).with_interval(Duration::ZERO);
+    stream.push(&"é".repeat(5000)).await.unwrap();
+    let ids = stream.finish().await.unwrap();
+
+    // Messages are limited to 4096 characters, so the text is split in two.
+    assert_eq!(ids.len(), 2);
+    assert_eq!(
+        chat.recv_update()
+            .await
+            .unwrap()
+            .to_string()
+            .chars()
+            .count(),
+        4096
+    );
+    assert_eq!(
+        chat.recv_update()
+            .await
+            .unwrap()
+            .to_string()
+            .chars()
+            .count(),
+        904
+    );
+}
@@ -0,0 +1,221 @@
+use mobot::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Profile {
+    nickname: Option<String>,
+}
+
+impl ChatSetting for Profile {
+    const KEY: &'static str = "profile";
+}
+
+impl Versioned for Profile {}
+
+#[tokio::test]
+async fn export_and_delete() {
+    let storage = MemoryStorage::new();
+    storage.set("warnings", 7, json!(2)).await.unwrap();
+    storage
+        .set("notes", 7, json!(["likes cats"]))
+        .await
+        .unwrap();
+    storage
+        .set("notes", 8, json!(["someone else"]))
+        .await
+        .unwrap();
+
+    let settings = Settings::default();
+    settings
+        .set(
+            7,
+            &Profile {
+                nickname: Some("seven".into()),
+            },
+        )
+        .await
+        .unwrap();
+
+    let stores = UserDataStores::new()
+        .with_store("storage", storage.clone())
+        .with_store("settings", settings.clone());
+
+    assert_eq!(
+        stores.export(7).await.unwrap(),
+        json!({
+            "storage": {"warnings": 2, "notes": ["likes cats"]},
+            "settings": {"profile": {"nickname": "seven"}},
+        })
+    );
+
+    stores.delete(7).await.unwrap();
+    assert_eq!(stores.export(7).await.unwrap(), json!({}));
+    assert!(settings.get::<Profile>(7).await.unwrap().nickname.is_none());
+
+    // Other users' data is untouched.
+    assert_eq!(
+        stores.export(8).await.unwrap(),
+        json!({"storage": {"notes": ["someone else"]}})
+    );
+}
+
+#[derive(Debug, Clone, Default, BotState, Serialize)]
+struct Counter {
+    count: usize,
+}
+
+#[tokio::test]
+async fn router_user_data() {
+    mobot::init_logger();
+    let fakeserver = fake::FakeAPI::new();
+    let client = Client::new("token".to_string()).with_post_handler(fakeserver.clone());
+    let notes = MemoryStorage::new();
+    let mut router = Router::<Counter>::new(client)
+        .with_poll_timeout_s(1)
+        .with_user_data("notes", notes.clone())
+        .with_state_export();
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    assert_eq!(
+        router.user_data().names(),
+        vec!["state", "settings", "notes"]
+    );
+
+    // FakeAPI chats aren't private chats, so the chat ID stands in for the user ID here.
+    router.add_route(
+        Route::Default,
+        |e: Event, state: State<Counter>| async move {
+            let user_id = e.update.chat_id()?;
+            match e.update.text()? {
+                "/export" => Ok(Action::ReplyText(
+                    e.user_data.export(user_id).await?.to_string(),
+                )),
+                "/forget" => {
+                    e.user_data.delete(user_id).await?;
+                    Ok(Action::ReplyText("forgotten".into()))
+                }
+                _ => {
+                    let mut state = state.get().write().await;
+                    state.count += 1;
+                    e.settings
+                        .set(
+                            user_id,
+                            &Profile {
+                                nickname: Some("q".into()),
+                            },
+                        )
+                        .await?;
+                    Ok(Action::ReplyText(state.count.to_string()))
+                }
+            }
+        },
+    );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let chat = fakeserver.create_chat("qubyte").await;
+    notes
+        .set("notes", chat.chat_id, json!("vip"))
+        .await
+        .unwrap();
+
+    chat.send_text("hi").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "1");
+    chat.send_text("hi").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "2");
+
+    chat.send_text("/export").await.unwrap();
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        json!({
+            "state": {"count": 2},
+            "settings": {"profile": {"nickname": "q"}},
+            "notes": {"notes": "vip"}
+        })
+        .to_string()
+    );
+
+    chat.send_text("/forget").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "forgotten");
+    chat.send_text("/export").await.unwrap();
+    // The update itself creates a fresh state for the chat.
+    assert_eq!(
+        chat.recv_update().await.unwrap().to_string(),
+        json!({"state": {"count": 0}}).to_string()
+    );
+
+    // The chat's state was reset.
+    chat.send_text("hi").await.unwrap();
+    assert_eq!(chat.recv_update().await.unwrap().to_string(), "1");
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn history_user_data() {
+    let history = MessageHistory::default();
+    let message = |chat_id: i64, from_id: i64, message_id: i64| {
+        let mut message = api::Message::fake("qubyte");
+        message.chat.id = chat_id;
+        message.from.as_mut().unwrap().id = from_id;
+        message.message_id = message_id;
+        message.text = Some(format!("{} in {}", from_id, chat_id));
+        message
+    };
+
+    // A private chat with user 7, a group they're in, and a group they aren't.
+    history.record(&message(7, 7, 1)).await.unwrap();
+    history.record(&message(-100, 7, 2)).await.unwrap();
+    history.record(&message(-100, 8, 3)).await.unwrap();
+    history.record(&message(-200, 8, 4)).await.unwrap();
+
+    let texts = |entries: &serde_json::Value| {
+        entries
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["text"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let export = history.export_user(7).await.unwrap();
+    assert_eq!(export.as_object().unwrap().len(), 2);
+    assert_eq!(texts(&export["7"]), vec!["7 in 7"]);
+    assert_eq!(texts(&export["-100"]), vec!["7 in -100"]);
+
+    history.delete_user(7).await.unwrap();
+    assert_eq!(
+        history.export_user(7).await.unwrap(),
+        serde_json::Value::Null
+    );
+    assert!(history.all(7).await.unwrap().is_empty());
+
+    // Other users' messages are kept.
+    let ids = |entries: Vec<HistoryEntry>| entries.iter().map(|e| e.message_id).collect::<Vec<_>>();
+    assert_eq!(ids(history.all(-100).await.unwrap()), vec![3]);
+    assert_eq!(ids(history.all(-200).await.unwrap()), vec![4]);
+}
+
+#[tokio::test]
+async fn encrypted_user_data() {
+    let key = EncryptedStorage::<MemoryStorage>::generate_key();
+    let inner = MemoryStorage::new();
+    let storage = EncryptedStorage::new(inner.clone(), &key);
+    storage
+        .set("notes", 7, json!(["likes cats"]))
+        .await
+        .unwrap();
+
+    // Exports are decrypted.
+    assert!(inner.export_user(7).await.unwrap()["notes"].is_string());
+    assert_eq!(
+        storage.export_user(7).await.unwrap(),
+        json!({"notes": ["likes cats"]})
+    );
+
+    storage.delete_user(7).await.unwrap();
+    assert_eq!(storage.get("notes", 7).await.unwrap(), None);
+}
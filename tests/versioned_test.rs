@@ -0,0 +1,100 @@
+use anyhow::Result;
+use mobot::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Version 0 stored a single `limit`. Version 1 renamed it, and version 2 added `enabled`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, BotState)]
+struct Flood {
+    max_messages: u32,
+    enabled: bool,
+}
+
+impl Versioned for Flood {
+    const VERSION: u32 = 2;
+
+    fn migrate(old_version: u32, mut value: Value) -> Result<Value> {
+        if old_version < 1 {
+            value = json!({"max_messages": value["limit"]});
+        }
+        if old_version < 2 {
+            value["enabled"] = json!(true);
+        }
+        Ok(value)
+    }
+}
+
+impl ChatSetting for Flood {
+    const KEY: &'static str = "flood";
+}
+
+#[test]
+fn decode() {
+    let current = Flood {
+        max_messages: 5,
+        enabled: true,
+    };
+
+    // Untagged data is version 0.
+    assert_eq!(
+        versioned::decode::<Flood>(json!({"limit": 5})).unwrap(),
+        current
+    );
+    assert_eq!(
+        versioned::decode::<Flood>(json!({"__mobot_version": 1, "value": {"max_messages": 5}}))
+            .unwrap(),
+        current
+    );
+
+    let encoded = versioned::encode(&current).unwrap();
+    assert_eq!(encoded["__mobot_version"], 2);
+    assert_eq!(versioned::decode::<Flood>(encoded).unwrap(), current);
+
+    // Data from a newer release isn't misread.
+    assert!(
+        versioned::decode::<Flood>(json!({"__mobot_version": 3, "value": {}}))
+            .unwrap_err()
+            .to_string()
+            .contains("newer")
+    );
+}
+
+#[tokio::test]
+async fn settings_migration() {
+    let storage = MemoryStorage::new();
+    storage
+        .set("settings", 1, json!({"flood": {"limit": 10}}))
+        .await
+        .unwrap();
+    let settings = Settings::new(storage.clone());
+
+    let flood = settings.get::<Flood>(1).await.unwrap();
+    assert_eq!(flood.max_messages, 10);
+    assert!(flood.enabled);
+
+    // Writes store the current version.
+    settings
+        .update::<Flood>(1, |f| f.enabled = false)
+        .await
+        .unwrap();
+    let raw = storage.get("settings", 1).await.unwrap().unwrap();
+    assert_eq!(
+        raw["flood"],
+        json!({"__mobot_version": 2, "value": {"max_messages": 10, "enabled": false}})
+    );
+}
+
+#[tokio::test]
+async fn state_snapshot() {
+    let state = State::new(Flood {
+        max_messages: 3,
+        enabled: false,
+    });
+
+    let snapshot = state.snapshot().await.unwrap();
+    let restored = State::<Flood>::restore(snapshot).unwrap();
+    assert_eq!(*restored.get().read().await, *state.get().read().await);
+
+    let restored = State::<Flood>::restore(json!({"limit": 7})).unwrap();
+    assert_eq!(restored.get().read().await.max_messages, 7);
+}
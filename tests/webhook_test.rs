@@ -0,0 +1,395 @@
+#![cfg(feature = "webhooks")]
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use mobot::{webhook::WebhookConfig, *};
+
+/// A server that records requests by method, and reports the webhook as failing while
+/// `failing` is set.
+#[derive(Clone, Default)]
+struct WebhookServer {
+    failing: Arc<AtomicBool>,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl WebhookServer {
+    fn count(&self, method: &str) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| *m == method)
+            .count()
+    }
+
+    async fn wait_for(&self, method: &str, count: usize) {
+        while self.count(method) < count {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl client::Post for WebhookServer {
+    async fn post(&self, method: String, _: String) -> Result<String> {
+        self.calls.lock().unwrap().push(method.clone());
+        Ok(match method.as_str() {
+            "getWebhookInfo" if self.failing.load(Ordering::SeqCst) => r#"{"ok": true, "result": {"url": "https://bot.example.com", "pending_update_count": 3, "last_error_date": 4000000000, "last_error_message": "Connection refused"}}"#.into(),
+            "getWebhookInfo" => r#"{"ok": true, "result": {"url": "https://bot.example.com", "pending_update_count": 0}}"#.into(),
+            "getUpdates" => {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                r#"{"ok": true, "result": []}"#.into()
+            }
+            _ => r#"{"ok": true, "result": true}"#.into(),
+        })
+    }
+}
+
+fn update(update_id: i64, text: &str) -> String {
+    let mut message = api::Message::fake("qubyte");
+    message.text = Some(text.into());
+    serde_json::to_string(&api::Update {
+        update_id,
+        message: Some(message),
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn webhook_failover() {
+    mobot::init_logger();
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let url = format!("http://{}/telegram", addr);
+
+    let server = WebhookServer::default();
+    let client = Client::new("token".to_string()).with_post_handler(server.clone());
+    let webhook = WebhookConfig::new(&url, addr)
+        .with_secret_token("s3cret")
+        .with_check_interval(Duration::from_millis(50));
+    let mut router: Router<()> = Router::new(client).with_webhook(webhook);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&received);
+    router.add_route(
+        Route::Message(Matcher::Any),
+        move |e: Event, _: State<()>| {
+            let recorded = Arc::clone(&recorded);
+            async move {
+                recorded.lock().unwrap().push(e.update.text()?.to_string());
+                Ok(Action::Done)
+            }
+        },
+    );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+    server.wait_for("setWebhook", 1).await;
+
+    // Updates are posted to the webhook, with the secret token.
+    let http = reqwest::Client::new();
+    let post = |body: String, token: &'static str| {
+        http.post(&url)
+            .header("X-Telegram-Bot-Api-Secret-Token", token)
+            .body(body)
+            .send()
+    };
+    let response = post(update(1, "forged"), "wrong").await.unwrap();
+    assert_eq!(response.status(), 401);
+    let response = post(update(1, "hello"), "s3cret").await.unwrap();
+    assert_eq!(response.status(), 200);
+    while received.lock().unwrap().is_empty() {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(*received.lock().unwrap(), vec!["hello"]);
+    assert_eq!(server.count("getUpdates"), 0);
+
+    // If Telegram can't deliver to the webhook, the router deletes it and polls instead...
+    server.failing.store(true, Ordering::SeqCst);
+    server.wait_for("deleteWebhook", 1).await;
+    server.wait_for("getUpdates", 1).await;
+
+    // ...until the webhook is reachable again.
+    server.failing.store(false, Ordering::SeqCst);
+    server.wait_for("setWebhook", 2).await;
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn secret_rotation() {
+    let fail = Arc::new(AtomicBool::new(false));
+    let sent = Arc::new(Mutex::new(vec![]));
+    let (failing, recorded) = (Arc::clone(&fail), Arc::clone(&sent));
+    let client =
+        Client::new("token".to_string()).with_post_handler_fn(move |_: String, req: String| {
+            recorded
+                .lock()
+                .unwrap()
+                .push(serde_json::from_str::<serde_json::Value>(&req).unwrap());
+            Ok(if failing.load(Ordering::SeqCst) {
+                r#"{"ok": false, "description": "Bad Request: bad webhook"}"#.into()
+            } else {
+                r#"{"ok": true, "result": true}"#.into()
+            })
+        });
+    let api = api::API::new(client);
+
+    let webhook =
+        WebhookConfig::new("https://bot.example.com", ([127, 0, 0, 1], 0)).with_secret_token("old");
+    let secret = webhook.secret.clone();
+    assert!(secret.accepts(Some("old")));
+    assert!(!secret.accepts(Some("new")));
+    assert!(!secret.accepts(None));
+
+    // Both tokens are accepted during the grace period, and only the new one after it.
+    secret
+        .rotate(&api, &webhook, "new", Duration::from_millis(50))
+        .await
+        .unwrap();
+    assert_eq!(sent.lock().unwrap()[0]["secret_token"], "new");
+    assert_eq!(webhook.request().secret_token.as_deref(), Some("new"));
+    assert!(secret.accepts(Some("old")));
+    assert!(secret.accepts(Some("new")));
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(!secret.accepts(Some("old")));
+    assert!(secret.accepts(Some("new")));
+
+    // If Telegram rejects the new token, the current one stays.
+    fail.store(true, Ordering::SeqCst);
+    assert!(secret
+        .rotate(&api, &webhook, "newer", Duration::from_secs(60))
+        .await
+        .is_err());
+    assert_eq!(secret.current().as_deref(), Some("new"));
+    assert!(!secret.accepts(Some("newer")));
+    assert!(!secret.accepts(Some("old")));
+}
+
+#[tokio::test]
+async fn oversized_requests() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, _rx) = tokio::sync::mpsc::channel(1);
+    tokio::spawn(webhook::serve(listener, Default::default(), tx));
+
+    let respond = |request: Vec<u8>| async move {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(&request).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    };
+
+    // A header line that never ends is cut off...
+    let mut request = b"POST / HTTP/1.1\r\nx-padding: ".to_vec();
+    request.extend(std::iter::repeat_n(b'a', 64 * 1024));
+    assert!(respond(request).await.starts_with("HTTP/1.1 431"));
+
+    // ...and so are too many headers.
+    let mut request = b"POST / HTTP/1.1\r\n".to_vec();
+    for i in 0..100 {
+        request.extend(format!("x-header-{}: {}\r\n", i, i).bytes());
+    }
+    request.extend(b"\r\n");
+    assert!(respond(request).await.starts_with("HTTP/1.1 431"));
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+struct Visits {
+    count: u32,
+}
+
+impl ChatSetting for Visits {
+    const KEY: &'static str = "visits";
+}
+
+impl Versioned for Visits {}
+
+/// Requests sent by tenants' clients: the tenant, the method, and the request.
+type Sent = Arc<Mutex<Vec<(&'static str, String, serde_json::Value)>>>;
+
+/// A tenant whose client records the requests it sends in `sent`.
+fn recording_tenant(id: &'static str, sent: &Sent) -> tenants::Tenant {
+    let recorded = Arc::clone(sent);
+    let client =
+        Client::new(id.to_string()).with_post_handler_fn(move |method: String, req: String| {
+            let req: serde_json::Value = serde_json::from_str(&req).unwrap();
+            let response = match method.as_str() {
+                "sendMessage" => r#"{"ok": true, "result": {"message_id": 1, "date": 0, "chat": {"id": 0, "type": "private"}}}"#,
+                _ => r#"{"ok": true, "result": true}"#,
+            };
+            recorded.lock().unwrap().push((id, method, req));
+            Ok(response.to_string())
+        });
+    tenants::Tenant::new(id, client)
+}
+
+#[tokio::test]
+async fn tenants() {
+    use mobot::tenants::Tenants;
+
+    mobot::init_logger();
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let url = format!("http://{}/telegram", addr);
+    let webhook = WebhookConfig::new(&url, addr).with_secret_token("s3cret");
+
+    // Each tenant's client records the requests it sends.
+    let sent = Sent::default();
+    let tenants = Tenants::new();
+    for id in ["acme", "globex"] {
+        let tenant = recording_tenant(id, &sent);
+        tenant.set_webhook(&webhook).await.unwrap();
+        tenants.add(tenant);
+    }
+    assert_eq!(sent.lock().unwrap()[1].2["url"], format!("{}/globex", url));
+    sent.lock().unwrap().clear();
+
+    let mut router: Router<()> = Router::for_tenants(tenants.clone()).with_webhook(webhook);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    router.add_route(
+        Route::Message(Matcher::Any),
+        |e: Event, _: State<()>| async move {
+            let visits = e
+                .settings
+                .update::<Visits>(e.update.chat_id()?, |v| v.count += 1)
+                .await?;
+            e.send_message(format!("{} {}", e.tenant.as_deref().unwrap(), visits.count))
+                .await?;
+            Ok(Action::Done)
+        },
+    );
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let http = reqwest::Client::new();
+    let post = |tenant: &str, update_id: i64| {
+        http.post(format!("{}/{}", url, tenant))
+            .header("X-Telegram-Bot-Api-Secret-Token", "s3cret")
+            .body(update(update_id, "hi"))
+            .send()
+    };
+    let status = loop {
+        match post("acme", 1).await {
+            Ok(response) => break response.status(),
+            Err(_) => tokio::time::sleep(Duration::from_millis(5)).await,
+        }
+    };
+    assert_eq!(status, 200);
+    assert_eq!(post("globex", 1).await.unwrap().status(), 200);
+    assert_eq!(post("initech", 1).await.unwrap().status(), 404);
+    while sent.lock().unwrap().len() < 2 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(post("acme", 2).await.unwrap().status(), 200);
+    while sent.lock().unwrap().len() < 3 {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    // Each tenant's replies are sent with its own client, and its settings are its own.
+    let mut replies: Vec<_> = sent
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, method, req)| {
+            assert_eq!(method, "sendMessage");
+            format!("{} {}", id, req["text"].as_str().unwrap())
+        })
+        .collect();
+    replies.sort();
+    assert_eq!(replies, ["acme acme 1", "acme acme 2", "globex globex 1"]);
+
+    // Removed tenants' updates are rejected.
+    tenants.remove("globex");
+    assert_eq!(post("globex", 2).await.unwrap().status(), 404);
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}
+
+#[tokio::test]
+async fn throttled_tenant() {
+    use mobot::tenants::Tenants;
+
+    mobot::init_logger();
+    let addr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let url = format!("http://{}/telegram", addr);
+    let webhook = WebhookConfig::new(&url, addr);
+
+    // acme may only have one update handled a minute.
+    let sent = Sent::default();
+    let tenants = Tenants::new();
+    tenants.add(
+        recording_tenant("acme", &sent)
+            .with_update_limiter(RateLimiter::new(Duration::from_secs(60))),
+    );
+    tenants.add(recording_tenant("globex", &sent));
+
+    let mut router: Router<()> = Router::for_tenants(tenants).with_webhook(webhook);
+    let (shutdown_notifier, shutdown_tx) = router.shutdown();
+    router.add_route(Route::Default, |e: Event, _: State<()>| async move {
+        Ok(Action::ReplyText(e.tenant.unwrap_or_default()))
+    });
+
+    tokio::spawn(async move {
+        router.start().await;
+    });
+
+    let http = reqwest::Client::new();
+    let post = |tenant: &str, update_id: i64| {
+        http.post(format!("{}/{}", url, tenant))
+            .body(update(update_id, "hi"))
+            .send()
+    };
+    while post("acme", 1).await.is_err() {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    for update_id in 2..=3 {
+        assert_eq!(post("acme", update_id).await.unwrap().status(), 200);
+    }
+
+    // globex's update is handled while acme's wait their turn.
+    assert_eq!(post("globex", 1).await.unwrap().status(), 200);
+    let replied = |id: &str| {
+        sent.lock()
+            .unwrap()
+            .iter()
+            .filter(|(tenant, method, _)| *tenant == id && method == "sendMessage")
+            .count()
+    };
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while replied("globex") < 1 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("globex's update is stuck behind acme's");
+    assert_eq!(replied("acme"), 1);
+
+    shutdown_tx.send(()).await.unwrap();
+    shutdown_notifier.notified().await;
+}